@@ -1,3 +1,5 @@
+use crate::protocol::protocol_notations::consistency::Consistency;
+
 /// Enum representing the different types of errors that can occur during program execution.
 ///
 /// ### Error Codes:
@@ -12,11 +14,27 @@
 /// - `code` (`i32`): The unique error code.
 /// - `message` (`String`): A detailed error message.
 ///
+/// The writes that can not reach their consistency level have their own variants, since the client is told how many
+/// replicas they needed.
+///
 /// This structure ensures a standardized way to handle and propagate errors across the system.
 #[derive(PartialEq)]
 pub enum ErrorTypes {
     /// Represents an error with a specific code and message.
     Error { code: i32, message: String },
+    /// Represents a write that was not sent because there are not enough live replicas for its consistency level.
+    Unavailable {
+        consistency: Consistency,
+        required: usize,
+        alive: usize,
+    },
+    /// Represents a write that was not acknowledged by as many replicas as its consistency level needs.
+    WriteTimeout {
+        consistency: Consistency,
+        received: usize,
+        block_for: usize,
+        write_type: String,
+    },
 }
 
 impl ErrorTypes {
@@ -39,6 +57,29 @@ impl ErrorTypes {
     pub fn get(&self) -> (i32, String) {
         match self {
             ErrorTypes::Error { code, message } => (*code, message.clone()),
+            ErrorTypes::Unavailable {
+                consistency,
+                required,
+                alive,
+            } => (
+                543,
+                format!(
+                    "Cannot achieve consistency level {:?}: {} replicas required but only {} alive",
+                    consistency, required, alive
+                ),
+            ),
+            ErrorTypes::WriteTimeout {
+                consistency,
+                received,
+                block_for,
+                ..
+            } => (
+                544,
+                format!(
+                    "Write timed out at consistency level {:?}: {} of {} replicas acknowledged it",
+                    consistency, received, block_for
+                ),
+            ),
         }
     }
 }
@@ -46,10 +87,7 @@ impl ErrorTypes {
 impl std::fmt::Debug for ErrorTypes {
     /// Formats the error information for debugging purposes.
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        match self {
-            ErrorTypes::Error { code, message } => {
-                write!(f, "An error has occured: {:?}, code: [{:?}]", message, code)
-            }
-        }
+        let (code, message) = self.get();
+        write!(f, "An error has occured: {:?}, code: [{:?}]", message, code)
     }
 }
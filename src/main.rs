@@ -1,22 +1,23 @@
 use aerolineas_rusticas::{
     errors::error_types::ErrorTypes,
     protocol::protocol_body::compression::Compression,
-    server::query_execute::{authenticate, startup},
+    server::query_execute::{authenticate, listen_events, register, startup, use_keyspace},
     ui::lib::MyApp,
 };
 use native_tls::{TlsConnector, TlsStream};
-use std::net::TcpStream;
+use std::{net::TcpStream, sync::mpsc::channel, thread};
 
 const COMPRESSION: Option<Compression> = None;
+const SERVER_ADDRESS: &str = "127.0.0.1:8090";
 
-#[cfg(not(target_arch = "wasm32"))]
-fn main() -> Result<(), ErrorTypes> {
+/// This function opens a connection with the server, already started and authenticated.
+fn connect() -> Result<TlsStream<TcpStream>, ErrorTypes> {
     let connector = TlsConnector::builder()
         .danger_accept_invalid_certs(true)
         .build()
         .unwrap();
 
-    let stream: TcpStream = TcpStream::connect("127.0.0.1:8090").unwrap();
+    let stream: TcpStream = TcpStream::connect(SERVER_ADDRESS).unwrap();
     let mut server: TlsStream<TcpStream> = connector.connect("127.0.0.1", stream).unwrap();
 
     println!("Connected to the server!");
@@ -29,15 +30,42 @@ fn main() -> Result<(), ErrorTypes> {
         COMPRESSION,
     )?;
     println!("Authenticated!");
+    Ok(server)
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn main() -> Result<(), ErrorTypes> {
+    let mut server = connect()?;
+    use_keyspace(&mut server, COMPRESSION)?;
+    // Los eventos llegan por otra conexion, que no espera respuestas a las consultas
+    let mut events_server = connect()?;
+    register(
+        &mut events_server,
+        &COMPRESSION,
+        vec![
+            "TOPOLOGY_CHANGE".to_string(),
+            "STATUS_CHANGE".to_string(),
+            "SCHEMA_CHANGE".to_string(),
+        ],
+    )?;
     env_logger::init();
     let _ = eframe::run_native(
         "MyApp",
         Default::default(),
         Box::new(|cc| {
+            let (sender, events) = channel();
+            let egui_ctx = cc.egui_ctx.clone();
+            thread::spawn(move || {
+                let _ = listen_events(&mut events_server, &COMPRESSION, |event| {
+                    let _ = sender.send(event);
+                    egui_ctx.request_repaint();
+                });
+            });
             Ok(Box::new(MyApp::new(
                 cc.egui_ctx.clone(),
                 &mut server,
                 COMPRESSION,
+                events,
             )))
         }),
     );
@@ -15,7 +15,7 @@ pub fn main() -> Result<(), Box<dyn Error>> {
                 "cargo run --bin node -- {}{} {}{}",
                 nodes[0], i, nodes[1], i
             ))
-            .spawn()
+            .status()
             .expect("Failed to spawn terminal");
     }
 
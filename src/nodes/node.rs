@@ -1,16 +1,19 @@
-use aerolineas_rusticas::{errors::error_types::ErrorTypes, server::nodes::Node};
+use aerolineas_rusticas::{
+    errors::error_types::ErrorTypes,
+    server::{node_config::NodeConfig, nodes::Node},
+};
 use std::env;
 
 fn main() -> Result<(), ErrorTypes> {
     let args: Vec<String> = env::args().collect();
 
-    if args.len() != 3 {
-        eprintln!("Usage: cargo run --bin node -- <INTERNAL_IP_ADDRESS> <CLIENT_IP_ADDRESS>");
-        std::process::exit(1);
-    }
-
-    let ip_address_internal = &args[1];
-    let ip_address_client = &args[2];
-    let mut node = Node::new(ip_address_internal, ip_address_client).unwrap();
+    let config = match NodeConfig::from_args(&args[1..]) {
+        Ok(config) => config,
+        Err(_) => {
+            eprintln!("Usage: cargo run --bin node -- <INTERNAL_IP_ADDRESS> <CLIENT_IP_ADDRESS> [--data-dir <DIR>] [--admin-address <ADMIN_IP_ADDRESS>] [--replace-address <DEAD_IP_ADDRESS>] [--dc <DATACENTER>] [--rack <RACK>]");
+            std::process::exit(1);
+        }
+    };
+    let mut node = Node::new(&config).unwrap();
     node.run()
 }
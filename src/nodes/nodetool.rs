@@ -0,0 +1,19 @@
+use aerolineas_rusticas::server::admin::send_admin_command;
+use std::env;
+
+/// This binary sends an administration command to a node, like `decommission`, `removenode` or `cleanup`.
+fn main() {
+    let args: Vec<String> = env::args().collect();
+    if args.len() < 3 {
+        eprintln!("Usage: cargo run --bin nodetool -- <ADMIN_IP_ADDRESS> decommission | removenode <IP_ADDRESS> | cleanup");
+        std::process::exit(1);
+    }
+    let command = args[2..].join(" ");
+    match send_admin_command(&args[1], &command) {
+        Ok(_) => println!("{} done", command),
+        Err(e) => {
+            eprintln!("{} {}", e.get().0, e.get().1);
+            std::process::exit(1);
+        }
+    }
+}
@@ -1,4 +1,8 @@
-use super::{flags::Flags, opcode::Opcode, version::Version};
+use super::{
+    flags::Flags,
+    opcode::Opcode,
+    version::{Version, PROTOCOL_VERSION},
+};
 
 #[derive(Debug)]
 /// Represents the header of a frame.
@@ -8,13 +12,15 @@ use super::{flags::Flags, opcode::Opcode, version::Version};
 /// is essential for interpreting the frame's contents and handling it appropriately.
 ///
 /// ### Fields:
-/// - **version**: Specifies the protocol version used in the frame.
+/// - **version**: Specifies if the frame is a request or a response.
+/// - **protocol_version**: The version of the native protocol used in the frame.
 /// - **flag**: A list of `Flags` that modify the behavior or provide additional metadata for the frame.
 /// - **stream**: The stream ID (`u16`) that uniquely identifies the frame within a connection.
 /// - **opcode**: The `Opcode` specifying the type of operation the frame represents (e.g., query, response).
 /// - **length**: The length (`i32`) of the frame's body in bytes.e.
 pub struct Header {
     version: Version,
+    protocol_version: u8,
     flag: Vec<Flags>,
     stream: u16,
     opcode: Opcode,
@@ -32,6 +38,7 @@ impl Header {
     pub fn new() -> Header {
         Header {
             version: Version::Request,
+            protocol_version: PROTOCOL_VERSION,
             flag: Vec::new(),
             stream: 0,
             opcode: Opcode::Error,
@@ -44,6 +51,11 @@ impl Header {
         self.version
     }
 
+    /// Retrieves the version of the native protocol of the `Header`.
+    pub fn get_protocol_version(&self) -> u8 {
+        self.protocol_version
+    }
+
     /// Retrieves the flags set in the `Header`.
     pub fn get_flag(&self) -> &Vec<Flags> {
         &self.flag
@@ -80,6 +92,14 @@ impl Header {
         self.version = version;
     }
 
+    /// Sets the version of the native protocol of the header.
+    ///
+    /// # Arguments:
+    /// - `protocol_version`: The new version of the native protocol.
+    pub fn set_protocol_version(&mut self, protocol_version: u8) {
+        self.protocol_version = protocol_version;
+    }
+
     /// Sets the operation code of the header.
     ///
     /// # Arguments:
@@ -107,7 +127,7 @@ impl Header {
     /// Converts the header into its binary representation. # Returns: A `Vec<u8>` containing the serialized header. # Details: - The binary representation includes the version, combined flags, stream ID, opcode, and length.
     pub fn get_binary(&self) -> Vec<u8> {
         let mut bits_res: Vec<u8> = Vec::new();
-        bits_res.push(self.version as u8);
+        bits_res.push(self.version as u8 | self.protocol_version);
         bits_res.push(self.flag.iter().map(|x| *x as u8).sum());
         bits_res.extend(self.stream.to_be_bytes());
         bits_res.push(self.opcode as u8);
@@ -133,7 +153,12 @@ mod tests {
         assert_eq!(header.get_opcode(), Opcode::StartUp);
         assert_eq!(
             header.get_binary(),
-            vec![0x05, 0x03, 0x00, 0x01, 0x01, 0x00, 0x00, 0x00, 0x00]
+            vec![0x04, 0x03, 0x00, 0x01, 0x01, 0x00, 0x00, 0x00, 0x00]
         );
+
+        header.set_version(Version::Response);
+        header.set_protocol_version(4);
+        assert_eq!(header.get_binary()[0], 0x84);
+        assert_eq!(Version::from_byte(0x84), (Version::Response, 4));
     }
 }
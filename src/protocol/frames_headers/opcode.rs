@@ -26,8 +26,15 @@ pub enum Opcode {
     StartUp = 0x01,
     Ready = 0x02,
     Authenticate = 0x03,
+    Options = 0x05,
+    Supported = 0x06,
     Query = 0x07,
     Result = 0x08,
+    Prepare = 0x09,
+    Execute = 0x0A,
+    Register = 0x0B,
+    Event = 0x0C,
+    Batch = 0x0D,
     AuthResponse = 0x0F,
     AuthSuccess = 0x10,
 }
@@ -1,13 +1,39 @@
 #[derive(Debug, Clone, Copy, PartialEq)]
-/// Represents the different protocol versions used in messages.
+/// Represents the direction of the messages in the version byte of the header.
 ///
-/// The `Version` enum distinguishes between requests and responses within the protocol.
-/// Each version is encoded as a specific byte value.
+/// The `Version` enum distinguishes between requests and responses within the protocol. The byte of the header has
+/// the direction in its most significant bit and the version of the native protocol in the other ones.
 ///
 /// ### Variants:
-/// - **Request (0x05)**: Indicates that the message is a request.
-/// - **Response (0x85)**: Indicates that the message is a response.
+/// - **Request (0x00)**: Indicates that the message is a request.
+/// - **Response (0x80)**: Indicates that the message is a response.
 pub enum Version {
-    Request = 0x05,
-    Response = 0x85,
+    Request = 0x00,
+    Response = 0x80,
+}
+
+/// The versions of the native protocol that the nodes support. The frames of the version 5 are split in segments with
+/// checksums, which the nodes do not implement, so the drivers that try it first fall back to the version 4.
+pub const PROTOCOL_VERSIONS: [u8; 1] = [4];
+
+/// The version of the native protocol that the client uses.
+pub const PROTOCOL_VERSION: u8 = 4;
+
+impl Version {
+    /// This function returns the direction and the version of the native protocol of a version byte.
+    pub fn from_byte(byte: u8) -> (Version, u8) {
+        let version = match byte & 0x80 {
+            0x00 => Version::Request,
+            _ => Version::Response,
+        };
+        (version, byte & 0x7F)
+    }
+
+    /// This function returns the supported versions as they are advertised in the SUPPORTED message, like `4/v4`.
+    pub fn supported() -> Vec<String> {
+        PROTOCOL_VERSIONS
+            .iter()
+            .map(|version| format!("{}/v{}", version, version))
+            .collect()
+    }
 }
@@ -0,0 +1,23 @@
+/// Represents the type of a Batch message.
+///
+/// ### Variants:
+/// - **Logged**: The batch is written to the batchlog before it is applied, so every statement is eventually applied (0x00).
+/// - **Unlogged**: The statements are applied without the batchlog (0x01).
+/// - **Counter**: A batch of counter updates, which is not supported (0x02).
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum BatchType {
+    Logged = 0x00,
+    Unlogged = 0x01,
+    Counter = 0x02,
+}
+
+/// Represents how a statement of a Batch message is sent.
+///
+/// ### Variants:
+/// - **Query**: The text of the query (0x00).
+/// - **Prepared**: The identifier of a prepared statement (0x01).
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum BatchQueryKind {
+    Query = 0x00,
+    Prepared = 0x01,
+}
@@ -1,8 +1,6 @@
 use serde::{Deserialize, Serialize};
 extern crate snap;
-use snap::read::FrameDecoder;
-use snap::write::FrameEncoder;
-use std::io::{Read, Write};
+use snap::raw::{Decoder, Encoder};
 extern crate lz4;
 use crate::errors::error_types::ErrorTypes;
 use lz4::block::{compress, decompress};
@@ -11,7 +9,8 @@ use lz4::block::{compress, decompress};
 /// Represents the different compression algorithms supported by the protocol.
 ///
 /// Compression algorithms are used to reduce the size of data transmitted in messages,
-/// improving performance and reducing network load.
+/// improving performance and reducing network load. A Snappy body is a raw Snappy block, and an LZ4 body is the length
+/// of the uncompressed body, as an int, followed by an LZ4 block, as the version 4 of the native protocol defines them.
 pub enum Compression {
     Snappy,
     LZ4,
@@ -32,18 +31,17 @@ impl Compression {
     /// - `Err(ErrorTypes)`: An error indicating the failure of the compression process.
     pub fn compression(&self, data: Vec<u8>) -> Result<Vec<u8>, ErrorTypes> {
         match self {
-            Compression::Snappy => {
-                let mut encoder = FrameEncoder::new(Vec::new());
-                encoder.write_all(&data).map_err(|_| {
-                    ErrorTypes::new(410, "Error compressing data by Snappy".to_string())
-                })?;
-                let compressed = encoder.into_inner().map_err(|_| {
-                    ErrorTypes::new(410, "Error compressing data by Snappy".to_string())
+            Compression::Snappy => Encoder::new()
+                .compress_vec(&data)
+                .map_err(|_| ErrorTypes::new(410, "Error compressing data by Snappy".to_string())),
+            Compression::LZ4 => {
+                let block = compress(&data, None, false).map_err(|_| {
+                    ErrorTypes::new(411, "Error compressing data by LZ4".to_string())
                 })?;
+                let mut compressed = (data.len() as i32).to_be_bytes().to_vec();
+                compressed.extend(block);
                 Ok(compressed)
             }
-            Compression::LZ4 => compress(&data, None, false)
-                .map_err(|_| ErrorTypes::new(411, "Error compressing data by LZ4".to_string())),
         }
     }
 
@@ -61,18 +59,16 @@ impl Compression {
     /// - `Err(ErrorTypes)`: An error indicating the failure of the decompression process.    
     pub fn decompression(&self, data: Vec<u8>) -> Result<Vec<u8>, ErrorTypes> {
         match self {
-            Compression::Snappy => {
-                let mut decoder = FrameDecoder::new(&data[4..]);
-                let mut decompressed = Vec::new();
-                decoder
-                    .read_to_end(&mut decompressed)
-                    .map_err(|_| ErrorTypes::new(411, "Error decompressing data".to_string()))?;
-                Ok(decompressed)
-            }
+            Compression::Snappy => Decoder::new()
+                .decompress_vec(&data)
+                .map_err(|_| ErrorTypes::new(411, "Error decompressing data".to_string())),
             Compression::LZ4 => {
-                let len: [u8; 4] = data[..4].try_into().map_err(|_| {
-                    ErrorTypes::new(411, "Error decompressing data lz4".to_string())
-                })?;
+                let len: [u8; 4] = data
+                    .get(..4)
+                    .and_then(|len| len.try_into().ok())
+                    .ok_or_else(|| {
+                        ErrorTypes::new(411, "Error decompressing data lz4".to_string())
+                    })?;
                 decompress(&data[4..], Some(i32::from_be_bytes(len)))
                     .map_err(|_| ErrorTypes::new(411, "Error decompressing data lz4".to_string()))
             }
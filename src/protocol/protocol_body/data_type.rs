@@ -0,0 +1,93 @@
+/// Represents the type of a column, as the native protocol identifies it in the metadata of a result.
+///
+/// The nodes store every value as text, so the types that are not listed here are sent as `Varchar`.
+///
+/// ### Variants:
+/// - **Ascii**: An ASCII text (0x0001).
+/// - **Bigint**: A 64-bit signed integer (0x0002).
+/// - **Blob**: Arbitrary bytes (0x0003).
+/// - **Boolean**: `true` or `false` (0x0004).
+/// - **Double**: A 64-bit floating point number (0x0007).
+/// - **Float**: A 32-bit floating point number (0x0008).
+/// - **Int**: A 32-bit signed integer (0x0009).
+/// - **Timestamp**: The milliseconds since the epoch (0x000B).
+/// - **Uuid**: A UUID (0x000C).
+/// - **Varchar**: A UTF-8 text, also called `text` (0x000D).
+/// - **Inet**: An IPv4 or IPv6 address (0x0010).
+/// - **Date**: A day, like `2024-10-28` (0x0011).
+/// - **Smallint**: A 16-bit signed integer (0x0013).
+/// - **Tinyint**: An 8-bit signed integer (0x0014).
+/// - **Map**: A map of texts to texts (0x0021), like the replication of a keyspace in `system_schema.keyspaces`. Its
+///   metadata is followed by the types of its keys and values, `Varchar`.
+/// - **Set**: A set of texts (0x0022), like the tokens of a node in the tables of the `system` keyspace. Its metadata
+///   is followed by the type of its elements, `Varchar`.
+///
+/// The collections are only sent in the system tables.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum DataType {
+    Ascii = 0x0001,
+    Bigint = 0x0002,
+    Blob = 0x0003,
+    Boolean = 0x0004,
+    Double = 0x0007,
+    Float = 0x0008,
+    Int = 0x0009,
+    Timestamp = 0x000B,
+    Uuid = 0x000C,
+    Varchar = 0x000D,
+    Inet = 0x0010,
+    Date = 0x0011,
+    Smallint = 0x0013,
+    Tinyint = 0x0014,
+    Map = 0x0021,
+    Set = 0x0022,
+}
+
+impl DataType {
+    /// This function returns the type of a column from the name it has in a `CREATE TABLE`. The other types are
+    /// `Varchar`.
+    pub fn from_name(name: &str) -> DataType {
+        match name.to_lowercase().as_str() {
+            "ascii" => DataType::Ascii,
+            "bigint" => DataType::Bigint,
+            "blob" => DataType::Blob,
+            "boolean" => DataType::Boolean,
+            "double" => DataType::Double,
+            "float" => DataType::Float,
+            "int" => DataType::Int,
+            "timestamp" => DataType::Timestamp,
+            "uuid" => DataType::Uuid,
+            "date" => DataType::Date,
+            "smallint" => DataType::Smallint,
+            "tinyint" => DataType::Tinyint,
+            "inet" => DataType::Inet,
+            "map<text, text>" => DataType::Map,
+            "set<text>" => DataType::Set,
+            _ => DataType::Varchar,
+        }
+    }
+
+    /// This function returns the type with the identifier `id`, if it is one of the listed ones.
+    pub fn from_id(id: u16) -> Option<DataType> {
+        let data_type = match id {
+            0x0001 => DataType::Ascii,
+            0x0002 => DataType::Bigint,
+            0x0003 => DataType::Blob,
+            0x0004 => DataType::Boolean,
+            0x0007 => DataType::Double,
+            0x0008 => DataType::Float,
+            0x0009 => DataType::Int,
+            0x000B => DataType::Timestamp,
+            0x000C => DataType::Uuid,
+            0x000D => DataType::Varchar,
+            0x0010 => DataType::Inet,
+            0x0011 => DataType::Date,
+            0x0013 => DataType::Smallint,
+            0x0014 => DataType::Tinyint,
+            0x0021 => DataType::Map,
+            0x0022 => DataType::Set,
+            _ => return None,
+        };
+        Some(data_type)
+    }
+}
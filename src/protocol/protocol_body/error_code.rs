@@ -0,0 +1,39 @@
+/// Represents the code of an Error message.
+///
+/// This enum categorizes the errors that the server can report to a client. Each variant
+/// corresponds to the code that the native protocol assigns to it.
+///
+/// ### Variants:
+/// - **ServerError**: An unexpected error in the server (0x0000).
+/// - **ProtocolError**: A frame that breaks the protocol (0x000A).
+/// - **BadCredentials**: The authentication failed (0x0100).
+/// - **Unavailable**: There are not enough live replicas to reach the consistency level (0x1000).
+/// - **Overloaded**: The coordinator is overloaded (0x1001).
+/// - **IsBootstrapping**: The coordinator is still joining the cluster (0x1002).
+/// - **TruncateError**: A truncation failed (0x1003).
+/// - **WriteTimeout**: The replicas did not acknowledge a write in time (0x1100).
+/// - **ReadTimeout**: The replicas did not answer a read in time (0x1200).
+/// - **SyntaxError**: The query has a syntax error (0x2000).
+/// - **Unauthorized**: The user can not run the query (0x2100).
+/// - **Invalid**: The query is syntactically correct but invalid (0x2200).
+/// - **ConfigError**: The query is invalid because of a configuration issue (0x2300).
+/// - **AlreadyExists**: The keyspace or table to create already exists (0x2400).
+/// - **Unprepared**: The prepared statement to execute is unknown (0x2500).
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum ErrorCode {
+    ServerError = 0x0000,
+    ProtocolError = 0x000A,
+    BadCredentials = 0x0100,
+    Unavailable = 0x1000,
+    Overloaded = 0x1001,
+    IsBootstrapping = 0x1002,
+    TruncateError = 0x1003,
+    WriteTimeout = 0x1100,
+    ReadTimeout = 0x1200,
+    SyntaxError = 0x2000,
+    Unauthorized = 0x2100,
+    Invalid = 0x2200,
+    ConfigError = 0x2300,
+    AlreadyExists = 0x2400,
+    Unprepared = 0x2500,
+}
@@ -9,13 +9,26 @@ use super::{
 /// or schema changes.
 ///
 /// ### Variants:
-/// - **Topology**: Represents changes in the network topology, using `TopologyChangeType` to describe the change.
-/// - **Status**: Represents changes in the status of a node, using `StatusNode` to describe the new status.
-/// - **Schema**: Represents schema changes, using `SchemaChangeType` to describe the type of schema change.
-#[derive(Clone)]
-
+/// - **Topology**: Represents changes in the network topology, using `TopologyChangeType` to describe the change, with
+///   the address where the node talks with the clients.
+/// - **Status**: Represents changes in the status of a node, using `StatusNode` to describe the new status, with the
+///   address where the node talks with the clients.
+/// - **Schema**: Represents schema changes, using `SchemaChangeType` to describe the type of schema change, with its
+///   target, `KEYSPACE` or `TABLE`, and the name of the keyspace or of the table as `keyspace.table`.
+#[derive(Debug, Clone, PartialEq)]
 pub enum EventKindChange {
-    Topology(TopologyChangeType),
-    Status(StatusNode),
-    Schema(SchemaChangeType),
+    Topology(TopologyChangeType, String),
+    Status(StatusNode, String),
+    Schema(SchemaChangeType, String, String),
+}
+
+impl EventKindChange {
+    /// This function returns the name of the type of the event, the one that the clients register for.
+    pub fn event_type(&self) -> &'static str {
+        match self {
+            EventKindChange::Topology(..) => "TOPOLOGY_CHANGE",
+            EventKindChange::Status(..) => "STATUS_CHANGE",
+            EventKindChange::Schema(..) => "SCHEMA_CHANGE",
+        }
+    }
 }
@@ -1,4 +1,7 @@
+pub mod batch_type;
 pub mod compression;
+pub mod data_type;
+pub mod error_code;
 pub mod event_kind;
 pub mod query_flags;
 pub mod result_kind;
@@ -6,7 +6,7 @@
 /// ### Variants:
 /// - **Up**: Represents a node that is currently active and operational.
 /// - **Down**: Represents a node that is currently inactive or not operational.
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq)]
 pub enum StatusNode {
     Up,
     Down,
@@ -8,7 +8,7 @@
 /// - **NewNode**: Represents the addition of a new node to the cluster.
 /// - **RemovedNode**: Represents the removal of a node from the cluster.
 /// - **MovedNode**: Represents the movement or reallocation of a node within the cluster.
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum TopologyChangeType {
     NewNode,
     RemovedNode,
@@ -0,0 +1,82 @@
+use super::cql_value::CqlValue;
+use crate::protocol::protocol_body::data_type::DataType;
+
+/// This struct represents the metadata of some columns of a table, like the ones of the rows of a result or the ones
+/// the bind markers of a prepared statement are bound to.
+///
+/// Every column belongs to the same table, so the keyspace and the name of the table are written once, with the flag
+/// `GlobalTablesSpec`, followed by the name and the type of each column.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct ColumnsMetadata {
+    pub keyspace: String,
+    pub table: String,
+    pub columns: Vec<(String, DataType)>,
+}
+
+impl ColumnsMetadata {
+    /// This function creates the metadata of the columns of a table whose name is qualified with its keyspace.
+    pub fn new(table_name: &str, columns: Vec<(String, DataType)>) -> ColumnsMetadata {
+        let (keyspace, table) = table_name.split_once('.').unwrap_or_default();
+        ColumnsMetadata {
+            keyspace: keyspace.to_string(),
+            table: table.to_string(),
+            columns,
+        }
+    }
+
+    /// This function reads every value of the rows, which the nodes store as text, as a value of the type of its
+    /// column. A column with a value that is not valid for its type, because it was written before the type was
+    /// checked, is sent as `Varchar`, so no value is lost.
+    pub fn parse_rows(&mut self, rows: &[Vec<String>]) -> Vec<Vec<CqlValue>> {
+        for (i, (_, data_type)) in self.columns.iter_mut().enumerate() {
+            let valid = rows.iter().all(|row| {
+                row.get(i)
+                    .is_some_and(|text| CqlValue::parse(*data_type, text).is_ok())
+            });
+            if !valid {
+                *data_type = DataType::Varchar;
+            }
+        }
+        rows.iter()
+            .map(|row| {
+                self.columns
+                    .iter()
+                    .zip(row)
+                    .map(|((_, data_type), text)| {
+                        CqlValue::parse(*data_type, text)
+                            .unwrap_or_else(|_| CqlValue::Text(text.to_string()))
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+pub mod test {
+    use super::*;
+
+    #[test]
+    fn test_parse_rows() {
+        let mut metadata = ColumnsMetadata::new(
+            "ks.flights",
+            vec![
+                ("id".to_string(), DataType::Int),
+                ("fuel".to_string(), DataType::Float),
+            ],
+        );
+        let rows = vec![
+            vec!["1".to_string(), "900.5".to_string()],
+            vec!["2".to_string(), "lleno".to_string()],
+        ];
+        assert_eq!(
+            metadata.parse_rows(&rows),
+            vec![
+                vec![CqlValue::Int(1), CqlValue::Text("900.5".to_string())],
+                vec![CqlValue::Int(2), CqlValue::Text("lleno".to_string())],
+            ]
+        );
+        assert_eq!(metadata.keyspace, "ks");
+        assert_eq!(metadata.columns[1].1, DataType::Varchar);
+    }
+}
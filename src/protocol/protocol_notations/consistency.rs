@@ -1,4 +1,4 @@
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Clone, Copy)]
 /// This enum represents the different consistency levels that can be used in Cassandra queries.
 ///
 /// Consistency levels control the number of replicas that must respond to a query before it is considered successful.
@@ -0,0 +1,377 @@
+use super::value::Value;
+use crate::{errors::error_types::ErrorTypes, protocol::protocol_body::data_type::DataType};
+use chrono::{DateTime, NaiveDate, NaiveDateTime};
+use std::{fmt, net::IpAddr};
+
+const DATE_FORMAT: &str = "%Y-%m-%d";
+const TIMESTAMP_FORMAT: &str = "%Y-%m-%d %H:%M:%S";
+const DATE_EPOCH: i64 = 1 << 31;
+/// The text that the nodes store for a column that has no value, like the columns that a row did not have when they
+/// were added to its table. Unlike an empty text, it is null for every type.
+pub const NULL_TEXT: &str = "\u{0}";
+
+/// Represents a value of a column with its type, like the values bound to a prepared statement or the ones of a row.
+///
+/// The nodes store every value as the text it was written with, so a value can be read from that text and written
+/// back to it, and also from and to the binary form that the native protocol uses for its type.
+///
+/// ## Variants:
+/// - `Null`: A column without value.
+/// - `Text`: An `ascii` or `text` value, or one of a type that is sent as text.
+/// - `Bigint`, `Int`, `Smallint` and `Tinyint`: Signed integers of 64, 32, 16 and 8 bits.
+/// - `Double` and `Float`: Floating point numbers of 64 and 32 bits.
+/// - `Boolean`: `true` or `false`.
+/// - `Blob`: Arbitrary bytes, written as text like `0x0a1b`.
+/// - `Timestamp`: The milliseconds since the epoch, written as text like `2024-10-28 10:30:00`.
+/// - `Uuid`: A UUID, written as text like `123e4567-e89b-12d3-a456-426614174000`.
+/// - `Date`: A day, written as text like `2024-10-28`.
+/// - `Inet`: An IPv4 or IPv6 address, written as text like `127.0.0.1`.
+/// - `Map`: A map of texts to texts, written as text like `{'a': '1', 'b': '2'}`.
+/// - `Set`: A set of texts, written as text like `{'a', 'b'}`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum CqlValue {
+    Null,
+    Text(String),
+    Bigint(i64),
+    Int(i32),
+    Smallint(i16),
+    Tinyint(i8),
+    Double(f64),
+    Float(f32),
+    Boolean(bool),
+    Blob(Vec<u8>),
+    Timestamp(i64),
+    Uuid(u128),
+    Date(NaiveDate),
+    Inet(IpAddr),
+    Map(Vec<(String, String)>),
+    Set(Vec<String>),
+}
+
+impl CqlValue {
+    /// This function reads a value of type `data_type` from the text the nodes store. An empty text is a null value,
+    /// unless the type is a text, and `NULL_TEXT` is always a null value.
+    pub fn parse(data_type: DataType, text: &str) -> Result<CqlValue, ErrorTypes> {
+        let invalid = || {
+            ErrorTypes::new(
+                330,
+                format!("Invalid value {} for the type {:?}", text, data_type),
+            )
+        };
+        if text == NULL_TEXT || (text.is_empty() && !is_text(data_type)) {
+            return Ok(CqlValue::Null);
+        }
+        let value = match data_type {
+            DataType::Ascii | DataType::Varchar => CqlValue::Text(text.to_string()),
+            DataType::Bigint => CqlValue::Bigint(text.parse().map_err(|_| invalid())?),
+            DataType::Int => CqlValue::Int(text.parse().map_err(|_| invalid())?),
+            DataType::Smallint => CqlValue::Smallint(text.parse().map_err(|_| invalid())?),
+            DataType::Tinyint => CqlValue::Tinyint(text.parse().map_err(|_| invalid())?),
+            DataType::Double => CqlValue::Double(text.parse().map_err(|_| invalid())?),
+            DataType::Float => CqlValue::Float(text.parse().map_err(|_| invalid())?),
+            DataType::Boolean => {
+                CqlValue::Boolean(text.to_lowercase().parse().map_err(|_| invalid())?)
+            }
+            DataType::Blob => {
+                let hex = text.strip_prefix("0x").ok_or_else(invalid)?;
+                let bytes = (0..hex.len())
+                    .step_by(2)
+                    .map(|i| {
+                        hex.get(i..i + 2)
+                            .and_then(|byte| u8::from_str_radix(byte, 16).ok())
+                    })
+                    .collect::<Option<Vec<u8>>>();
+                CqlValue::Blob(bytes.ok_or_else(invalid)?)
+            }
+            DataType::Timestamp => CqlValue::Timestamp(parse_timestamp(text).ok_or_else(invalid)?),
+            DataType::Uuid => {
+                let hex = text.replace('-', "");
+                if hex.len() != 32 {
+                    return Err(invalid());
+                }
+                CqlValue::Uuid(u128::from_str_radix(&hex, 16).map_err(|_| invalid())?)
+            }
+            DataType::Date => {
+                CqlValue::Date(NaiveDate::parse_from_str(text, DATE_FORMAT).map_err(|_| invalid())?)
+            }
+            DataType::Inet => CqlValue::Inet(text.parse().map_err(|_| invalid())?),
+            DataType::Map => CqlValue::Map(
+                split_collection(text)
+                    .ok_or_else(invalid)?
+                    .iter()
+                    .map(|entry| {
+                        let (key, value) = entry.split_once(':')?;
+                        Some((unquote(key), unquote(value)))
+                    })
+                    .collect::<Option<Vec<(String, String)>>>()
+                    .ok_or_else(invalid)?,
+            ),
+            DataType::Set => CqlValue::Set(
+                split_collection(text)
+                    .ok_or_else(invalid)?
+                    .iter()
+                    .map(|element| unquote(element))
+                    .collect(),
+            ),
+        };
+        Ok(value)
+    }
+
+    /// This function reads a value of type `data_type` from its binary form in the native protocol.
+    pub fn from_value(data_type: DataType, value: &Value) -> Result<CqlValue, ErrorTypes> {
+        let bytes = match value {
+            Value::Normal(bytes) => bytes.as_slice(),
+            Value::Null | Value::NotSet => return Ok(CqlValue::Null),
+        };
+        let invalid =
+            || ErrorTypes::new(329, format!("Invalid bytes for the type {:?}", data_type));
+        let value = match data_type {
+            DataType::Ascii | DataType::Varchar => {
+                CqlValue::Text(String::from_utf8(bytes.to_vec()).map_err(|_| invalid())?)
+            }
+            DataType::Bigint => {
+                CqlValue::Bigint(i64::from_be_bytes(fixed(bytes).ok_or_else(invalid)?))
+            }
+            DataType::Int => CqlValue::Int(i32::from_be_bytes(fixed(bytes).ok_or_else(invalid)?)),
+            DataType::Smallint => {
+                CqlValue::Smallint(i16::from_be_bytes(fixed(bytes).ok_or_else(invalid)?))
+            }
+            DataType::Tinyint => {
+                CqlValue::Tinyint(i8::from_be_bytes(fixed(bytes).ok_or_else(invalid)?))
+            }
+            DataType::Double => {
+                CqlValue::Double(f64::from_be_bytes(fixed(bytes).ok_or_else(invalid)?))
+            }
+            DataType::Float => {
+                CqlValue::Float(f32::from_be_bytes(fixed(bytes).ok_or_else(invalid)?))
+            }
+            DataType::Boolean => match bytes {
+                [byte] => CqlValue::Boolean(*byte != 0),
+                _ => return Err(invalid()),
+            },
+            DataType::Blob => CqlValue::Blob(bytes.to_vec()),
+            DataType::Timestamp => {
+                CqlValue::Timestamp(i64::from_be_bytes(fixed(bytes).ok_or_else(invalid)?))
+            }
+            DataType::Uuid => {
+                CqlValue::Uuid(u128::from_be_bytes(fixed(bytes).ok_or_else(invalid)?))
+            }
+            DataType::Date => {
+                let days =
+                    u32::from_be_bytes(fixed(bytes).ok_or_else(invalid)?) as i64 - DATE_EPOCH;
+                let date = NaiveDate::default().checked_add_signed(chrono::Duration::days(days));
+                CqlValue::Date(date.ok_or_else(invalid)?)
+            }
+            DataType::Inet => match bytes.len() {
+                4 => CqlValue::Inet(IpAddr::from(fixed::<4>(bytes).ok_or_else(invalid)?)),
+                16 => CqlValue::Inet(IpAddr::from(fixed::<16>(bytes).ok_or_else(invalid)?)),
+                _ => return Err(invalid()),
+            },
+            DataType::Map => {
+                let texts = read_texts(bytes, 2).ok_or_else(invalid)?;
+                CqlValue::Map(
+                    texts
+                        .chunks(2)
+                        .map(|entry| (entry[0].clone(), entry[1].clone()))
+                        .collect(),
+                )
+            }
+            DataType::Set => CqlValue::Set(read_texts(bytes, 1).ok_or_else(invalid)?),
+        };
+        Ok(value)
+    }
+
+    /// This function returns the binary form of the value in the native protocol.
+    pub fn to_value(&self) -> Value {
+        let bytes = match self {
+            CqlValue::Null => return Value::Null,
+            CqlValue::Text(text) => text.as_bytes().to_vec(),
+            CqlValue::Bigint(number) | CqlValue::Timestamp(number) => number.to_be_bytes().to_vec(),
+            CqlValue::Int(number) => number.to_be_bytes().to_vec(),
+            CqlValue::Smallint(number) => number.to_be_bytes().to_vec(),
+            CqlValue::Tinyint(number) => number.to_be_bytes().to_vec(),
+            CqlValue::Double(number) => number.to_be_bytes().to_vec(),
+            CqlValue::Float(number) => number.to_be_bytes().to_vec(),
+            CqlValue::Boolean(boolean) => vec![*boolean as u8],
+            CqlValue::Blob(bytes) => bytes.clone(),
+            CqlValue::Uuid(uuid) => uuid.to_be_bytes().to_vec(),
+            CqlValue::Date(date) => {
+                let days = date.signed_duration_since(NaiveDate::default()).num_days();
+                ((days + DATE_EPOCH) as u32).to_be_bytes().to_vec()
+            }
+            CqlValue::Inet(IpAddr::V4(address)) => address.octets().to_vec(),
+            CqlValue::Inet(IpAddr::V6(address)) => address.octets().to_vec(),
+            CqlValue::Map(entries) => {
+                let texts = entries.iter().flat_map(|(key, value)| [key, value]);
+                write_texts(entries.len(), texts)
+            }
+            CqlValue::Set(elements) => write_texts(elements.len(), elements.iter()),
+        };
+        Value::Normal(bytes)
+    }
+}
+
+impl fmt::Display for CqlValue {
+    /// This function writes the value as the text the nodes store.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CqlValue::Null => Ok(()),
+            CqlValue::Text(text) => write!(f, "{}", text),
+            CqlValue::Bigint(number) => write!(f, "{}", number),
+            CqlValue::Int(number) => write!(f, "{}", number),
+            CqlValue::Smallint(number) => write!(f, "{}", number),
+            CqlValue::Tinyint(number) => write!(f, "{}", number),
+            CqlValue::Double(number) => write!(f, "{:?}", number),
+            CqlValue::Float(number) => write!(f, "{:?}", number),
+            CqlValue::Boolean(boolean) => write!(f, "{}", boolean),
+            CqlValue::Blob(bytes) => {
+                write!(f, "0x")?;
+                bytes.iter().try_for_each(|byte| write!(f, "{:02x}", byte))
+            }
+            CqlValue::Timestamp(millis) => match DateTime::from_timestamp_millis(*millis) {
+                Some(timestamp) => write!(f, "{}", timestamp.format(TIMESTAMP_FORMAT)),
+                None => write!(f, "{}", millis),
+            },
+            CqlValue::Uuid(uuid) => {
+                let hex = format!("{:032x}", uuid);
+                write!(
+                    f,
+                    "{}-{}-{}-{}-{}",
+                    &hex[..8],
+                    &hex[8..12],
+                    &hex[12..16],
+                    &hex[16..20],
+                    &hex[20..]
+                )
+            }
+            CqlValue::Date(date) => write!(f, "{}", date.format(DATE_FORMAT)),
+            CqlValue::Inet(address) => write!(f, "{}", address),
+            CqlValue::Map(entries) => {
+                let entries = entries
+                    .iter()
+                    .map(|(key, value)| format!("'{}': '{}'", key, value))
+                    .collect::<Vec<String>>();
+                write!(f, "{{{}}}", entries.join(", "))
+            }
+            CqlValue::Set(elements) => {
+                let elements = elements
+                    .iter()
+                    .map(|element| format!("'{}'", element))
+                    .collect::<Vec<String>>();
+                write!(f, "{{{}}}", elements.join(", "))
+            }
+        }
+    }
+}
+
+/// This function checks if the values of a type are texts, whose empty value is not null.
+fn is_text(data_type: DataType) -> bool {
+    matches!(data_type, DataType::Ascii | DataType::Varchar)
+}
+
+/// This function returns the bytes of a value of fixed length, if they have that length.
+fn fixed<const N: usize>(bytes: &[u8]) -> Option<[u8; N]> {
+    bytes.try_into().ok()
+}
+
+/// This function returns the elements of a collection written as text between braces, like `{'a', 'b'}`.
+fn split_collection(text: &str) -> Option<Vec<&str>> {
+    let elements = text.strip_prefix('{')?.strip_suffix('}')?;
+    Some(
+        elements
+            .split(',')
+            .filter(|element| !element.trim().is_empty())
+            .collect(),
+    )
+}
+
+/// This function returns a text of a collection without its spaces and quotes.
+fn unquote(text: &str) -> String {
+    text.trim().trim_matches('\'').to_string()
+}
+
+/// This function writes the texts of a collection in its binary form: the amount of elements followed by every text
+/// with its length. A map has two texts, its key and its value, for every element.
+fn write_texts<'a>(count: usize, texts: impl Iterator<Item = &'a String>) -> Vec<u8> {
+    let mut bytes = (count as i32).to_be_bytes().to_vec();
+    for text in texts {
+        bytes.extend((text.len() as i32).to_be_bytes());
+        bytes.extend(text.as_bytes());
+    }
+    bytes
+}
+
+/// This function reads the texts of a collection from its binary form, with `per_element` texts for every element.
+fn read_texts(bytes: &[u8], per_element: usize) -> Option<Vec<String>> {
+    let count = i32::from_be_bytes(fixed(bytes.get(..4)?)?) as usize * per_element;
+    let mut rest = &bytes[4..];
+    let mut elements = Vec::new();
+    for _ in 0..count {
+        let len = i32::from_be_bytes(fixed(rest.get(..4)?)?) as usize;
+        let element = rest.get(4..4 + len)?;
+        elements.push(String::from_utf8(element.to_vec()).ok()?);
+        rest = &rest[4 + len..];
+    }
+    Some(elements)
+}
+
+/// This function reads a timestamp written as its milliseconds since the epoch or as a date with its time.
+fn parse_timestamp(text: &str) -> Option<i64> {
+    if let Ok(millis) = text.parse::<i64>() {
+        return Some(millis);
+    }
+    if let Ok(timestamp) = DateTime::parse_from_rfc3339(text) {
+        return Some(timestamp.timestamp_millis());
+    }
+    NaiveDateTime::parse_from_str(text, TIMESTAMP_FORMAT)
+        .ok()
+        .map(|timestamp| timestamp.and_utc().timestamp_millis())
+}
+
+#[cfg(test)]
+pub mod test {
+    use super::*;
+
+    #[test]
+    fn test_cql_value() {
+        let values = [
+            (DataType::Varchar, "Buenos Aires, AR"),
+            (DataType::Int, "-42"),
+            (DataType::Float, "900.5"),
+            (DataType::Boolean, "true"),
+            (DataType::Date, "2024-10-28"),
+            (DataType::Timestamp, "2024-10-28 10:30:00"),
+            (DataType::Uuid, "123e4567-e89b-12d3-a456-426614174000"),
+            (DataType::Blob, "0x0a1b"),
+            (DataType::Inet, "127.0.0.1"),
+            (DataType::Set, "{'-42', '1234'}"),
+            (
+                DataType::Map,
+                "{'class': 'SimpleStrategy', 'replication_factor': '1'}",
+            ),
+        ];
+        for (data_type, text) in values {
+            let value = CqlValue::parse(data_type, text).unwrap();
+            assert_eq!(value.to_string(), text);
+            assert_eq!(
+                CqlValue::from_value(data_type, &value.to_value()).unwrap(),
+                value
+            );
+        }
+        assert_eq!(
+            CqlValue::Date(NaiveDate::from_ymd_opt(1970, 1, 2).unwrap()).to_value(),
+            Value::Normal(vec![0x80, 0, 0, 1])
+        );
+        assert_eq!(CqlValue::parse(DataType::Int, "").unwrap(), CqlValue::Null);
+        assert_eq!(
+            CqlValue::parse(DataType::Varchar, "").unwrap(),
+            CqlValue::Text(String::new())
+        );
+        assert_eq!(
+            CqlValue::parse(DataType::Varchar, NULL_TEXT).unwrap(),
+            CqlValue::Null
+        );
+        assert!(CqlValue::parse(DataType::Int, "1.5").is_err());
+        assert!(CqlValue::from_value(DataType::Int, &Value::Normal(vec![1, 2])).is_err());
+    }
+}
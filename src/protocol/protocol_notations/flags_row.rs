@@ -3,10 +3,12 @@
 /// Flags can be used to indicate special conditions or characteristics of a row in the result set.
 /// Each flag corresponds to a specific condition, represented by a bitmask value.
 ///
+/// - `GlobalTablesSpec`: Indicates that every column belongs to the same table, whose keyspace and name are written once.
 /// - `HasMorePages`: Indicates that there are more pages of data available, meaning the result set is paginated.
 /// - `NoMetadata`: Specifies that the row does not contain metadata information (such as column names or types).
 #[derive(Debug, PartialEq)]
 pub enum FlagsRow {
+    GlobalTablesSpec = 0x0001,
     HasMorePages = 0x0002,
     NoMetadata = 0x0004,
 }
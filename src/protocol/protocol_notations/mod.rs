@@ -1,6 +1,9 @@
 pub mod bytes_map;
+pub mod columns_metadata;
 pub mod consistency;
+pub mod cql_value;
 pub mod flags_row;
+pub mod paging_state;
 pub mod protocol_body_writer;
 pub mod result;
 pub mod value;
@@ -0,0 +1,28 @@
+use crate::errors::error_types::ErrorTypes;
+use serde::{Deserialize, Serialize};
+
+/// This struct represents the position where the next page of the result of a select starts. The client receives it
+/// with a page that has more pages after it, and sends it back without reading it to get the next one.
+///
+/// ### Fields:
+/// - `key`: The values of the partition key and the clustering key of the last row of the page, in the order of the
+///   primary key of the table.
+/// - `returned`: The amount of rows that the previous pages returned, to apply the limit of the select to every page.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PagingState {
+    pub key: Vec<String>,
+    pub returned: usize,
+}
+
+impl PagingState {
+    /// This function returns the paging state as the opaque bytes that the client sends back.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        serde_json::to_vec(self).unwrap_or_default()
+    }
+
+    /// This function reads the paging state from the bytes that the client sent back.
+    pub fn from_bytes(bytes: &[u8]) -> Result<PagingState, ErrorTypes> {
+        serde_json::from_slice(bytes)
+            .map_err(|_| ErrorTypes::new(336, "Invalid paging state".to_string()))
+    }
+}
@@ -1,12 +1,27 @@
-use std::vec;
+use std::{
+    net::{IpAddr, ToSocketAddrs},
+    vec,
+};
 
 use super::{
-    frames_headers::{flags, header::Header, opcode::Opcode, version::Version},
+    frames_headers::{header::Header, opcode::Opcode, version::Version},
     protocol_body::{
-        compression::Compression, query_flags::QueryFlags, result_kind::ResultKind,
+        batch_type::{BatchQueryKind, BatchType},
+        compression::Compression,
+        data_type::DataType,
+        error_code::ErrorCode,
+        event_kind::EventKindChange,
+        query_flags::QueryFlags,
+        result_kind::ResultKind,
         schema_change::SchemaChangeType,
+        status_node::StatusNode,
+        topology_change::TopologyChangeType,
+    },
+    protocol_notations::{
+        columns_metadata::ColumnsMetadata, consistency, cql_value::CqlValue,
+        protocol_body_writer::ProtocolBody, value::Value,
     },
-    protocol_notations::{consistency, protocol_body_writer::ProtocolBody},
+    query_parser::syntax_error::SyntaxError,
 };
 
 use crate::{
@@ -14,6 +29,9 @@ use crate::{
     protocol::protocol_notations::flags_row::FlagsRow,
 };
 
+/// The stream of the Event messages, that do not answer a request of the client.
+pub const EVENT_STREAM: u16 = u16::MAX;
+
 pub struct Protocol {
     header: Header,
     body: ProtocolBody,
@@ -51,32 +69,45 @@ impl Protocol {
     pub fn set_compress_algorithm(&mut self, compression: Option<Compression>) {
         self.compression = compression;
     }
+
+    /// This function sets the version of the native protocol of the frame, the one that the connection negotiated.
+    pub fn set_protocol_version(&mut self, version: u8) {
+        self.header.set_protocol_version(version);
+    }
+
+    /// This function sets the stream of the frame. A response has the stream of its request, so the client can match
+    /// them when it sends several requests without waiting for their responses.
+    pub fn set_stream(&mut self, stream: u16) {
+        self.header.set_stream(stream);
+    }
+    /// This function returns the frame, with its body compressed if it has the `Compression` flag. The length of the
+    /// header is the one of the body that is sent, after compressing it.
     pub fn get_binary(&mut self) -> Vec<u8> {
-        let mut binary: Vec<u8> = Vec::new();
-        binary.append(&mut self.header.get_binary());
-        self.length = binary.len() as i32;
+        let mut body = self.body.get_binary();
+        let compressed = self.header.get_flag().contains(&Flags::Compression);
         match &self.compression {
-            Some(compres) => {
-                let mut body = self.body.get_binary();
-                let len = body.len();
-
-                if !body.is_empty() {
-                    let mut compressed = compres.compression(body).unwrap();
-                    let bytes: [u8; 4] = len.to_be_bytes()[4..].try_into().unwrap();
-                    binary.extend_from_slice(&bytes);
-                    binary.append(&mut compressed);
-                } else {
-                    binary.append(&mut body);
-                }
+            Some(compression) if compressed && !body.is_empty() => {
+                body = compression.compression(body).unwrap();
             }
-            None => {
-                binary.append(&mut self.body.get_binary());
+            _ if compressed => {
+                let flags = self.header.get_flag().clone();
+                self.header.set_flag(
+                    flags
+                        .into_iter()
+                        .filter(|flag| *flag != Flags::Compression)
+                        .collect(),
+                );
             }
+            _ => {}
         }
+        self.header.set_length(body.len() as i32);
+        let mut binary: Vec<u8> = self.header.get_binary();
+        self.length = binary.len() as i32;
+        binary.append(&mut body);
         binary
     }
 
-    /// This function writes the StartUp message
+    /// This function writes the StartUp message. Its body is never compressed, it chooses the compression of the next ones.
     pub fn write_startup(&mut self, compression: Option<Compression>) -> Result<(), ErrorTypes> {
         self.header.set_version(Version::Request);
         self.header.set_opcode(Opcode::StartUp);
@@ -87,20 +118,38 @@ impl Protocol {
                 Compression::LZ4 => "lz4",
             };
             vec.push((String::from("COMPRESSION"), String::from(compression)));
-            self.header.set_flag(vec![flags::Flags::Compression]);
         };
         self.body.write_string_map(vec)?;
         self.header.set_length(self.body.get_length() as i32);
         Ok(())
     }
 
-    /// This function writes the Auth_Response message
+    /// This function writes the Options message, that asks the server for the options it supports.
+    pub fn write_options(&mut self) {
+        self.header.set_version(Version::Request);
+        self.header.set_opcode(Opcode::Options);
+        self.header.set_length(self.body.get_length() as i32);
+    }
+
+    /// This function writes the Register message, with the types of the events that the client wants to receive.
+    pub fn write_register(&mut self, events: Vec<String>) -> Result<(), ErrorTypes> {
+        self.header.set_version(Version::Request);
+        self.header.set_opcode(Opcode::Register);
+        self.set_compression();
+        self.body.write_string_list(events)?;
+        self.header.set_length(self.body.get_length() as i32);
+        Ok(())
+    }
+
+    /// This function writes the Auth_Response message, with the SASL token of the PasswordAuthenticator: the user and
+    /// the password, each one after a zero byte.
     pub fn write_auth_response(&mut self, user: (String, String)) -> Result<(), ErrorTypes> {
         self.header.set_version(Version::Request);
         self.header.set_opcode(Opcode::AuthResponse);
         self.set_compression();
-        let mut user_password = user.0.into_bytes();
-        user_password.push(b',');
+        let mut user_password = vec![0];
+        user_password.extend_from_slice(user.0.as_bytes());
+        user_password.push(0);
         user_password.extend_from_slice(user.1.as_bytes());
         let len = user_password.len() as i32;
         self.body.write_bytes(user_password, len)?;
@@ -115,21 +164,112 @@ impl Protocol {
         query: &str,
         consistency: consistency::Consistency,
         flags: Vec<QueryFlags>,
+    ) -> Result<(), ErrorTypes> {
+        self.write_query_body(query, consistency, flags, None)
+    }
+
+    /// This function writes the body of a Query message that runs in `keyspace` instead of the keyspace in use of the connection
+    pub fn write_query_in_keyspace(
+        &mut self,
+        query: &str,
+        consistency: consistency::Consistency,
+        flags: Vec<QueryFlags>,
+        keyspace: &str,
+    ) -> Result<(), ErrorTypes> {
+        self.write_query_body(query, consistency, flags, Some(keyspace))
+    }
+
+    fn write_query_body(
+        &mut self,
+        query: &str,
+        consistency: consistency::Consistency,
+        flags: Vec<QueryFlags>,
+        keyspace: Option<&str>,
     ) -> Result<(), ErrorTypes> {
         self.header.set_version(Version::Request);
         self.set_compression();
         self.header.set_opcode(Opcode::Query);
         self.body.write_long_string(query.to_string())?;
-        self.body.write_consistency(consistency);
-        self.body.write_byte(flags.iter().map(|x| *x as u8).sum());
-
-        for flag in flags.iter() {
-            write_flag(
-                &mut self.body,
-                flag,
-                Some(vec![QueryFlags::SkipMetadata as u8]),
-            )?;
+        write_query_parameters(&mut self.body, consistency, flags, &[], None, keyspace)?;
+        self.header.set_length(self.body.get_length() as i32);
+        Ok(())
+    }
+
+    /// This function writes the body of a Prepare message, which prepares a query with bind markers `?` to execute it
+    /// later.
+    pub fn write_prepare(&mut self, query: &str) -> Result<(), ErrorTypes> {
+        self.header.set_version(Version::Request);
+        self.set_compression();
+        self.header.set_opcode(Opcode::Prepare);
+        self.body.write_long_string(query.to_string())?;
+        self.header.set_length(self.body.get_length() as i32);
+        Ok(())
+    }
+
+    /// This function writes the body of an Execute message, which executes the prepared statement `id` with `values`
+    /// bound to its bind markers, in their order.
+    pub fn write_execute(
+        &mut self,
+        id: &[u8],
+        consistency: consistency::Consistency,
+        values: &[CqlValue],
+    ) -> Result<(), ErrorTypes> {
+        self.write_execute_body(id, consistency, values, None)
+    }
+
+    /// This function writes the body of an Execute message of a select whose result is returned in pages of
+    /// `page_size` rows. The paging state is the one of the previous page, to get the page after it, or none to get
+    /// the first one.
+    pub fn write_execute_page(
+        &mut self,
+        id: &[u8],
+        consistency: consistency::Consistency,
+        values: &[CqlValue],
+        page_size: i32,
+        paging_state: Option<&[u8]>,
+    ) -> Result<(), ErrorTypes> {
+        self.write_execute_body(id, consistency, values, Some((page_size, paging_state)))
+    }
+
+    fn write_execute_body(
+        &mut self,
+        id: &[u8],
+        consistency: consistency::Consistency,
+        values: &[CqlValue],
+        page: Option<(i32, Option<&[u8]>)>,
+    ) -> Result<(), ErrorTypes> {
+        self.header.set_version(Version::Request);
+        self.set_compression();
+        self.header.set_opcode(Opcode::Execute);
+        self.body.write_short_bytes(id.to_vec())?;
+        write_query_parameters(&mut self.body, consistency, vec![], values, page, None)?;
+        self.header.set_length(self.body.get_length() as i32);
+        Ok(())
+    }
+
+    /// This function writes the body of a Batch message, which executes the prepared statements of `statements`, each
+    /// one with its values. A logged batch is applied completely even if the coordinator fails in the middle of it.
+    pub fn write_batch(
+        &mut self,
+        logged: bool,
+        statements: &[(Vec<u8>, Vec<CqlValue>)],
+        consistency: consistency::Consistency,
+    ) -> Result<(), ErrorTypes> {
+        self.header.set_version(Version::Request);
+        self.set_compression();
+        self.header.set_opcode(Opcode::Batch);
+        self.body.write_byte(match logged {
+            true => BatchType::Logged as u8,
+            false => BatchType::Unlogged as u8,
+        });
+        self.body.write_short(statements.len() as u16);
+        for (id, values) in statements {
+            self.body.write_byte(BatchQueryKind::Prepared as u8);
+            self.body.write_short_bytes(id.to_vec())?;
+            write_values(&mut self.body, values)?;
         }
+        self.body.write_consistency(consistency);
+        self.body.write_byte(0);
         self.header.set_length(self.body.get_length() as i32);
         Ok(())
     }
@@ -146,6 +286,70 @@ impl Protocol {
         Ok(())
     }
 
+    /// This function writes the body of an AlreadyExists error, reported when a keyspace or table is created twice.
+    /// The name of the table is empty when the keyspace is the one that already exists.
+    pub fn write_already_exists(&mut self, keyspace: &str, table: &str) -> Result<(), ErrorTypes> {
+        let message = match table {
+            "" => format!("Keyspace {} already exists", keyspace),
+            _ => format!("Table {}.{} already exists", keyspace, table),
+        };
+        self.write_error(ErrorCode::AlreadyExists as i32, &message)?;
+        self.body.write_string(keyspace.to_string())?;
+        self.body.write_string(table.to_string())?;
+        self.header.set_length(self.body.get_length() as i32);
+        Ok(())
+    }
+
+    /// This function writes the body of an Unavailable error, reported when there are not enough live nodes
+    /// for the consistency level. It has the consistency, the amount of nodes required and the amount that are alive.
+    pub fn write_unavailable(
+        &mut self,
+        message: &str,
+        consistency: consistency::Consistency,
+        required: i32,
+        alive: i32,
+    ) -> Result<(), ErrorTypes> {
+        self.write_error(ErrorCode::Unavailable as i32, message)?;
+        self.body.write_short(consistency as u16);
+        self.body.write_int(required);
+        self.body.write_int(alive);
+        self.header.set_length(self.body.get_length() as i32);
+        Ok(())
+    }
+
+    /// This function writes the body of a WriteTimeout error, reported when the replicas did not acknowledge a write in time.
+    /// It has the consistency, the amount of replicas that acknowledged it, the amount that were needed and the type of write.
+    pub fn write_write_timeout(
+        &mut self,
+        message: &str,
+        consistency: consistency::Consistency,
+        (received, block_for): (i32, i32),
+        write_type: &str,
+    ) -> Result<(), ErrorTypes> {
+        self.write_error(ErrorCode::WriteTimeout as i32, message)?;
+        self.body.write_short(consistency as u16);
+        self.body.write_int(received);
+        self.body.write_int(block_for);
+        self.body.write_string(write_type.to_string())?;
+        self.header.set_length(self.body.get_length() as i32);
+        Ok(())
+    }
+
+    /// This function writes the body of an Unprepared error, reported when a client executes a statement that the node
+    /// does not have, with the identifier of the statement. The client has to prepare it again.
+    pub fn write_unprepared(&mut self, id: &[u8]) -> Result<(), ErrorTypes> {
+        self.write_error(ErrorCode::Unprepared as i32, "Prepared statement not found")?;
+        self.body.write_short_bytes(id.to_vec())?;
+        self.header.set_length(self.body.get_length() as i32);
+        Ok(())
+    }
+
+    /// This function writes the body of a SyntaxError error, reported when a query can not be parsed. The message has
+    /// the line and the column of the token where the error was found, the token and the tokens that were expected.
+    pub fn write_syntax_error(&mut self, error: &SyntaxError) -> Result<(), ErrorTypes> {
+        self.write_error(ErrorCode::SyntaxError as i32, &error.to_string())
+    }
+
     /// This function writes the body of Ready message
     pub fn write_ready(&mut self) {
         self.header.set_version(Version::Response);
@@ -163,19 +367,66 @@ impl Protocol {
         Ok(())
     }
 
-    /// This function writes the body of an AuthSuccess message
-    pub fn write_auth_success(&mut self) {
+    /// This function writes the body of a Supported message, with the values that the server supports for every option.
+    pub fn write_supported(
+        &mut self,
+        options: Vec<(String, Vec<String>)>,
+    ) -> Result<(), ErrorTypes> {
+        self.header.set_version(Version::Response);
+        self.header.set_opcode(Opcode::Supported);
+        self.body.write_string_multimap(options)?;
+        self.header.set_length(self.body.get_length() as i32);
+        Ok(())
+    }
+
+    /// This function writes the body of an AuthSuccess message, whose final SASL token is null.
+    pub fn write_auth_success(&mut self) -> Result<(), ErrorTypes> {
         self.header.set_version(Version::Response);
         self.set_compression();
         self.header.set_opcode(Opcode::AuthSuccess);
+        self.body.write_bytes(vec![], -1)?;
         self.header.set_length(self.body.get_length() as i32);
+        Ok(())
     }
 
-    /// This function writes the body of a Result message
+    /// This function writes an Event message, that the server pushes to the clients that registered for its type. It
+    /// has the stream -1, since it does not answer any request.
+    pub fn write_event(&mut self, event: &EventKindChange) -> Result<(), ErrorTypes> {
+        self.header.set_version(Version::Response);
+        self.set_compression();
+        self.header.set_opcode(Opcode::Event);
+        self.header.set_stream(EVENT_STREAM);
+        self.body.write_string(event.event_type().to_string())?;
+        match event {
+            EventKindChange::Topology(change, address) => {
+                let change = match change {
+                    TopologyChangeType::NewNode => "NEW_NODE",
+                    TopologyChangeType::RemovedNode => "REMOVED_NODE",
+                    TopologyChangeType::MovedNode => "MOVED_NODE",
+                };
+                self.body.write_string(change.to_string())?;
+                write_event_address(&mut self.body, address)?;
+            }
+            EventKindChange::Status(status, address) => {
+                let status = match status {
+                    StatusNode::Up => "UP",
+                    StatusNode::Down => "DOWN",
+                };
+                self.body.write_string(status.to_string())?;
+                write_event_address(&mut self.body, address)?;
+            }
+            EventKindChange::Schema(change_type, target, options) => {
+                write_schema_change(&mut self.body, change_type, target.to_string(), options)?;
+            }
+        }
+        self.header.set_length(self.body.get_length() as i32);
+        Ok(())
+    }
+
+    /// This function writes the body of a Result message. The Rows results are written with `write_rows`.
     pub fn write_result(
         &mut self,
         result_kind: ResultKind,
-        values: Option<Vec<Vec<String>>>,
         keyspace: Option<&str>,
         schema_change: Option<SchemaChangeType>,
         target: Option<String>,
@@ -189,7 +440,6 @@ impl Protocol {
         write_result_kind(
             &mut self.body,
             result_kind,
-            values,
             keyspace,
             schema_change,
             target,
@@ -199,6 +449,77 @@ impl Protocol {
         self.header.set_length(self.body.get_length() as i32);
     }
 
+    /// This function writes the body of a Rows result, with the keyspace, the table, the names and the types of the
+    /// columns once, unless the client asked to skip them, followed by the rows with every value in its binary form.
+    /// The paging state is the position where the next page starts, if there are more rows after these.
+    pub fn write_rows(
+        &mut self,
+        metadata: &ColumnsMetadata,
+        rows: &[Vec<CqlValue>],
+        skip_metadata: bool,
+        paging_state: Option<&[u8]>,
+    ) -> Result<(), ErrorTypes> {
+        self.header.set_version(Version::Response);
+        self.set_compression();
+        self.header.set_opcode(Opcode::Result);
+        self.body.write_int(ResultKind::Rows as i32);
+        let mut flags = match skip_metadata {
+            true => FlagsRow::NoMetadata as i32,
+            false => FlagsRow::GlobalTablesSpec as i32,
+        };
+        if paging_state.is_some() {
+            flags |= FlagsRow::HasMorePages as i32;
+        }
+        self.body.write_int(flags);
+        self.body.write_int(metadata.columns.len() as i32);
+        if let Some(paging_state) = paging_state {
+            self.body
+                .write_bytes(paging_state.to_vec(), paging_state.len() as i32)?;
+        }
+        if !skip_metadata {
+            write_columns_metadata(&mut self.body, metadata)?;
+        }
+        self.body.write_int(rows.len() as i32);
+        for row in rows {
+            for value in row {
+                match value.to_value() {
+                    Value::Normal(bytes) => {
+                        self.body.write_bytes(bytes.clone(), bytes.len() as i32)?
+                    }
+                    _ => self.body.write_bytes(vec![], -1)?,
+                }
+            }
+        }
+        self.header.set_length(self.body.get_length() as i32);
+        Ok(())
+    }
+
+    /// This function writes the body of a Prepared result, with the identifier of the statement and the metadata of its
+    /// bind markers. The statement does not send the metadata of its rows, which comes with every result.
+    pub fn write_prepared(
+        &mut self,
+        id: &[u8],
+        pk_indexes: &[u16],
+        bind_markers: &ColumnsMetadata,
+    ) -> Result<(), ErrorTypes> {
+        self.header.set_version(Version::Response);
+        self.set_compression();
+        self.header.set_opcode(Opcode::Result);
+        self.body.write_int(ResultKind::Prepared as i32);
+        self.body.write_short_bytes(id.to_vec())?;
+        self.body.write_int(FlagsRow::GlobalTablesSpec as i32);
+        self.body.write_int(bind_markers.columns.len() as i32);
+        self.body.write_int(pk_indexes.len() as i32);
+        for index in pk_indexes {
+            self.body.write_short(*index);
+        }
+        write_columns_metadata(&mut self.body, bind_markers)?;
+        self.body.write_int(FlagsRow::NoMetadata as i32);
+        self.body.write_int(0);
+        self.header.set_length(self.body.get_length() as i32);
+        Ok(())
+    }
+
     fn set_compression(&mut self) {
         if self.compression.is_some() {
             self.header.set_flag(vec![Flags::Compression]);
@@ -210,60 +531,24 @@ impl Protocol {
 fn write_result_kind(
     body: &mut ProtocolBody,
     result_kind: ResultKind,
-    values: Option<Vec<Vec<String>>>,
     keyspace: Option<&str>,
     schema_change: Option<SchemaChangeType>,
     schema_change_target: Option<String>,
     schema_change_options: Option<&String>,
 ) -> Result<(), ErrorTypes> {
-    if let ResultKind::Rows = result_kind {
-        let values = match values {
-            Some(v) => v,
-            None => {
-                return Err(ErrorTypes::new(
-                    404,
-                    "Error in values while writing result".to_string(),
-                ))
-            }
-        };
-        body.write_int(FlagsRow::NoMetadata as i32);
-        let len = values[0].len() as i32;
-        body.write_int(len);
-        body.write_int(values.len() as i32);
-        for row in values.iter() {
-            for column in row.iter() {
-                let column = column.as_bytes();
-                body.write_bytes(column.to_vec(), column.len() as i32)?;
-            }
-        }
-    } else if let ResultKind::SetKeyspace = result_kind {
+    if let ResultKind::SetKeyspace = result_kind {
         body.write_string(keyspace.unwrap().to_string())?
     } else if let ResultKind::SchemaChange = result_kind {
-        match schema_change {
-            Some(change_type) => {
-                let change_type = match change_type {
-                    SchemaChangeType::Created => "CREATED",
-                    SchemaChangeType::Updated => "UPDATED",
-                    SchemaChangeType::Dropped => "DROPPED",
-                };
-                body.write_string(change_type.to_string())?;
-            }
-            None => {
-                return Err(ErrorTypes::new(405, "Error writing result".to_string()));
-            }
-        }
-        match schema_change_target {
-            Some(target) => body.write_string(target)?,
-            None => {
-                return Err(ErrorTypes::new(406, "Error writing result".to_string()));
-            }
-        }
-        match schema_change_options {
-            Some(options) => body.write_string(options.to_string())?,
-            None => {
-                return Err(ErrorTypes::new(407, "Error writing result".to_string()));
-            }
-        }
+        let Some(change_type) = schema_change else {
+            return Err(ErrorTypes::new(405, "Error writing result".to_string()));
+        };
+        let Some(target) = schema_change_target else {
+            return Err(ErrorTypes::new(406, "Error writing result".to_string()));
+        };
+        let Some(options) = schema_change_options else {
+            return Err(ErrorTypes::new(407, "Error writing result".to_string()));
+        };
+        write_schema_change(body, &change_type, target, options)?;
     } else if let ResultKind::Void = result_kind {
         //No body
     } else {
@@ -272,14 +557,123 @@ fn write_result_kind(
     Ok(())
 }
 
-/// This private function writes the flags of a Query message
-fn write_flag(
+/// This function writes a schema change, the one of a SchemaChange result or of a SCHEMA_CHANGE event: its type, its
+/// target and the keyspace, followed by the table if the target is not the keyspace.
+fn write_schema_change(
     body: &mut ProtocolBody,
-    flag: &QueryFlags,
-    _values: Option<Vec<u8>>,
+    change_type: &SchemaChangeType,
+    target: String,
+    options: &str,
 ) -> Result<(), ErrorTypes> {
-    if let QueryFlags::SkipMetadata = flag {
-        body.write_byte(QueryFlags::SkipMetadata as u8)
+    let change_type = match change_type {
+        SchemaChangeType::Created => "CREATED",
+        SchemaChangeType::Updated => "UPDATED",
+        SchemaChangeType::Dropped => "DROPPED",
+    };
+    body.write_string(change_type.to_string())?;
+    body.write_string(target)?;
+    for name in options.split('.') {
+        body.write_string(name.to_string())?
+    }
+    Ok(())
+}
+
+/// This function writes the client address of a node of an event, written as `ip:port` or as `host:port`, in which
+/// case the host is resolved to its first IP.
+fn write_event_address(body: &mut ProtocolBody, address: &str) -> Result<(), ErrorTypes> {
+    let invalid = || ErrorTypes::new(106, "Inet Address is invalid".to_string());
+    let address = address
+        .to_socket_addrs()
+        .ok()
+        .and_then(|mut addresses| addresses.next())
+        .ok_or_else(invalid)?;
+    let port = address.port() as i32;
+    let ip = match address.ip() {
+        IpAddr::V4(ip) => ip.octets().to_vec(),
+        IpAddr::V6(ip) => ip.octets().to_vec(),
+    };
+    body.write_inet(ip, port)
+}
+
+/// This function writes the consistency and the flags of a Query or Execute message, followed by the values of the
+/// flags. The `Values` and `Keyspace` flags are added when there are values or a keyspace.
+fn write_query_parameters(
+    body: &mut ProtocolBody,
+    consistency: consistency::Consistency,
+    mut flags: Vec<QueryFlags>,
+    values: &[CqlValue],
+    page: Option<(i32, Option<&[u8]>)>,
+    keyspace: Option<&str>,
+) -> Result<(), ErrorTypes> {
+    body.write_consistency(consistency);
+    flags.retain(|flag| {
+        !matches!(
+            flag,
+            QueryFlags::Values
+                | QueryFlags::PageSize
+                | QueryFlags::PagingState
+                | QueryFlags::Keyspace
+        )
+    });
+    if !values.is_empty() {
+        flags.push(QueryFlags::Values);
+    }
+    if let Some((_, paging_state)) = page {
+        flags.push(QueryFlags::PageSize);
+        if paging_state.is_some() {
+            flags.push(QueryFlags::PagingState);
+        }
+    }
+    if keyspace.is_some() {
+        flags.push(QueryFlags::Keyspace);
+    }
+    body.write_byte(flags.iter().fold(0, |acc, flag| acc | *flag as u8));
+    if !values.is_empty() {
+        write_values(body, values)?;
+    }
+    if let Some((page_size, paging_state)) = page {
+        body.write_int(page_size);
+        if let Some(paging_state) = paging_state {
+            body.write_bytes(paging_state.to_vec(), paging_state.len() as i32)?;
+        }
+    }
+    if let Some(keyspace) = keyspace {
+        body.write_string(keyspace.to_string())?;
+    }
+    Ok(())
+}
+
+/// This function writes the amount of values followed by each one, where a null value has length -1.
+fn write_values(body: &mut ProtocolBody, values: &[CqlValue]) -> Result<(), ErrorTypes> {
+    body.write_short(values.len() as u16);
+    for value in values {
+        match value.to_value() {
+            Value::Normal(bytes) => body.write_value(bytes.clone(), bytes.len() as i32)?,
+            Value::Null => body.write_value(vec![], -1)?,
+            Value::NotSet => body.write_value(vec![], -2)?,
+        }
+    }
+    Ok(())
+}
+
+/// This function writes the keyspace and the table of some columns once, followed by the name and the type of each one.
+fn write_columns_metadata(
+    body: &mut ProtocolBody,
+    metadata: &ColumnsMetadata,
+) -> Result<(), ErrorTypes> {
+    body.write_string(metadata.keyspace.clone())?;
+    body.write_string(metadata.table.clone())?;
+    for (name, data_type) in &metadata.columns {
+        body.write_string(name.clone())?;
+        body.write_short(*data_type as u16);
+        match data_type {
+            DataType::Set => body.write_short(DataType::Varchar as u16),
+            DataType::Map => {
+                body.write_short(DataType::Varchar as u16);
+                body.write_short(DataType::Varchar as u16);
+            }
+            _ => {}
+        }
     }
     Ok(())
 }
@@ -287,6 +681,11 @@ fn write_flag(
 #[cfg(test)]
 pub mod test {
     use super::*;
+    use crate::receiver::{
+        batch_statement::BatchStatement, message::Message, receiver_impl::receive_message,
+        request_message::RequestMessage, response_message::ResponseMessage,
+        result_response::ResultResponse,
+    };
 
     #[test]
     fn test_write_startup() {
@@ -305,4 +704,326 @@ pub mod test {
         );
         assert_eq!(res, Ok(()));
     }
+
+    #[test]
+    fn test_write_query_in_keyspace() {
+        let mut protocol = Protocol::new();
+        protocol
+            .write_query_in_keyspace(
+                "SELECT * FROM users;",
+                consistency::Consistency::One,
+                vec![QueryFlags::SkipMetadata],
+                "flights_keyspace",
+            )
+            .unwrap();
+        let message = receive_message(&mut protocol.get_binary(), &None).unwrap();
+        match message {
+            Message::SolicitationMessage(RequestMessage::Query(_, _, parameters)) => {
+                assert_eq!(parameters.keyspace, Some("flights_keyspace".to_string()))
+            }
+            _ => panic!("Unexpected message"),
+        }
+    }
+
+    #[test]
+    fn test_write_execute() {
+        let mut protocol = Protocol::new();
+        let values = [
+            CqlValue::Int(3),
+            CqlValue::Text("ON TIME".to_string()),
+            CqlValue::Null,
+        ];
+        protocol
+            .write_execute(&[1, 2, 3], consistency::Consistency::Quorum, &values)
+            .unwrap();
+        let message = receive_message(&mut protocol.get_binary(), &None).unwrap();
+        match message {
+            Message::SolicitationMessage(RequestMessage::Execute(id, parameters)) => {
+                assert_eq!(id, vec![1, 2, 3]);
+                assert_eq!(parameters.consistency, consistency::Consistency::Quorum);
+                assert_eq!(
+                    parameters.values,
+                    values
+                        .iter()
+                        .map(CqlValue::to_value)
+                        .collect::<Vec<Value>>()
+                );
+            }
+            _ => panic!("Unexpected message"),
+        }
+    }
+
+    #[test]
+    fn test_write_execute_page() {
+        let mut protocol = Protocol::new();
+        protocol
+            .write_execute_page(
+                &[1],
+                consistency::Consistency::One,
+                &[CqlValue::Text("EZE".to_string())],
+                50,
+                Some(b"state"),
+            )
+            .unwrap();
+        let message = receive_message(&mut protocol.get_binary(), &None).unwrap();
+        match message {
+            Message::SolicitationMessage(RequestMessage::Execute(_, parameters)) => {
+                assert_eq!(parameters.values.len(), 1);
+                assert_eq!(parameters.page_size, Some(50));
+                assert_eq!(parameters.paging_state, Some(b"state".to_vec()));
+            }
+            _ => panic!("Unexpected message"),
+        }
+    }
+
+    #[test]
+    fn test_write_rows() {
+        let mut protocol = Protocol::new();
+        let metadata = ColumnsMetadata::new(
+            "ks.flights",
+            vec![
+                ("id".to_string(), DataType::Int),
+                ("status".to_string(), DataType::Varchar),
+            ],
+        );
+        let rows = vec![vec![CqlValue::Int(1), CqlValue::Null]];
+        protocol
+            .write_rows(&metadata, &rows, false, Some(b"next"))
+            .unwrap();
+        let message = receive_message(&mut protocol.get_binary(), &None).unwrap();
+        match message {
+            Message::ReplyMessage(ResponseMessage::Result {
+                kind:
+                    ResultResponse::Rows {
+                        metadata: received_metadata,
+                        rows: received,
+                        paging_state,
+                    },
+            }) => {
+                assert_eq!(received_metadata, Some(metadata.clone()));
+                assert_eq!(received, rows);
+                assert_eq!(paging_state, Some(b"next".to_vec()));
+            }
+            _ => panic!("Unexpected message"),
+        }
+
+        let mut protocol = Protocol::new();
+        protocol.write_rows(&metadata, &rows, true, None).unwrap();
+        let message = receive_message(&mut protocol.get_binary(), &None).unwrap();
+        match message {
+            Message::ReplyMessage(ResponseMessage::Result {
+                kind:
+                    ResultResponse::Rows {
+                        metadata,
+                        rows,
+                        paging_state,
+                    },
+            }) => {
+                assert!(metadata.is_none());
+                assert_eq!(
+                    rows,
+                    vec![vec![CqlValue::Blob(vec![0, 0, 0, 1]), CqlValue::Null]]
+                );
+                assert!(paging_state.is_none());
+            }
+            _ => panic!("Unexpected message"),
+        }
+    }
+
+    #[test]
+    fn test_write_batch() {
+        let mut protocol = Protocol::new();
+        let statements = [
+            (vec![1], vec![CqlValue::Text("DELAYED".to_string())]),
+            (vec![2], vec![CqlValue::Int(3), CqlValue::Null]),
+        ];
+        protocol
+            .write_batch(true, &statements, consistency::Consistency::Quorum)
+            .unwrap();
+        let message = receive_message(&mut protocol.get_binary(), &None).unwrap();
+        match message {
+            Message::SolicitationMessage(RequestMessage::Batch(logged, batch, parameters)) => {
+                assert!(logged);
+                assert_eq!(parameters.consistency, consistency::Consistency::Quorum);
+                assert_eq!(batch.len(), 2);
+                for ((statement, values), (id, expected)) in batch.iter().zip(statements.iter()) {
+                    assert!(
+                        matches!(statement, BatchStatement::Prepared(statement_id) if statement_id == id)
+                    );
+                    assert_eq!(
+                        *values,
+                        expected
+                            .iter()
+                            .map(CqlValue::to_value)
+                            .collect::<Vec<Value>>()
+                    );
+                }
+            }
+            _ => panic!("Unexpected message"),
+        }
+    }
+
+    #[test]
+    fn test_write_prepared() {
+        let mut protocol = Protocol::new();
+        let bind_markers = ColumnsMetadata::new(
+            "flights_keyspace.arrivals",
+            vec![
+                ("status".to_string(), DataType::Varchar),
+                ("destination".to_string(), DataType::Varchar),
+            ],
+        );
+        protocol
+            .write_prepared(&[7; 16], &[1], &bind_markers)
+            .unwrap();
+        let message = receive_message(&mut protocol.get_binary(), &None).unwrap();
+        match message {
+            Message::ReplyMessage(ResponseMessage::Result {
+                kind:
+                    ResultResponse::Prepared {
+                        id,
+                        pk_indexes,
+                        bind_markers: metadata,
+                    },
+            }) => {
+                assert_eq!(id, vec![7; 16]);
+                assert_eq!(pk_indexes, vec![1]);
+                assert_eq!(metadata, bind_markers);
+            }
+            _ => panic!("Unexpected message"),
+        }
+    }
+
+    #[test]
+    fn test_write_already_exists() {
+        let mut protocol = Protocol::new();
+        protocol
+            .write_already_exists("flights_keyspace", "arrivals")
+            .unwrap();
+        let message = receive_message(&mut protocol.get_binary(), &None).unwrap();
+        match message {
+            Message::ReplyMessage(ResponseMessage::Error { code, message }) => {
+                assert_eq!(code, ErrorCode::AlreadyExists as i32);
+                assert_eq!(message, "Table flights_keyspace.arrivals already exists");
+            }
+            _ => panic!("Unexpected message"),
+        }
+    }
+
+    #[test]
+    fn test_write_unavailable() {
+        let mut protocol = Protocol::new();
+        protocol
+            .write_unavailable("Not enough nodes", consistency::Consistency::All, 3, 2)
+            .unwrap();
+        let message = receive_message(&mut protocol.get_binary(), &None).unwrap();
+        match message {
+            Message::ReplyMessage(ResponseMessage::Error { code, message }) => {
+                assert_eq!(code, ErrorCode::Unavailable as i32);
+                assert_eq!(message, "Not enough nodes");
+            }
+            _ => panic!("Unexpected message"),
+        }
+    }
+
+    #[test]
+    fn test_write_write_timeout() {
+        let mut protocol = Protocol::new();
+        protocol
+            .write_write_timeout(
+                "Write timed out",
+                consistency::Consistency::Quorum,
+                (1, 2),
+                "SIMPLE",
+            )
+            .unwrap();
+        let message = receive_message(&mut protocol.get_binary(), &None).unwrap();
+        match message {
+            Message::ReplyMessage(ResponseMessage::Error { code, message }) => {
+                assert_eq!(code, ErrorCode::WriteTimeout as i32);
+                assert_eq!(message, "Write timed out");
+            }
+            _ => panic!("Unexpected message"),
+        }
+    }
+
+    #[test]
+    fn test_write_syntax_error() {
+        let mut protocol = Protocol::new();
+        protocol
+            .write_syntax_error(&SyntaxError {
+                code: 211,
+                message: "Syntax error".to_string(),
+                token: Some("LIMT".to_string()),
+                line: 1,
+                column: 28,
+                expected: vec!["LIMIT".to_string(), ";".to_string()],
+            })
+            .unwrap();
+        let message = receive_message(&mut protocol.get_binary(), &None).unwrap();
+        match message {
+            Message::ReplyMessage(ResponseMessage::Error { code, message }) => {
+                assert_eq!(code, ErrorCode::SyntaxError as i32);
+                assert_eq!(
+                    message,
+                    "Syntax error at line 1, column 28, found LIMT, expected one of LIMIT, ;"
+                );
+            }
+            _ => panic!("Unexpected message"),
+        }
+    }
+
+    #[test]
+    fn test_write_options_and_supported() {
+        let mut protocol = Protocol::new();
+        protocol.write_options();
+        let message = receive_message(&mut protocol.get_binary(), &None).unwrap();
+        assert!(matches!(
+            message,
+            Message::SolicitationMessage(RequestMessage::Options)
+        ));
+
+        let mut protocol = Protocol::new();
+        protocol.set_protocol_version(4);
+        protocol
+            .write_supported(vec![(
+                "PROTOCOL_VERSIONS".to_string(),
+                vec!["4/v4".to_string(), "5/v5".to_string()],
+            )])
+            .unwrap();
+        let mut binary = protocol.get_binary();
+        assert_eq!(binary[0], 0x84);
+        match receive_message(&mut binary, &None).unwrap() {
+            Message::ReplyMessage(ResponseMessage::Supported { options }) => {
+                assert_eq!(options["PROTOCOL_VERSIONS"], vec!["4/v4", "5/v5"]);
+            }
+            _ => panic!("Unexpected message"),
+        }
+    }
+
+    #[test]
+    fn test_write_event() {
+        let events = [
+            EventKindChange::Topology(TopologyChangeType::NewNode, "127.0.0.1:8081".to_string()),
+            EventKindChange::Status(StatusNode::Down, "127.0.0.1:8082".to_string()),
+            EventKindChange::Schema(
+                SchemaChangeType::Created,
+                "TABLE".to_string(),
+                "aviation.flights".to_string(),
+            ),
+        ];
+        for event in events {
+            let mut protocol = Protocol::new();
+            protocol.set_compress_algorithm(Some(Compression::LZ4));
+            protocol.write_event(&event).unwrap();
+            let mut binary = protocol.get_binary();
+            assert_eq!(u16::from_be_bytes([binary[2], binary[3]]), EVENT_STREAM);
+            match receive_message(&mut binary, &Some(Compression::LZ4)).unwrap() {
+                Message::ReplyMessage(ResponseMessage::Event { event: received }) => {
+                    assert_eq!(received, event)
+                }
+                _ => panic!("Unexpected message"),
+            }
+        }
+    }
 }
@@ -13,8 +13,6 @@ use super::relation::Relation;
 /// - `Or`: Represents a logical OR operation between two clauses. It contains two subclauses, `left` and `right`.
 /// - `Term`: Represents a single term in the query, which is associated with a `relation`. This could be a comparison or other relational operation.
 /// - `Placeholder`: Represents a placeholder in the query, usually for prepared statements or parameterized queries.
-/// - `Lpar`: Represents a left parenthesis (`(`) in the query, used for grouping clauses.
-/// - `Rpar`: Represents a right parenthesis (`)`) in the query, used for grouping clauses.
 
 #[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
 pub enum Clause {
@@ -33,6 +31,4 @@ pub enum Clause {
         relation: Relation,
     },
     Placeholder,
-    Lpar,
-    Rpar,
 }
@@ -0,0 +1,338 @@
+use super::syntax_error::SyntaxError;
+
+const UUID_GROUPS: [usize; 5] = [8, 4, 4, 4, 12];
+const UUID_LENGTH: usize = 36;
+
+#[derive(Debug, PartialEq, Clone)]
+/// This enum represents the kinds of tokens of a CQL query.
+///
+/// ## Variants:
+/// - `Identifier`: A keyword or a name, like `SELECT` or `flights`, as it was written.
+/// - `QuotedIdentifier`: A name between double quotes, without them. Two double quotes stand for one.
+/// - `String`: A text between single quotes, without them. Two single quotes stand for one.
+/// - `Number`: An integer or decimal number, which can be negative.
+/// - `Uuid`: A UUID written without quotes.
+/// - `Operator`: One of `=`, `!=`, `<`, `<=`, `>`, `>=`, `+` and `-`.
+/// - `Punctuation`: One of `(`, `)`, `{`, `}`, `[`, `]`, `,`, `;`, `.`, `:`, `*` and `?`.
+pub enum TokenKind {
+    Identifier(String),
+    QuotedIdentifier(String),
+    String(String),
+    Number(String),
+    Uuid(String),
+    Operator(String),
+    Punctuation(char),
+}
+
+#[derive(Debug, PartialEq, Clone)]
+/// This struct represents a token of a query with the line and the column, both starting at 1, where it begins.
+pub struct Token {
+    pub kind: TokenKind,
+    pub line: usize,
+    pub column: usize,
+}
+
+impl Token {
+    /// This function checks if the token is the keyword `keyword`, without caring about its case.
+    pub fn is_keyword(&self, keyword: &str) -> bool {
+        matches!(&self.kind, TokenKind::Identifier(word) if word.eq_ignore_ascii_case(keyword))
+    }
+
+    /// This function checks if the token is the punctuation `symbol`.
+    pub fn is_punctuation(&self, symbol: char) -> bool {
+        self.kind == TokenKind::Punctuation(symbol)
+    }
+
+    /// This function returns the token as it can be written in a query.
+    pub fn text(&self) -> String {
+        match &self.kind {
+            TokenKind::Identifier(word) | TokenKind::Number(word) | TokenKind::Uuid(word) => {
+                word.to_string()
+            }
+            TokenKind::QuotedIdentifier(word) => format!("\"{}\"", word.replace('"', "\"\"")),
+            TokenKind::String(text) => format!("'{}'", text.replace('\'', "''")),
+            TokenKind::Operator(operator) => operator.to_string(),
+            TokenKind::Punctuation(symbol) => symbol.to_string(),
+        }
+    }
+}
+
+/// This struct walks the characters of a query keeping the line and the column of the next one.
+struct Cursor<'a> {
+    rest: &'a str,
+    line: usize,
+    column: usize,
+}
+
+impl<'a> Cursor<'a> {
+    fn new(query: &'a str) -> Self {
+        Cursor {
+            rest: query,
+            line: 1,
+            column: 1,
+        }
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.rest.chars().next()
+    }
+
+    fn peek_second(&self) -> Option<char> {
+        self.rest.chars().nth(1)
+    }
+
+    fn next(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.rest = &self.rest[c.len_utf8()..];
+        if c == '\n' {
+            self.line += 1;
+            self.column = 1;
+        } else {
+            self.column += 1;
+        }
+        Some(c)
+    }
+
+    /// This function consumes the characters while they meet `condition`, and returns them.
+    fn take_while<F: Fn(char) -> bool>(&mut self, condition: F) -> String {
+        let mut taken = String::new();
+        while let Some(c) = self.peek().filter(|c| condition(*c)) {
+            taken.push(c);
+            self.next();
+        }
+        taken
+    }
+
+    /// This function returns the UUID the rest of the query starts with, if it does.
+    fn starts_with_uuid(&self) -> Option<&'a str> {
+        let candidate = self.rest.get(..UUID_LENGTH)?;
+        let groups = candidate.split('-').map(str::len).collect::<Vec<usize>>();
+        let is_uuid = groups == UUID_GROUPS
+            && candidate.chars().all(|c| c == '-' || c.is_ascii_hexdigit())
+            && !self.rest[UUID_LENGTH..]
+                .chars()
+                .next()
+                .is_some_and(is_identifier_char);
+        is_uuid.then_some(candidate)
+    }
+}
+
+/// This function splits a query in tokens, skipping the whitespaces and the comments, which can be `-- ...` and
+/// `// ...` until the end of the line or `/* ... */`.
+pub fn tokenize(query: &str) -> Result<Vec<Token>, SyntaxError> {
+    let mut cursor = Cursor::new(query);
+    let mut tokens = Vec::new();
+    while let Some(c) = cursor.peek() {
+        let (line, column) = (cursor.line, cursor.column);
+        let kind = match c {
+            c if c.is_whitespace() => {
+                cursor.next();
+                continue;
+            }
+            '-' if cursor.peek_second() == Some('-') => {
+                cursor.take_while(|c| c != '\n');
+                continue;
+            }
+            '/' if cursor.peek_second() == Some('/') => {
+                cursor.take_while(|c| c != '\n');
+                continue;
+            }
+            '/' if cursor.peek_second() == Some('*') => {
+                skip_block_comment(&mut cursor, line, column)?;
+                continue;
+            }
+            '\'' => TokenKind::String(quoted(&mut cursor, '\'', line, column)?),
+            '"' => TokenKind::QuotedIdentifier(quoted(&mut cursor, '"', line, column)?),
+            _ if cursor.starts_with_uuid().is_some() => {
+                let uuid = cursor.starts_with_uuid().unwrap_or_default().to_string();
+                uuid.chars().for_each(|_| {
+                    cursor.next();
+                });
+                TokenKind::Uuid(uuid)
+            }
+            c if c.is_ascii_digit() => TokenKind::Number(number(&mut cursor)),
+            '-' if cursor.peek_second().is_some_and(|c| c.is_ascii_digit()) => {
+                cursor.next();
+                TokenKind::Number(format!("-{}", number(&mut cursor)))
+            }
+            c if c.is_alphabetic() || c == '_' => {
+                TokenKind::Identifier(cursor.take_while(is_identifier_char))
+            }
+            '<' | '>' | '!' => {
+                cursor.next();
+                if cursor.peek() == Some('=') {
+                    cursor.next();
+                    TokenKind::Operator(format!("{}=", c))
+                } else if c == '!' {
+                    return Err(invalid_character(c, line, column));
+                } else {
+                    TokenKind::Operator(c.to_string())
+                }
+            }
+            '=' | '+' | '-' => {
+                cursor.next();
+                TokenKind::Operator(c.to_string())
+            }
+            '(' | ')' | '{' | '}' | '[' | ']' | ',' | ';' | '.' | ':' | '*' | '?' => {
+                cursor.next();
+                TokenKind::Punctuation(c)
+            }
+            c => return Err(invalid_character(c, line, column)),
+        };
+        tokens.push(Token { kind, line, column });
+    }
+    Ok(tokens)
+}
+
+/// This function checks if a character can be part of an identifier.
+fn is_identifier_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+/// This function reads an integer or a decimal number.
+fn number(cursor: &mut Cursor) -> String {
+    let mut number = cursor.take_while(|c| c.is_ascii_digit());
+    if cursor.peek() == Some('.') && cursor.peek_second().is_some_and(|c| c.is_ascii_digit()) {
+        cursor.next();
+        number.push('.');
+        number.push_str(&cursor.take_while(|c| c.is_ascii_digit()));
+    }
+    number
+}
+
+/// This function reads a text between `quote`, where two quotes in a row stand for one, and returns it without them.
+/// The null character is not valid in a text, so it can mark the bind markers of a prepared statement.
+fn quoted(
+    cursor: &mut Cursor,
+    quote: char,
+    line: usize,
+    column: usize,
+) -> Result<String, SyntaxError> {
+    cursor.next();
+    let mut text = String::new();
+    loop {
+        let (c_line, c_column) = (cursor.line, cursor.column);
+        match cursor.next() {
+            Some('\0') => return Err(invalid_character('\0', c_line, c_column)),
+            Some(c) if c == quote && cursor.peek() == Some(quote) => {
+                cursor.next();
+                text.push(quote);
+            }
+            Some(c) if c == quote => return Ok(text),
+            Some(c) => text.push(c),
+            None => {
+                return Err(SyntaxError {
+                    code: 241,
+                    message: "Unterminated quote".to_string(),
+                    token: Some(quote.to_string()),
+                    line,
+                    column,
+                    expected: vec![quote.to_string()],
+                })
+            }
+        }
+    }
+}
+
+/// This function skips a comment that starts with `/*` until its `*/`.
+fn skip_block_comment(cursor: &mut Cursor, line: usize, column: usize) -> Result<(), SyntaxError> {
+    cursor.next();
+    cursor.next();
+    while let Some(c) = cursor.next() {
+        if c == '*' && cursor.peek() == Some('/') {
+            cursor.next();
+            return Ok(());
+        }
+    }
+    Err(SyntaxError {
+        code: 242,
+        message: "Unterminated comment".to_string(),
+        token: Some("/*".to_string()),
+        line,
+        column,
+        expected: vec!["*/".to_string()],
+    })
+}
+
+/// This function returns the error of a character that can not start a token.
+fn invalid_character(c: char, line: usize, column: usize) -> SyntaxError {
+    SyntaxError {
+        code: 243,
+        message: "Invalid character".to_string(),
+        token: Some(c.to_string()),
+        line,
+        column,
+        expected: vec![],
+    }
+}
+
+#[cfg(test)]
+pub mod test {
+    use super::*;
+
+    fn kinds(query: &str) -> Vec<TokenKind> {
+        tokenize(query)
+            .unwrap()
+            .into_iter()
+            .map(|token| token.kind)
+            .collect()
+    }
+
+    #[test]
+    fn test_tokenize() {
+        assert_eq!(
+            kinds("SELECT * FROM \"Flights\" WHERE status = 'ON TIME' AND delay >= -1.5;"),
+            vec![
+                TokenKind::Identifier("SELECT".to_string()),
+                TokenKind::Punctuation('*'),
+                TokenKind::Identifier("FROM".to_string()),
+                TokenKind::QuotedIdentifier("Flights".to_string()),
+                TokenKind::Identifier("WHERE".to_string()),
+                TokenKind::Identifier("status".to_string()),
+                TokenKind::Operator("=".to_string()),
+                TokenKind::String("ON TIME".to_string()),
+                TokenKind::Identifier("AND".to_string()),
+                TokenKind::Identifier("delay".to_string()),
+                TokenKind::Operator(">=".to_string()),
+                TokenKind::Number("-1.5".to_string()),
+                TokenKind::Punctuation(';'),
+            ]
+        );
+        assert_eq!(
+            kinds("'it''s = ok', 123e4567-e89b-12d3-a456-426614174000"),
+            vec![
+                TokenKind::String("it's = ok".to_string()),
+                TokenKind::Punctuation(','),
+                TokenKind::Uuid("123e4567-e89b-12d3-a456-426614174000".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_tokenize_comments_and_positions() {
+        let tokens = tokenize("-- flights\nUSE /* the keyspace */ ks; // done").unwrap();
+        assert_eq!(
+            tokens,
+            vec![
+                Token {
+                    kind: TokenKind::Identifier("USE".to_string()),
+                    line: 2,
+                    column: 1,
+                },
+                Token {
+                    kind: TokenKind::Identifier("ks".to_string()),
+                    line: 2,
+                    column: 24,
+                },
+                Token {
+                    kind: TokenKind::Punctuation(';'),
+                    line: 2,
+                    column: 26,
+                },
+            ]
+        );
+        assert!(tokenize("SELECT * FROM t WHERE a = 'open;").is_err());
+        assert!(tokenize("SELECT /* open").is_err());
+        assert!(tokenize("SELECT # FROM t;").is_err());
+    }
+}
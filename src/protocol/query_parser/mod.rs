@@ -1,12 +1,18 @@
 pub mod clause;
+pub mod lexer;
+pub mod parser_alter;
+pub mod parser_batch;
 pub mod parser_create;
 pub mod parser_delete;
+pub mod parser_drop;
 pub mod parser_impl;
 pub mod parser_insert;
 pub mod parser_keyspace;
 pub mod parser_select;
+pub mod parser_truncate;
 pub mod parser_update;
 pub mod parser_use;
 pub mod parser_utils;
 pub mod query;
 pub mod relation;
+pub mod syntax_error;
@@ -0,0 +1,74 @@
+use super::{
+    parser_keyspace::parse_replication,
+    parser_utils::{parse_keyspace_name, parse_table_name, Tokens},
+    query::{AlterTableOperation, Query},
+    syntax_error::SyntaxError,
+};
+
+///This function parses the ALTER TABLE query, which adds or drops a column, and the ALTER KEYSPACE query, which changes its replication.
+pub fn parse_alter(tokens: &mut Tokens) -> Result<Query, SyntaxError> {
+    tokens.expect_keyword("alter")?;
+    if tokens.take_keyword("keyspace") {
+        return Ok(Query::AlterKeyspace {
+            keyspace_name: parse_keyspace_name(tokens)?,
+            replication: parse_replication(tokens)?,
+        });
+    }
+    if !tokens.take_keyword("table") {
+        return Err(tokens.error(239, "Invalid syntax"));
+    }
+    let table_name = parse_table_name(tokens)?;
+    let operation = if tokens.take_keyword("add") {
+        let column = tokens.identifier()?;
+        AlterTableOperation::Add(column, tokens.identifier()?.to_lowercase())
+    } else if tokens.take_keyword("drop") {
+        AlterTableOperation::Drop(tokens.identifier()?)
+    } else {
+        return Err(tokens.error(239, "Invalid syntax"));
+    };
+    Ok(Query::AlterTable {
+        table_name,
+        operation,
+    })
+}
+
+#[cfg(test)]
+pub mod test {
+    use crate::protocol::query_parser::{
+        parser_impl::parse_query,
+        query::{AlterTableOperation, Query},
+    };
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_alter() {
+        assert_eq!(
+            parse_query("ALTER TABLE flights_keyspace.departures ADD gate text;".to_string())
+                .unwrap(),
+            Query::AlterTable {
+                table_name: "flights_keyspace.departures".to_string(),
+                operation: AlterTableOperation::Add("gate".to_string(), "text".to_string()),
+            }
+        );
+        assert_eq!(
+            parse_query("ALTER TABLE departures DROP airline;".to_string()).unwrap(),
+            Query::AlterTable {
+                table_name: "departures".to_string(),
+                operation: AlterTableOperation::Drop("airline".to_string()),
+            }
+        );
+        assert_eq!(
+            parse_query(
+                "ALTER KEYSPACE flights_keyspace WITH REPLICATION = {'replication_factor': 3};"
+                    .to_string()
+            )
+            .unwrap(),
+            Query::AlterKeyspace {
+                keyspace_name: "flights_keyspace".to_string(),
+                replication: HashMap::from([("replication_factor".to_string(), "3".to_string())]),
+            }
+        );
+        assert!(parse_query("ALTER TABLE departures ADD gate;".to_string()).is_err());
+        assert!(parse_query("ALTER TABLE departures RENAME a TO b;".to_string()).is_err());
+    }
+}
@@ -0,0 +1,29 @@
+use super::{
+    parser_delete::parse_delete, parser_insert::parse_insert, parser_update::parse_update,
+    parser_utils::Tokens, query::Query, syntax_error::SyntaxError,
+};
+
+/// This function parses a batch, `BEGIN [UNLOGGED] BATCH ... APPLY BATCH`, whose statements are INSERT, UPDATE and
+/// DELETE queries. The `;` after each statement is optional.
+pub fn parse_batch(tokens: &mut Tokens) -> Result<Query, SyntaxError> {
+    tokens.expect_keyword("begin")?;
+    let logged = !tokens.take_keyword("unlogged");
+    tokens.expect_keyword("batch")?;
+    let mut queries = Vec::new();
+    while !tokens.take_keywords(&["apply", "batch"]) {
+        let query = if tokens.is_keyword("insert") {
+            parse_insert(tokens)
+        } else if tokens.is_keyword("update") {
+            parse_update(tokens)
+        } else if tokens.is_keyword("delete") {
+            parse_delete(tokens)
+        } else if tokens.is_at_end() {
+            return Err(tokens.unexpected());
+        } else {
+            return Err(tokens.error(245, "Only INSERT, UPDATE and DELETE can be in a batch"));
+        }?;
+        queries.push(query);
+        tokens.take_punctuation(';');
+    }
+    Ok(Query::Batch { logged, queries })
+}
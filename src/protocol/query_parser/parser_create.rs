@@ -1,72 +1,69 @@
-use super::{parser_utils::split_par, query::Query};
-use crate::errors::error_types::ErrorTypes;
+use super::{
+    parser_utils::{parse_table_name, take_if_not_exists, Tokens},
+    query::Query,
+    syntax_error::SyntaxError,
+};
 
-/// This function parses the create query.
-pub fn parse_create(query: Vec<String>) -> Result<Query, ErrorTypes> {
-    let query_split = split_par(query);
-    let mut table_name = String::new();
+/// This function parses the create query, which can have IF NOT EXISTS before the name of the table.
+///
+/// The primary key is written as `PRIMARY KEY ((partition key columns), clustering key columns)`. If the partition key
+/// columns are not between their own parenthesis, every column is part of the clustering key.
+pub fn parse_create(tokens: &mut Tokens) -> Result<Query, SyntaxError> {
+    tokens.expect_keyword("create")?;
+    tokens.expect_keyword("table")?;
+    let if_not_exists = take_if_not_exists(tokens);
+    let table_name = parse_table_name(tokens)?;
     let mut columns_type: Vec<(String, String)> = Vec::new();
     let mut primary_key = Vec::new();
     let mut clustering_key = Vec::new();
-
-    let mut index_table = None;
-    let mut index_lpar = None;
-    let mut index_lpar2 = None;
-    let mut index_key = None;
-    let mut vec_columns = Vec::new();
-    for (i, word) in query_split.iter().enumerate() {
-        if word.to_uppercase() == "TABLE" {
-            index_table = Some(i);
-        } else if let Some(i_table) = index_table {
-            if i == i_table + 1 {
-                table_name = word.to_string();
-            }
-            index_table = None;
-        } else if word == "(" && index_key.is_none() {
-            index_lpar = Some(i);
-        } else if index_lpar.is_some() && index_key.is_none() {
-            if word == "PRIMARY" {
-                index_lpar = None;
-            } else {
-                vec_columns.push(word.trim_end_matches(',').to_string());
-                if vec_columns.len() == 2 {
-                    columns_type.push((vec_columns[0].to_string(), vec_columns[1].to_string()));
-                    vec_columns.clear();
-                }
-            }
-        } else if word.to_uppercase() == "KEY" {
-            index_key = Some(i);
-        } else if index_key.is_some() && word == "(" && index_lpar.is_none() {
-            index_lpar = Some(i);
-        } else if index_lpar.is_some() && index_key.is_some() {
-            if word == "(" {
-                index_lpar2 = Some(i);
-            } else if index_lpar2.is_some() {
-                if word.trim_matches(',') == ")" {
-                    index_lpar2 = None;
-                } else {
-                    primary_key.push(word.trim_end_matches(',').to_string());
-                }
-            } else if word != ")" {
-                clustering_key.push(word.trim_end_matches(',').to_string());
-            }
+    tokens.expect_punctuation('(')?;
+    loop {
+        if tokens.take_keywords(&["primary", "key"]) {
+            (primary_key, clustering_key) = parse_primary_key(tokens)?;
+            break;
+        }
+        columns_type.push((tokens.identifier()?, tokens.identifier()?));
+        if !tokens.take_punctuation(',') && !tokens.is_keyword("primary") {
+            break;
         }
     }
-    clustering_key.retain(|x| !x.is_empty());
-    primary_key.retain(|x| !x.is_empty());
-
-    let query = Query::CreateTable {
+    tokens.expect_punctuation(')')?;
+    Ok(Query::CreateTable {
         table_name,
         columns_type,
         clustering_key,
         primary_key,
-    };
-    Ok(query)
+        if_not_exists,
+    })
+}
+
+/// This function parses the columns of the primary key and returns the ones of the partition key and the ones of the
+/// clustering key.
+fn parse_primary_key(tokens: &mut Tokens) -> Result<(Vec<String>, Vec<String>), SyntaxError> {
+    tokens.expect_punctuation('(')?;
+    let mut primary_key = Vec::new();
+    if tokens.peek().is_some_and(|token| token.is_punctuation('(')) {
+        primary_key = tokens.list(|tokens| tokens.identifier())?;
+        if !tokens.take_punctuation(',') {
+            tokens.expect_punctuation(')')?;
+            return Ok((primary_key, vec![]));
+        }
+    }
+    let mut clustering_key = vec![tokens.identifier()?];
+    while tokens.take_punctuation(',') {
+        clustering_key.push(tokens.identifier()?);
+    }
+    tokens.expect_punctuation(')')?;
+    Ok((primary_key, clustering_key))
 }
 
 #[cfg(test)]
 pub mod test {
-    use crate::protocol::query_parser::{parser_impl::parse_query, query::Query};
+    use crate::protocol::query_parser::{
+        parser_impl::parse_query,
+        parser_utils::{parse_table_name, Tokens},
+        query::Query,
+    };
     #[test]
     fn test_create_table() {
         let query = "CREATE TABLE cycling (race_name text, race_position int, PRIMARY KEY ((race_name), race_position));"
@@ -81,7 +78,8 @@ pub mod test {
                     ("race_position".to_string(), "int".to_string())
                 ],
                 clustering_key: vec!["race_position".to_string()],
-                primary_key: vec!["race_name".to_string()]
+                primary_key: vec!["race_name".to_string()],
+                if_not_exists: false,
             }
         )
     }
@@ -99,7 +97,8 @@ pub mod test {
                     ("origin".to_string(), "text".to_string())
                 ],
                 clustering_key: vec!["flight_name".to_string()],
-                primary_key: vec!["id".to_string(), "origin".to_string()]
+                primary_key: vec!["id".to_string(), "origin".to_string()],
+                if_not_exists: false,
             }
         )
     }
@@ -117,7 +116,8 @@ pub mod test {
                     ("origin".to_string(), "text".to_string())
                 ],
                 clustering_key: vec!["origin".to_string(), "flight_name".to_string()],
-                primary_key: vec![]
+                primary_key: vec![],
+                if_not_exists: false,
             }
         )
     }
@@ -137,7 +137,8 @@ pub mod test {
                     ("date".to_string(), "date".to_string())
                 ],
                 clustering_key: vec!["date".to_string()],
-                primary_key: vec!["destination".to_string()]
+                primary_key: vec!["destination".to_string()],
+                if_not_exists: false,
             }
         )
     }
@@ -157,8 +158,60 @@ pub mod test {
                     ("date".to_string(), "date".to_string())
                 ],
                 clustering_key: vec!["date".to_string()],
-                primary_key: vec!["origin".to_string()]
+                primary_key: vec!["origin".to_string()],
+                if_not_exists: false,
             }
         )
     }
+
+    #[test]
+    fn test_create_table_in_keyspace() {
+        let query = "CREATE TABLE flights_keyspace.arrivals (id int, destination text PRIMARY KEY ((destination), id));";
+        let result = parse_query(query.to_string()).unwrap();
+        assert_eq!(
+            result,
+            Query::CreateTable {
+                table_name: "flights_keyspace.arrivals".to_string(),
+                columns_type: vec![
+                    ("id".to_string(), "int".to_string()),
+                    ("destination".to_string(), "text".to_string())
+                ],
+                clustering_key: vec!["id".to_string()],
+                primary_key: vec!["destination".to_string()],
+                if_not_exists: false,
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse_table_name() {
+        let mut tokens = Tokens::new("flights_keyspace.\"arrivals\"").unwrap();
+        assert_eq!(
+            parse_table_name(&mut tokens).unwrap(),
+            "flights_keyspace.arrivals"
+        );
+        assert!(tokens.is_at_end());
+        assert!(parse_table_name(&mut Tokens::new("'arrivals'").unwrap()).is_err());
+        let query = "SELECT id FROM a.b.c WHERE id = 1;";
+        assert!(parse_query(query.to_string()).is_err());
+    }
+
+    #[test]
+    fn test_create_table_if_not_exists() {
+        let query = "CREATE TABLE IF NOT EXISTS arrivals (id int, destination text PRIMARY KEY ((destination), id));";
+        let result = parse_query(query.to_string()).unwrap();
+        assert_eq!(
+            result,
+            Query::CreateTable {
+                table_name: "arrivals".to_string(),
+                columns_type: vec![
+                    ("id".to_string(), "int".to_string()),
+                    ("destination".to_string(), "text".to_string())
+                ],
+                clustering_key: vec!["id".to_string()],
+                primary_key: vec!["destination".to_string()],
+                if_not_exists: true,
+            }
+        );
+    }
 }
@@ -0,0 +1,56 @@
+use super::{
+    parser_utils::{parse_keyspace_name, parse_table_name, Tokens},
+    query::Query,
+    syntax_error::SyntaxError,
+};
+
+///This function parses the DROP KEYSPACE and DROP TABLE queries, which can have IF EXISTS before the name.
+pub fn parse_drop(tokens: &mut Tokens) -> Result<Query, SyntaxError> {
+    tokens.expect_keyword("drop")?;
+    if tokens.take_keyword("keyspace") {
+        let if_exists = tokens.take_keywords(&["if", "exists"]);
+        return Ok(Query::DropKeyspace {
+            keyspace_name: parse_keyspace_name(tokens)?,
+            if_exists,
+        });
+    }
+    if !tokens.take_keyword("table") {
+        return Err(tokens.error(236, "Invalid syntax"));
+    }
+    let if_exists = tokens.take_keywords(&["if", "exists"]);
+    Ok(Query::DropTable {
+        table_name: parse_table_name(tokens)?,
+        if_exists,
+    })
+}
+
+#[cfg(test)]
+pub mod test {
+    use crate::protocol::query_parser::{parser_impl::parse_query, query::Query};
+
+    #[test]
+    fn test_drop() {
+        assert_eq!(
+            parse_query("DROP KEYSPACE IF EXISTS flights_keyspace;".to_string()).unwrap(),
+            Query::DropKeyspace {
+                keyspace_name: "flights_keyspace".to_string(),
+                if_exists: true,
+            }
+        );
+        assert_eq!(
+            parse_query("DROP TABLE flights_keyspace.arrivals;".to_string()).unwrap(),
+            Query::DropTable {
+                table_name: "flights_keyspace.arrivals".to_string(),
+                if_exists: false,
+            }
+        );
+        assert_eq!(
+            parse_query("TRUNCATE TABLE arrivals;".to_string()).unwrap(),
+            Query::Truncate {
+                table_name: "arrivals".to_string(),
+            }
+        );
+        assert!(parse_query("DROP KEYSPACE a.b;".to_string()).is_err());
+        assert!(parse_query("DROP TABLE IF arrivals;".to_string()).is_err());
+    }
+}
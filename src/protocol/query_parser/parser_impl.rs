@@ -1,242 +1,333 @@
 use super::{
-    clause::Clause, parser_create::parse_create, parser_delete::parse_delete,
+    clause::Clause, lexer::TokenKind, parser_alter::parse_alter, parser_batch::parse_batch,
+    parser_create::parse_create, parser_delete::parse_delete, parser_drop::parse_drop,
     parser_insert::parse_insert, parser_keyspace::parse_keyspace, parser_select::parse_select,
-    parser_update::parse_update, parser_use::parse_use, parser_utils::*, query::Query,
-    relation::Relation,
+    parser_truncate::parse_truncate, parser_update::parse_update, parser_use::parse_use,
+    parser_utils::Tokens, query::Query, relation::Relation, syntax_error::SyntaxError,
 };
-use crate::errors::error_types::ErrorTypes;
 
-/// This function is the main function that will parse the query and return a Query object
-pub fn parse_query(query: String) -> Result<Query, ErrorTypes> {
-    let mut query = query.replace("\n", " ");
-    if query.ends_with(";") {
-        query.pop();
+/// This function is the main function that will parse the query and return a Query object.
+///
+/// If the query is not valid, the error has the token where it was found and the tokens that could have been there.
+pub fn parse_query(query: String) -> Result<Query, SyntaxError> {
+    let mut tokens = Tokens::new(&query)?;
+    let parsed = if tokens.is_keyword("insert") {
+        parse_insert(&mut tokens)
+    } else if tokens.is_keyword("update") {
+        parse_update(&mut tokens)
+    } else if tokens.is_keyword("delete") {
+        parse_delete(&mut tokens)
+    } else if tokens.is_keyword("select") {
+        parse_select(&mut tokens)
+    } else if tokens.is_keyword("create") {
+        match tokens.is_at_keyword(1, "keyspace") {
+            true => parse_keyspace(&mut tokens),
+            false => parse_create(&mut tokens),
+        }
+    } else if tokens.is_keyword("use") {
+        parse_use(&mut tokens)
+    } else if tokens.is_keyword("drop") {
+        parse_drop(&mut tokens)
+    } else if tokens.is_keyword("truncate") {
+        parse_truncate(&mut tokens)
+    } else if tokens.is_keyword("alter") {
+        parse_alter(&mut tokens)
+    } else if tokens.is_keyword("begin") {
+        parse_batch(&mut tokens)
     } else {
-        return Err(ErrorTypes::new(
-            204,
-            "Queries must end with ';'".to_string(),
-        ));
+        return Err(tokens.error(205, "Invalid query"));
+    }?;
+    // Como en CQL el ';' final es opcional, los drivers no lo envian
+    tokens.take_punctuation(';');
+    if !tokens.is_at_end() {
+        return Err(tokens.unexpected());
     }
-    let splitted_query: Vec<String> = query.split_whitespace().map(|s| s.to_string()).collect();
-    match splitted_query[0].to_lowercase().as_str() {
-        "insert" => parse_insert(splitted_query),
-        "update" => parse_update(splitted_query),
-        "delete" => parse_delete(splitted_query),
-        "select" => parse_select(splitted_query),
-        "create" if splitted_query[1].to_lowercase().as_str() == "keyspace" => {
-            parse_keyspace(splitted_query)
-        }
-        "create" => parse_create(splitted_query),
-        "use" => parse_use(splitted_query),
+    Ok(parsed)
+}
 
-        _ => Err(ErrorTypes::new(205, "Invalid query".to_string())),
+/// This function parses the conditions of a `WHERE`, until a token that can not be part of them.
+///
+/// `NOT` binds tighter than `AND`, and `AND` tighter than `OR`. The parenthesis group the conditions as usual.
+pub fn parse_conditions(tokens: &mut Tokens) -> Result<Clause, SyntaxError> {
+    let mut clause = parse_and(tokens)?;
+    while tokens.take_keyword("or") {
+        clause = Clause::Or {
+            left: Box::new(clause),
+            right: Box::new(parse_and(tokens)?),
+        };
     }
+    Ok(clause)
 }
 
-/// This function validates the query to check if it is a valid query
-pub fn generic_validate(
-    query: &[String],
-    pos_keyword1: &usize,
-    pos_keyword2: &usize,
-) -> Result<(), ErrorTypes> {
-    if pos_keyword2 < pos_keyword1 {
-        return Err(ErrorTypes::new(206, "Invalid query".to_string()));
-    }
-    if *pos_keyword2 == pos_keyword1 + 1 && query[*pos_keyword1].to_lowercase() != *"from" {
-        return Err(ErrorTypes::new(207, "No columns where written".to_string()));
-    }
-    if *pos_keyword2 == query.len() - 1 {
-        return Err(ErrorTypes::new(208, "No values where written".to_string()));
+/// This function parses conditions joined by `AND`.
+fn parse_and(tokens: &mut Tokens) -> Result<Clause, SyntaxError> {
+    let mut clause = parse_not(tokens)?;
+    while tokens.take_keyword("and") {
+        clause = Clause::And {
+            left: Box::new(clause),
+            right: Box::new(parse_not(tokens)?),
+        };
     }
-    Ok(())
+    Ok(clause)
 }
 
-/// This function allows to parse the conditions of the query
-pub fn parse_conditions(vec: Vec<String>) -> Result<Clause, ErrorTypes> {
-    let vec = join_conditions(join_compounds(split_par(vec)))?;
-    let mut clauses: Vec<Clause> = Vec::new();
-    for elem in vec {
-        if elem.is_empty() {
-            continue;
-        }
-        match elem.to_lowercase().as_str() {
-            "and" => clauses.push(Clause::And {
-                left: Box::new(Clause::Placeholder),
-                right: Box::new(Clause::Placeholder),
-            }),
-            "or" => clauses.push(Clause::Or {
-                left: Box::new(Clause::Placeholder),
-                right: Box::new(Clause::Placeholder),
-            }),
-            "not" => clauses.push(Clause::Not {
-                right: Box::new(Clause::Placeholder),
-            }),
-            "(" => {
-                clauses.push(Clause::Lpar);
-            }
-            ")" => {
-                clauses.push(Clause::Rpar);
-            }
-            _ => clauses.push(Clause::Term {
-                relation: parse_relation(split_operators(vec![elem]))?,
-            }),
+/// This function parses a condition that can be negated with `NOT` or grouped with parenthesis.
+fn parse_not(tokens: &mut Tokens) -> Result<Clause, SyntaxError> {
+    if tokens.take_keyword("not") {
+        return Ok(Clause::Not {
+            right: Box::new(parse_not(tokens)?),
+        });
+    }
+    if tokens.take_punctuation('(') {
+        let clause = parse_conditions(tokens)?;
+        if !tokens.take_punctuation(')') {
+            return Err(tokens.error(210, "Unbalanced parenthesis"));
         }
+        return Ok(clause);
     }
-    if clauses.len() == 1 {
-        return Ok(clauses.remove(0));
+    Ok(Clause::Term {
+        relation: parse_relation(tokens)?,
+    })
+}
+
+/// This function parses a relation between a column and a value, or a list of them for `IN`.
+fn parse_relation(tokens: &mut Tokens) -> Result<Relation, SyntaxError> {
+    let v1 = tokens.identifier()?;
+    if tokens.take_keyword("in") {
+        let values = tokens.list(|tokens| tokens.term())?;
+        return Ok(Relation::In { v1, values });
     }
-    deepen_clauses(clauses)
+    let Some(operator) = ["=", ">=", "<=", ">", "<"]
+        .into_iter()
+        .find(|operator| tokens.take_operator(operator))
+    else {
+        return match tokens.peek().map(|token| &token.kind) {
+            Some(TokenKind::Operator(_)) => Err(tokens.error(212, "Not supported operator")),
+            _ => Err(tokens.error(213, "Invalid input format")),
+        };
+    };
+    let v2 = tokens.term()?;
+    Ok(match operator {
+        "=" => Relation::Equal { v1, v2 },
+        ">" => Relation::Higher { v1, v2 },
+        "<" => Relation::Lower { v1, v2 },
+        ">=" => Relation::HigherEqual { v1, v2 },
+        _ => Relation::LowerEqual { v1, v2 },
+    })
 }
 
-/// This functuon allows to join the clauses, the most internal clauses will be the ones inside the parenthesis to respect the evaluation order. Thanks to the stack we can always join the most internal clauses
-fn deepen_clauses(vector: Vec<Clause>) -> Result<Clause, ErrorTypes> {
-    let mut stack: Vec<Vec<Clause>> = Vec::new();
-    let mut actual: Vec<Clause> = Vec::new();
-    for clause in vector {
-        match clause {
-            Clause::Lpar => {
-                stack.push(actual);
-                actual = Vec::new();
-            }
-            Clause::Rpar => match stack.pop() {
-                Some(anterior) => {
-                    let clause_parcial = join_clauses(actual);
-                    actual = anterior;
-                    actual.push(clause_parcial);
+#[cfg(test)]
+pub mod test {
+    use super::*;
+
+    #[test]
+    fn test_parse_in() {
+        let query = "SELECT * FROM departures WHERE origin IN ('EZE', 'AEP') AND id = 1;";
+        match parse_query(query.to_string()).unwrap() {
+            Query::Select { conditions, .. } => assert_eq!(
+                conditions,
+                Clause::And {
+                    left: Box::new(Clause::Term {
+                        relation: Relation::In {
+                            v1: "origin".to_string(),
+                            values: vec!["EZE".to_string(), "AEP".to_string()],
+                        },
+                    }),
+                    right: Box::new(Clause::Term {
+                        relation: Relation::Equal {
+                            v1: "id".to_string(),
+                            v2: "1".to_string(),
+                        },
+                    }),
                 }
-                None => return Err(ErrorTypes::new(210, "Unbalanced parenthesis".to_string())),
-            },
-            _ => actual.push(clause),
+            ),
+            _ => panic!("Unexpected query"),
         }
-    }
-    Ok(join_clauses(actual))
-}
-/// This function allows to join a vector of clauses in a single clause
-fn join_clauses(mut vector: Vec<Clause>) -> Clause {
-    while let Some(pos) = vector.iter().position(|s| {
-        if let Clause::Not { right } = s {
-            matches!(**right, Clause::Placeholder)
-        } else {
-            false
+        let query = "SELECT * FROM departures WHERE city IN ('Buenos Aires, AR', 'Cordoba');";
+        match parse_query(query.to_string()).unwrap() {
+            Query::Select { conditions, .. } => assert_eq!(
+                conditions,
+                Clause::Term {
+                    relation: Relation::In {
+                        v1: "city".to_string(),
+                        values: vec!["Buenos Aires, AR".to_string(), "Cordoba".to_string()],
+                    },
+                }
+            ),
+            _ => panic!("Unexpected query"),
         }
-    }) {
-        let right = vector.remove(pos + 1);
-        let new = Clause::Not {
-            right: Box::new(right),
-        };
-        vector[pos] = new;
+        let query = "SELECT * FROM departures WHERE origin IN 'EZE';";
+        assert!(parse_query(query.to_string()).is_err());
     }
 
-    while let Some(pos) = vector.iter().position(|s| {
-        if let Clause::And { left, right } = s {
-            matches!(**left, Clause::Placeholder) && matches!(**right, Clause::Placeholder)
-        } else {
-            false
+    #[test]
+    fn test_parse_allow_filtering() {
+        let query = "SELECT id FROM departures WHERE status = 'delayed' ALLOW FILTERING;";
+        match parse_query(query.to_string()).unwrap() {
+            Query::Select {
+                conditions,
+                allow_filtering,
+                ..
+            } => {
+                assert!(allow_filtering);
+                assert_eq!(
+                    conditions,
+                    Clause::Term {
+                        relation: Relation::Equal {
+                            v1: "status".to_string(),
+                            v2: "delayed".to_string(),
+                        },
+                    }
+                );
+            }
+            _ => panic!("Unexpected query"),
         }
-    }) {
-        let left = vector.remove(pos - 1);
-        let right = vector.remove(pos); // Note: pos is now the position of `right` after removing `left`
-        vector[pos - 1] = Clause::And {
-            left: Box::new(left),
-            right: Box::new(right),
-        };
-    }
-
-    while let Some(pos) = vector.iter().position(|s| {
-        if let Clause::Or { left, right } = s {
-            matches!(**left, Clause::Placeholder) && matches!(**right, Clause::Placeholder)
-        } else {
-            false
+        let query = "SELECT * FROM departures;";
+        match parse_query(query.to_string()).unwrap() {
+            Query::Select {
+                allow_filtering, ..
+            } => assert!(!allow_filtering),
+            _ => panic!("Unexpected query"),
         }
-    }) {
-        let left = vector.remove(pos - 1);
-        let right = vector.remove(pos); // Note: pos is now the position of `right` after removing `left`
-        vector[pos - 1] = Clause::Or {
-            left: Box::new(left),
-            right: Box::new(right),
-        };
     }
-    vector.pop().unwrap_or(Clause::Placeholder)
-}
 
-/// This function allows to join the conditions of the query
-fn join_conditions(vec: Vec<String>) -> Result<Vec<String>, ErrorTypes> {
-    let vector: Vec<String> = split_operators(vec);
-    let mut result: Vec<String> = vec![];
-    for i in 0..vector.len() {
-        let elem_lower = vector[i].to_lowercase();
-        let elem = elem_lower.as_str();
-        match elem {
-            "=" | ">" | "<" | ">=" | "<=" => {
-                if i == 0 || i == vector.len() - 1 {
-                    return Err(ErrorTypes::new(211, "Syntax error".to_string()));
-                }
-                result.push(format!("{} {} {}", vector[i - 1], elem, vector[i + 1]));
+    #[test]
+    fn test_parse_limit() {
+        let query =
+            "SELECT * FROM departures WHERE origin = 'EZE' ORDER BY id DESC PER PARTITION LIMIT 2 LIMIT 5;";
+        match parse_query(query.to_string()).unwrap() {
+            Query::Select {
+                order,
+                limit,
+                per_partition_limit,
+                ..
+            } => {
+                assert_eq!(order, vec!["id".to_string(), "DESC".to_string()]);
+                assert_eq!(limit, Some(5));
+                assert_eq!(per_partition_limit, Some(2));
             }
-            "and" | "or" | "not" | "(" | ")" => {
-                result.push(elem.to_string());
+            _ => panic!("Unexpected query"),
+        }
+        let query = "SELECT * FROM departures PER PARTITION LIMIT 3 ALLOW FILTERING;";
+        match parse_query(query.to_string()).unwrap() {
+            Query::Select {
+                limit,
+                per_partition_limit,
+                ..
+            } => {
+                assert_eq!(limit, None);
+                assert_eq!(per_partition_limit, Some(3));
             }
-            _ => (),
+            _ => panic!("Unexpected query"),
         }
+        let query = "SELECT * FROM departures LIMIT 0;";
+        assert!(parse_query(query.to_string()).is_err());
     }
-    Ok(result)
-}
 
-/// This function allows to split the operators of the query
-fn split_operators(vec: Vec<String>) -> Vec<String> {
-    let mut result = Vec::new();
-    let operators = [
-        String::from(">="),
-        String::from("<="),
-        String::from(">"),
-        String::from("<"),
-        String::from("="),
-    ];
-
-    for elem in vec.iter() {
-        let mut added = false;
-        for op in operators.iter() {
-            if elem.contains(op) {
-                let partes: Vec<&str> = elem.split(op).collect();
-                for (i, parte) in partes.iter().enumerate() {
-                    result.push(parte.trim());
-                    if i < partes.len() - 1 {
-                        result.push(op);
-                    }
+    #[test]
+    fn test_parse_quoted_values() {
+        let query = "-- estado de los vuelos\nSELECT * FROM departures WHERE status = 'ON TIME' OR gate = 'a=b' /* puertas */ ;";
+        match parse_query(query.to_string()).unwrap() {
+            Query::Select { conditions, .. } => assert_eq!(
+                conditions,
+                Clause::Or {
+                    left: Box::new(Clause::Term {
+                        relation: Relation::Equal {
+                            v1: "status".to_string(),
+                            v2: "ON TIME".to_string(),
+                        },
+                    }),
+                    right: Box::new(Clause::Term {
+                        relation: Relation::Equal {
+                            v1: "gate".to_string(),
+                            v2: "a=b".to_string(),
+                        },
+                    }),
                 }
-                added = true;
-                break;
-            }
+            ),
+            _ => panic!("Unexpected query"),
         }
-
-        if !added {
-            result.push(elem.as_str());
+        let query = "SELECT * FROM departures WHERE city IN ('Cordoba (AR)', 'O''Higgins');";
+        match parse_query(query.to_string()).unwrap() {
+            Query::Select { conditions, .. } => assert_eq!(
+                conditions,
+                Clause::Term {
+                    relation: Relation::In {
+                        v1: "city".to_string(),
+                        values: vec!["Cordoba (AR)".to_string(), "O'Higgins".to_string()],
+                    },
+                }
+            ),
+            _ => panic!("Unexpected query"),
         }
+        let query = "INSERT INTO departures (id, city) VALUES (1, 'Buenos Aires, AR');";
+        assert_eq!(
+            parse_query(query.to_string()).unwrap(),
+            Query::Insert {
+                table_name: "departures".to_string(),
+                columns_name: vec!["id".to_string(), "city".to_string()],
+                values: vec![vec!["1".to_string(), "Buenos Aires, AR".to_string()]],
+            }
+        );
+        let query = "SELECT * FROM departures WHERE (status = 'ON TIME';";
+        assert!(parse_query(query.to_string()).is_err());
+        let query = "SELECT * FROM departures;;";
+        assert!(parse_query(query.to_string()).is_err());
     }
 
-    result
-        .iter()
-        .filter(|s| !s.is_empty())
-        .map(|s| s.to_string())
-        .collect()
-}
-
-/// This function allows to parse the relation of the query
-fn parse_relation(mut vec: Vec<String>) -> Result<Relation, ErrorTypes> {
-    if vec.len() == 3 {
-        let v1 = vec.remove(0);
-        let operator = vec.remove(0);
-        let v2 = vec.remove(0);
+    #[test]
+    fn test_parse_syntax_error() {
+        let query = "SELECT * FROM t WHERE a = 1 LIMT 5;";
+        let error = parse_query(query.to_string()).unwrap_err();
+        assert_eq!(error.code, 211);
+        assert_eq!(error.token, Some("LIMT".to_string()));
+        assert_eq!((error.line, error.column), (1, 29));
+        assert_eq!(
+            error.expected,
+            vec![
+                "AND",
+                "OR",
+                "ORDER BY",
+                "PER PARTITION LIMIT",
+                "LIMIT",
+                "ALLOW FILTERING",
+                ";"
+            ]
+        );
+        let error = parse_query("SELECT * FROM t WHERE a =".to_string()).unwrap_err();
+        assert_eq!(error.token, None);
+        assert_eq!((error.line, error.column), (1, 26));
+        assert_eq!(error.expected, vec!["?", "<value>"]);
+        let error = parse_query("SELECT * FROM t WHERE a != 1;".to_string()).unwrap_err();
+        assert_eq!(error.code, 212);
+        assert_eq!(error.token, Some("!=".to_string()));
+        assert!(parse_query("SELECT * FROM t WHERE a = 1".to_string()).is_ok());
+    }
 
-        match operator.as_str() {
-            "=" => Ok(Relation::Equal { v1, v2 }),
-            ">" => Ok(Relation::Higher { v1, v2 }),
-            "<" => Ok(Relation::Lower { v1, v2 }),
-            ">=" => Ok(Relation::HigherEqual { v1, v2 }),
-            "<=" => Ok(Relation::LowerEqual { v1, v2 }),
-            _ => Err(ErrorTypes::new(212, "Not supported operator".to_string())),
+    #[test]
+    fn test_parse_batch() {
+        let query = "BEGIN BATCH
+            UPDATE arrivals SET status = 'DELAYED' WHERE id = 1 AND destination = 'EZE';
+            UPDATE departures SET status = ? WHERE id = 1 AND origin = 'AEP'
+            APPLY BATCH;";
+        match parse_query(query.to_string()).unwrap() {
+            Query::Batch { logged, queries } => {
+                assert!(logged);
+                assert_eq!(queries.len(), 2);
+                assert_eq!(queries[1].table_name(), Some(&"departures".to_string()));
+            }
+            _ => panic!("Expected a batch"),
         }
-    } else {
-        Err(ErrorTypes::new(213, "Invalid input format".to_string()))
+        let query = "BEGIN UNLOGGED BATCH DELETE FROM arrivals WHERE id = 1; APPLY BATCH;";
+        assert!(matches!(
+            parse_query(query.to_string()).unwrap(),
+            Query::Batch { logged: false, .. }
+        ));
+        let query = "BEGIN BATCH SELECT * FROM arrivals; APPLY BATCH;";
+        assert_eq!(parse_query(query.to_string()).unwrap_err().code, 245);
+        let query = "BEGIN BATCH DELETE FROM arrivals WHERE id = 1;";
+        let error = parse_query(query.to_string()).unwrap_err();
+        assert_eq!(error.token, None);
+        assert!(error.expected.contains(&"APPLY BATCH".to_string()));
     }
 }
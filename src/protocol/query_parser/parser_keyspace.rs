@@ -1,47 +1,53 @@
-use super::{parser_utils::split_keyspace, query::Query};
-use crate::errors::error_types::ErrorTypes;
+use super::{
+    parser_utils::{parse_keyspace_name, take_if_not_exists, Tokens},
+    query::Query,
+    syntax_error::SyntaxError,
+};
+use std::collections::HashMap;
 
-///This function parses the keyspace query
-pub fn parse_keyspace(query: Vec<String>) -> Result<Query, ErrorTypes> {
-    let query_split: Vec<String> = split_keyspace(query);
+///This function parses the keyspace query, which can have IF NOT EXISTS before the name. The replication options are kept
+///as they are written, with the quotes removed, so the server can build the replication strategy from them.
+pub fn parse_keyspace(tokens: &mut Tokens) -> Result<Query, SyntaxError> {
+    tokens.expect_keyword("create")?;
+    tokens.expect_keyword("keyspace")?;
+    let if_not_exists = take_if_not_exists(tokens);
+    let keyspace_name = parse_keyspace_name(tokens)?;
+    let replication = parse_replication(tokens)?;
+    Ok(Query::CreateKeyspace {
+        keyspace_name,
+        replication,
+        if_not_exists,
+    })
+}
 
-    if query_split.len() < 2 {
-        return Err(ErrorTypes::new(220, "Table name missing".to_string()));
+///This function parses the replication options of a keyspace, written as `WITH REPLICATION = { 'option': value, ... }`.
+pub fn parse_replication(tokens: &mut Tokens) -> Result<HashMap<String, String>, SyntaxError> {
+    tokens.expect_keyword("with")?;
+    tokens.expect_keyword("replication")?;
+    if !tokens.take_operator("=") {
+        return Err(tokens.unexpected());
     }
-    let keyspace_name = query_split[2].trim_matches('\'').to_string();
-    let mut rep = None;
-
-    let mut index_colon = None;
-
-    for (i, word) in query_split.iter().enumerate() {
-        if word == ":"
-            && query_split[i - 1].to_lowercase().trim_matches('\'') == "replication_factor"
-        {
-            index_colon = Some(i);
-        } else if let Some(i_equal) = index_colon {
-            if i == i_equal + 1 {
-                rep = Some(word.parse::<usize>().unwrap());
-                break;
-            } else {
-                return Err(ErrorTypes::new(221, "Invalid query".to_string()));
-            }
+    tokens.expect_punctuation('{')?;
+    let mut replication = HashMap::new();
+    while !tokens.take_punctuation('}') {
+        if !replication.is_empty() {
+            tokens.expect_punctuation(',')?;
         }
+        let option = tokens.value()?;
+        tokens.expect_punctuation(':')?;
+        replication.insert(option, tokens.value()?);
     }
-    if let Some(replic) = rep {
-        let query = Query::CreateKeyspace {
-            keyspace_name,
-            replication: replic,
-        };
-        Ok(query)
-    } else {
-        Err(ErrorTypes::new(222, "Invalid query".to_string()))
+    if replication.is_empty() {
+        return Err(tokens.error(222, "Invalid query"));
     }
+    Ok(replication)
 }
 
 ///Test of the parse_keyspace function  
 #[cfg(test)]
 pub mod test {
     use crate::protocol::query_parser::{parser_impl::parse_query, query::Query};
+    use std::collections::HashMap;
     #[test]
     fn test_create_keyspace() {
         let query =
@@ -53,7 +59,44 @@ pub mod test {
             result,
             Query::CreateKeyspace {
                 keyspace_name: "flights_keyspace".to_string(),
-                replication: 4
+                replication: HashMap::from([("replication_factor".to_string(), "4".to_string())]),
+                if_not_exists: false,
+            }
+        )
+    }
+
+    #[test]
+    fn test_create_keyspace_network_topology() {
+        let query = "CREATE KEYSPACE flights_keyspace WITH REPLICATION = {'class': 'NetworkTopologyStrategy', 'dc1': 3, 'dc2': 2};"
+            .to_string();
+
+        let result = parse_query(query).unwrap();
+        assert_eq!(
+            result,
+            Query::CreateKeyspace {
+                keyspace_name: "flights_keyspace".to_string(),
+                replication: HashMap::from([
+                    ("class".to_string(), "NetworkTopologyStrategy".to_string()),
+                    ("dc1".to_string(), "3".to_string()),
+                    ("dc2".to_string(), "2".to_string()),
+                ]),
+                if_not_exists: false,
+            }
+        )
+    }
+
+    #[test]
+    fn test_create_keyspace_if_not_exists() {
+        let query = "CREATE KEYSPACE IF NOT EXISTS flights_keyspace WITH REPLICATION = {'replication_factor': 3};"
+            .to_string();
+
+        let result = parse_query(query).unwrap();
+        assert_eq!(
+            result,
+            Query::CreateKeyspace {
+                keyspace_name: "flights_keyspace".to_string(),
+                replication: HashMap::from([("replication_factor".to_string(), "3".to_string())]),
+                if_not_exists: true,
             }
         )
     }
@@ -1,78 +1,65 @@
 use super::{
-    clause::Clause,
-    parser_impl::{generic_validate, parse_conditions},
-    parser_utils::{get_position, get_position_conditional, normalize_vector},
-    query::Query,
+    clause::Clause, parser_impl::parse_conditions, parser_utils::parse_table_name,
+    parser_utils::Tokens, query::Query, syntax_error::SyntaxError,
 };
-use crate::errors::error_types::ErrorTypes;
+
+const PER_PARTITION_LIMIT: &[&str] = &["per", "partition", "limit"];
+const ALLOW_FILTERING: &[&str] = &["allow", "filtering"];
 
 /// This function parses a SELECT query.
-pub fn parse_select(mut query: Vec<String>) -> Result<Query, ErrorTypes> {
-    let from = String::from("from");
-    let pos_from = get_position(&query, &from)?;
-    if pos_from + 1 >= query.len() {
-        return Err(ErrorTypes::new(223, "Table name missing".to_string()));
+pub fn parse_select(tokens: &mut Tokens) -> Result<Query, SyntaxError> {
+    tokens.expect_keyword("select")?;
+    let selected_columns = parse_selected_columns(tokens)?;
+    tokens.expect_keyword("from")?;
+    let table_name = parse_table_name(tokens)?;
+    let mut conditions = Clause::Placeholder;
+    if tokens.take_keyword("where") {
+        conditions = parse_conditions(tokens)?;
     }
-    let table_name = query.remove(pos_from + 1);
-    let size = query.len();
-
-    let pos_from = get_position(&query, &from)?;
-    let where_ = String::from("where");
-    let order = String::from("order");
-    let pos_where = get_position_conditional(&query, &where_);
-    let pos_order = get_position_conditional(&query, &order);
-    let by = String::from("by");
-    let pos_by = get_position_conditional(&query, &by);
-
-    generic_validate(&query, &pos_from, &pos_where)?;
-    generic_validate(&query, &pos_from, &pos_order)?;
-    select_validate(&query, &pos_from, &pos_where, &pos_order, &pos_by)?;
-
-    let mut where_order = query.split_off(pos_from);
-    let selected_columns = normalize_vector(query.split_off(1));
-
-    let mut rest = vec![];
-
-    if pos_where != size {
-        rest = where_order.split_off(2);
-    } else if pos_order != size && pos_by != size {
-        rest = where_order.split_off(pos_by - query.len() - 1);
+    let mut order = vec![];
+    if tokens.take_keywords(&["order", "by"]) {
+        order = parse_order(tokens)?;
     }
-    let mut order: Vec<String> = vec![];
-    if pos_order != size {
-        let mut order_vec = rest.split_off(pos_by - query.len() - where_order.len() - 1);
-        order_vec.remove(0);
-        order = normalize_vector(order_vec);
+    let mut per_partition_limit = None;
+    if tokens.take_keywords(PER_PARTITION_LIMIT) {
+        per_partition_limit = Some(tokens.positive_integer()?);
     }
-    let mut conditions = Clause::Placeholder;
-    if pos_where != size {
-        conditions = parse_conditions(rest)?;
+    let mut limit = None;
+    if tokens.take_keyword("limit") {
+        limit = Some(tokens.positive_integer()?);
     }
+    let allow_filtering = tokens.take_keywords(ALLOW_FILTERING);
     Ok(Query::Select {
         table_name,
         selected_columns,
         conditions,
         order,
+        limit,
+        per_partition_limit,
+        allow_filtering,
     })
 }
 
-/// Validates the SELECT query
-fn select_validate(
-    query: &[String],
-    pos_from: &usize,
-    pos_where: &usize,
-    pos_order: &usize,
-    pos_by: &usize,
-) -> Result<(), ErrorTypes> {
-    if *pos_where != query.len() && *pos_from != *pos_where - 1 {
-        return Err(ErrorTypes::new(224, "Invalid syntax".to_string()));
+/// This function parses the selected columns, which are `*` or a list of columns separated by commas.
+fn parse_selected_columns(tokens: &mut Tokens) -> Result<Vec<String>, SyntaxError> {
+    if tokens.take_punctuation('*') {
+        return Ok(vec!["*".to_string()]);
     }
+    let mut columns = vec![tokens.identifier()?];
+    while tokens.take_punctuation(',') {
+        columns.push(tokens.identifier()?);
+    }
+    Ok(columns)
+}
 
-    if *pos_where != query.len() && *pos_order != query.len() && *pos_order == pos_where + 1 {
-        return Err(ErrorTypes::new(225, "There is no WHERE clause".to_string()));
+/// This function parses the columns of an ORDER BY, followed by the direction, `ASC` or `DESC`, if it is written.
+fn parse_order(tokens: &mut Tokens) -> Result<Vec<String>, SyntaxError> {
+    let mut order = vec![tokens.identifier()?];
+    while tokens.take_punctuation(',') {
+        order.push(tokens.identifier()?);
     }
-    if (*pos_order != query.len() || *pos_by != query.len()) && *pos_by != pos_order + 1 {
-        return Err(ErrorTypes::new(226, "Invalid syntax".to_string()));
+    if tokens.is_keyword("asc") || tokens.is_keyword("desc") {
+        order.push(tokens.identifier()?);
     }
-    Ok(())
+    Ok(order)
 }
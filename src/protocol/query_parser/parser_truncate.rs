@@ -0,0 +1,14 @@
+use super::{
+    parser_utils::{parse_table_name, Tokens},
+    query::Query,
+    syntax_error::SyntaxError,
+};
+
+///This function parses the TRUNCATE query, where the keyword TABLE is optional.
+pub fn parse_truncate(tokens: &mut Tokens) -> Result<Query, SyntaxError> {
+    tokens.expect_keyword("truncate")?;
+    tokens.take_keyword("table");
+    Ok(Query::Truncate {
+        table_name: parse_table_name(tokens)?,
+    })
+}
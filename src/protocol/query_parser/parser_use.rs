@@ -1,16 +1,15 @@
-use super::query::Query;
-use crate::errors::error_types::ErrorTypes;
+use super::{
+    parser_utils::{parse_keyspace_name, Tokens},
+    query::Query,
+    syntax_error::SyntaxError,
+};
 
 ///This function parses the keyspace query
-pub fn parse_use(query: Vec<String>) -> Result<Query, ErrorTypes> {
-    if query.len() < 2 {
-        return Err(ErrorTypes::new(230, "Table name missing".to_string()));
-    }
-    let keyspace_name = query[1]
-        .trim_end_matches(';')
-        .trim_matches('\'')
-        .to_string();
-    Ok(Query::Use { keyspace_name })
+pub fn parse_use(tokens: &mut Tokens) -> Result<Query, SyntaxError> {
+    tokens.expect_keyword("use")?;
+    Ok(Query::Use {
+        keyspace_name: parse_keyspace_name(tokens)?,
+    })
 }
 
 #[cfg(test)]
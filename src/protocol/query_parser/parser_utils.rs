@@ -1,162 +1,285 @@
-use crate::errors::error_types::ErrorTypes;
+use super::{
+    lexer::{tokenize, Token, TokenKind},
+    query::bind_marker,
+    syntax_error::SyntaxError,
+};
 
-/// This function splits a vector of strings'
-pub fn split_par(vec: Vec<String>) -> Vec<String> {
-    let mut result: Vec<String> = Vec::new();
+/// This struct holds the tokens of a query and the position of the next one to parse. Every `parse_*` function takes
+/// the tokens it understands from it and leaves the rest to the function that called it.
+///
+/// It also remembers the tokens that were tried at the position of the next token and were not there, so an error
+/// at that position can tell which tokens were expected.
+pub struct Tokens {
+    tokens: Vec<Token>,
+    position: usize,
+    end: (usize, usize),
+    expected: Vec<String>,
+    expected_at: usize,
+    markers: usize,
+}
+
+impl Tokens {
+    /// This function splits a query in tokens.
+    pub fn new(query: &str) -> Result<Self, SyntaxError> {
+        let line = query.matches('\n').count() + 1;
+        let column = query
+            .rsplit('\n')
+            .next()
+            .unwrap_or_default()
+            .chars()
+            .count()
+            + 1;
+        Ok(Tokens {
+            tokens: tokenize(query)?,
+            position: 0,
+            end: (line, column),
+            expected: Vec::new(),
+            expected_at: 0,
+            markers: 0,
+        })
+    }
+
+    /// This function returns the next token without taking it.
+    pub fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position)
+    }
+
+    /// This function takes the next token.
+    pub fn advance(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.position).cloned();
+        if token.is_some() {
+            self.position += 1;
+        }
+        token
+    }
+
+    /// This function checks if every token was taken.
+    pub fn is_at_end(&self) -> bool {
+        self.position >= self.tokens.len()
+    }
+
+    /// This function returns the position of the next token, to report an error there after taking more tokens.
+    pub fn position(&self) -> usize {
+        self.position
+    }
 
-    for elem in vec {
-        let left_part: Vec<&str> = elem.split_inclusive("(").collect();
+    /// This function checks if the next token is the keyword `keyword`, without taking it.
+    pub fn is_keyword(&mut self, keyword: &str) -> bool {
+        let found = self.is_at_keyword(0, keyword);
+        if !found {
+            self.expecting(keyword.to_uppercase());
+        }
+        found
+    }
 
-        for part in left_part {
-            let right_part: Vec<&str> = part.split(")").collect();
+    /// This function checks if the token `offset` places after the next one is the keyword `keyword`.
+    pub fn is_at_keyword(&self, offset: usize, keyword: &str) -> bool {
+        self.tokens
+            .get(self.position + offset)
+            .is_some_and(|token| token.is_keyword(keyword))
+    }
 
-            for (i, sub_part) in right_part.iter().enumerate() {
-                if !sub_part.is_empty() {
-                    result.push(sub_part.trim_end_matches(";").into());
-                }
-                if i < right_part.len() - 1 {
-                    result.push(")".into());
-                }
+    /// This function takes the next tokens if they are the keywords `keywords`, and returns whether they were.
+    pub fn take_keywords(&mut self, keywords: &[&str]) -> bool {
+        let found = keywords
+            .iter()
+            .enumerate()
+            .all(|(i, keyword)| self.is_at_keyword(i, keyword));
+        if found {
+            self.position += keywords.len();
+        } else {
+            self.expecting(keywords.join(" ").to_uppercase());
+        }
+        found
+    }
+
+    /// This function takes the next token if it is the keyword `keyword`, and returns whether it was.
+    pub fn take_keyword(&mut self, keyword: &str) -> bool {
+        self.take_keywords(&[keyword])
+    }
+
+    /// This function takes the next token, which has to be the keyword `keyword`.
+    pub fn expect_keyword(&mut self, keyword: &str) -> Result<(), SyntaxError> {
+        if self.take_keyword(keyword) {
+            return Ok(());
+        }
+        Err(self.unexpected())
+    }
+
+    /// This function takes the next token if it is the punctuation `symbol`, and returns whether it was.
+    pub fn take_punctuation(&mut self, symbol: char) -> bool {
+        let found = self
+            .peek()
+            .is_some_and(|token| token.is_punctuation(symbol));
+        if found {
+            self.position += 1;
+        } else {
+            self.expecting(symbol.to_string());
+        }
+        found
+    }
+
+    /// This function takes the next token, which has to be the punctuation `symbol`.
+    pub fn expect_punctuation(&mut self, symbol: char) -> Result<(), SyntaxError> {
+        if self.take_punctuation(symbol) {
+            return Ok(());
+        }
+        Err(self.unexpected())
+    }
+
+    /// This function takes the next token if it is the operator `operator`, and returns whether it was.
+    pub fn take_operator(&mut self, operator: &str) -> bool {
+        let found = self
+            .peek()
+            .is_some_and(|token| token.kind == TokenKind::Operator(operator.to_string()));
+        if found {
+            self.position += 1;
+        } else {
+            self.expecting(operator.to_string());
+        }
+        found
+    }
+
+    /// This function takes the next token, which has to be an identifier, with or without double quotes, and returns its name.
+    pub fn identifier(&mut self) -> Result<String, SyntaxError> {
+        match self.peek().map(|token| &token.kind) {
+            Some(TokenKind::Identifier(name)) | Some(TokenKind::QuotedIdentifier(name)) => {
+                let name = name.to_string();
+                self.position += 1;
+                Ok(name)
+            }
+            _ => {
+                self.expecting("<identifier>".to_string());
+                Err(self.unexpected())
             }
         }
     }
 
-    result
-}
-/// This function splits a vector of strings by keyspaces.
-pub fn split_keyspace(vec: Vec<String>) -> Vec<String> {
-    let mut result: Vec<String> = Vec::new();
-    for elem in vec {
-        let left_part: Vec<&str> = elem.split_inclusive("{").collect();
-
-        for part in left_part {
-            let right_part: Vec<&str> = part.split("}").collect();
-
-            for (i, sub_part) in right_part.iter().enumerate() {
-                if sub_part.contains(":") {
-                    result.push(sub_part.trim_end_matches(":").into());
-                    result.push(":".into());
-                } else if !sub_part.is_empty() {
-                    result.push(sub_part.trim_end_matches(";").into());
-                }
-                if i < right_part.len() - 1 {
-                    result.push("}".into());
-                }
+    /// This function takes the next token, which has to be a value: a text, a number, a UUID or a constant like `true`.
+    /// It returns the value without quotes.
+    pub fn value(&mut self) -> Result<String, SyntaxError> {
+        match self.peek().map(|token| &token.kind) {
+            Some(TokenKind::String(value))
+            | Some(TokenKind::Number(value))
+            | Some(TokenKind::Uuid(value))
+            | Some(TokenKind::Identifier(value)) => {
+                let value = value.to_string();
+                self.position += 1;
+                Ok(value)
+            }
+            _ => {
+                self.expecting("<value>".to_string());
+                Err(self.unexpected())
             }
         }
     }
-    result
-}
 
-/// This function splits a vector of strings by commas
-pub fn split_comma(vec: Vec<String>) -> Vec<String> {
-    vec.iter()
-        .flat_map(|s| {
-            s.split(',')
-                .map(|x| x.trim().to_string())
-                .filter(|x| !x.is_empty())
-        })
-        .collect()
-}
+    /// This function takes the next token, which has to be a value or a bind marker `?`. A bind marker is returned as a
+    /// value that can not be written in a query, which is replaced when the prepared statement is executed.
+    pub fn term(&mut self) -> Result<String, SyntaxError> {
+        if self.take_punctuation('?') {
+            self.markers += 1;
+            return Ok(bind_marker(self.markers - 1));
+        }
+        self.value()
+    }
 
-/// This function joins the compounds
-pub fn join_compounds(vec: Vec<String>) -> Vec<String> {
-    let mut new: Vec<String> = vec![];
-    let mut aux: Vec<String> = vec![];
-    let mut operating = false;
-    for word in vec {
-        if word.starts_with("'") && word.ends_with("'")
-            || word.starts_with("'") && word.ends_with("')")
-        {
-            new.push(
-                word.trim_start_matches("'")
-                    .trim_end_matches("'")
-                    .to_string(),
-            );
-        } else if word.starts_with("'") {
-            aux.push(word.trim_start_matches("'").to_string());
-            operating = true
-        } else if word.ends_with("'") || word.ends_with("')") || word.ends_with(')') {
-            aux.push(word.trim_end_matches("'").to_string());
-            new.push(aux.join(" "));
-            aux.clear();
-            operating = false;
-        } else if operating {
-            aux.push(format!(" {}", word));
-        } else {
-            new.push(word);
+    /// This function takes a positive integer, like the one of a `LIMIT`.
+    pub fn positive_integer(&mut self) -> Result<usize, SyntaxError> {
+        let number = match self.peek().map(|token| &token.kind) {
+            Some(TokenKind::Number(number)) => number.parse::<usize>(),
+            _ => {
+                self.expecting("<positive integer>".to_string());
+                return Err(self.unexpected());
+            }
+        };
+        match number {
+            Ok(number) if number > 0 => {
+                self.position += 1;
+                Ok(number)
+            }
+            _ => Err(self.error(240, "The limit must be a positive integer")),
         }
     }
-    new
-}
 
-/// This function splits the whitespaces
-pub fn split_whitespace(query: String) -> Vec<String> {
-    let mut result: Vec<String> = vec![];
-    let mut word = String::from("");
-    for ch in query.chars() {
-        if ch != ' ' {
-            word.push(ch);
-            continue;
+    /// This function takes a list between parenthesis, whose elements are separated by commas and parsed with `element`.
+    pub fn list<T, F>(&mut self, mut element: F) -> Result<Vec<T>, SyntaxError>
+    where
+        F: FnMut(&mut Tokens) -> Result<T, SyntaxError>,
+    {
+        self.expect_punctuation('(')?;
+        let mut elements = vec![element(self)?];
+        while self.take_punctuation(',') {
+            elements.push(element(self)?);
         }
-        result.push(word);
-        word = String::from("")
+        self.expect_punctuation(')')?;
+        Ok(elements)
     }
-    result.push(word);
-    result
-}
 
-/// This function returns the position of an element that is not mandatory to be in the vector, if it is not, it returns the length of the vector
-pub fn get_position_conditional(vec: &[String], keyword: &String) -> usize {
-    match vec.iter().position(|t| t.to_lowercase() == *keyword) {
-        Some(pos) => pos,
-        None => vec.len(),
+    /// This function remembers that `expected` was tried at the position of the next token.
+    fn expecting(&mut self, expected: String) {
+        if self.expected_at != self.position {
+            self.expected.clear();
+            self.expected_at = self.position;
+        }
+        if !self.expected.contains(&expected) {
+            self.expected.push(expected);
+        }
     }
-}
 
-/// This function returns the position of an element that is mandatory to be in the vector, if it is not, it returns an error
-pub fn get_position(vec: &[String], keyword: &String) -> Result<usize, ErrorTypes> {
-    match vec.iter().position(|t| t.to_lowercase() == *keyword) {
-        Some(pos) => Ok(pos),
-        None => Err(ErrorTypes::new(
-            231,
-            format!("Keyword not found {}", keyword),
-        )),
+    /// This function returns the error `code` with its `message` at the next token.
+    pub fn error(&self, code: i32, message: &str) -> SyntaxError {
+        self.error_at(self.position, code, message)
+    }
+
+    /// This function returns the error `code` with its `message` at the token in `position`, with the tokens that
+    /// were expected there.
+    pub fn error_at(&self, position: usize, code: i32, message: &str) -> SyntaxError {
+        let (token, (line, column)) = match self.tokens.get(position) {
+            Some(token) => (Some(token.text()), (token.line, token.column)),
+            None => (None, self.end),
+        };
+        let expected = match self.expected_at == position {
+            true => self.expected.clone(),
+            false => vec![],
+        };
+        SyntaxError {
+            code,
+            message: message.to_string(),
+            token,
+            line,
+            column,
+            expected,
+        }
+    }
+
+    /// This function returns the error of a next token that is not one of the expected ones.
+    pub fn unexpected(&self) -> SyntaxError {
+        self.error(211, "Syntax error")
     }
 }
 
-/// This function orders the selected columns by position
-pub fn order_by_position(
-    column: String,
-    order: Vec<String>,
-    mut selected: Vec<Vec<String>>,
-    file_columns: &[String],
-) -> Result<Vec<Vec<String>>, ErrorTypes> {
-    let pos = get_position(file_columns, &column)?;
-    if order.is_empty() || order[0].to_lowercase().as_str() == "asc" {
-        selected.sort_by(|a, b| b[pos].cmp(&a[pos]));
-    } else if order[0].to_lowercase().as_str() == "desc" {
-        selected.sort_by(|a, b| a[pos].cmp(&b[pos]));
-    } else {
-        return Err(ErrorTypes::new(232, "Invalid sorting".to_string()));
-    }
-    Ok(selected)
+/// This function takes the words `IF NOT EXISTS` if they are next, and returns whether they were.
+pub fn take_if_not_exists(tokens: &mut Tokens) -> bool {
+    tokens.take_keywords(&["if", "not", "exists"])
 }
 
-/// This function normalizes the vector
-pub fn normalize_vector(vec: Vec<String>) -> Vec<String> {
-    let vector = split_comma(vec);
-    join_compounds(
-        vector
-            .into_iter()
-            .map(|s| {
-                s.trim_start_matches("(")
-                    .trim_end_matches(";")
-                    .trim_end_matches(")")
-                    .trim_end_matches(")")
-                    .trim_end_matches(",")
-                    .to_string()
-            })
-            .collect(),
-    )
+/// This function parses the name of a table, which can be qualified with the name of its keyspace as `keyspace.table`.
+/// It returns the name without quotes.
+pub fn parse_table_name(tokens: &mut Tokens) -> Result<String, SyntaxError> {
+    let mut name = tokens.identifier()?;
+    if tokens.take_punctuation('.') {
+        name = format!("{}.{}", name, tokens.identifier()?);
+    }
+    Ok(name)
+}
+
+/// This function parses the name of a keyspace, which can also be written between single quotes.
+pub fn parse_keyspace_name(tokens: &mut Tokens) -> Result<String, SyntaxError> {
+    if let Some(TokenKind::String(name)) = tokens.peek().map(|token| &token.kind) {
+        let name = name.to_string();
+        tokens.advance();
+        return Ok(name);
+    }
+    tokens.identifier()
 }
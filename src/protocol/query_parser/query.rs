@@ -1,6 +1,11 @@
-use super::clause::Clause;
+use super::{clause::Clause, relation::Relation};
+use crate::errors::error_types::ErrorTypes;
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
-#[derive(Debug, PartialEq)]
+
+const BIND_MARKER: &str = "\0?";
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
 
 /// This enum represents the different types of Queries that can be sent to the server.
 pub enum Query {
@@ -23,18 +28,259 @@ pub enum Query {
         selected_columns: Vec<String>,
         conditions: Clause,
         order: Vec<String>,
+        limit: Option<usize>,
+        per_partition_limit: Option<usize>,
+        allow_filtering: bool,
     },
     CreateTable {
         table_name: String,
         columns_type: Vec<(String, String)>,
         clustering_key: Vec<String>,
         primary_key: Vec<String>,
+        if_not_exists: bool,
     },
     CreateKeyspace {
         keyspace_name: String,
-        replication: usize,
+        replication: HashMap<String, String>,
+        if_not_exists: bool,
     },
     Use {
         keyspace_name: String,
     },
+    DropKeyspace {
+        keyspace_name: String,
+        if_exists: bool,
+    },
+    DropTable {
+        table_name: String,
+        if_exists: bool,
+    },
+    Truncate {
+        table_name: String,
+    },
+    AlterTable {
+        table_name: String,
+        operation: AlterTableOperation,
+    },
+    AlterKeyspace {
+        keyspace_name: String,
+        replication: HashMap<String, String>,
+    },
+    Batch {
+        logged: bool,
+        queries: Vec<Query>,
+    },
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+/// This enum represents the changes that an ALTER TABLE query makes to the columns of a table.
+pub enum AlterTableOperation {
+    Add(String, String),
+    Drop(String),
+}
+
+impl Query {
+    /// This function qualifies the name of the table of the query with the keyspace in use, unless it already has one.
+    /// It fails if the name of the table is not qualified and there is no keyspace in use.
+    pub fn qualify(mut self, keyspace: Option<&String>) -> Result<Query, ErrorTypes> {
+        let table_name = match &mut self {
+            Query::Batch { logged, queries } => {
+                return Ok(Query::Batch {
+                    logged: *logged,
+                    queries: std::mem::take(queries)
+                        .into_iter()
+                        .map(|query| query.qualify(keyspace))
+                        .collect::<Result<Vec<Query>, ErrorTypes>>()?,
+                })
+            }
+            Query::Insert { table_name, .. }
+            | Query::Update { table_name, .. }
+            | Query::Delete { table_name, .. }
+            | Query::Select { table_name, .. }
+            | Query::CreateTable { table_name, .. }
+            | Query::DropTable { table_name, .. }
+            | Query::Truncate { table_name }
+            | Query::AlterTable { table_name, .. } => table_name,
+            Query::CreateKeyspace { .. }
+            | Query::Use { .. }
+            | Query::DropKeyspace { .. }
+            | Query::AlterKeyspace { .. } => return Ok(self),
+        };
+        if !table_name.contains('.') {
+            match keyspace {
+                Some(keyspace) => *table_name = format!("{}.{}", keyspace, table_name),
+                None => return Err(ErrorTypes::new(234, "Keyspace not selected".to_string())),
+            }
+        }
+        Ok(self)
+    }
+
+    /// This function returns the name of the table of the query, if it has one.
+    pub fn table_name(&self) -> Option<&String> {
+        match self {
+            Query::Insert { table_name, .. }
+            | Query::Update { table_name, .. }
+            | Query::Delete { table_name, .. }
+            | Query::Select { table_name, .. }
+            | Query::CreateTable { table_name, .. }
+            | Query::DropTable { table_name, .. }
+            | Query::Truncate { table_name }
+            | Query::AlterTable { table_name, .. } => Some(table_name),
+            _ => None,
+        }
+    }
+
+    /// This function returns the keyspaces whose data or schema the query changes, or the ones of every query of a
+    /// batch. The names of the tables must be already qualified with their keyspace.
+    pub fn written_keyspaces(&self) -> Vec<&str> {
+        match self {
+            Query::Batch { queries, .. } => queries
+                .iter()
+                .flat_map(|query| query.written_keyspaces())
+                .collect(),
+            Query::Select { .. } | Query::Use { .. } => Vec::new(),
+            Query::CreateKeyspace { keyspace_name, .. }
+            | Query::DropKeyspace { keyspace_name, .. }
+            | Query::AlterKeyspace { keyspace_name, .. } => vec![keyspace_name.as_str()],
+            _ => self
+                .table_name()
+                .and_then(|table_name| table_name.split_once('.'))
+                .map(|(keyspace, _)| keyspace)
+                .into_iter()
+                .collect(),
+        }
+    }
+
+    /// This function returns the column of each bind marker `?` of the query, in the order they were written.
+    pub fn bind_markers(&self) -> Vec<String> {
+        let mut markers = Vec::new();
+        self.clone()
+            .for_each_value(&mut |column, value| {
+                if let Some(index) = bind_marker_index(value) {
+                    markers.push((index, column.to_string()));
+                }
+                Ok(())
+            })
+            .unwrap_or_default();
+        markers.sort();
+        markers.into_iter().map(|(_, column)| column).collect()
+    }
+
+    /// This function replaces the bind markers `?` of the query with `values`, in the order the markers were written.
+    /// It fails if there is not a value for each marker.
+    pub fn bind(mut self, values: &[String]) -> Result<Query, ErrorTypes> {
+        let markers = self.bind_markers().len();
+        if values.len() != markers {
+            return Err(ErrorTypes::new(
+                244,
+                format!(
+                    "There are {} bind markers but {} values",
+                    markers,
+                    values.len()
+                ),
+            ));
+        }
+        self.for_each_value(&mut |_, value| {
+            if let Some(index) = bind_marker_index(value) {
+                *value = values[index].clone();
+            }
+            Ok(())
+        })?;
+        Ok(self)
+    }
+
+    /// This function calls `f` with every value of the query that is written for a column, with the name of that column.
+    fn for_each_value<F>(&mut self, f: &mut F) -> Result<(), ErrorTypes>
+    where
+        F: FnMut(&str, &mut String) -> Result<(), ErrorTypes>,
+    {
+        match self {
+            Query::Insert {
+                columns_name,
+                values,
+                ..
+            } => {
+                for row in values.iter_mut() {
+                    for (column, value) in columns_name.iter().zip(row.iter_mut()) {
+                        f(column, value)?;
+                    }
+                }
+                Ok(())
+            }
+            Query::Update {
+                column_value,
+                conditions,
+                ..
+            } => {
+                for (column, value) in column_value.iter_mut() {
+                    f(column, value)?;
+                }
+                clause_values(conditions, f)
+            }
+            Query::Delete { conditions, .. } | Query::Select { conditions, .. } => {
+                clause_values(conditions, f)
+            }
+            Query::Batch { queries, .. } => queries
+                .iter_mut()
+                .try_for_each(|query| query.for_each_value(f)),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// This function returns the value that stands for the bind marker number `index` of a query until it is bound.
+pub fn bind_marker(index: usize) -> String {
+    format!("{}{}", BIND_MARKER, index)
+}
+
+/// This function returns the number of the bind marker that a value stands for, if it stands for one.
+fn bind_marker_index(value: &str) -> Option<usize> {
+    value.strip_prefix(BIND_MARKER)?.parse().ok()
+}
+
+/// This function calls `f` with every value of the relations of a clause, with the name of its column.
+fn clause_values<F>(clause: &mut Clause, f: &mut F) -> Result<(), ErrorTypes>
+where
+    F: FnMut(&str, &mut String) -> Result<(), ErrorTypes>,
+{
+    match clause {
+        Clause::And { left, right } | Clause::Or { left, right } => {
+            clause_values(left, f)?;
+            clause_values(right, f)
+        }
+        Clause::Not { right } => clause_values(right, f),
+        Clause::Term { relation } => match relation {
+            Relation::Equal { v1, v2 }
+            | Relation::Higher { v1, v2 }
+            | Relation::HigherEqual { v1, v2 }
+            | Relation::LowerEqual { v1, v2 }
+            | Relation::Lower { v1, v2 } => f(v1, v2),
+            Relation::In { v1, values } => values.iter_mut().try_for_each(|value| f(v1, value)),
+        },
+        Clause::Placeholder => Ok(()),
+    }
+}
+
+#[cfg(test)]
+pub mod test {
+    use crate::protocol::query_parser::parser_impl::parse_query;
+
+    #[test]
+    fn test_bind() {
+        let query = "UPDATE arrivals SET status = ? WHERE id = ? AND destination IN ('AEP', ?);";
+        let query = parse_query(query.to_string()).unwrap();
+        assert_eq!(query.bind_markers(), vec!["status", "id", "destination"]);
+        let values = [
+            "DELAYED', 'x".to_string(),
+            "3".to_string(),
+            "EZE".to_string(),
+        ];
+        let expected = "UPDATE arrivals SET status = 'DELAYED'', ''x' WHERE id = 3 AND destination IN ('AEP', 'EZE');";
+        assert_eq!(
+            query.clone().bind(&values).unwrap(),
+            parse_query(expected.to_string()).unwrap()
+        );
+        assert!(query.bind(&values[..2]).is_err());
+        assert!(parse_query("SELECT * FROM t WHERE a = '\0?0';".to_string()).is_err());
+    }
 }
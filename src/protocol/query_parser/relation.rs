@@ -8,4 +8,5 @@ pub enum Relation {
     HigherEqual { v1: String, v2: String },
     LowerEqual { v1: String, v2: String },
     Lower { v1: String, v2: String },
+    In { v1: String, values: Vec<String> },
 }
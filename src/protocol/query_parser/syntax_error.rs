@@ -0,0 +1,42 @@
+use crate::errors::error_types::ErrorTypes;
+use std::fmt;
+
+#[derive(Debug, PartialEq, Clone)]
+/// This struct represents an error found while parsing a query.
+///
+/// It has the code and the description of the error, the token where it was found, as it was written, with its line
+/// and column, and the tokens that could have been written there instead. The token is `None` when the query ended
+/// before the error.
+pub struct SyntaxError {
+    pub code: i32,
+    pub message: String,
+    pub token: Option<String>,
+    pub line: usize,
+    pub column: usize,
+    pub expected: Vec<String>,
+}
+
+impl fmt::Display for SyntaxError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} at line {}, column {}",
+            self.message, self.line, self.column
+        )?;
+        match &self.token {
+            Some(token) => write!(f, ", found {}", token)?,
+            None => write!(f, ", found the end of the query")?,
+        }
+        match self.expected.as_slice() {
+            [] => Ok(()),
+            [expected] => write!(f, ", expected {}", expected),
+            expected => write!(f, ", expected one of {}", expected.join(", ")),
+        }
+    }
+}
+
+impl From<SyntaxError> for ErrorTypes {
+    fn from(error: SyntaxError) -> Self {
+        ErrorTypes::new(error.code, error.to_string())
+    }
+}
@@ -0,0 +1,12 @@
+use crate::protocol::query_parser::query::Query;
+
+/// Represents a statement of a Batch message, which is sent with the values of its bind markers.
+///
+/// ## Variants:
+/// - `Query`: A query that was sent as text, already parsed.
+/// - `Prepared`: The identifier of a prepared statement.
+#[derive(Debug)]
+pub enum BatchStatement {
+    Query(Query),
+    Prepared(Vec<u8>),
+}
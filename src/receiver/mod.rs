@@ -1,4 +1,6 @@
+pub mod batch_statement;
 pub mod message;
+pub mod query_parameters;
 pub mod read_notation;
 pub mod receiver_impl;
 pub mod request_message;
@@ -0,0 +1,25 @@
+use crate::protocol::protocol_notations::{consistency::Consistency, value::Value};
+
+/// This struct represents the parameters that a client sends with a query, in a Query or an Execute message.
+///
+/// ## Fields:
+/// - `consistency`: The consistency level of the query.
+/// - `values`: The values of the bind markers `?` of the query, in the order they were written, in their binary form.
+///   They are sent with the `Values` flag.
+/// - `keyspace`: The keyspace in which the query has to run, if the client sent one with the `Keyspace` flag.
+///   Otherwise, the query runs in the keyspace in use of the connection.
+/// - `skip_metadata`: If the client sent the `SkipMetadata` flag, because it already has the metadata of the rows of
+///   the result, so they are sent without it.
+/// - `page_size`: The amount of rows of each page of the result of a select, if the client sent it with the `PageSize`
+///   flag. Otherwise, every row is returned at once.
+/// - `paging_state`: The position where the page of the result starts, as the previous page returned it, if the client
+///   sent it with the `PagingState` flag. Otherwise, the result starts at its first row.
+#[derive(Debug)]
+pub struct QueryParameters {
+    pub consistency: Consistency,
+    pub values: Vec<Value>,
+    pub keyspace: Option<String>,
+    pub skip_metadata: bool,
+    pub page_size: Option<usize>,
+    pub paging_state: Option<Vec<u8>>,
+}
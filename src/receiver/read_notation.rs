@@ -73,9 +73,9 @@ pub fn read_long_string(bytes: &mut Vec<u8>) -> Result<String, ErrorTypes> {
     Ok(result)
 }
 
-/// This function receives an array of bytes and decode it to a Vec<String>.
+/// This function receives an array of bytes and decode it to a Vec<String>. The length of the list is a short.
 pub fn read_string_list(bytes: &mut Vec<u8>) -> Result<Vec<String>, ErrorTypes> {
-    let length = read_int(bytes)? as usize;
+    let length = read_short(bytes)? as usize;
     let mut result = Vec::new();
     for _ in 0..length {
         result.push(read_string(bytes)?);
@@ -108,6 +108,9 @@ pub fn read_value(bytes: &mut Vec<u8>) -> Result<Value, ErrorTypes> {
     if value_type == -2 {
         return Ok(Value::NotSet);
     }
+    if bytes.len() < value_type as usize {
+        return Err(ErrorTypes::new(306, "Bytes is too short".to_string()));
+    }
 
     Ok(Value::Normal(bytes.drain(0..value_type as usize).collect()))
 }
@@ -259,8 +262,7 @@ mod tests {
     #[test]
     fn test_read_string_list() {
         let mut bytes = vec![
-            0x00, 0x00, 0x00, 0x02, 0x00, 0x04, 0x74, 0x65, 0x73, 0x74, 0x00, 0x04, 0x74, 0x65,
-            0x73, 0x74,
+            0x00, 0x02, 0x00, 0x04, 0x74, 0x65, 0x73, 0x74, 0x00, 0x04, 0x74, 0x65, 0x73, 0x74,
         ];
         assert_eq!(
             read_string_list(&mut bytes),
@@ -316,8 +318,8 @@ mod tests {
 
     fn test_read_string_multimap() {
         let mut bytes = vec![
-            0x00, 0x01, 0x00, 0x04, 0x74, 0x65, 0x73, 0x74, 0x00, 0x00, 0x00, 0x02, 0x00, 0x04,
-            0x74, 0x65, 0x73, 0x74, 0x00, 0x04, 0x74, 0x65, 0x73, 0x74,
+            0x00, 0x01, 0x00, 0x04, 0x74, 0x65, 0x73, 0x74, 0x00, 0x02, 0x00, 0x04, 0x74, 0x65,
+            0x73, 0x74, 0x00, 0x04, 0x74, 0x65, 0x73, 0x74,
         ];
         let mut map = HashMap::new();
         map.insert(
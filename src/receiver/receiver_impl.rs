@@ -5,11 +5,27 @@ use crate::errors::error_types::ErrorTypes;
 use crate::protocol::frames_headers::{
     flags::Flags, header::Header, opcode::Opcode, version::Version,
 };
+use crate::protocol::protocol_body::batch_type::{BatchQueryKind, BatchType};
 use crate::protocol::protocol_body::compression::{self, Compression};
-use crate::protocol::protocol_notations::{flags_row::FlagsRow, value::Value};
+use crate::protocol::protocol_body::data_type::DataType;
+use crate::protocol::protocol_body::query_flags::QueryFlags;
+use crate::protocol::protocol_body::{
+    event_kind::EventKindChange, schema_change::SchemaChangeType, status_node::StatusNode,
+    topology_change::TopologyChangeType,
+};
+use crate::protocol::protocol_notations::{
+    columns_metadata::ColumnsMetadata, cql_value::CqlValue, flags_row::FlagsRow, value::Value,
+};
 use crate::protocol::query_parser::parser_impl::parse_query;
-use crate::receiver::{message::Message, request_message::RequestMessage};
+use crate::receiver::{
+    batch_statement::BatchStatement, message::Message, query_parameters::QueryParameters,
+    request_message::RequestMessage,
+};
 use read_notation::*;
+use std::io::{ErrorKind, Read};
+
+/// The longest body of a frame that a node accepts, 256 MB like the native protocol.
+const MAX_FRAME_LENGTH: i32 = 256 * 1024 * 1024;
 
 /// Parses a frame and returns the corresponding Message struct.
 pub fn receive_message(
@@ -27,11 +43,7 @@ pub fn receive_message(
 
 /// This function receives an array of bytes and returns a Header struct.
 fn create_header(bytes: &mut [u8]) -> Result<Header, ErrorTypes> {
-    let version: Version = match bytes[0] {
-        0x05 => Version::Request,
-        0x85 => Version::Response,
-        _ => return Err(ErrorTypes::new(312, "Invalid version".to_string())),
-    };
+    let (version, protocol_version) = Version::from_byte(bytes[0]);
     let flag: Vec<Flags> = get_flag(bytes[1])?;
     let stream = u16::from_be_bytes([bytes[2], bytes[3]]);
     let opcode = get_opcode(bytes[4])?;
@@ -42,15 +54,58 @@ fn create_header(bytes: &mut [u8]) -> Result<Header, ErrorTypes> {
     header.set_opcode(opcode);
     header.set_length(length);
     header.set_version(version);
+    header.set_protocol_version(protocol_version);
     Ok(header)
 }
 
-/// This function receives the header and an array of bytes, decode it if its request or response.
+/// This function reads a whole frame from the stream, whose length is the one of its header, and returns it. The bytes
+/// that were read after it are kept in `pending` for the next frame, as the client can send several frames at once.
+/// If the stream has a read timeout and no frame arrives before it, it fails with the error 341 and the bytes of the
+/// frame that were already read stay in `pending`.
+pub fn read_frame<R: Read>(stream: &mut R, pending: &mut Vec<u8>) -> Result<Vec<u8>, ErrorTypes> {
+    loop {
+        if pending.len() >= 9 {
+            let length = i32::from_be_bytes([pending[5], pending[6], pending[7], pending[8]]);
+            if !(0..=MAX_FRAME_LENGTH).contains(&length) {
+                return Err(ErrorTypes::new(338, "Invalid frame length".to_string()));
+            }
+            if pending.len() >= 9 + length as usize {
+                let rest = pending.split_off(9 + length as usize);
+                return Ok(std::mem::replace(pending, rest));
+            }
+        }
+        let mut buf = [0; 4096];
+        match stream.read(&mut buf) {
+            Err(e) if matches!(e.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) => {
+                return Err(ErrorTypes::new(
+                    341,
+                    "No frame arrived before the timeout".to_string(),
+                ))
+            }
+            Ok(0) | Err(_) => {
+                return Err(ErrorTypes::new(
+                    339,
+                    "The connection was closed".to_string(),
+                ))
+            }
+            Ok(len) => pending.extend_from_slice(&buf[..len]),
+        }
+    }
+}
+
+/// This function receives the header and an array of bytes, decode it if its request or response. The body is
+/// decompressed if the header has the `Compression` flag.
 fn analyze_body(
     header: Header,
     bytes: &mut Vec<u8>,
     compression: Option<Compression>,
 ) -> Result<Message, ErrorTypes> {
+    if !header.get_flag().contains(&Flags::Compression) {
+        return match header.get_version() {
+            Version::Request => handle_request(header, bytes),
+            Version::Response => handle_response(header, bytes),
+        };
+    }
     if let Some(compress) = compression {
         match compression::Compression::decompression(&compress, bytes.clone()) {
             Ok(data) => {
@@ -72,8 +127,15 @@ fn get_opcode(byte: u8) -> Result<Opcode, ErrorTypes> {
         0x01 => Ok(Opcode::StartUp),
         0x02 => Ok(Opcode::Ready),
         0x03 => Ok(Opcode::Authenticate),
+        0x05 => Ok(Opcode::Options),
+        0x06 => Ok(Opcode::Supported),
         0x07 => Ok(Opcode::Query),
         0x08 => Ok(Opcode::Result),
+        0x09 => Ok(Opcode::Prepare),
+        0x0A => Ok(Opcode::Execute),
+        0x0B => Ok(Opcode::Register),
+        0x0C => Ok(Opcode::Event),
+        0x0D => Ok(Opcode::Batch),
         0x0F => Ok(Opcode::AuthResponse),
         0x10 => Ok(Opcode::AuthSuccess),
         _ => Err(ErrorTypes::new(313, "Invalid opcode".to_string())),
@@ -120,8 +182,13 @@ fn get_flag(byte: u8) -> Result<Vec<Flags>, ErrorTypes> {
 fn handle_request(header: Header, bytes: &mut Vec<u8>) -> Result<Message, ErrorTypes> {
     match header.get_opcode() {
         Opcode::StartUp => handle_startup(bytes),
+        Opcode::Options => handle_options(bytes),
         Opcode::AuthResponse => handle_auth_response(bytes),
         Opcode::Query => handle_query(bytes),
+        Opcode::Prepare => handle_prepare(bytes),
+        Opcode::Execute => handle_execute(bytes),
+        Opcode::Batch => handle_batch(bytes),
+        Opcode::Register => handle_register(bytes),
         _ => Err(ErrorTypes::new(315, "Invalid opcode".to_string())),
     }
 }
@@ -132,18 +199,25 @@ fn handle_response(header: Header, bytes: &mut Vec<u8>) -> Result<Message, Error
         Opcode::Error => handle_error(bytes),
         Opcode::Ready => handle_ready(bytes),
         Opcode::Authenticate => handle_authenticate(bytes),
+        Opcode::Supported => handle_supported(bytes),
         Opcode::Result => handle_result(bytes),
         Opcode::AuthSuccess => handle_auth_success(bytes),
+        Opcode::Event => handle_event(bytes),
         _ => Err(ErrorTypes::new(316, "Invalid opcode".to_string())),
     }
 }
 
-/// This function handle the startup message.
+/// This function handle the startup message. The CQL version has to be 3.0.0 or a later one, like the 4.0.0 that some
+/// drivers send, and the options that the nodes do not use, like the name of the driver, are ignored.
 fn handle_startup(bytes: &mut Vec<u8>) -> Result<Message, ErrorTypes> {
     let options = read_string_map(bytes)?;
     match options.get("CQL_VERSION") {
         Some(version) => {
-            if version != "3.0.0" {
+            let major = version
+                .split('.')
+                .next()
+                .and_then(|major| major.parse::<u8>().ok());
+            if major.is_none_or(|major| major < 3) {
                 return Err(ErrorTypes::new(317, "Invalid CQL version".to_string()));
             }
         }
@@ -169,7 +243,16 @@ fn handle_startup(bytes: &mut Vec<u8>) -> Result<Message, ErrorTypes> {
     }))
 }
 
-/// This function handle the auth response message.
+/// This function handle the options message, that has no body.
+fn handle_options(bytes: &mut [u8]) -> Result<Message, ErrorTypes> {
+    if !bytes.is_empty() {
+        return Err(ErrorTypes::new(337, "Invalid body".to_string()));
+    }
+    Ok(Message::SolicitationMessage(RequestMessage::Options))
+}
+
+/// This function handle the auth response message, with the SASL token of the PasswordAuthenticator: an optional
+/// authorization id, the user and the password, separated by zero bytes.
 fn handle_auth_response(bytes: &mut Vec<u8>) -> Result<Message, ErrorTypes> {
     let (_, vec) = read_bytes(bytes)?;
     match vec {
@@ -178,25 +261,38 @@ fn handle_auth_response(bytes: &mut Vec<u8>) -> Result<Message, ErrorTypes> {
                 Ok(token) => token,
                 _ => return Err(ErrorTypes::new(327, "Invalid auth response".to_string())),
             };
-            let user_password = token.split(",").collect::<Vec<&str>>();
-            Ok(Message::SolicitationMessage(RequestMessage::AuthResponse {
-                auth_response: (user_password[0].to_string(), user_password[1].to_string()),
-            }))
+            match token.split('\0').collect::<Vec<&str>>().as_slice() {
+                [_, user, password] => {
+                    Ok(Message::SolicitationMessage(RequestMessage::AuthResponse {
+                        auth_response: (user.to_string(), password.to_string()),
+                    }))
+                }
+                _ => Err(ErrorTypes::new(327, "Invalid auth response".to_string())),
+            }
         }
         _ => Err(ErrorTypes::new(320, "Invalid auth response".to_string())),
     }
 }
 
-/// This function handle the auth success message.
-fn handle_auth_success(bytes: &mut [u8]) -> Result<Message, ErrorTypes> {
+/// This function handle the auth success message, whose body is the final SASL token, which is null for the
+/// PasswordAuthenticator.
+fn handle_auth_success(bytes: &mut Vec<u8>) -> Result<Message, ErrorTypes> {
     if !bytes.is_empty() {
-        return Err(ErrorTypes::new(321, "Invalid auth response".to_string()));
+        read_bytes(bytes)?;
     }
     Ok(Message::ReplyMessage(ResponseMessage::AuthSuccess {
         body: "".to_string(),
     }))
 }
 
+/// This function handle the register message, with the types of the events that the client wants to receive.
+fn handle_register(bytes: &mut Vec<u8>) -> Result<Message, ErrorTypes> {
+    let events = read_string_list(bytes)?;
+    Ok(Message::SolicitationMessage(RequestMessage::Register {
+        events,
+    }))
+}
+
 /// This function handle the error message.
 fn handle_error(bytes: &mut Vec<u8>) -> Result<Message, ErrorTypes> {
     let code: i32 = read_int(bytes)?;
@@ -217,6 +313,14 @@ fn handle_ready(bytes: &mut [u8]) -> Result<Message, ErrorTypes> {
     }))
 }
 
+/// This function handle the supported message, with the values of every option that the server supports.
+fn handle_supported(bytes: &mut Vec<u8>) -> Result<Message, ErrorTypes> {
+    let options = read_string_multimap(bytes)?;
+    Ok(Message::ReplyMessage(ResponseMessage::Supported {
+        options,
+    }))
+}
+
 /// This function handle the authenticate message.
 fn handle_authenticate(bytes: &mut Vec<u8>) -> Result<Message, ErrorTypes> {
     let class: String = read_string(bytes)?;
@@ -233,6 +337,7 @@ fn handle_result(bytes: &mut Vec<u8>) -> Result<Message, ErrorTypes> {
         2 => handle_rows_result(bytes)?,
         5 => handle_schema_change_result(bytes)?,
         3 => handle_set_keyspace_result(bytes)?,
+        4 => handle_prepared_result(bytes)?,
         _ => return Err(ErrorTypes::new(322, "Invalid result kind".to_string())),
     };
     Ok(message)
@@ -256,17 +361,84 @@ fn handle_set_keyspace_result(bytes: &mut Vec<u8>) -> Result<Message, ErrorTypes
     }))
 }
 
+/// This function handle the prepared result, with the metadata of the bind markers of the statement.
+fn handle_prepared_result(bytes: &mut Vec<u8>) -> Result<Message, ErrorTypes> {
+    let Value::Normal(id) = read_short_bytes(bytes)? else {
+        return Err(ErrorTypes::new(
+            331,
+            "Invalid prepared statement id".to_string(),
+        ));
+    };
+    let flags = read_int(bytes)?;
+    let columns_count = read_int(bytes)?;
+    let mut pk_indexes = Vec::new();
+    for _ in 0..read_int(bytes)? {
+        pk_indexes.push(read_short(bytes)?);
+    }
+    let bind_markers = read_columns_metadata(bytes, flags, columns_count)?;
+    let result_flags = read_int(bytes)?;
+    let result_columns_count = read_int(bytes)?;
+    if result_flags & FlagsRow::NoMetadata as i32 == 0 {
+        read_columns_metadata(bytes, result_flags, result_columns_count)?;
+    }
+    Ok(Message::ReplyMessage(ResponseMessage::Result {
+        kind: ResultResponse::Prepared {
+            id,
+            pk_indexes,
+            bind_markers,
+        },
+    }))
+}
+
+/// This function reads the specification of `count` columns. If `flags` has `GlobalTablesSpec`, their keyspace and
+/// table are written once before them. Otherwise, every column has its own.
+fn read_columns_metadata(
+    bytes: &mut Vec<u8>,
+    flags: i32,
+    count: i32,
+) -> Result<ColumnsMetadata, ErrorTypes> {
+    let mut metadata = ColumnsMetadata::default();
+    let global = flags & FlagsRow::GlobalTablesSpec as i32 != 0;
+    if global {
+        metadata.keyspace = read_string(bytes)?;
+        metadata.table = read_string(bytes)?;
+    }
+    for _ in 0..count {
+        if !global {
+            metadata.keyspace = read_string(bytes)?;
+            metadata.table = read_string(bytes)?;
+        }
+        let name = read_string(bytes)?;
+        let id = read_short(bytes)?;
+        let data_type = DataType::from_id(id)
+            .ok_or_else(|| ErrorTypes::new(332, format!("Unsupported type {:#06x}", id)))?;
+        let elements = match data_type {
+            DataType::Set => 1,
+            DataType::Map => 2,
+            _ => 0,
+        };
+        for _ in 0..elements {
+            if read_short(bytes)? != DataType::Varchar as u16 {
+                return Err(ErrorTypes::new(
+                    332,
+                    "Unsupported type of collection".to_string(),
+                ));
+            }
+        }
+        metadata.columns.push((name, data_type));
+    }
+    Ok(metadata)
+}
+
 /// This function handle the schema change result.
 fn handle_schema_change_result(bytes: &mut Vec<u8>) -> Result<Message, ErrorTypes> {
     if !bytes.is_empty() {
-        let change_type = read_string(bytes)?;
-        let target = read_string(bytes)?;
-        let options = read_string(bytes)?;
+        let (change_type, target, options) = read_schema_change(bytes)?;
         Ok(Message::ReplyMessage(ResponseMessage::Result {
             kind: ResultResponse::SchemaChange {
-                change_type: change_type.to_string(),
-                target: target.to_string(),
-                options: options.to_string(),
+                change_type,
+                target,
+                options,
             },
         }))
     } else {
@@ -274,54 +446,224 @@ fn handle_schema_change_result(bytes: &mut Vec<u8>) -> Result<Message, ErrorType
     }
 }
 
-/// This function returns the rows result.
-fn handle_rows_result(bytes: &mut Vec<u8>) -> Result<Message, ErrorTypes> {
-    let flags = match read_int(bytes)? {
-        0x0002 => FlagsRow::HasMorePages,
-        0x0004 => FlagsRow::NoMetadata,
-        _ => return Err(ErrorTypes::new(325, "Invalid flags".to_string())),
+/// This function reads a schema change, the one of a SchemaChange result or of a SCHEMA_CHANGE event, and returns its
+/// type, its target and the name of the keyspace or of the table as `keyspace.table`.
+fn read_schema_change(bytes: &mut Vec<u8>) -> Result<(String, String, String), ErrorTypes> {
+    let change_type = read_string(bytes)?;
+    let target = read_string(bytes)?;
+    let mut options = read_string(bytes)?;
+    if target != "KEYSPACE" {
+        options = format!("{}.{}", options, read_string(bytes)?);
+    }
+    Ok((change_type, target, options))
+}
+
+/// This function handle the event message, that the server pushes to the clients that registered for its type.
+fn handle_event(bytes: &mut Vec<u8>) -> Result<Message, ErrorTypes> {
+    let event_type = read_string(bytes)?;
+    let invalid = || ErrorTypes::new(340, "Invalid event".to_string());
+    let event = match event_type.as_str() {
+        "TOPOLOGY_CHANGE" => {
+            let change = match read_string(bytes)?.as_str() {
+                "NEW_NODE" => TopologyChangeType::NewNode,
+                "REMOVED_NODE" => TopologyChangeType::RemovedNode,
+                "MOVED_NODE" => TopologyChangeType::MovedNode,
+                _ => return Err(invalid()),
+            };
+            EventKindChange::Topology(change, read_inet(bytes)?)
+        }
+        "STATUS_CHANGE" => {
+            let status = match read_string(bytes)?.as_str() {
+                "UP" => StatusNode::Up,
+                "DOWN" => StatusNode::Down,
+                _ => return Err(invalid()),
+            };
+            EventKindChange::Status(status, read_inet(bytes)?)
+        }
+        "SCHEMA_CHANGE" => {
+            let (change_type, target, options) = read_schema_change(bytes)?;
+            let change_type = match change_type.as_str() {
+                "CREATED" => SchemaChangeType::Created,
+                "UPDATED" => SchemaChangeType::Updated,
+                "DROPPED" => SchemaChangeType::Dropped,
+                _ => return Err(invalid()),
+            };
+            EventKindChange::Schema(change_type, target, options)
+        }
+        _ => return Err(invalid()),
     };
+    Ok(Message::ReplyMessage(ResponseMessage::Event { event }))
+}
 
+/// This function returns the rows result.
+fn handle_rows_result(bytes: &mut Vec<u8>) -> Result<Message, ErrorTypes> {
+    let flags = read_int(bytes)?;
     let column_count = read_int(bytes)?;
+    let paging_state = match flags & FlagsRow::HasMorePages as i32 {
+        0 => None,
+        _ => match read_bytes(bytes)? {
+            (_, Value::Normal(paging_state)) => Some(paging_state),
+            _ => return Err(ErrorTypes::new(325, "Invalid paging state".to_string())),
+        },
+    };
+    let metadata = match flags & FlagsRow::NoMetadata as i32 {
+        0 => Some(read_columns_metadata(bytes, flags, column_count)?),
+        _ => None,
+    };
     let row_count = read_int(bytes)?;
-    let mut row: Vec<String> = Vec::new();
-    let mut rows: Vec<Vec<String>> = Vec::new();
-
+    let mut rows: Vec<Vec<CqlValue>> = Vec::new();
     for _ in 0..row_count {
-        for _ in 0..column_count {
+        let mut row = Vec::new();
+        for i in 0..column_count as usize {
             let (_, value) = read_bytes(bytes)?;
-            match value {
-                Value::Normal(bytes) => {
-                    row.push(String::from_utf8(bytes).unwrap());
-                }
-                Value::Null => {
-                    row.push("".to_string());
-                }
-                _ => return Err(ErrorTypes::new(326, "Invalid value".to_string())),
-            }
+            let data_type = metadata
+                .as_ref()
+                .and_then(|metadata| metadata.columns.get(i))
+                .map_or(DataType::Blob, |(_, data_type)| *data_type);
+            row.push(CqlValue::from_value(data_type, &value)?);
         }
         rows.push(row);
-        row = Vec::new();
     }
 
     Ok(Message::ReplyMessage(ResponseMessage::Result {
         kind: ResultResponse::Rows {
-            metadata: flags,
+            metadata,
             rows,
+            paging_state,
         },
     }))
 }
 
-/// This functions receives the query and parse it.
+/// This functions receives the query and parse it. A query that can not be parsed is not an invalid frame, so its
+/// error is returned as a message to answer it.
 fn handle_query(bytes: &mut Vec<u8>) -> Result<Message, ErrorTypes> {
     let query = read_long_string(bytes)?;
-    let consistency = read_consistency(bytes)?;
+    let parameters = read_query_parameters(bytes)?;
     match parse_query(query.clone()) {
         Ok(parsed_query) => Ok(Message::SolicitationMessage(RequestMessage::Query(
             parsed_query,
-            consistency,
+            query,
+            parameters,
+        ))),
+        Err(e) => Ok(Message::SolicitationMessage(RequestMessage::SyntaxError(e))),
+    }
+}
+
+/// This function receives the query to prepare and parses it, like `handle_query`.
+fn handle_prepare(bytes: &mut Vec<u8>) -> Result<Message, ErrorTypes> {
+    let query = read_long_string(bytes)?;
+    match parse_query(query.clone()) {
+        Ok(parsed_query) => Ok(Message::SolicitationMessage(RequestMessage::Prepare(
+            parsed_query,
             query,
         ))),
-        Err(e) => Err(e),
+        Err(e) => Ok(Message::SolicitationMessage(RequestMessage::SyntaxError(e))),
+    }
+}
+
+/// This function receives the identifier of the prepared statement to execute and the parameters of the execution.
+fn handle_execute(bytes: &mut Vec<u8>) -> Result<Message, ErrorTypes> {
+    let id = match read_short_bytes(bytes)? {
+        Value::Normal(id) => id,
+        _ => {
+            return Err(ErrorTypes::new(
+                331,
+                "Invalid prepared statement id".to_string(),
+            ))
+        }
+    };
+    let parameters = read_query_parameters(bytes)?;
+    Ok(Message::SolicitationMessage(RequestMessage::Execute(
+        id, parameters,
+    )))
+}
+
+/// This function receives the type of a batch and its statements, each one with its values, followed by the
+/// parameters of the batch. A statement is the text of a query or the identifier of a prepared statement. If a query
+/// can not be parsed, its error is returned as a message to answer it, like in `handle_query`.
+fn handle_batch(bytes: &mut Vec<u8>) -> Result<Message, ErrorTypes> {
+    let logged = match read_byte(bytes)? {
+        byte if byte == BatchType::Logged as u8 => true,
+        byte if byte == BatchType::Unlogged as u8 => false,
+        _ => return Err(ErrorTypes::new(333, "Unsupported batch type".to_string())),
+    };
+    let mut statements = Vec::new();
+    for _ in 0..read_short(bytes)? {
+        let kind = read_byte(bytes)?;
+        let statement = if kind == BatchQueryKind::Query as u8 {
+            match parse_query(read_long_string(bytes)?) {
+                Ok(query) => BatchStatement::Query(query),
+                Err(e) => return Ok(Message::SolicitationMessage(RequestMessage::SyntaxError(e))),
+            }
+        } else if kind == BatchQueryKind::Prepared as u8 {
+            match read_short_bytes(bytes)? {
+                Value::Normal(id) => BatchStatement::Prepared(id),
+                _ => {
+                    return Err(ErrorTypes::new(
+                        331,
+                        "Invalid prepared statement id".to_string(),
+                    ))
+                }
+            }
+        } else {
+            return Err(ErrorTypes::new(334, "Invalid batch statement".to_string()));
+        };
+        let mut values = Vec::new();
+        for _ in 0..read_short(bytes)? {
+            values.push(read_value(bytes)?);
+        }
+        statements.push((statement, values));
+    }
+    let parameters = read_query_parameters(bytes)?;
+    Ok(Message::SolicitationMessage(RequestMessage::Batch(
+        logged, statements, parameters,
+    )))
+}
+
+/// This function reads the consistency and the flags of a Query or Execute message, followed by the values of the flags.
+fn read_query_parameters(bytes: &mut Vec<u8>) -> Result<QueryParameters, ErrorTypes> {
+    let mut parameters = QueryParameters {
+        consistency: read_consistency(bytes)?,
+        values: Vec::new(),
+        keyspace: None,
+        skip_metadata: false,
+        page_size: None,
+        paging_state: None,
+    };
+    if bytes.is_empty() {
+        return Ok(parameters);
+    }
+    let flags = read_byte(bytes)?;
+    if flags & QueryFlags::NamesForValues as u8 != 0 {
+        return Err(ErrorTypes::new(328, "Unsupported query flags".to_string()));
+    }
+    if flags & QueryFlags::Values as u8 != 0 {
+        for _ in 0..read_short(bytes)? {
+            parameters.values.push(read_value(bytes)?);
+        }
+    }
+    parameters.skip_metadata = flags & QueryFlags::SkipMetadata as u8 != 0;
+    if flags & QueryFlags::PageSize as u8 != 0 {
+        let page_size = read_int(bytes)?;
+        if page_size <= 0 {
+            return Err(ErrorTypes::new(335, "Invalid page size".to_string()));
+        }
+        parameters.page_size = Some(page_size as usize);
+    }
+    if flags & QueryFlags::PagingState as u8 != 0 {
+        if let (_, Value::Normal(paging_state)) = read_bytes(bytes)? {
+            parameters.paging_state = Some(paging_state);
+        }
+    }
+    // Las consultas no tienen transacciones ni timestamps del cliente, se leen y se ignoran
+    if flags & QueryFlags::SerialConsistency as u8 != 0 {
+        read_consistency(bytes)?;
+    }
+    if flags & QueryFlags::DefaultTimestamp as u8 != 0 {
+        read_long(bytes)?;
+    }
+    if flags & QueryFlags::Keyspace as u8 != 0 {
+        parameters.keyspace = Some(read_string(bytes)?);
     }
+    Ok(parameters)
 }
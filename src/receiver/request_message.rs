@@ -1,6 +1,7 @@
 use crate::protocol::protocol_body::compression::Compression;
-use crate::protocol::protocol_notations::consistency::Consistency;
-use crate::protocol::query_parser::query::Query;
+use crate::protocol::protocol_notations::value::Value;
+use crate::protocol::query_parser::{query::Query, syntax_error::SyntaxError};
+use crate::receiver::{batch_statement::BatchStatement, query_parameters::QueryParameters};
 /// Represents the different types of Request messages that can be sent to the server.
 ///
 /// This enum categorizes the types of requests a client can send to the server, encapsulating various kinds of messages
@@ -14,19 +15,45 @@ use crate::protocol::query_parser::query::Query;
 /// - `AuthResponse`: Represents an authentication response sent by the client to the server.
 ///   - `auth_response`: A tuple containing the authentication username and password.
 ///   
-/// - `Options`: Represents a request to retrieve the server's options.
-///   - `options`: A string representing the specific options the client is requesting.
+/// - `Options`: Represents a request to retrieve the server's options, answered with a `Supported` message that has
+///   the CQL versions, the compression algorithms and the versions of the native protocol that the server supports.
 ///   
 /// - `Register`: Represents a request to register for certain events or notifications from the server.
-///   - `string_list`: A list of strings that specifies the events the client wants to register for.
+///   - `events`: A list of strings that specifies the events the client wants to register for.
 ///   
 /// - `Query`: Represents a query sent to the server to execute an operation.
 ///   - `Query`: A `Query` object that defines the query to be executed.
-///   - `Consistency`: The consistency level for the query to ensure how the data is replicated or distributed.
-///   - `String`: An additional string (e.g., keyspace or session-related information) to include with the query.
+///   - `String`: The original text of the query.
+///   - `QueryParameters`: The consistency level of the query, the values of its bind markers and the keyspace in
+///     which it has to run, if the client sent one.
+///
+/// - `Prepare`: Represents a query that the client prepares, to execute it later with different values for its bind
+///   markers.
+///   - `Query`: The query, with its bind markers.
+///   - `String`: The original text of the query.
+///
+/// - `Execute`: Represents the execution of a prepared statement.
+///   - `Vec<u8>`: The identifier of the prepared statement, returned when it was prepared.
+///   - `QueryParameters`: The consistency level and the values of the bind markers.
+///
+/// - `Batch`: Represents a batch of INSERT, UPDATE and DELETE statements, applied in order.
+///   - `bool`: Whether the batch is logged, so it is written to the batchlog before it is applied.
+///   - `Vec<(BatchStatement, Vec<Value>)>`: Each statement, as a query or a prepared statement, with the values of its
+///     bind markers.
+///   - `QueryParameters`: The consistency level of the batch and the keyspace in which it has to run.
+///
+/// - `SyntaxError`: Represents a query that could not be parsed. The server answers it with a `SyntaxError` error
+///   without closing the connection.
+///   - `SyntaxError`: The error, with the token where it was found and the tokens that were expected there.
 #[derive(Debug)]
 pub enum RequestMessage {
     StartUp { compression: Option<Compression> },
     AuthResponse { auth_response: (String, String) },
-    Query(Query, Consistency, String),
+    Options,
+    Register { events: Vec<String> },
+    Query(Query, String, QueryParameters),
+    Prepare(Query, String),
+    Execute(Vec<u8>, QueryParameters),
+    Batch(bool, Vec<(BatchStatement, Vec<Value>)>, QueryParameters),
+    SyntaxError(SyntaxError),
 }
@@ -1,4 +1,5 @@
 use super::result_response::ResultResponse;
+use crate::protocol::protocol_body::event_kind::EventKindChange;
 use std::collections::HashMap;
 
 #[derive(Debug)]
@@ -27,6 +28,9 @@ use std::collections::HashMap;
 ///   
 /// - `Result`: Represents a response containing the result of a query or operation.
 ///   - `kind`: A `ResultResponse` object that encapsulates the details of the query or operation result.
+///
+/// - `Event`: Represents an event that the server pushes to a client that registered for its type.
+///   - `event`: The change of the topology, of the status of a node or of the schema.
 pub enum ResponseMessage {
     Error {
         code: i32,
@@ -47,4 +51,7 @@ pub enum ResponseMessage {
     Result {
         kind: ResultResponse,
     },
+    Event {
+        event: EventKindChange,
+    },
 }
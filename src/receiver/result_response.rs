@@ -1,4 +1,4 @@
-use crate::protocol::protocol_notations::flags_row::FlagsRow;
+use crate::protocol::protocol_notations::{columns_metadata::ColumnsMetadata, cql_value::CqlValue};
 
 /// Represents the different types of Result responses that can be sent by the server.
 ///
@@ -9,12 +9,21 @@ use crate::protocol::protocol_notations::flags_row::FlagsRow;
 /// - `Void`: Represents a void response, indicating that the query executed successfully without returning any data.
 ///   
 /// - `Rows`: Represents a response that contains query results in the form of rows.
-///   - `metadata`: A `FlagsRow` that provides metadata related to the result set (e.g., information on whether there are more pages of results).
-///   - `rows`: A vector of vectors of strings, where each inner vector represents a row, and each string represents a value in that row.
+///   - `metadata`: The keyspace, the table, the names and the types of the columns of the rows, unless the client asked
+///     to skip them.
+///   - `rows`: The rows, with every value read as the type of its column. Without metadata the values are blobs.
+///   - `paging_state`: The position where the next page of rows starts, to send it back and get that page, if there are
+///     more pages.
 ///   
 /// - `SetKeyspace`: Represents a response that indicates the keyspace has been set or modified.
 ///   - `keyspace`: A string containing the name of the keyspace that has been set or changed.
 ///   
+/// - `Prepared`: Represents a response to a query that was prepared.
+///   - `id`: The identifier of the prepared statement, to execute it.
+///   - `pk_indexes`: The position of the bind markers that are bound to the columns of the partition key, in the order
+///     of those columns, if every column of the partition key has one.
+///   - `bind_markers`: The table and the column that each bind marker is bound to, with its type.
+///
 /// - `SchemaChange`: Represents a response indicating a schema change operation (e.g., table creation or modification).
 ///   - `change_type`: A string describing the type of schema change (e.g., "CREATE", "ALTER", etc.).
 ///   - `target`: A string describing the target of the schema change (e.g., the name of the table or column).
@@ -23,12 +32,18 @@ use crate::protocol::protocol_notations::flags_row::FlagsRow;
 pub enum ResultResponse {
     Void,
     Rows {
-        metadata: FlagsRow,
-        rows: Vec<Vec<String>>,
+        metadata: Option<ColumnsMetadata>,
+        rows: Vec<Vec<CqlValue>>,
+        paging_state: Option<Vec<u8>>,
     },
     SetKeyspace {
         keyspace: String,
     },
+    Prepared {
+        id: Vec<u8>,
+        pk_indexes: Vec<u16>,
+        bind_markers: ColumnsMetadata,
+    },
     SchemaChange {
         change_type: String,
         target: String,
@@ -0,0 +1,184 @@
+use super::{
+    address::Address,
+    log_type::LogType,
+    nodes::{decommission, remove_dead_node, write_log_message},
+    schema::Schema,
+    streaming::cleanup,
+};
+use crate::errors::error_types::ErrorTypes;
+use std::{
+    io::{BufRead, BufReader, Write},
+    net::{TcpListener, TcpStream},
+    sync::{Arc, Mutex},
+    thread,
+};
+
+const OK: &str = "OK";
+const ERROR: &str = "ERROR";
+
+/// This enum represents the commands a node receives through its administration interface.
+///
+/// Every command is sent as a single line of text, and the node answers with a single line that starts with
+/// `OK` or with `ERROR` followed by the code and the description of the error.
+#[derive(Debug, PartialEq)]
+pub enum AdminCommand {
+    Decommission,
+    RemoveNode(String),
+    Cleanup,
+}
+
+impl AdminCommand {
+    /// This function parses a command line sent to the administration interface.
+    pub fn parse(line: &str) -> Result<AdminCommand, ErrorTypes> {
+        let words = line.split_whitespace().collect::<Vec<&str>>();
+        match words
+            .iter()
+            .map(|word| word.to_lowercase())
+            .collect::<Vec<String>>()
+            .as_slice()
+        {
+            [command] if command == "decommission" => Ok(AdminCommand::Decommission),
+            [command] if command == "cleanup" => Ok(AdminCommand::Cleanup),
+            [command, _] if command == "removenode" => {
+                Ok(AdminCommand::RemoveNode(words[1].to_string()))
+            }
+            _ => Err(ErrorTypes::new(
+                586,
+                format!("Unknown admin command {}", line.trim()),
+            )),
+        }
+    }
+}
+
+/// This function is responsible for receiving the administration commands. Once a node is decommissioned it shuts down.
+pub fn receive_admin_message(
+    listener: TcpListener,
+    schema: Arc<Mutex<Schema>>,
+    address: &Address,
+) -> Result<(), ErrorTypes> {
+    for stream in listener.incoming() {
+        match stream {
+            Ok(mut stream) => {
+                let schema = Arc::clone(&schema);
+                let address = address.clone();
+                thread::spawn(move || {
+                    if let Ok(AdminCommand::Decommission) =
+                        handle_admin_message(&mut stream, &schema, &address)
+                    {
+                        write_log_message(
+                            &address.i_port,
+                            LogType::Info,
+                            "Shutting down".to_string(),
+                        );
+                        std::process::exit(0);
+                    }
+                });
+            }
+            Err(_) => {
+                return Err(ErrorTypes::new(
+                    510,
+                    "Error connecting to the server".to_string(),
+                ))
+            }
+        }
+    }
+    Ok(())
+}
+
+/// This function reads a command, executes it and writes the result back.
+fn handle_admin_message(
+    stream: &mut TcpStream,
+    schema: &Arc<Mutex<Schema>>,
+    address: &Address,
+) -> Result<AdminCommand, ErrorTypes> {
+    let mut line = String::new();
+    if BufReader::new(&mut *stream).read_line(&mut line).is_err() {
+        return Err(ErrorTypes::new(513, "Error reading message".to_string()));
+    }
+    write_log_message(
+        &address.i_port,
+        LogType::Info,
+        format!("Admin command {}", line.trim()),
+    );
+    let result = AdminCommand::parse(&line).and_then(|command| match command {
+        AdminCommand::Decommission => decommission(schema, address).map(|_| command),
+        AdminCommand::RemoveNode(ref node) => {
+            remove_dead_node(schema, address, node).map(|_| command)
+        }
+        AdminCommand::Cleanup => cleanup(schema, address).map(|_| command),
+    });
+    let reply = match &result {
+        Ok(_) => format!("{}\n", OK),
+        Err(e) => {
+            write_log_message(
+                &address.i_port,
+                LogType::Error,
+                format!("{} {}", e.get().0, e.get().1),
+            );
+            format!("{} {} {}\n", ERROR, e.get().0, e.get().1)
+        }
+    };
+    let _ = stream.write_all(reply.as_bytes());
+    result
+}
+
+/// This function sends a command to the administration interface of a node and waits for its answer.
+pub fn send_admin_command(admin_address: &str, command: &str) -> Result<(), ErrorTypes> {
+    let mut stream = match TcpStream::connect(admin_address) {
+        Ok(stream) => stream,
+        Err(_) => {
+            return Err(ErrorTypes::new(
+                521,
+                format!("Error connecting to {}", admin_address),
+            ))
+        }
+    };
+    if stream
+        .write_all(format!("{}\n", command).as_bytes())
+        .is_err()
+    {
+        return Err(ErrorTypes::new(
+            521,
+            format!("Error sending the command to {}", admin_address),
+        ));
+    }
+    let mut reply = String::new();
+    let _ = BufReader::new(stream).read_line(&mut reply);
+    match reply.trim().strip_prefix(ERROR) {
+        None if reply.trim() == OK => Ok(()),
+        None => Err(ErrorTypes::new(
+            521,
+            format!("{} closed the connection", admin_address),
+        )),
+        Some(error) => {
+            let (code, description) = error.trim().split_once(' ').unwrap_or(("521", error));
+            Err(ErrorTypes::new(
+                code.parse().unwrap_or(521),
+                description.to_string(),
+            ))
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_parse_command() {
+        assert_eq!(
+            AdminCommand::parse("Decommission\n").unwrap(),
+            AdminCommand::Decommission
+        );
+        assert_eq!(
+            AdminCommand::parse("removenode 127.0.0.1:8081").unwrap(),
+            AdminCommand::RemoveNode("127.0.0.1:8081".to_string())
+        );
+        assert_eq!(
+            AdminCommand::parse("cleanup").unwrap(),
+            AdminCommand::Cleanup
+        );
+        assert!(AdminCommand::parse("removenode").is_err());
+        assert!(AdminCommand::parse("exit").is_err());
+    }
+}
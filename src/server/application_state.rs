@@ -1,4 +1,4 @@
-use super::status::Status;
+use super::{node_state::NodeState, snitch::Location, status::Status};
 use serde::{Deserialize, Serialize};
 
 /// Represents the state of an application node, managing its status and address.
@@ -8,27 +8,102 @@ use serde::{Deserialize, Serialize};
 /// ## Fields:
 /// - `status`: The current status of the node, which can either be `Up` or `Down`. This is represented by the `Status` enum.
 /// - `address`: A `String` that contains the network address of the node.
+/// - `tokens`: The tokens the node owns in the ring.
+/// - `state`: The state of the node in the ring, represented by the `NodeState` enum.
+/// - `location`: The datacenter and the rack of the node, represented by the `Location` struct.
+/// - `schema_version`: The version of the schema of the node.
+/// - `native_address`: The address where the node talks with the clients.
 ///
 /// ## Methods:
-/// - `new(status: Status, address: String) -> Self`: Constructs a new `ApplicationState` instance with the specified status and address.
+/// - `new(status: Status, address: String, location: Location) -> Self`: Constructs a new `ApplicationState` instance with the specified status, address and location.
 /// - `get_address(&self) -> Option<&String>`: Returns a reference to the address of the node, wrapped in an `Option` for safe handling of potential `None` values.
+/// - `get_tokens(&self) -> &Vec<u128>`: Returns the tokens of the node.
+/// - `set_tokens(&mut self, tokens: Vec<u128>)`: Replaces the tokens of the node.
+/// - `get_state(&self) -> &NodeState`: Returns the state of the node in the ring.
+/// - `set_state(&mut self, state: NodeState)`: Changes the state of the node in the ring.
+/// - `get_location(&self) -> &Location`: Returns the datacenter and the rack of the node.
+/// - `get_schema_version(&self) -> &String`: Returns the version of the schema of the node.
+/// - `set_schema_version(&mut self, version: String)`: Changes the version of the schema of the node.
+/// - `get_native_address(&self) -> &String`: Returns the address where the node talks with the clients.
+/// - `set_native_address(&mut self, address: String)`: Changes the address where the node talks with the clients.
 /// - `change_status(&mut self)`: Toggles the status of the node between `Up` and `Down`.
 /// - `is_down(&self) -> bool`: Returns `true` if the node's status is `Down`, otherwise `false`.
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct ApplicationState {
     status: Status,
     address: String,
+    tokens: Vec<u128>,
+    state: NodeState,
+    #[serde(default)]
+    location: Location,
+    #[serde(default)]
+    schema_version: String,
+    #[serde(default)]
+    native_address: String,
 }
 
 impl ApplicationState {
-    pub fn new(status: Status, address: String) -> Self {
-        ApplicationState { status, address }
+    pub fn new(status: Status, address: String, location: Location) -> Self {
+        ApplicationState {
+            status,
+            address,
+            tokens: Vec::new(),
+            state: NodeState::Joining,
+            location,
+            schema_version: String::new(),
+            native_address: String::new(),
+        }
     }
 
     /// This function returns the addres of the node.
     pub fn get_address(&self) -> Option<&String> {
         Some(&self.address)
     }
+    /// This function returns the tokens of the node.
+    pub fn get_tokens(&self) -> &Vec<u128> {
+        &self.tokens
+    }
+
+    /// This function replaces the tokens of the node.
+    pub fn set_tokens(&mut self, tokens: Vec<u128>) {
+        self.tokens = tokens;
+    }
+
+    /// This function returns the state of the node in the ring.
+    pub fn get_state(&self) -> &NodeState {
+        &self.state
+    }
+
+    /// This function changes the state of the node in the ring.
+    pub fn set_state(&mut self, state: NodeState) {
+        self.state = state;
+    }
+
+    /// This function returns the datacenter and the rack of the node.
+    pub fn get_location(&self) -> &Location {
+        &self.location
+    }
+
+    /// This function returns the version of the schema of the node.
+    pub fn get_schema_version(&self) -> &String {
+        &self.schema_version
+    }
+
+    /// This function changes the version of the schema of the node.
+    pub fn set_schema_version(&mut self, version: String) {
+        self.schema_version = version;
+    }
+
+    /// This function returns the address where the node talks with the clients.
+    pub fn get_native_address(&self) -> &String {
+        &self.native_address
+    }
+
+    /// This function changes the address where the node talks with the clients.
+    pub fn set_native_address(&mut self, address: String) {
+        self.native_address = address;
+    }
+
     /// This function changes the state of the node (Up or Down).
     pub fn change_status(&mut self) {
         self.status = match self.status {
@@ -0,0 +1,260 @@
+use super::{
+    address::Address,
+    data_dir::{batchlog_dir, join},
+    gossiper::get_gossiper,
+    log_type::LogType,
+    node_message::NodeMessage,
+    nodes::write_log_message,
+    schema::Schema,
+};
+use crate::{
+    errors::error_types::ErrorTypes,
+    protocol::{protocol_notations::consistency::Consistency, query_parser::query::Query},
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    fs,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+    thread,
+    time::Duration,
+};
+
+const BATCHLOG_REPLICAS: usize = 2;
+const BATCHLOG_TIMEOUT: u64 = 10;
+const REPLAY_INTERVAL: u64 = 10;
+const REPLAY_AFTER_MILLIS: i64 = 30_000;
+
+static BATCH_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+/// This struct represents a logged batch, which the coordinator writes to the batchlog of other nodes before applying it.
+///
+/// If the coordinator fails before applying every statement, the first live node that keeps the batch applies it once it
+/// is old enough, so every statement of the batch is eventually applied.
+///
+/// ### Fields:
+/// - `id` (`String`): The identifier of the batch.
+/// - `written_at` (`i64`): The moment, in milliseconds, the coordinator wrote the batch. It is also the timestamp of every
+///   statement of the batch, so applying it again does not overwrite newer writes.
+/// - `queries` (`Vec<Query>`): The INSERT, UPDATE and DELETE queries of the batch, with their tables qualified and their values bound.
+/// - `endpoints` (`Vec<String>`): The nodes the coordinator chose to keep the batch, in the order they replay it.
+pub struct Batch {
+    pub id: String,
+    pub written_at: i64,
+    pub queries: Vec<Query>,
+    #[serde(default)]
+    pub endpoints: Vec<String>,
+}
+
+impl Batch {
+    pub fn new(coordinator: &str, queries: Vec<Query>) -> Self {
+        let written_at = chrono::Utc::now().timestamp_millis();
+        Batch {
+            id: format!(
+                "{}_{}_{}",
+                coordinator.replace(":", "_"),
+                written_at,
+                BATCH_COUNTER.fetch_add(1, Ordering::Relaxed)
+            ),
+            written_at,
+            queries,
+            endpoints: Vec::new(),
+        }
+    }
+
+    /// This function returns the timestamp the statements of the batch are written with.
+    pub fn timestamp(&self) -> String {
+        chrono::DateTime::from_timestamp_millis(self.written_at)
+            .unwrap_or_default()
+            .to_rfc3339()
+    }
+
+    /// This function returns `true` if the local node has to replay the batch, which happens when every node that keeps
+    /// it before the local node is down, so only one of them replays it.
+    fn replays(&self, local: &String) -> bool {
+        let gossiper = get_gossiper();
+        self.endpoints
+            .iter()
+            .take_while(|endpoint| *endpoint != local)
+            .all(|endpoint| {
+                gossiper
+                    .get_endpoint_state(endpoint)
+                    .is_none_or(|state| state.is_down())
+            })
+    }
+
+    /// This function saves the batch in the batchlog of the local node.
+    pub fn save(&self, data_dir: &str) -> Result<(), ErrorTypes> {
+        let saved = fs::create_dir_all(batchlog_dir(data_dir)).and_then(|_| {
+            fs::write(
+                batch_path(data_dir, &self.id),
+                serde_json::to_string(self).unwrap_or_default(),
+            )
+        });
+        saved.map_err(|_| ErrorTypes::new(599, "Error saving the batch".to_string()))
+    }
+}
+
+/// This function returns the route of the file of a batch.
+fn batch_path(data_dir: &str, id: &str) -> String {
+    join(&batchlog_dir(data_dir), &format!("{}.json", id))
+}
+
+/// This function removes a batch from the batchlog of the local node, once it was applied.
+pub fn remove_batch(data_dir: &str, id: &str) {
+    let _ = fs::remove_file(batch_path(data_dir, id));
+}
+
+/// This function writes a batch to the batchlog of up to two live nodes other than the coordinator, or of the coordinator
+/// if it is the only one, and returns the nodes that keep it. It fails if no node could keep it.
+pub fn write_batchlog(
+    schema: &Arc<Mutex<Schema>>,
+    address: &Address,
+    batch: &mut Batch,
+) -> Result<Vec<String>, ErrorTypes> {
+    let gossiper = get_gossiper();
+    let mut endpoints = gossiper.get_normal_nodes(&address.i_address);
+    endpoints.sort();
+    endpoints.truncate(BATCHLOG_REPLICAS);
+    if endpoints.is_empty() {
+        batch.endpoints = vec![address.i_address.to_string()];
+        let data_dir = schema.lock().unwrap().get_data_dir();
+        batch.save(&data_dir)?;
+        return Ok(batch.endpoints.clone());
+    }
+    batch.endpoints = endpoints.clone();
+    let message = NodeMessage::BatchStore(batch.clone());
+    let stored = endpoints
+        .into_iter()
+        .filter(|endpoint| {
+            matches!(
+                gossiper.request(endpoint, &message, Duration::from_secs(BATCHLOG_TIMEOUT)),
+                Some(NodeMessage::Confirmation())
+            )
+        })
+        .collect::<Vec<String>>();
+    if stored.is_empty() {
+        return Err(ErrorTypes::new(
+            569,
+            "No node could write the batch to its batchlog".to_string(),
+        ));
+    }
+    Ok(stored)
+}
+
+/// This function removes an applied batch from the batchlog of the nodes that keep it.
+pub fn remove_from_batchlog(
+    schema: &Arc<Mutex<Schema>>,
+    address: &Address,
+    endpoints: &[String],
+    id: &str,
+) {
+    let gossiper = get_gossiper();
+    for endpoint in endpoints {
+        if *endpoint == address.i_address {
+            remove_batch(&schema.lock().unwrap().get_data_dir(), id);
+        } else if let Some(sender) = gossiper.get_sender(endpoint) {
+            let _ = sender.send(NodeMessage::BatchRemove(id.to_string()).to_bytes());
+        }
+    }
+}
+
+/// This function applies the queries of a batch in order, each one with the consistency level and the timestamp of the batch.
+pub fn apply_batch(
+    schema: &Arc<Mutex<Schema>>,
+    queries: Vec<Query>,
+    address: &Address,
+    consistency: Consistency,
+    timestamp: &str,
+) -> Result<(), ErrorTypes> {
+    for query in queries {
+        let mut schema_lock = schema.lock().unwrap();
+        match query {
+            Query::Insert {
+                table_name,
+                columns_name,
+                values,
+            } => schema_lock.execute_insert(
+                table_name,
+                values,
+                columns_name,
+                address,
+                consistency,
+                timestamp,
+            ),
+            Query::Update {
+                table_name,
+                column_value,
+                conditions,
+            } => schema_lock.execute_update(
+                table_name,
+                column_value,
+                conditions,
+                address.i_address.to_string(),
+                consistency,
+                timestamp,
+            ),
+            Query::Delete {
+                table_name,
+                conditions,
+            } => schema_lock.execute_delete(
+                table_name,
+                conditions,
+                address.i_address.to_string(),
+                consistency,
+                timestamp,
+            ),
+            _ => Err(ErrorTypes::new(
+                571,
+                "Only INSERT, UPDATE and DELETE can be in a batch".to_string(),
+            )),
+        }?;
+    }
+    Ok(())
+}
+
+/// This function applies, every few seconds, the batches of the batchlog of the local node whose coordinator did not
+/// remove them in time, which means it failed before applying them. A batch that can not be applied is tried again later,
+/// and an applied batch is removed from every node that keeps it.
+pub fn replay_batchlog(schema: &Arc<Mutex<Schema>>, address: &Address) {
+    loop {
+        thread::sleep(Duration::from_secs(REPLAY_INTERVAL));
+        let data_dir = schema.lock().unwrap().get_data_dir();
+        let entries = match fs::read_dir(batchlog_dir(&data_dir)) {
+            Ok(entries) => entries,
+            Err(_) => continue,
+        };
+        let now = chrono::Utc::now().timestamp_millis();
+        for entry in entries.flatten() {
+            let batch = fs::read_to_string(entry.path())
+                .ok()
+                .and_then(|content| serde_json::from_str::<Batch>(&content).ok());
+            let Some(batch) = batch else {
+                continue;
+            };
+            if now - batch.written_at < REPLAY_AFTER_MILLIS || !batch.replays(&address.i_address) {
+                continue;
+            }
+            write_log_message(
+                &address.i_port,
+                LogType::Info,
+                format!("Replaying batch {}", batch.id),
+            );
+            let timestamp = batch.timestamp();
+            match apply_batch(schema, batch.queries, address, Consistency::One, &timestamp) {
+                Ok(()) => {
+                    remove_batch(&data_dir, &batch.id);
+                    remove_from_batchlog(schema, address, &batch.endpoints, &batch.id);
+                }
+                Err(e) => write_log_message(
+                    &address.i_port,
+                    LogType::Error,
+                    format!("{} {}", e.get().0, e.get().1),
+                ),
+            }
+        }
+    }
+}
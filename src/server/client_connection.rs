@@ -0,0 +1,169 @@
+use super::events::get_events;
+use crate::{
+    errors::error_types::ErrorTypes,
+    protocol::{
+        frames_headers::version::{Version, PROTOCOL_VERSION, PROTOCOL_VERSIONS},
+        protocol_body::{compression::Compression, event_kind::EventKindChange},
+        protocol_writer::Protocol,
+    },
+    receiver::receiver_impl::read_frame,
+};
+use native_tls::TlsStream;
+use std::{io::Write, net::TcpStream, sync::mpsc::Receiver, time::Duration};
+
+/// How long the connection waits for a frame of a client that registered for events before sending them the events.
+const EVENTS_POLL_INTERVAL: u64 = 100;
+
+/// Represents the connection of a client with a node.
+///
+/// The client chooses the version of the native protocol with the frame of its STARTUP, and every frame after it has
+/// to use the same version. The node answers every frame with the version of the connection and the stream of the
+/// frame, since a driver sends several frames without waiting for their responses and matches them by their stream.
+///
+/// ## Fields:
+/// - `stream`: The TLS stream of the client.
+/// - `pending`: The bytes that were read after the last frame, which start the next one.
+/// - `stream_id`: The stream of the last frame, which its responses have.
+/// - `version`: The version of the native protocol of the responses.
+/// - `negotiated`: Whether the client already chose the version with its STARTUP.
+/// - `authenticated`: Whether the client authenticated after its STARTUP, which it needs to run queries.
+/// - `events`: The identifier of the client in the events of the node, with the channel of the events that it
+///   registered for with a REGISTER, if it did. The client is unregistered when the connection is dropped.
+pub struct ClientConnection {
+    stream: TlsStream<TcpStream>,
+    pending: Vec<u8>,
+    stream_id: u16,
+    version: u8,
+    negotiated: bool,
+    authenticated: bool,
+    events: Option<(usize, Receiver<EventKindChange>)>,
+}
+
+impl ClientConnection {
+    /// This function creates the connection of a client that did not choose the version of the native protocol yet.
+    pub fn new(stream: TlsStream<TcpStream>) -> ClientConnection {
+        ClientConnection {
+            stream,
+            pending: Vec::new(),
+            stream_id: 0,
+            version: PROTOCOL_VERSION,
+            negotiated: false,
+            authenticated: false,
+            events: None,
+        }
+    }
+
+    /// This function reads the next frame that the client sent, and keeps its stream for the responses. It fails when
+    /// the client closes the connection. While it waits for the frame, it sends the client the events it registered
+    /// for, with the compression of the connection.
+    pub fn read_frame(&mut self, compression: &Option<Compression>) -> Result<Vec<u8>, ErrorTypes> {
+        let frame = loop {
+            match read_frame(&mut self.stream, &mut self.pending) {
+                Err(e) if e.get().0 == 341 => self.push_events(compression),
+                result => break result?,
+            }
+        };
+        self.stream_id = u16::from_be_bytes([frame[2], frame[3]]);
+        Ok(frame)
+    }
+
+    /// This function registers the connection for the types of events of a REGISTER. The reads of the connection
+    /// stop every `EVENTS_POLL_INTERVAL` milliseconds to send the events that the node published. A new REGISTER
+    /// replaces the types of events of the previous one.
+    pub fn register(&mut self, events: Vec<String>) {
+        self.unregister();
+        self.events = Some(get_events().register(events));
+        let _ = self
+            .stream
+            .get_ref()
+            .set_read_timeout(Some(Duration::from_millis(EVENTS_POLL_INTERVAL)));
+    }
+
+    /// This function sends the client the events that were published since the last time, with the stream of the
+    /// events and the version of the connection.
+    fn push_events(&mut self, compression: &Option<Compression>) {
+        let Some((_, events)) = &self.events else {
+            return;
+        };
+        let events = events.try_iter().collect::<Vec<EventKindChange>>();
+        for event in events {
+            let mut frame = Protocol::new();
+            frame.set_compress_algorithm(compression.clone());
+            if frame.write_event(&event).is_ok() {
+                frame.set_protocol_version(self.version);
+                let _ = self.stream.write_all(&frame.get_binary());
+            }
+        }
+    }
+
+    /// This function checks the version of the native protocol of a frame that the client sent, and returns the
+    /// message of the ProtocolError to answer it with if the node does not support it, or if it is not the version
+    /// that the client chose with its STARTUP.
+    ///
+    /// The response to an unsupported version uses the closest version that the node supports, so the client can read it.
+    pub fn check_version(&mut self, version: u8) -> Result<(), String> {
+        if !PROTOCOL_VERSIONS.contains(&version) {
+            let min = PROTOCOL_VERSIONS
+                .iter()
+                .min()
+                .copied()
+                .unwrap_or(PROTOCOL_VERSION);
+            let max = PROTOCOL_VERSIONS
+                .iter()
+                .max()
+                .copied()
+                .unwrap_or(PROTOCOL_VERSION);
+            self.version = version.clamp(min, max);
+            return Err(format!(
+                "Invalid or unsupported protocol version ({}); supported versions are ({})",
+                version,
+                Version::supported().join(", ")
+            ));
+        }
+        if self.negotiated && version != self.version {
+            return Err(format!(
+                "Invalid message version. Got {} but previous messages on this connection had version {}",
+                version, self.version
+            ));
+        }
+        self.version = version;
+        Ok(())
+    }
+
+    /// This function keeps the version of the last frame as the version of the connection, when the client sends its
+    /// STARTUP.
+    pub fn negotiate(&mut self) {
+        self.negotiated = true;
+    }
+
+    /// This function marks the client as authenticated, if it already sent its STARTUP.
+    pub fn authenticate(&mut self) {
+        self.authenticated = self.negotiated;
+    }
+
+    /// This function tells if the client sent its STARTUP and then authenticated, so it can run queries.
+    pub fn is_authenticated(&self) -> bool {
+        self.authenticated
+    }
+
+    /// This function sends a response to the client, with the version of the native protocol of the connection and the
+    /// stream of the frame it answers.
+    pub fn send(&mut self, response: &mut Protocol) {
+        response.set_protocol_version(self.version);
+        response.set_stream(self.stream_id);
+        let _ = self.stream.write_all(&response.get_binary());
+    }
+
+    /// This function removes the client from the events of the node, if it registered for them.
+    fn unregister(&mut self) {
+        if let Some((id, _)) = self.events.take() {
+            get_events().unregister(id);
+        }
+    }
+}
+
+impl Drop for ClientConnection {
+    fn drop(&mut self) {
+        self.unregister();
+    }
+}
@@ -1,29 +1,55 @@
-use std::sync::mpsc::{Receiver, Sender};
+use crate::errors::error_types::ErrorTypes;
+use std::{
+    collections::VecDeque,
+    sync::{
+        mpsc::{Receiver, RecvTimeoutError, Sender},
+        Arc, Mutex,
+    },
+    time::{Duration, Instant},
+};
+
+const POLL_INTERVAL: u64 = 50;
+const MAX_PENDING: usize = 1024;
 
 /// Represents a connection between two threads using channels.
 ///
 /// This struct manages communication between threads by utilizing `Sender` and `Receiver` to send and receive messages.
 /// It is used to facilitate the exchange of data between different parts.
+/// The connection can be cloned, so a reader can wait for a message without holding the lock of the map that stores it.
 ///
 /// ## Fields:
 /// - `to`: A `Sender<Vec<u8>>` used to send messages from the current thread to another.
 /// - `from`: A `Receiver<Vec<u8>>` used to receive messages from another thread.
+/// - `pending`: The messages that a reader received but did not expect, kept for the other readers. There can be at most
+///   `MAX_PENDING` of them, after that the readers fail instead of dropping the messages of another one.
+/// - `abandoned`: The amount of requests that gave up waiting for their responses. It is held while a request waits for
+///   its response, so only one request is waiting at a time.
 ///
 /// ## Methods:
 /// - `new(to: Sender<Vec<u8>>, from: Receiver<Vec<u8>>) -> Self`: Creates a new instance of `Connection` with the provided sender and receiver.
 /// - `get_sender(&self) -> Sender<Vec<u8>>`: Returns a clone of the sender, allowing the caller to send messages through the connection.
 /// - `send(&self, message: Vec<u8>)`: Sends a message (as a `Vec<u8>`) through the connection using the sender.
 /// - `receive(&self) -> Vec<u8>`: Receives a message (as a `Vec<u8>`) from the connection using the receiver.
+/// - `receive_timeout(&self, timeout: Duration) -> Option<Vec<u8>>`: Receives a message, giving up if none arrives before the timeout.
+/// - `receive_matching(&self, timeout, accept) -> Result<Option<Vec<u8>>, ErrorTypes>`: Receives the first message that `accept` matches, keeping the other ones.
+/// - `request(&self, message, timeout, accept) -> Option<Vec<u8>>`: Sends a message and receives its response.
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Connection {
     to: Sender<Vec<u8>>,
-    from: Receiver<Vec<u8>>,
+    from: Arc<Mutex<Receiver<Vec<u8>>>>,
+    pending: Arc<Mutex<VecDeque<Vec<u8>>>>,
+    abandoned: Arc<Mutex<usize>>,
 }
 
 impl Connection {
     pub fn new(to: Sender<Vec<u8>>, from: Receiver<Vec<u8>>) -> Self {
-        Connection { to, from }
+        Connection {
+            to,
+            from: Arc::new(Mutex::new(from)),
+            pending: Arc::new(Mutex::new(VecDeque::new())),
+            abandoned: Arc::new(Mutex::new(0)),
+        }
     }
     pub fn get_sender(&self) -> Sender<Vec<u8>> {
         self.to.clone()
@@ -34,6 +60,136 @@ impl Connection {
     }
 
     pub fn receive(&self) -> Vec<u8> {
-        self.from.recv().unwrap_or_default()
+        self.receive_matching(None, |_| true)
+            .ok()
+            .flatten()
+            .unwrap_or_default()
+    }
+
+    pub fn receive_timeout(&self, timeout: Duration) -> Option<Vec<u8>> {
+        self.receive_matching(Some(timeout), |_| true)
+            .ok()
+            .flatten()
+    }
+
+    /// This function receives the first message that `accept` matches, waiting forever if there is no timeout.
+    ///
+    /// The receiver is only locked for short intervals, so several readers can wait on the same connection.
+    /// The messages that are not accepted are kept in `pending` for the reader that expects them. If there are already
+    /// `MAX_PENDING` of them the message is still kept, but the reader fails, since nobody is reading the other ones.
+    pub fn receive_matching<F: Fn(&[u8]) -> bool>(
+        &self,
+        timeout: Option<Duration>,
+        accept: F,
+    ) -> Result<Option<Vec<u8>>, ErrorTypes> {
+        let deadline = timeout.map(|timeout| Instant::now() + timeout);
+        loop {
+            if let Some(message) = self.take_pending(&accept) {
+                return Ok(Some(message));
+            }
+            let interval = Duration::from_millis(POLL_INTERVAL);
+            let interval = match deadline {
+                Some(deadline) => match deadline.checked_duration_since(Instant::now()) {
+                    Some(remaining) => remaining.min(interval),
+                    None => return Ok(None),
+                },
+                None => interval,
+            };
+            let received = self.from.lock().unwrap().recv_timeout(interval);
+            match received {
+                Ok(message) if accept(&message) => return Ok(Some(message)),
+                Ok(message) => {
+                    let mut pending = self.pending.lock().unwrap();
+                    pending.push_back(message);
+                    if pending.len() > MAX_PENDING {
+                        return Err(ErrorTypes::new(
+                            537,
+                            "Too many messages of the connection are waiting to be read"
+                                .to_string(),
+                        ));
+                    }
+                }
+                Err(RecvTimeoutError::Timeout) => {}
+                Err(RecvTimeoutError::Disconnected) => return Ok(self.take_pending(&accept)),
+            }
+        }
+    }
+
+    /// This function sends a message and waits for the first response that `accept` matches.
+    ///
+    /// The other node answers the requests in order, so the first responses that arrive after some requests gave up
+    /// belong to them and are discarded instead of being taken as the response of this one. A request that fails because there
+    /// are too many pending messages gives up as well.
+    pub fn request<F: Fn(&[u8]) -> bool>(
+        &self,
+        message: Vec<u8>,
+        timeout: Duration,
+        accept: F,
+    ) -> Option<Vec<u8>> {
+        let mut abandoned = self.abandoned.lock().unwrap();
+        self.to.send(message).ok()?;
+        let deadline = Instant::now() + timeout;
+        loop {
+            let response = deadline
+                .checked_duration_since(Instant::now())
+                .and_then(|remaining| {
+                    self.receive_matching(Some(remaining), &accept)
+                        .ok()
+                        .flatten()
+                });
+            match response {
+                Some(_) if *abandoned > 0 => *abandoned -= 1,
+                Some(response) => return Some(response),
+                None => {
+                    *abandoned += 1;
+                    return None;
+                }
+            }
+        }
+    }
+
+    /// This function removes and returns the first pending message that `accept` matches.
+    fn take_pending<F: Fn(&[u8]) -> bool>(&self, accept: &F) -> Option<Vec<u8>> {
+        let mut pending = self.pending.lock().unwrap();
+        let index = pending.iter().position(|message| accept(message))?;
+        pending.remove(index)
+    }
+}
+
+#[cfg(test)]
+pub mod test {
+    use super::*;
+    use std::sync::mpsc::channel;
+
+    #[test]
+    fn test_receive_matching() {
+        let (to, _) = channel();
+        let (from_tx, from_rx) = channel();
+        let connection = Connection::new(to, from_rx);
+        for i in 0..3 {
+            from_tx.send(vec![i]).unwrap();
+        }
+        let timeout = Some(Duration::from_millis(100));
+        assert_eq!(
+            connection.receive_matching(timeout, |m| m == [2]).unwrap(),
+            Some(vec![2])
+        );
+        assert_eq!(
+            connection.receive_timeout(Duration::from_millis(100)),
+            Some(vec![0])
+        );
+        assert_eq!(
+            connection.receive_matching(timeout, |m| m == [3]).unwrap(),
+            None
+        );
+
+        for _ in 0..MAX_PENDING {
+            from_tx.send(vec![0]).unwrap();
+        }
+        assert!(connection.receive_matching(timeout, |m| m == [3]).is_err());
+        assert_eq!(
+            connection.receive_timeout(Duration::from_millis(100)),
+            Some(vec![1])
+        );
     }
 }
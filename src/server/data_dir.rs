@@ -0,0 +1,42 @@
+use std::path::Path;
+
+const DATA_ROOT: &str = "data";
+const SCHEMA_FILE: &str = "schema.json";
+const TOKENS_FILE: &str = "tokens.json";
+const STREAMS_DIR: &str = "streams";
+const BATCHLOG_DIR: &str = "batchlog";
+
+/// This function returns the data directory used by a node when none is configured.
+pub fn default_data_dir(port: &str) -> String {
+    format!("{}/{}", DATA_ROOT, port)
+}
+
+/// This function returns the route of the schema file inside a data directory.
+pub fn schema_path(data_dir: &str) -> String {
+    join(data_dir, SCHEMA_FILE)
+}
+
+/// This function returns the route of the file where the node keeps its tokens.
+pub fn tokens_path(data_dir: &str) -> String {
+    join(data_dir, TOKENS_FILE)
+}
+
+/// This function returns the directory where the node keeps the streaming sessions that are not finished.
+pub fn streams_dir(data_dir: &str) -> String {
+    join(data_dir, STREAMS_DIR)
+}
+
+/// This function returns the directory where the node keeps the logged batches of other coordinators until they are applied.
+pub fn batchlog_dir(data_dir: &str) -> String {
+    join(data_dir, BATCHLOG_DIR)
+}
+
+/// This function returns the directory where a table of a keyspace keeps its sstables.
+pub fn table_dir(data_dir: &str, keyspace: &str, table: &str) -> String {
+    join(&join(data_dir, keyspace), table)
+}
+
+/// This function joins a directory and a file name.
+pub fn join(dir: &str, name: &str) -> String {
+    Path::new(dir).join(name).to_string_lossy().to_string()
+}
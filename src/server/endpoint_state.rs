@@ -1,6 +1,8 @@
 use super::application_state::ApplicationState;
 use super::gossip_digest::GossipDigest;
 use super::heartbeat_state::HeartbeatState;
+use super::node_state::NodeState;
+use super::snitch::Location;
 use serde::{Deserialize, Serialize};
 
 /// This struct is responsible for managing the endpoint state.
@@ -49,6 +51,50 @@ impl EndpointState {
         self.application_states.is_down()
     }
 
+    /// This function is responsible for returning the tokens of the node.
+    pub fn get_tokens(&self) -> Vec<u128> {
+        self.application_states.get_tokens().clone()
+    }
+
+    /// This function is responsible for setting the tokens of the node.
+    pub fn set_tokens(&mut self, tokens: Vec<u128>) {
+        self.application_states.set_tokens(tokens);
+    }
+
+    /// This function is responsible for returning the state of the node in the ring.
+    pub fn get_state(&self) -> NodeState {
+        self.application_states.get_state().clone()
+    }
+
+    /// This function is responsible for changing the state of the node in the ring.
+    pub fn set_state(&mut self, state: NodeState) {
+        self.application_states.set_state(state);
+    }
+
+    /// This function is responsible for returning the datacenter and the rack of the node.
+    pub fn get_location(&self) -> Location {
+        self.application_states.get_location().clone()
+    }
+
+    /// This function is responsible for returning the version of the schema of the node.
+    pub fn get_schema_version(&self) -> String {
+        self.application_states.get_schema_version().clone()
+    }
+
+    /// This function is responsible for changing the version of the schema of the node.
+    pub fn set_schema_version(&mut self, version: String) {
+        self.application_states.set_schema_version(version);
+    }
+
+    /// This function is responsible for returning the address where the node talks with the clients. A node that did
+    /// not gossip it yet is known only by its internal address.
+    pub fn get_native_address(&self) -> String {
+        match self.application_states.get_native_address() {
+            native if native.is_empty() => self.get_address(),
+            native => native.clone(),
+        }
+    }
+
     /// This function is responsible for changing the status of the node (Up or Down).
     pub fn change_status(&mut self) {
         self.application_states.change_status();
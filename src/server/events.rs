@@ -0,0 +1,212 @@
+use super::schema_definition::SchemaDefinition;
+use crate::protocol::protocol_body::{
+    event_kind::EventKindChange, schema_change::SchemaChangeType,
+};
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    mpsc::{channel, Receiver, Sender},
+    Arc, Mutex, OnceLock,
+};
+
+type Listener = (usize, Vec<String>, Sender<EventKindChange>);
+
+/// Struct that keeps the client connections that registered for events with a REGISTER message, and sends them the
+/// changes of the topology, of the status of the nodes and of the schema that the node sees.
+///
+/// ### Fields:
+/// - `listeners`: The identifier of every connection that registered, with the types of events it registered for and
+///   the channel that sends them to it.
+/// - `next_listener`: The identifier of the next connection that registers.
+pub struct Events {
+    listeners: Mutex<Vec<Listener>>,
+    next_listener: AtomicUsize,
+}
+
+static EVENTS: OnceLock<Arc<Events>> = OnceLock::new();
+
+pub fn get_events() -> Arc<Events> {
+    EVENTS
+        .get_or_init(|| {
+            Arc::new(Events {
+                listeners: Mutex::new(Vec::new()),
+                next_listener: AtomicUsize::new(0),
+            })
+        })
+        .clone()
+}
+
+impl Events {
+    /// This function registers a connection for the types of events, and returns its identifier with the channel where
+    /// it receives them.
+    pub fn register(&self, events: Vec<String>) -> (usize, Receiver<EventKindChange>) {
+        let (sender, receiver) = channel();
+        let id = self.next_listener.fetch_add(1, Ordering::Relaxed);
+        self.listeners.lock().unwrap().push((id, events, sender));
+        (id, receiver)
+    }
+
+    /// This function removes the connection with the identifier, so it does not receive more events.
+    pub fn unregister(&self, id: usize) {
+        self.listeners
+            .lock()
+            .unwrap()
+            .retain(|(listener, _, _)| *listener != id);
+    }
+
+    /// This function sends an event to every connection that registered for its type. The connections that were
+    /// closed are removed.
+    pub fn publish(&self, event: EventKindChange) {
+        self.listeners
+            .lock()
+            .unwrap()
+            .retain(|(_, events, sender)| {
+                !events.iter().any(|kind| kind == event.event_type())
+                    || sender.send(event.clone()).is_ok()
+            });
+    }
+}
+
+/// This function returns the SCHEMA_CHANGE events between two versions of the schema, for the keyspaces and the tables
+/// that were created, altered or dropped. The node uses it when it learns a schema change from another node.
+pub fn schema_changes(
+    previous: &SchemaDefinition,
+    current: &SchemaDefinition,
+) -> Vec<EventKindChange> {
+    let mut changes = Vec::new();
+    let event = |change_type: SchemaChangeType, target: &str, name: String| {
+        EventKindChange::Schema(change_type, target.to_string(), name)
+    };
+    for (name, (keyspace, tables)) in &current.keyspaces {
+        let previous_tables = match previous.keyspaces.get(name) {
+            Some((previous_keyspace, previous_tables)) => {
+                if previous_keyspace != keyspace {
+                    changes.push(event(SchemaChangeType::Updated, "KEYSPACE", name.clone()));
+                }
+                Some(previous_tables)
+            }
+            None => {
+                changes.push(event(SchemaChangeType::Created, "KEYSPACE", name.clone()));
+                None
+            }
+        };
+        for (table_name, table) in tables {
+            let table_event =
+                |change_type| event(change_type, "TABLE", format!("{}.{}", name, table_name));
+            match previous_tables.and_then(|tables| tables.get(table_name)) {
+                Some(previous_table) if previous_table != table => {
+                    changes.push(table_event(SchemaChangeType::Updated))
+                }
+                Some(_) => {}
+                None => changes.push(table_event(SchemaChangeType::Created)),
+            }
+        }
+    }
+    for (name, (_, tables)) in &previous.keyspaces {
+        match current.keyspaces.get(name) {
+            None => changes.push(event(SchemaChangeType::Dropped, "KEYSPACE", name.clone())),
+            Some((_, current_tables)) => {
+                for table_name in tables.keys() {
+                    if !current_tables.contains_key(table_name) {
+                        changes.push(event(
+                            SchemaChangeType::Dropped,
+                            "TABLE",
+                            format!("{}.{}", name, table_name),
+                        ));
+                    }
+                }
+            }
+        }
+    }
+    changes
+}
+
+#[cfg(test)]
+pub mod test {
+    use super::*;
+    use crate::{
+        protocol::protocol_body::status_node::StatusNode,
+        server::{
+            keyspace::Keyspace, replication_strategy::ReplicationStrategy,
+            schema_definition::TableSchema,
+        },
+    };
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn test_publish_event() {
+        let events = Events {
+            listeners: Mutex::new(Vec::new()),
+            next_listener: AtomicUsize::new(0),
+        };
+        let (_, status) = events.register(vec!["STATUS_CHANGE".to_string()]);
+        let (schema_id, schema) = events.register(vec!["SCHEMA_CHANGE".to_string()]);
+        let (_, closed) = events.register(vec!["STATUS_CHANGE".to_string()]);
+        drop(closed);
+        let event = EventKindChange::Status(StatusNode::Down, "127.0.0.1:9042".to_string());
+        events.publish(event.clone());
+        assert_eq!(status.try_recv(), Ok(event));
+        assert!(schema.try_recv().is_err());
+        assert_eq!(events.listeners.lock().unwrap().len(), 2);
+        events.unregister(schema_id);
+        assert_eq!(events.listeners.lock().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_schema_changes() {
+        let table = TableSchema {
+            name: "flights".to_string(),
+            columns_type: vec![("id".to_string(), "int".to_string())],
+            partition_key: vec!["id".to_string()],
+            clustering_key: vec![],
+            created_at: 1,
+            altered_at: 0,
+        };
+        let keyspace = Keyspace::new("aviation".to_string(), ReplicationStrategy::Simple(1));
+        let mut previous = SchemaDefinition::default();
+        previous.keyspaces.insert(
+            "aviation".to_string(),
+            (
+                keyspace.clone(),
+                BTreeMap::from([("flights".to_string(), table.clone())]),
+            ),
+        );
+        previous.keyspaces.insert(
+            "old".to_string(),
+            (
+                Keyspace::new("old".to_string(), ReplicationStrategy::Simple(1)),
+                BTreeMap::new(),
+            ),
+        );
+        let mut current = SchemaDefinition::default();
+        let altered = TableSchema {
+            altered_at: 2,
+            ..table.clone()
+        };
+        let airports = TableSchema {
+            name: "airports".to_string(),
+            ..table
+        };
+        current.keyspaces.insert(
+            "aviation".to_string(),
+            (
+                keyspace,
+                BTreeMap::from([
+                    ("flights".to_string(), altered),
+                    ("airports".to_string(), airports),
+                ]),
+            ),
+        );
+        let schema = |change_type, target: &str, name: &str| {
+            EventKindChange::Schema(change_type, target.to_string(), name.to_string())
+        };
+        assert_eq!(
+            schema_changes(&previous, &current),
+            vec![
+                schema(SchemaChangeType::Created, "TABLE", "aviation.airports"),
+                schema(SchemaChangeType::Updated, "TABLE", "aviation.flights"),
+                schema(SchemaChangeType::Dropped, "KEYSPACE", "old"),
+            ]
+        );
+        assert!(schema_changes(&current, &current).is_empty());
+    }
+}
@@ -1,16 +1,24 @@
 use super::address::Address;
 use super::connection::Connection;
 use super::endpoint_state::EndpointState;
+use super::events::get_events;
 use super::gossip_digest::GossipDigest;
 use super::gossip_message::GossipMessage;
-use super::hashring::HashRing;
-use super::hashring::NODOS;
+use super::hashring::{HashRing, ReplicationChange};
 use super::log_type::LogType;
-use super::node_message::NodeMessage;
+use super::node_message::{NodeMessage, SchemaChange};
+use super::node_state::NodeState;
 use super::nodes::receive_internal_message;
 use super::nodes::write_log_message;
+use super::replication_strategy::ReplicationStrategy;
 use super::schema::Schema;
+use super::schema_definition::SchemaDefinition;
+use super::snitch::Location;
+use super::streaming::{cleanup, stream_to_new_node};
 use crate::errors::error_types::ErrorTypes;
+use crate::protocol::protocol_body::{
+    event_kind::EventKindChange, status_node::StatusNode, topology_change::TopologyChangeType,
+};
 use rand::seq::SliceRandom;
 use rand::thread_rng;
 use serde_json;
@@ -21,6 +29,10 @@ use std::net::TcpStream;
 use std::sync::mpsc::{channel, Sender};
 use std::sync::{Arc, Mutex, OnceLock};
 use std::thread;
+use std::time::{Duration, Instant};
+
+const SCHEMA_PULL_INTERVAL: u64 = 5;
+const SCHEMA_CHANGE_TIMEOUT: u64 = 10;
 
 /// Struct responsible for managing the gossip state in a distributed system.
 /// The `Gossiper` struct maintains the necessary data structures to handle the
@@ -31,6 +43,9 @@ pub struct Gossiper {
     neighbours: Mutex<Vec<String>>,
     hashring: Mutex<HashRing>,
     removed: Mutex<HashSet<String>>,
+    bootstrapped: Mutex<HashMap<String, Vec<u128>>>,
+    completed_streams: Mutex<HashSet<String>>,
+    schema_pulls: Mutex<HashMap<String, Instant>>,
 }
 static GOSSIPER: OnceLock<Arc<Gossiper>> = OnceLock::new();
 
@@ -43,19 +58,52 @@ pub fn get_gossiper() -> Arc<Gossiper> {
                 neighbours: Mutex::new(Vec::new()),
                 hashring: Mutex::new(HashRing::new()),
                 removed: Mutex::new(HashSet::new()),
+                bootstrapped: Mutex::new(HashMap::new()),
+                completed_streams: Mutex::new(HashSet::new()),
+                schema_pulls: Mutex::new(HashMap::new()),
             })
         })
         .clone()
 }
 
 impl Gossiper {
+    /// This function is responsible for returning the replicas of a key for the replication strategy, starting with the node responsible of it.
     pub fn get_replicas(
         &self,
         key: u128,
-        rf: usize,
-        local: &String,
+        strategy: &ReplicationStrategy,
     ) -> Result<Vec<String>, ErrorTypes> {
-        self.hashring.lock().unwrap().get_replicas(key, rf, local)
+        let locations = self.get_locations();
+        self.hashring
+            .lock()
+            .unwrap()
+            .get_replicas(key, strategy, &locations)
+    }
+
+    /// This function is responsible for returning every range of the ring with its replicas for the replication strategy.
+    pub fn get_ranges(&self, strategy: &ReplicationStrategy) -> Vec<((u128, u128), Vec<String>)> {
+        let locations = self.get_locations();
+        self.hashring
+            .lock()
+            .unwrap()
+            .get_ranges(strategy, &locations)
+    }
+
+    /// This function is responsible for returning the datacenter and the rack of every known node.
+    pub fn get_locations(&self) -> HashMap<String, Location> {
+        self.endpoint_state_map
+            .lock()
+            .unwrap()
+            .values()
+            .map(|endpoint| (endpoint.get_address(), endpoint.get_location()))
+            .collect()
+    }
+
+    /// This function is responsible for returning the datacenter and the rack of a node.
+    pub fn get_location(&self, node: &str) -> Location {
+        self.get_endpoint_state(node)
+            .map(|endpoint| endpoint.get_location())
+            .unwrap_or_default()
     }
 
     pub fn get_sender(&self, address: &String) -> Option<Sender<Vec<u8>>> {
@@ -65,13 +113,45 @@ impl Gossiper {
         }
         None
     }
-    pub fn receive(&self, address: &String) -> Vec<u8> {
-        let connections = self.connections.lock().unwrap();
-        if let Some(connection) = connections.get(address) {
-            return connection.receive();
-        }
-        Vec::new()
+    /// This function is responsible for receiving the first message of a node that `accept` matches, giving up if none arrives before the timeout.
+    /// The other messages are kept for the readers that expect them, and it fails if there are too many of them.
+    pub fn receive_matching<F: Fn(&NodeMessage) -> bool>(
+        &self,
+        address: &String,
+        timeout: Duration,
+        accept: F,
+    ) -> Result<Option<NodeMessage>, ErrorTypes> {
+        let connection = match self.get_connection(address) {
+            Some(connection) => connection,
+            None => return Ok(None),
+        };
+        let message = connection.receive_matching(Some(timeout), |bytes| {
+            !bytes.is_empty() && accept(&NodeMessage::from_bytes(bytes[1..].to_vec()))
+        })?;
+        Ok(message.map(|bytes| NodeMessage::from_bytes(bytes[1..].to_vec())))
     }
+
+    /// This function is responsible for sending a request to a node and waiting for its response, giving up if none arrives before the timeout.
+    /// The requests to the same node wait one at a time, so a response is never taken by another request.
+    pub fn request(
+        &self,
+        address: &String,
+        message: &NodeMessage,
+        timeout: Duration,
+    ) -> Option<NodeMessage> {
+        self.get_connection(address)?
+            .request(message.to_bytes(), timeout, |bytes| {
+                !is_stream_message(bytes)
+            })
+            .filter(|bytes| !bytes.is_empty())
+            .map(|bytes| NodeMessage::from_bytes(bytes[1..].to_vec()))
+    }
+
+    /// This function is responsible for returning a copy of the connection with a node, so it can be used without holding the lock.
+    fn get_connection(&self, address: &String) -> Option<Connection> {
+        self.connections.lock().unwrap().get(address).cloned()
+    }
+
     /// This function is responsible for returning the endpoint state.
     pub fn get_endpoint_state(&self, endpoint: &str) -> Option<EndpointState> {
         self.endpoint_state_map
@@ -124,8 +204,6 @@ impl Gossiper {
             {
                 return;
             }
-            self.add_node(endpoint_address.to_string());
-
             if *endpoint_address != address.i_address {
                 neighbours.push(endpoint_address.to_string());
             }
@@ -133,26 +211,41 @@ impl Gossiper {
             if need_connection {
                 self.establish_connection(endpoint_address, address, Arc::clone(&schema));
             }
-            if self.hashring.lock().unwrap().quantity <= NODOS {
-                return;
-            }
-            schema
-                .lock()
-                .unwrap()
-                .new_node(endpoint_address, &address.i_address);
         }
     }
 
+    /// This function returns the partitions that the local node has to transfer to a joining node, and if it stops replicating each of them.
     pub fn get_partitions(
         &self,
         endpoint_address: &String,
         local: &String,
-        rf: usize,
-    ) -> Vec<(u128, u128)> {
-        self.hashring
+        strategy: &ReplicationStrategy,
+    ) -> Vec<((u128, u128), bool)> {
+        let tokens = match self.get_endpoint_state(endpoint_address) {
+            Some(endpoint_state) => endpoint_state.get_tokens(),
+            None => return Vec::new(),
+        };
+        let down = self.get_down_nodes();
+        let locations = self.get_locations();
+        self.hashring.lock().unwrap().get_partitions(
+            endpoint_address,
+            &tokens,
+            local,
+            strategy,
+            &locations,
+            &down,
+        )
+    }
+
+    /// This function returns the nodes that are down.
+    fn get_down_nodes(&self) -> HashSet<String> {
+        self.endpoint_state_map
             .lock()
             .unwrap()
-            .get_partitions(endpoint_address, local, rf)
+            .values()
+            .filter(|endpoint| endpoint.is_down())
+            .map(|endpoint| endpoint.get_address())
+            .collect()
     }
 
     pub fn get_neighbours(&self) -> Vec<String> {
@@ -175,6 +268,7 @@ impl Gossiper {
         }
         let (tx_to, rx_to) = channel();
         let (tx_from, rx_from) = channel();
+        let writer = tx_to.clone();
         let connection = Connection::new(tx_to, rx_from);
         self.connections
             .lock()
@@ -183,25 +277,283 @@ impl Gossiper {
         let address = address.clone();
         if let Ok(client_stream) = TcpStream::connect(endpoint_address.clone()) {
             thread::spawn(move || {
-                receive_internal_message(client_stream, schema, None, address, rx_to, tx_from);
+                receive_internal_message(
+                    client_stream,
+                    schema,
+                    None,
+                    address,
+                    (writer, rx_to),
+                    tx_from,
+                );
             });
         }
     }
 
     /// This function is responsible for adding a node to the hashring.
-    pub fn add_node(&self, endpoint_address: String) {
-        self.hashring.lock().unwrap().add_node(endpoint_address);
+    pub fn add_node(&self, endpoint_address: String, tokens: Vec<u128>) {
+        self.hashring
+            .lock()
+            .unwrap()
+            .add_node(endpoint_address, tokens);
+    }
+
+    /// This function is responsible for choosing the tokens of a new node with the current state of the hashring.
+    pub fn allocate_tokens(&self, node: &String, num_tokens: usize, rf: usize) -> Vec<u128> {
+        self.hashring
+            .lock()
+            .unwrap()
+            .allocate_tokens(node, num_tokens, rf)
+    }
+
+    /// This function is responsible for choosing random tokens for a new node.
+    pub fn random_tokens(&self, num_tokens: usize) -> Vec<u128> {
+        self.hashring.lock().unwrap().random_tokens(num_tokens)
+    }
+
+    /// This function is responsible for setting the tokens and the state of the local node and announcing them in its endpoint state.
+    /// The node is only placed in the hashring once it is `Normal`.
+    pub fn set_local_state(&self, local: &String, tokens: Vec<u128>, state: NodeState) {
+        if let Some(endpoint_state) = self.endpoint_state_map.lock().unwrap().get_mut(local) {
+            endpoint_state.set_tokens(tokens.clone());
+            endpoint_state.set_state(state.clone());
+            endpoint_state.increment_heartbeat();
+        }
+        if state == NodeState::Normal {
+            self.add_node(local.to_string(), tokens);
+        }
+    }
+
+    /// This function is responsible for returning the nodes that own ranges of the ring, except the local node.
+    pub fn get_normal_nodes(&self, local: &String) -> Vec<String> {
+        self.endpoint_state_map
+            .lock()
+            .unwrap()
+            .values()
+            .filter(|endpoint| {
+                endpoint.get_address() != *local
+                    && endpoint.get_state() == NodeState::Normal
+                    && !endpoint.is_down()
+            })
+            .map(|endpoint| endpoint.get_address())
+            .collect()
+    }
+
+    /// This function is responsible for registering that a node finished streaming its ranges to the local node.
+    pub fn stream_completed(&self, node: &str) {
+        self.completed_streams
+            .lock()
+            .unwrap()
+            .insert(node.to_string());
+    }
+
+    /// This function is responsible for checking if every owner finished streaming its ranges to the local node.
+    /// It is never completed while no owner is known.
+    pub fn is_bootstrap_completed(&self, local: &String) -> bool {
+        let completed = self.completed_streams.lock().unwrap();
+        let owners = self.get_normal_nodes(local);
+        !owners.is_empty() && owners.iter().all(|node| completed.contains(node))
     }
 
-    /// This function is responsible for updating the endpoint state map.
+    /// This function is responsible for streaming the ranges of a joining node the first time its state is known with
+    /// its tokens. Once the local node has sent its data, it lets the joining node know with a `StreamCompleted`
+    /// message. If the streaming fails, or the node joins again with other tokens, it is streamed again the next time
+    /// its state is gossiped.
+    ///
+    /// The local node keeps the ranges it streamed until the joining node is `Normal`, and then cleans up the ones it
+    /// does not replicate anymore. A joining node that left or was removed is forgotten.
+    fn bootstrap_node(
+        &self,
+        endpoint_state: &EndpointState,
+        address: &Address,
+        schema: Arc<Mutex<Schema>>,
+    ) {
+        let joining = endpoint_state.get_address();
+        if joining == address.i_address {
+            return;
+        }
+        match endpoint_state.get_state() {
+            NodeState::Joining if !endpoint_state.get_tokens().is_empty() => {}
+            NodeState::Normal => {
+                if self.bootstrapped.lock().unwrap().remove(&joining).is_some() {
+                    let address = address.clone();
+                    thread::spawn(move || {
+                        let _ = cleanup(&schema, &address);
+                    });
+                }
+                return;
+            }
+            NodeState::Left => {
+                self.bootstrapped.lock().unwrap().remove(&joining);
+                return;
+            }
+            _ => return,
+        }
+        match self.get_endpoint_state(&address.i_address) {
+            Some(local) if local.get_state() == NodeState::Normal => {}
+            _ => return,
+        }
+        let tokens = endpoint_state.get_tokens();
+        {
+            let mut bootstrapped = self.bootstrapped.lock().unwrap();
+            if bootstrapped.get(&joining) == Some(&tokens) {
+                return;
+            }
+            bootstrapped.insert(joining.clone(), tokens.clone());
+        }
+        let address = address.clone();
+        thread::spawn(move || {
+            let gossiper = get_gossiper();
+            if gossiper.get_sender(&joining).is_none() {
+                gossiper.establish_connection(&joining, &address, Arc::clone(&schema));
+            }
+            let msg = NodeMessage::StreamCompleted(address.i_address.clone());
+            let completed = stream_to_new_node(&schema, &joining, &address).is_ok()
+                && gossiper
+                    .get_sender(&joining)
+                    .is_some_and(|sender| sender.send(msg.to_bytes()).is_ok());
+            if !completed {
+                let mut bootstrapped = gossiper.bootstrapped.lock().unwrap();
+                if bootstrapped.get(&joining) == Some(&tokens) {
+                    bootstrapped.remove(&joining);
+                }
+            }
+        });
+    }
+
+    /// This function is responsible for announcing the version of the schema of the local node.
+    pub fn set_schema_version(&self, local: &String, version: String) {
+        if let Some(endpoint_state) = self.endpoint_state_map.lock().unwrap().get_mut(local) {
+            endpoint_state.set_schema_version(version);
+            endpoint_state.increment_heartbeat();
+        }
+    }
+
+    /// This function is responsible for asking a node for its schema when its version is different from the local one.
+    /// The node answers with a `SchemaPush` message. The same node is asked at most once every `SCHEMA_PULL_INTERVAL` seconds.
+    fn pull_schema(
+        &self,
+        endpoint_state: &EndpointState,
+        address: &Address,
+        schema: Arc<Mutex<Schema>>,
+    ) {
+        let node = endpoint_state.get_address();
+        let version = endpoint_state.get_schema_version();
+        if node == address.i_address || endpoint_state.is_down() || version.is_empty() {
+            return;
+        }
+        match self.get_endpoint_state(&address.i_address) {
+            Some(local) if local.get_schema_version() != version => {}
+            _ => return,
+        }
+        {
+            let mut pulls = self.schema_pulls.lock().unwrap();
+            if pulls
+                .get(&node)
+                .is_some_and(|last| last.elapsed() < Duration::from_secs(SCHEMA_PULL_INTERVAL))
+            {
+                return;
+            }
+            pulls.insert(node.clone(), Instant::now());
+        }
+        if self.get_sender(&node).is_none() {
+            self.establish_connection(&node, address, schema);
+        }
+        if let Some(sender) = self.get_sender(&node) {
+            let _ = sender.send(NodeMessage::SchemaPull(address.i_address.clone()).to_bytes());
+        }
+    }
+
+    /// This function is responsible for sending the schema of the local node to every neighbour, so they do not have to wait
+    /// for gossip to learn about a change.
+    pub fn push_schema(&self, definition: SchemaDefinition) {
+        let message = NodeMessage::SchemaPush(definition).to_bytes();
+        for neighbour in self.get_neighbours() {
+            if let Some(sender) = self.get_sender(&neighbour) {
+                let _ = sender.send(message.clone());
+            }
+        }
+    }
+
+    /// This function sends a change of the schema to every live node and waits until each one applies it.
+    /// It returns an error if some node did not confirm the change.
+    pub fn schema_change(&self, change: SchemaChange) -> Result<(), ErrorTypes> {
+        let message = NodeMessage::SchemaChange(change);
+        let mut failed = Vec::new();
+        for neighbour in self.get_neighbours() {
+            if self.is_down(&neighbour) {
+                continue;
+            }
+            let confirmed = matches!(
+                self.request(
+                    &neighbour,
+                    &message,
+                    Duration::from_secs(SCHEMA_CHANGE_TIMEOUT)
+                ),
+                Some(NodeMessage::Confirmation())
+            );
+            if !confirmed {
+                failed.push(neighbour);
+            }
+        }
+        if failed.is_empty() {
+            Ok(())
+        } else {
+            Err(ErrorTypes::new(
+                510,
+                format!("The nodes {:?} did not apply the schema change", failed),
+            ))
+        }
+    }
+
+    /// This function is responsible for waiting until every live node announces the same schema version as the local node.
+    /// It returns `false` if they do not agree before the timeout.
+    pub fn wait_for_schema_agreement(&self, local: &String, timeout: Duration) -> bool {
+        let start = Instant::now();
+        loop {
+            let agreed = {
+                let map = self.endpoint_state_map.lock().unwrap();
+                let version = map.get(local).map(|endpoint| endpoint.get_schema_version());
+                map.values()
+                    .filter(|endpoint| {
+                        !endpoint.is_down() && endpoint.get_state() != NodeState::Left
+                    })
+                    .all(|endpoint| Some(endpoint.get_schema_version()) == version)
+            };
+            if agreed {
+                return true;
+            }
+            if start.elapsed() > timeout {
+                return false;
+            }
+            thread::sleep(Duration::from_millis(100));
+        }
+    }
+
+    /// This function is responsible for updating the endpoint state map. A node is added to the hashring once it owns its tokens,
+    /// and removed from it once it has `Left`. The state of a node that left is kept so the other nodes learn it too.
     pub fn update_endpoint_state(&self, endpoint_state: EndpointState, local: &String) {
         let address = endpoint_state.get_address();
         let actual = self.get_endpoint_state(&address);
-        if let Some(actual) = actual {
+        if let Some(actual) = &actual {
             if address == *local && actual.get_generation() > endpoint_state.get_generation() {
                 return;
             }
         }
+        publish_changes(actual.as_ref(), &endpoint_state);
+        if endpoint_state.get_state() == NodeState::Left
+            && address != *local
+            && !self.removed.lock().unwrap().contains(&address)
+        {
+            self.remove_node(&address);
+        }
+        if !self.removed.lock().unwrap().contains(&address)
+            && matches!(
+                endpoint_state.get_state(),
+                NodeState::Normal | NodeState::Leaving
+            )
+        {
+            self.add_node(address.clone(), endpoint_state.get_tokens());
+        }
 
         self.endpoint_state_map
             .lock()
@@ -260,6 +612,14 @@ impl Gossiper {
         let mut map = self.endpoint_state_map.lock().unwrap();
         if let Some(endpoint) = map.get_mut(address) {
             endpoint.change_status();
+            let status = match endpoint.is_down() {
+                true => StatusNode::Down,
+                false => StatusNode::Up,
+            };
+            get_events().publish(EventKindChange::Status(
+                status,
+                endpoint.get_native_address(),
+            ));
         } else {
             let e = ErrorTypes::new(519, "Error encripting gossip message".to_string());
             let a = address
@@ -281,44 +641,47 @@ impl Gossiper {
     pub fn get_partitions_remove(
         &self,
         node: &String,
-        rf: usize,
+        strategy: &ReplicationStrategy,
     ) -> HashMap<String, Vec<(u128, u128)>> {
+        let locations = self.get_locations();
         self.hashring
             .lock()
             .unwrap()
-            .get_partitions_remove(node, rf)
+            .get_partitions_remove(node, strategy, &locations)
     }
 
-    pub fn schema_change(&self, data: NodeMessage) -> Result<(), ErrorTypes> {
-        let mut agreed = 0;
-        let lock = self.neighbours.lock().unwrap();
-        let neighbours = lock.clone();
-        drop(lock);
-        for neighbour in neighbours {
-            if let Some(sender) = self.get_sender(&neighbour) {
-                if sender.send(data.to_bytes()).is_err() {
-                    continue;
-                }
-            }
-
-            let bytes = self
-                .connections
-                .lock()
-                .unwrap()
-                .get(&neighbour)
-                .unwrap()
-                .receive();
+    /// This function is responsible for returning, for every range that a dead node replicates, a surviving replica that has to
+    /// stream it to each of its new owners. The new owner is `replacement` if it takes the tokens of the dead node.
+    pub fn get_partitions_restore(
+        &self,
+        dead: &String,
+        replacement: Option<&String>,
+        strategy: &ReplicationStrategy,
+    ) -> HashMap<(String, String), Vec<(u128, u128)>> {
+        let down = self.get_down_nodes();
+        let locations = self.get_locations();
+        self.hashring.lock().unwrap().get_partitions_restore(
+            dead,
+            replacement,
+            strategy,
+            &locations,
+            &down,
+        )
+    }
 
-            let message = NodeMessage::from_bytes(bytes[1..].to_vec());
-            if let NodeMessage::Confirmation() = message {
-                agreed += 1;
-            }
-        }
-        if agreed >= self.neighbours.lock().unwrap().len() / 2 {
-            Ok(())
-        } else {
-            Err(ErrorTypes::new(510, "Error changing schema".to_string()))
-        }
+    /// This function returns the ranges that have to be streamed after the replication of a keyspace changes from `before` to `after`,
+    /// grouped by source and target, and the nodes that stop replicating some range.
+    pub fn get_partitions_replication(
+        &self,
+        before: &ReplicationStrategy,
+        after: &ReplicationStrategy,
+    ) -> ReplicationChange {
+        let down = self.get_down_nodes();
+        let locations = self.get_locations();
+        self.hashring
+            .lock()
+            .unwrap()
+            .get_partitions_replication(before, after, &locations, &down)
     }
 
     /// This function is responsible for handling the `Ack` message type. It will update the endpoint states and send an `Ack2` message back to the sender with the relevant information.
@@ -328,9 +691,12 @@ impl Gossiper {
         states: Vec<EndpointState>,
         socket: &mut TcpStream,
         address: &Address,
+        schema: Arc<Mutex<Schema>>,
     ) -> Result<(), ErrorTypes> {
         for state in states {
-            self.update_endpoint_state(state, &address.i_address);
+            self.update_endpoint_state(state.clone(), &address.i_address);
+            self.bootstrap_node(&state, address, Arc::clone(&schema));
+            self.pull_schema(&state, address, Arc::clone(&schema));
         }
 
         let mut requested_endpoints: Vec<EndpointState> = Vec::new();
@@ -395,9 +761,16 @@ impl Gossiper {
     }
 
     /// This function is responsible for handling the `Ack2` message type. It will update the endpoint states.
-    pub fn ack2_handler(&self, states: Vec<EndpointState>, address: &Address) {
+    pub fn ack2_handler(
+        &self,
+        states: Vec<EndpointState>,
+        address: &Address,
+        schema: Arc<Mutex<Schema>>,
+    ) {
         for state in states {
-            self.update_endpoint_state(state, &address.i_address);
+            self.update_endpoint_state(state.clone(), &address.i_address);
+            self.bootstrap_node(&state, address, Arc::clone(&schema));
+            self.pull_schema(&state, address, Arc::clone(&schema));
         }
     }
 
@@ -409,9 +782,15 @@ impl Gossiper {
         let mut removed = self.removed.lock().unwrap();
         connections.remove(node);
         hashring_lock.remove_node(node.to_string());
-        ep_lock.remove(node);
+        if let Some(endpoint) = ep_lock.remove(node) {
+            get_events().publish(EventKindChange::Topology(
+                TopologyChangeType::RemovedNode,
+                endpoint.get_native_address(),
+            ));
+        }
         neighbours.retain(|x| x != node);
         removed.insert(node.to_string());
+        self.bootstrapped.lock().unwrap().remove(node);
     }
     /// This function is responsible for receiving a gossip message and returning it.
     pub fn receive_gossip_message(socket: &mut TcpStream) -> Result<GossipMessage, ErrorTypes> {
@@ -449,3 +828,38 @@ impl Gossiper {
         }
     }
 }
+
+/// This function checks if a message belongs to a streaming session, so only the session that waits for it receives it.
+fn is_stream_message(bytes: &[u8]) -> bool {
+    !bytes.is_empty()
+        && matches!(
+            NodeMessage::from_bytes(bytes[1..].to_vec()),
+            NodeMessage::StreamAck(..) | NodeMessage::StreamResponse(..)
+        )
+}
+
+/// This function sends the clients registered for events the changes that a new state of a node brings: it joined the
+/// ring when it becomes `Normal`, and it is up or down when its status changes. The events carry the address where the
+/// node talks with the clients, the one the drivers know it by.
+fn publish_changes(actual: Option<&EndpointState>, endpoint_state: &EndpointState) {
+    let address = endpoint_state.get_native_address();
+    let joined = endpoint_state.get_state() == NodeState::Normal
+        && actual.is_none_or(|actual| {
+            !matches!(actual.get_state(), NodeState::Normal | NodeState::Leaving)
+        });
+    if joined {
+        get_events().publish(EventKindChange::Topology(
+            TopologyChangeType::NewNode,
+            address.clone(),
+        ));
+    }
+    match actual {
+        Some(actual) if actual.is_down() && !endpoint_state.is_down() => {
+            get_events().publish(EventKindChange::Status(StatusNode::Up, address))
+        }
+        Some(actual) if !actual.is_down() && endpoint_state.is_down() => {
+            get_events().publish(EventKindChange::Status(StatusNode::Down, address))
+        }
+        _ => {}
+    }
+}
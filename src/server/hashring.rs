@@ -1,16 +1,26 @@
+use super::replication_strategy::ReplicationStrategy;
+use super::snitch::Location;
 use crate::errors::error_types::ErrorTypes;
 use murmur3::murmur3_x64_128;
+use rand::Rng;
 use std::collections::BTreeMap;
+use std::collections::BTreeSet;
 use std::collections::HashMap;
 use std::collections::HashSet;
 use std::io::Cursor;
-use std::ops::Bound::Excluded;
-use std::ops::Bound::Included;
 
-const REPLICAS: i32 = 32;
-pub const NODOS: usize = 8;
+pub const DEFAULT_NUM_TOKENS: usize = 32;
+
+/// A range of the ring with its replicas before and after a change of the ring.
+type ChangedRange = ((u128, u128), Vec<String>, Vec<String>);
+/// The ranges that every pair `(source, target)` has to stream after a change of replication, and the nodes that stop replicating some range.
+pub type ReplicationChange = (
+    HashMap<(String, String), Vec<(u128, u128)>>,
+    HashSet<String>,
+);
 pub struct HashRing {
     pub node_ring: BTreeMap<u128, String>,
+    pub tokens: HashMap<String, Vec<u128>>,
     pub quantity: usize,
 }
 ///This struct represents a HashRing of data and nodes to implement the Consistent Hashing algorithm.
@@ -24,33 +34,98 @@ impl HashRing {
     pub fn new() -> HashRing {
         HashRing {
             node_ring: BTreeMap::new(),
+            tokens: HashMap::new(),
             quantity: 0,
         }
     }
     pub fn hash<T: AsRef<[u8]>>(key: T) -> u128 {
         murmur3_x64_128(&mut Cursor::new(key), 0).unwrap()
     }
-    ///This function adds a node to the HashRing.
-    pub fn add_node(&mut self, node: String) {
-        if self.node_ring.values().any(|v| v == &node) {
+    ///This function adds a node to the HashRing with the tokens it owns.
+    pub fn add_node(&mut self, node: String, tokens: Vec<u128>) {
+        if self.tokens.contains_key(&node) || tokens.is_empty() {
             return;
         }
-        for i in 0..REPLICAS {
-            let vnode = format!("{}-{}", node, i);
-            let hash = Self::hash(&vnode);
-            self.node_ring.insert(hash, node.clone());
+        for token in tokens.iter() {
+            self.node_ring.insert(*token, node.clone());
         }
+        self.tokens.insert(node, tokens);
         self.quantity += 1;
     }
 
     ///This function removes a node from the HashRing.
     pub fn remove_node(&mut self, node: String) {
-        for i in 0..REPLICAS {
-            let vnode = format!("{}-{}", node, i);
-            let hash = Self::hash(&vnode);
-            self.node_ring.remove(&hash);
+        if let Some(tokens) = self.tokens.remove(&node) {
+            for token in tokens {
+                if self.node_ring.get(&token) == Some(&node) {
+                    self.node_ring.remove(&token);
+                }
+            }
+            self.quantity -= 1;
         }
-        self.quantity -= 1;
+    }
+
+    /// This function returns the tokens owned by a node, if it belongs to the HashRing.
+    pub fn get_tokens(&self, node: &String) -> Option<&Vec<u128>> {
+        self.tokens.get(node)
+    }
+
+    /// This function returns `num_tokens` random tokens that are not used by any node of the HashRing.
+    pub fn random_tokens(&self, num_tokens: usize) -> Vec<u128> {
+        let mut rng = rand::thread_rng();
+        let mut tokens = Vec::new();
+        while tokens.len() < num_tokens {
+            let token: u128 = rng.gen();
+            if !self.node_ring.contains_key(&token) && !tokens.contains(&token) {
+                tokens.push(token);
+            }
+        }
+        tokens
+    }
+
+    /// This function chooses `num_tokens` tokens for a new node trying to balance the ownership of every node.
+    ///
+    /// Each token splits one of the current ranges in half. The chosen range is the one that minimizes the
+    /// imbalance of the replicated ownership of the nodes, taking into account the replication factor.
+    pub fn allocate_tokens(&self, node: &String, num_tokens: usize, rf: usize) -> Vec<u128> {
+        if self.node_ring.is_empty() {
+            return self.random_tokens(num_tokens);
+        }
+        let mut ring = self.node_ring.clone();
+        let mut tokens = Vec::new();
+        while tokens.len() < num_tokens {
+            let mut best: Option<(f64, u128)> = None;
+            let mut previous = *ring.keys().last().unwrap();
+            for token in ring.keys() {
+                let candidate = previous.wrapping_add(token.wrapping_sub(previous) / 2);
+                previous = *token;
+                if ring.contains_key(&candidate) {
+                    continue;
+                }
+                let mut new_ring = ring.clone();
+                new_ring.insert(candidate, node.to_string());
+                let imbalance = ownership(&new_ring, rf)
+                    .values()
+                    .map(|owned| owned * owned)
+                    .sum::<f64>();
+                if best.is_none_or(|(value, _)| imbalance < value) {
+                    best = Some((imbalance, candidate));
+                }
+            }
+            match best {
+                Some((_, token)) => {
+                    ring.insert(token, node.to_string());
+                    tokens.push(token);
+                }
+                None => tokens.extend(self.random_tokens(num_tokens - tokens.len())),
+            }
+        }
+        tokens
+    }
+
+    /// This function returns the fraction of the token space that every node replicates.
+    pub fn get_ownership(&self, rf: usize) -> HashMap<String, f64> {
+        ownership(&self.node_ring, rf)
     }
     ///This function returns the node that is responsible of the key.
     pub fn get_node(&self, key: Vec<&String>) -> (Option<String>, u128) {
@@ -66,203 +141,441 @@ impl HashRing {
         )
     }
 
-    /// This function returns the partitions that the local node is responsible of transfering to the new node.
-    pub fn get_partitions(&self, node: &String, local: &String, rf: usize) -> Vec<(u128, u128)> {
-        let mut partitions = Vec::new();
-        let mut used = HashSet::new();
-        let mut vnodes = Vec::new();
-        for i in 0..REPLICAS {
-            let vnode = format!("{}-{}", node, i);
-            let hash = Self::hash(&vnode);
-            vnodes.push(hash);
+    /// This function returns the partitions that the local node is responsible of transfering to the new node, which will own `tokens`.
+    ///
+    /// Each range is streamed by its first replica, skipping the ones that are `down`. The flag of each range tells if the local node stops replicating it.
+    pub fn get_partitions(
+        &self,
+        node: &String,
+        tokens: &[u128],
+        local: &String,
+        strategy: &ReplicationStrategy,
+        locations: &HashMap<String, Location>,
+        down: &HashSet<String>,
+    ) -> Vec<((u128, u128), bool)> {
+        let mut after = self.node_ring.clone();
+        for token in tokens {
+            after.insert(*token, node.to_string());
         }
-        vnodes.sort_by(|a, b| b.cmp(a));
-        for vnode in vnodes {
-            let mut j = 0;
-            let next = self.get_next(vnode, node);
-            let next_value = self.node_ring.get(&next).unwrap();
-            let mut previous = vnode;
-            let replicas = self.get_replicas(next, rf, next_value);
-            let mut replicas = replicas.unwrap();
-            replicas.insert(0, next_value.to_string());
-            if !replicas.contains(local) || used.contains(&vnode) {
+        let mut partitions = Vec::new();
+        for (range, before, after) in changed_ranges(&self.node_ring, &after, strategy, locations) {
+            if !after.contains(node) || before.contains(node) {
                 continue;
             }
-            while j < rf {
-                let prev = self.get_previous(previous, node, &mut used);
-                let range = (prev, previous);
-
-                previous = prev;
-                if replicas[replicas.len() - 1 - j] != *local {
-                    j += 1;
-                    continue;
-                }
-                partitions.push(range);
-                j += 1;
+            if before.iter().min_by_key(|replica| down.contains(*replica)) == Some(local) {
+                partitions.push((range, !after.contains(local)));
             }
         }
         partitions
     }
 
+    /// This function returns, for every node that takes over ranges of `node` once it leaves the ring, the ranges it has to receive.
     pub fn get_partitions_remove(
         &self,
         node: &String,
-        rf: usize,
+        strategy: &ReplicationStrategy,
+        locations: &HashMap<String, Location>,
     ) -> HashMap<String, Vec<(u128, u128)>> {
-        let mut partitions = HashMap::new();
-        let mut used = HashSet::new();
-        let mut vnodes = Vec::new();
-        for i in 0..REPLICAS {
-            let vnode = format!("{}-{}", node, i);
-            let hash = Self::hash(&vnode);
-            vnodes.push(hash);
-        }
-        vnodes.sort_by(|a, b| b.cmp(a));
-        for vnode in vnodes {
-            let mut j = 0;
-            let next = self.get_next(vnode, node);
-            let next_value = self.node_ring.get(&next).unwrap();
-            let mut previous = vnode;
-            let replicas = self.get_replicas(next, rf, next_value);
-            let mut replicas = replicas.unwrap();
-            replicas.insert(0, next_value.to_string());
-            if used.contains(&vnode) {
+        let mut after = self.node_ring.clone();
+        after.retain(|_, owner| owner != node);
+        let mut partitions: HashMap<String, Vec<(u128, u128)>> = HashMap::new();
+        for (range, before, after) in changed_ranges(&self.node_ring, &after, strategy, locations) {
+            if !before.contains(node) {
                 continue;
             }
-            while j < rf {
-                let prev = self.get_previous(previous, node, &mut used);
-                let range = (prev, previous);
-
-                previous = prev;
-
-                partitions
-                    .entry(replicas[replicas.len() - 1 - j].to_string())
-                    .or_insert(Vec::new())
-                    .push(range);
-                j += 1;
+            for target in after.into_iter().filter(|target| !before.contains(target)) {
+                partitions.entry(target).or_default().push(range);
             }
         }
         partitions
     }
 
-    fn get_next(&self, key: u128, value: &String) -> u128 {
-        let mut next = key;
-
-        for (k, v) in self.node_ring.range((Excluded(key), Included(u128::MAX))) {
-            if v == value {
-                continue;
+    /// This function returns, for every range that `dead` replicates, the nodes that have to receive it once `dead` leaves the ring
+    /// and a surviving replica they can get it from. If there is a `replacement`, it takes the tokens of `dead` instead.
+    ///
+    /// The ranges are grouped by the pair `(source, target)`. The source is a replica that is not `down` whenever there is one.
+    pub fn get_partitions_restore(
+        &self,
+        dead: &String,
+        replacement: Option<&String>,
+        strategy: &ReplicationStrategy,
+        locations: &HashMap<String, Location>,
+        down: &HashSet<String>,
+    ) -> HashMap<(String, String), Vec<(u128, u128)>> {
+        let mut after = self.node_ring.clone();
+        match replacement {
+            Some(replacement) => {
+                for node in after.values_mut().filter(|node| *node == dead) {
+                    *node = replacement.to_string();
+                }
             }
-            next = *k;
-            return next;
+            None => after.retain(|_, node| node != dead),
         }
-
-        for (k, v) in self.node_ring.range((Included(0), Included(key))) {
-            if v == value {
+        let mut partitions: HashMap<(String, String), Vec<(u128, u128)>> = HashMap::new();
+        for (range, before, after) in changed_ranges(&self.node_ring, &after, strategy, locations) {
+            if !before.contains(dead) {
                 continue;
             }
-            next = *k;
-            return next;
+            let source = match before
+                .iter()
+                .filter(|node| *node != dead)
+                .min_by_key(|node| down.contains(*node))
+            {
+                Some(source) => source,
+                None => continue,
+            };
+            for target in after.into_iter().filter(|target| !before.contains(target)) {
+                partitions
+                    .entry((source.to_string(), target))
+                    .or_default()
+                    .push(range);
+            }
         }
-
-        next
+        partitions
     }
 
-    fn get_previous(&self, key: u128, value: &String, used: &mut HashSet<u128>) -> u128 {
-        let mut previous = key;
-
-        for (k, v) in self.node_ring.range((Included(0), Excluded(key))).rev() {
-            if v == value {
-                used.insert(*k);
-                continue;
+    /// This function returns, for every range whose replicas change when a keyspace goes from the strategy `before` to `after`,
+    /// the nodes that start replicating it and a replica they can get it from, and the nodes that stop replicating some range.
+    ///
+    /// The ranges are grouped by the pair `(source, target)`. The source is a replica that is not `down` whenever there is one.
+    pub fn get_partitions_replication(
+        &self,
+        before: &ReplicationStrategy,
+        after: &ReplicationStrategy,
+        locations: &HashMap<String, Location>,
+        down: &HashSet<String>,
+    ) -> ReplicationChange {
+        let mut partitions: HashMap<(String, String), Vec<(u128, u128)>> = HashMap::new();
+        let mut leaving = HashSet::new();
+        let mut previous = match self.node_ring.keys().last() {
+            Some(last) => *last,
+            None => return (partitions, leaving),
+        };
+        for token in self.node_ring.keys() {
+            let range = (previous, *token);
+            previous = *token;
+            let replicas_before = range_replicas(&self.node_ring, *token, before, locations);
+            let replicas_after = range_replicas(&self.node_ring, *token, after, locations);
+            leaving.extend(
+                replicas_before
+                    .iter()
+                    .filter(|node| !replicas_after.contains(node))
+                    .cloned(),
+            );
+            let source = match replicas_before
+                .iter()
+                .min_by_key(|node| down.contains(*node))
+            {
+                Some(source) => source,
+                None => continue,
+            };
+            for target in replicas_after
+                .iter()
+                .filter(|target| !replicas_before.contains(target))
+            {
+                partitions
+                    .entry((source.to_string(), target.to_string()))
+                    .or_default()
+                    .push(range);
             }
-            previous = *k;
-            return previous;
         }
+        (partitions, leaving)
+    }
 
-        for (k, v) in self
-            .node_ring
-            .range((Included(key), Included(u128::MAX)))
-            .rev()
-        {
-            if v == value {
-                continue;
-            }
-            previous = *k;
-            return previous;
+    /// This function returns every range of the ring, in the order of the ring, with the nodes that replicate it.
+    pub fn get_ranges(
+        &self,
+        strategy: &ReplicationStrategy,
+        locations: &HashMap<String, Location>,
+    ) -> Vec<((u128, u128), Vec<String>)> {
+        let mut ranges = Vec::new();
+        let mut previous = match self.node_ring.keys().last() {
+            Some(last) => *last,
+            None => return ranges,
+        };
+        for token in self.node_ring.keys() {
+            let replicas = range_replicas(&self.node_ring, *token, strategy, locations);
+            ranges.push(((previous, *token), replicas));
+            previous = *token;
         }
-
-        previous
+        ranges
     }
-    ///This function returns the replicas of the node that is responsible of the key.
+
+    ///This function returns the replicas of the key, starting with the node that is responsible of it.
     pub fn get_replicas(
         &self,
-        mut key: u128,
-        rf: usize,
-        local: &String,
+        key: u128,
+        strategy: &ReplicationStrategy,
+        locations: &HashMap<String, Location>,
     ) -> Result<Vec<String>, ErrorTypes> {
-        if self.node_ring.len() - 1 < rf {
+        let replicas = range_replicas(&self.node_ring, key, strategy, locations);
+        if replicas.is_empty() {
             return Err(ErrorTypes::Error {
                 code: 543,
                 message: "There are not enough nodes to complete the replication factor"
                     .to_string(),
             });
         }
-        let mut nodes: Vec<String> = Vec::new();
-
-        while nodes.len() < rf - 1 {
-            let last = match self.node_ring.last_key_value() {
-                Some((last, _)) => last,
-                _ => {
-                    return Err(ErrorTypes::Error {
-                        code: 580,
-                        message: "There are not enough nodes to complete the replication factor"
-                            .to_string(),
-                    })
-                }
-            };
-            let node = self
-                .node_ring
-                .range((Excluded(key), Included(*last)))
-                .next();
-
-            if let Some((current_key, next_node)) = node {
-                if !nodes.contains(next_node) && next_node != local {
-                    nodes.push(next_node.to_string());
-                }
-                key = *current_key;
-            } else {
-                let first_node = self.node_ring.range(0..).next();
-                if let Some((first_key, first_node)) = first_node {
-                    if !nodes.contains(first_node) && first_node != local {
-                        nodes.push(first_node.to_string());
-                    }
-                    key = *first_key;
-                }
+        Ok(replicas)
+    }
+}
+/// This function returns the nodes that replicate the range that ends in `token`, walking the ring clockwise from it.
+fn range_replicas(
+    ring: &BTreeMap<u128, String>,
+    token: u128,
+    strategy: &ReplicationStrategy,
+    locations: &HashMap<String, Location>,
+) -> Vec<String> {
+    let walk = ring
+        .range(token..)
+        .chain(ring.range(..token))
+        .map(|(_, node)| node);
+    strategy.select_replicas(walk, locations)
+}
+
+/// This function returns the ranges whose replicas change from the ring `before` to the ring `after`, with the replicas in each ring.
+/// The ranges are delimited by the tokens of both rings.
+fn changed_ranges(
+    before: &BTreeMap<u128, String>,
+    after: &BTreeMap<u128, String>,
+    strategy: &ReplicationStrategy,
+    locations: &HashMap<String, Location>,
+) -> Vec<ChangedRange> {
+    let tokens = before
+        .keys()
+        .chain(after.keys())
+        .copied()
+        .collect::<BTreeSet<u128>>();
+    let mut ranges = Vec::new();
+    let mut previous = match tokens.last() {
+        Some(last) => *last,
+        None => return ranges,
+    };
+    for token in tokens {
+        let range = (previous, token);
+        previous = token;
+        let replicas_before = range_replicas(before, token, strategy, locations);
+        let replicas_after = range_replicas(after, token, strategy, locations);
+        if replicas_before != replicas_after {
+            ranges.push((range, replicas_before, replicas_after));
+        }
+    }
+    ranges
+}
+
+/// This function returns the fraction of the token space that every node of the ring replicates.
+fn ownership(ring: &BTreeMap<u128, String>, rf: usize) -> HashMap<String, f64> {
+    let mut owned: HashMap<String, f64> = HashMap::new();
+    let tokens = ring.iter().collect::<Vec<(&u128, &String)>>();
+    let nodes = ring.values().collect::<HashSet<&String>>().len();
+    for (i, (token, _)) in tokens.iter().enumerate() {
+        let previous = tokens[(i + tokens.len() - 1) % tokens.len()].0;
+        let size = if tokens.len() == 1 {
+            1.0
+        } else {
+            token.wrapping_sub(*previous) as f64 / u128::MAX as f64
+        };
+        let mut replicas: Vec<&String> = Vec::new();
+        let mut j = i;
+        while replicas.len() < usize::min(rf, nodes) {
+            let (_, node) = tokens[j % tokens.len()];
+            if !replicas.contains(&node) {
+                replicas.push(node);
             }
+            j += 1;
+        }
+        for replica in replicas {
+            *owned.entry(replica.to_string()).or_insert(0.0) += size;
         }
-        Ok(nodes)
     }
+    owned
 }
+
 #[cfg(test)]
 mod test {
     use super::*;
 
+    const SIMPLE: ReplicationStrategy = ReplicationStrategy::Simple(2);
+
+    fn hashed_tokens(node: &str) -> Vec<u128> {
+        (0..DEFAULT_NUM_TOKENS)
+            .map(|i| HashRing::hash(format!("{}-{}", node, i)))
+            .collect()
+    }
+
+    fn add_node(ring: &mut HashRing, node: &str) {
+        ring.add_node(node.to_string(), hashed_tokens(node));
+    }
+
     #[test]
     fn test_get_replicas() {
         let mut ring = HashRing::new();
-        ring.add_node("127.0.0.1:8080".to_string());
-        ring.add_node("127.0.0.1:8081".to_string());
-        ring.add_node("127.0.0.1:8082".to_string());
-        ring.add_node("127.0.0.1:8083".to_string());
-        ring.add_node("127.0.0.1:8084".to_string());
-        ring.add_node("127.0.0.1:8085".to_string());
-        ring.add_node("127.0.0.1:8086".to_string());
-        ring.add_node("127.0.0.1:8087".to_string());
-        ring.add_node("127.0.0.1:8088".to_string()); //MLO RHO, HER
+        add_node(&mut ring, "127.0.0.1:8080");
+        add_node(&mut ring, "127.0.0.1:8081");
+        add_node(&mut ring, "127.0.0.1:8082");
+        add_node(&mut ring, "127.0.0.1:8083");
+        add_node(&mut ring, "127.0.0.1:8084");
+        add_node(&mut ring, "127.0.0.1:8085");
+        add_node(&mut ring, "127.0.0.1:8086");
+        add_node(&mut ring, "127.0.0.1:8087");
+        add_node(&mut ring, "127.0.0.1:8088"); //MLO RHO, HER
 
         let (node, _) = ring.get_node(vec![&"MLO".to_string()]);
         let node = node.unwrap();
 
         assert_eq!(node, "127.0.0.1:8088")
     }
+
+    #[test]
+    fn test_remove_node() {
+        let mut ring = HashRing::new();
+        add_node(&mut ring, "127.0.0.1:8080");
+        add_node(&mut ring, "127.0.0.1:8081");
+        ring.remove_node("127.0.0.1:8081".to_string());
+
+        assert_eq!(ring.quantity, 1);
+        assert_eq!(ring.node_ring.len(), DEFAULT_NUM_TOKENS);
+        assert!(ring.node_ring.values().all(|v| v == "127.0.0.1:8080"));
+    }
+
+    #[test]
+    fn test_allocate_tokens() {
+        let mut ring = HashRing::new();
+        add_node(&mut ring, "127.0.0.1:8080");
+        add_node(&mut ring, "127.0.0.1:8081");
+        add_node(&mut ring, "127.0.0.1:8082");
+        let node = "127.0.0.1:8083".to_string();
+        let tokens = ring.allocate_tokens(&node, 16, 2);
+        ring.add_node(node.clone(), tokens);
+
+        let ownership = ring.get_ownership(2);
+        let expected = 2.0 / 4.0;
+        assert_eq!(ring.get_tokens(&node).unwrap().len(), 16);
+        assert!((ownership.get(&node).unwrap() - expected).abs() < 0.1);
+    }
+
+    #[test]
+    fn test_get_ranges() {
+        let mut ring = HashRing::new();
+        add_node(&mut ring, "127.0.0.1:8080");
+        add_node(&mut ring, "127.0.0.1:8081");
+        add_node(&mut ring, "127.0.0.1:8082");
+        let locations = HashMap::new();
+
+        let ranges = ring.get_ranges(&SIMPLE, &locations);
+        assert_eq!(ranges.len(), 3 * DEFAULT_NUM_TOKENS);
+        let mut previous = *ring.node_ring.keys().last().unwrap();
+        for ((start, end), replicas) in ranges {
+            assert_eq!(start, previous);
+            assert_eq!(
+                replicas,
+                ring.get_replicas(end, &SIMPLE, &locations).unwrap()
+            );
+            previous = end;
+        }
+    }
+
+    #[test]
+    fn test_partitions_replication() {
+        let mut ring = HashRing::new();
+        add_node(&mut ring, "127.0.0.1:8080");
+        add_node(&mut ring, "127.0.0.1:8081");
+        add_node(&mut ring, "127.0.0.1:8082");
+        let one = ReplicationStrategy::Simple(1);
+
+        let (partitions, leaving) =
+            ring.get_partitions_replication(&one, &SIMPLE, &HashMap::new(), &HashSet::new());
+        assert!(leaving.is_empty());
+        assert!(partitions.keys().all(|(source, target)| source != target));
+        assert_eq!(
+            partitions.values().map(|r| r.len()).sum::<usize>(),
+            ring.node_ring.len()
+        );
+
+        let (partitions, leaving) =
+            ring.get_partitions_replication(&SIMPLE, &one, &HashMap::new(), &HashSet::new());
+        assert!(partitions.is_empty());
+        assert_eq!(leaving.len(), 3);
+    }
+
+    #[test]
+    fn test_partitions_restore() {
+        let mut ring = HashRing::new();
+        add_node(&mut ring, "127.0.0.1:8080");
+        add_node(&mut ring, "127.0.0.1:8081");
+        add_node(&mut ring, "127.0.0.1:8082");
+        let dead = "127.0.0.1:8081".to_string();
+        let replaced = ring
+            .node_ring
+            .keys()
+            .filter(|token| {
+                range_replicas(&ring.node_ring, **token, &SIMPLE, &HashMap::new()).contains(&dead)
+            })
+            .count();
+
+        let partitions =
+            ring.get_partitions_restore(&dead, None, &SIMPLE, &HashMap::new(), &HashSet::new());
+        assert!(partitions
+            .keys()
+            .all(|(source, target)| *source != dead && *target != dead && source != target));
+        assert_eq!(
+            partitions.values().map(|r| r.len()).sum::<usize>(),
+            replaced
+        );
+
+        let replacement = "127.0.0.1:8083".to_string();
+        let partitions = ring.get_partitions_restore(
+            &dead,
+            Some(&replacement),
+            &SIMPLE,
+            &HashMap::new(),
+            &HashSet::new(),
+        );
+        assert!(partitions.keys().all(|(_, target)| *target == replacement));
+        assert_eq!(
+            partitions.values().map(|r| r.len()).sum::<usize>(),
+            replaced
+        );
+    }
+
+    #[test]
+    fn test_partitions_bootstrap() {
+        let mut ring = HashRing::new();
+        let nodes = ["127.0.0.1:8080", "127.0.0.1:8081", "127.0.0.1:8082"];
+        for node in nodes {
+            add_node(&mut ring, node);
+        }
+        let new_node = "127.0.0.1:8083".to_string();
+        let tokens = hashed_tokens(&new_node);
+        let mut after = ring.node_ring.clone();
+        for token in tokens.iter() {
+            after.insert(*token, new_node.clone());
+        }
+        let gained = changed_ranges(&ring.node_ring, &after, &SIMPLE, &HashMap::new())
+            .into_iter()
+            .filter(|(_, before, after)| after.contains(&new_node) && !before.contains(&new_node))
+            .map(|(range, _, _)| range)
+            .collect::<HashSet<(u128, u128)>>();
+
+        let mut streamed = Vec::new();
+        for node in nodes {
+            let local = node.to_string();
+            for (range, cleanup) in ring.get_partitions(
+                &new_node,
+                &tokens,
+                &local,
+                &SIMPLE,
+                &HashMap::new(),
+                &HashSet::new(),
+            ) {
+                assert_eq!(
+                    cleanup,
+                    !range_replicas(&after, range.1, &SIMPLE, &HashMap::new()).contains(&local)
+                );
+                streamed.push(range);
+            }
+        }
+        assert_eq!(streamed.len(), gained.len());
+        assert_eq!(streamed.into_iter().collect::<HashSet<_>>(), gained);
+    }
 }
@@ -1,11 +1,15 @@
+use super::replication_strategy::ReplicationStrategy;
 use serde::de::Error as DeError;
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
 #[derive(Debug, Clone, Eq, Hash, PartialEq)]
 
-/// This struct represents the Keyspace object. It contains the name of the keyspace and the replication factor.
+/// This struct represents the Keyspace object. It contains the name of the keyspace, its replication strategy,
+/// when it was created and when its replication was last altered, in milliseconds.
 pub struct Keyspace {
     pub name: String,
-    pub replication: usize,
+    pub replication: ReplicationStrategy,
+    pub created_at: i64,
+    pub altered_at: i64,
 }
 
 impl Serialize for Keyspace {
@@ -13,7 +17,10 @@ impl Serialize for Keyspace {
     where
         S: Serializer,
     {
-        let keyspace_string = format!("{}:{}", self.name, self.replication);
+        let keyspace_string = format!(
+            "{}:{}:{}:{}",
+            self.name, self.replication, self.created_at, self.altered_at
+        );
         serializer.serialize_str(&keyspace_string)
     }
 }
@@ -24,16 +31,51 @@ impl<'de> Deserialize<'de> for Keyspace {
         D: Deserializer<'de>,
     {
         let s = String::deserialize(deserializer)?;
-        let parts: Vec<&str> = s.split(':').collect();
-        let name = parts[0].to_string();
-        let replication = parts[1].parse::<usize>().map_err(D::Error::custom)?;
-        Ok(Keyspace { name, replication })
+        let invalid = || D::Error::custom(format!("Invalid keyspace {}", s));
+        let parts = s.split(':').collect::<Vec<&str>>();
+        // Los keyspaces guardados por versiones anteriores no tienen fecha de creación ni de modificación.
+        let timestamp = |index: usize| match parts.get(index) {
+            Some(timestamp) => timestamp.parse::<i64>().map_err(|_| invalid()),
+            None => Ok(0),
+        };
+        let (created_at, altered_at) = (timestamp(2)?, timestamp(3)?);
+        let (name, replication) = match parts[..] {
+            [name, replication, ..] if parts.len() <= 4 => (name, replication),
+            _ => return Err(invalid()),
+        };
+        let replication = replication
+            .parse::<ReplicationStrategy>()
+            .map_err(|e| D::Error::custom(e.get().1))?;
+        Ok(Keyspace {
+            name: name.to_string(),
+            replication,
+            created_at,
+            altered_at,
+        })
     }
 }
 
 impl Keyspace {
-    pub fn new(name: String, replication: usize) -> Self {
-        Self { name, replication }
+    pub fn new(name: String, replication: ReplicationStrategy) -> Self {
+        Self {
+            name,
+            replication,
+            created_at: chrono::Utc::now().timestamp_millis(),
+            altered_at: 0,
+        }
+    }
+
+    /// This function returns when the definition of the keyspace last changed, either because it was created or altered.
+    pub fn changed_at(&self) -> i64 {
+        self.created_at.max(self.altered_at)
+    }
+
+    /// This function changes the replication strategy of the keyspace.
+    pub fn alter(&mut self, replication: ReplicationStrategy) {
+        self.replication = replication;
+        self.altered_at = chrono::Utc::now()
+            .timestamp_millis()
+            .max(self.changed_at() + 1);
     }
     pub fn get_name(&self) -> &str {
         &self.name
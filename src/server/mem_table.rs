@@ -1,22 +1,27 @@
-use crate::server::sstable::{clean_line, meets_conditions, SSTable};
+use crate::protocol::protocol_notations::cql_value::NULL_TEXT;
+use crate::server::data_dir::join;
+use crate::server::sstable::{clean_line, decode_line, encode_line, meets_conditions, SSTable};
 use crate::{
     errors::error_types::ErrorTypes, protocol::query_parser::clause::Clause,
     server::sstable::sort_by_columns,
 };
 
 use chrono::DateTime;
-use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use serde::{de::Error as DeError, Deserialize, Deserializer, Serialize, Serializer};
 use std::fs::File;
 use std::io::BufReader;
 use std::str::FromStr;
 use std::{
-    collections::HashMap,
+    collections::{BTreeMap, HashMap},
     fs::{self, OpenOptions},
     io::{BufRead, Write},
 };
 
 use super::tabledefinition::TableDefinition;
 const MAX_ENTRIES: usize = 1;
+const SSTABLE_FILE: &str = "sstable.csv";
+const COMPACT_FILE: &str = "sstable_compact.csv";
+const TEMP_FILE: &str = "sstable_temp.csv";
 #[derive(Clone, Debug)]
 /// This struct represents a MemTable, where data is a Hashmap, Key is a u128 (token range) and Value is a Vec of Vec of Strings (rows).
 pub struct MemTable {
@@ -28,7 +33,9 @@ pub struct MemTable {
     pub columns_type: Vec<(String, String)>,
     pub max_entries: usize,
     pub ss_tables: SSTable,
-    pub id: String,
+    pub dir: String,
+    pub created_at: i64,
+    pub altered_at: i64,
 }
 
 impl Serialize for MemTable {
@@ -45,6 +52,8 @@ impl Serialize for MemTable {
             &self.clustering_key,
             &self.columns_type,
             &self.ss_tables,
+            &self.created_at,
+            &self.altered_at,
         )
             .serialize(serializer)
     }
@@ -55,7 +64,18 @@ impl<'de> Deserialize<'de> for MemTable {
     where
         D: Deserializer<'de>,
     {
-        let table_definition: TableDefinition = Deserialize::deserialize(deserializer)?;
+        let mut fields: Vec<serde_json::Value> = Deserialize::deserialize(deserializer)?;
+        // Las tablas guardadas por versiones anteriores no tienen fecha de creación ni de modificación.
+        let mut timestamps = fields.split_off(fields.len().min(7)).into_iter();
+        let mut timestamp = || {
+            timestamps
+                .next()
+                .and_then(|value| value.as_i64())
+                .unwrap_or(0)
+        };
+        let (created_at, altered_at) = (timestamp(), timestamp());
+        let table_definition: TableDefinition =
+            serde_json::from_value(serde_json::Value::Array(fields)).map_err(D::Error::custom)?;
         let (table_name, data, columns, partition_key, clustering_key, columns_type, ss_tables) =
             table_definition;
 
@@ -72,31 +92,35 @@ impl<'de> Deserialize<'de> for MemTable {
             clustering_key,
             columns_type,
             ss_tables,
-            id: "".to_string(),
+            dir: "".to_string(),
             max_entries: MAX_ENTRIES,
+            created_at,
+            altered_at,
         })
     }
 }
 
 impl MemTable {
-    /// This function creates a new MemTable
+    /// This function creates a new MemTable whose files are stored in the directory `dir`.
     pub fn new(
         columns_type: Vec<(String, String)>,
         partition_key: Vec<String>,
         table_name: String,
         clustering_key: Vec<String>,
-        id: String,
+        dir: String,
     ) -> MemTable {
         MemTable {
-            id: id.clone(),
+            ss_tables: SSTable::new(join(&dir, SSTABLE_FILE)),
+            dir,
             table_name: table_name.clone(),
             data: HashMap::new(),
             columns: columns_type.iter().map(|(name, _)| name.clone()).collect(),
             partition_key: Self::make_partition_key(partition_key, &columns_type),
             clustering_key: Self::make_clustering_key(clustering_key, &columns_type),
             max_entries: MAX_ENTRIES,
-            ss_tables: SSTable::new(format!("{}_{}_sstable.csv", id, table_name)),
             columns_type,
+            created_at: chrono::Utc::now().timestamp_millis(),
+            altered_at: 0,
         }
     }
 
@@ -119,11 +143,71 @@ impl MemTable {
             .collect::<Vec<usize>>()
     }
 
-    /// This function sets the id
-    pub fn set_id(&mut self, id: &String, name: &String) {
-        self.id = id.to_string();
-        self.ss_tables
-            .set_route(format!("{}_{}_sstable.csv", id, name));
+    /// This function deletes every row of the table, both the ones in memory and the ones in its files.
+    pub fn truncate(&mut self) -> Result<(), ErrorTypes> {
+        self.data.clear();
+        if fs::metadata(&self.dir).is_ok() && fs::remove_dir_all(&self.dir).is_err() {
+            return Err(ErrorTypes::new(
+                598,
+                "Error deleting the table files".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    /// This function changes the columns of the table, keeping the primary key. The rows in memory and in the SSTable are rewritten
+    /// with the new columns: the values of the columns that are kept are moved to their new position, the new columns are null
+    /// and the values of the dropped columns are deleted.
+    pub fn alter_columns(&mut self, columns_type: Vec<(String, String)>) -> Result<(), ErrorTypes> {
+        let indexes = self.get_columns_index();
+        let positions = columns_type
+            .iter()
+            .map(|(column, _)| indexes.get(column).map(|i| i + 1))
+            .collect::<Vec<Option<usize>>>();
+        let migrate = |row: &Vec<String>| -> Vec<String> {
+            let mut new_row = vec![row[0].clone()];
+            for position in &positions {
+                let value = position.and_then(|i| row.get(i)).cloned();
+                new_row.push(value.unwrap_or_else(|| NULL_TEXT.to_string()));
+            }
+            new_row.push(row.last().cloned().unwrap_or_default());
+            new_row
+        };
+        for rows in self.data.values_mut() {
+            *rows = rows.iter().map(migrate).collect();
+        }
+        if fs::metadata(self.ss_tables.get_route()).is_ok() {
+            let lines = self.get_sstables_lines(self.ss_tables.get_route())?;
+            let temp_file = join(&self.dir, TEMP_FILE);
+            let mut file = File::create(&temp_file)
+                .map_err(|_| ErrorTypes::new(501, "Could not open the file".to_string()))?;
+            for line in lines.iter().filter(|line| line.len() > 1) {
+                writeln!(file, "{}", encode_line(&migrate(line)))
+                    .map_err(|_| ErrorTypes::new(501, "Could not write the file".to_string()))?;
+            }
+            rename_file(self.ss_tables.get_route(), temp_file);
+        }
+        let partition_key = self
+            .get_partition_key()
+            .into_iter()
+            .map(|(column, _)| column)
+            .collect::<Vec<String>>();
+        let clustering_key = self
+            .get_clustering_key()
+            .into_iter()
+            .map(|(column, _)| column)
+            .collect::<Vec<String>>();
+        self.partition_key = Self::make_partition_key(partition_key, &columns_type);
+        self.clustering_key = Self::make_clustering_key(clustering_key, &columns_type);
+        self.columns = columns_type.iter().map(|(name, _)| name.clone()).collect();
+        self.columns_type = columns_type;
+        Ok(())
+    }
+
+    /// This function sets the directory where the files of the table are stored.
+    pub fn set_dir(&mut self, dir: &str) {
+        self.dir = dir.to_string();
+        self.ss_tables.set_route(join(dir, SSTABLE_FILE));
     }
 
     ///This function creates the partition key
@@ -222,7 +306,7 @@ impl MemTable {
     /// This function deletes a row from the MemTable
     pub fn delete_row(&mut self, key: u128, row: &Vec<String>) -> bool {
         if let Some(rows) = self.data.get_mut(&key) {
-            if let Some(pos) = rows.iter().position(|r| &clean_line(r.join(",")) == row) {
+            if let Some(pos) = rows.iter().position(|r| &clean_line(encode_line(r)) == row) {
                 rows.remove(pos);
                 if rows.is_empty() {
                     self.data.remove(&key);
@@ -235,6 +319,12 @@ impl MemTable {
 
     /// This function flushes the MemTable to the SSTable file.
     pub fn flush(&mut self) -> Result<(), ErrorTypes> {
+        if fs::create_dir_all(&self.dir).is_err() {
+            return Err(ErrorTypes::new(
+                588,
+                "Error creating the table directory".to_string(),
+            ));
+        }
         let _ = match OpenOptions::new()
             .append(true)
             .create(true)
@@ -324,10 +414,7 @@ impl MemTable {
         for line in res_lines {
             writeln!(new_sstable, "{}", line).unwrap();
         }
-        rename_file(
-            self.ss_tables.get_route(),
-            format!("{}_sstable_compact.csv", self.id),
-        );
+        rename_file(self.ss_tables.get_route(), join(&self.dir, COMPACT_FILE));
         Ok(())
     }
 
@@ -336,7 +423,7 @@ impl MemTable {
         OpenOptions::new()
             .append(true)
             .create(true)
-            .open(format!("{}_sstable_compact.csv", self.id))
+            .open(join(&self.dir, COMPACT_FILE))
             .map_err(|_| ErrorTypes::new(501, "Could not open the file".to_string()))
     }
 
@@ -348,7 +435,7 @@ impl MemTable {
 
         for line in reader.lines() {
             let line = line.map_err(|_| ErrorTypes::new(503, "Fallos".to_string()))?;
-            lines.push(line.split(",").map(|s| s.to_string()).collect());
+            lines.push(decode_line(&line));
         }
         Ok(lines)
     }
@@ -392,6 +479,9 @@ impl MemTable {
     }
 
     /// This function handles the select query. First, it checks if there is any row needed in the memtable, after that it does the same with te sstable.
+    ///
+    /// With a `limit` it stops once that many rows that are not tombstones are read; the tombstones before them are kept.
+    /// With `after`, the primary key of the last row of the previous page, only the rows after it are read.
     pub fn execute_select(
         &self,
         conditions: &Clause,
@@ -399,6 +489,7 @@ impl MemTable {
         order: &[String],
         need_ts: bool,
         include_tombstones: bool,
+        (limit, after): (Option<usize>, Option<&[String]>),
     ) -> Result<Vec<Vec<String>>, ErrorTypes> {
         let mut result = clean_rows_select(self.find_rows(conditions, true)?);
         let mut selected_columns = selected_columns.to_vec();
@@ -406,10 +497,10 @@ impl MemTable {
             selected_columns = self.columns.clone();
         }
         result.extend(clean_rows_select(
-            self.ss_tables
-                .execute_select(conditions, &selected_columns)?,
+            self.ss_tables.execute_select(conditions, &self.columns)?,
         ));
         let mut filtered_lines: Vec<Vec<String>> = self.get_newest(result);
+        self.retain_after(&mut filtered_lines, after);
         if !order.is_empty() {
             match sort_by_columns(order, filtered_lines, &self.columns) {
                 Ok(r) => filtered_lines = r,
@@ -436,6 +527,9 @@ impl MemTable {
             }
             filtered_lines = res;
         }
+        if let Some(limit) = limit {
+            filtered_lines = first_live_rows(filtered_lines, limit);
+        }
         if selected_columns.len() == 1 && selected_columns[0] == "*" {
             filtered_lines.insert(0, self.columns.clone());
             if need_ts {
@@ -449,21 +543,74 @@ impl MemTable {
         } else if selected_columns.len() == 1 {
             return Err(ErrorTypes::new(506, "Invalid fields".to_string()));
         }
-        let mut filtered: Vec<Vec<String>> = match field_filter(
+        let mut filtered: Vec<Vec<String>> = field_filter(
             filtered_lines,
             self.columns.clone(),
             selected_columns.to_vec(),
             need_ts,
-        ) {
-            Ok(f) => f,
-            Err(e) => return Err(e),
-        };
+        )?;
         filtered.insert(0, selected_columns.to_vec());
         Ok(filtered)
     }
 
-    /// This function handles the delete query.
-    pub fn execute_delete(&mut self, conditions: Clause) -> Result<(), ErrorTypes> {
+    /// This function returns the rows, with their timestamp and including the tombstones, whose token belongs to one of
+    /// the ranges and that meet the conditions, sorted by the primary key. The first row has the names of the columns.
+    /// With a limit, the rows after the `limit`-th live one are left out, and with `after` only the rows whose primary
+    /// key comes after it are read.
+    pub fn select_ranges(
+        &self,
+        conditions: &Clause,
+        ranges: &[(u128, u128)],
+        (limit, after): (Option<usize>, Option<&[String]>),
+    ) -> Result<Vec<Vec<String>>, ErrorTypes> {
+        let mut rows = self.find_rows(conditions, true)?;
+        rows.remove(0);
+        rows.extend(self.ss_tables.execute_select(conditions, &self.columns)?);
+        rows.retain(|(key, _)| ranges.iter().any(|range| in_range(*key, range)));
+        let primary_key = self
+            .get_primary_key()
+            .iter()
+            .map(|(_, index)| *index)
+            .collect::<Vec<usize>>();
+        let mut rows = newest_rows(clean_rows_select(rows), &primary_key);
+        self.retain_after(&mut rows, after);
+        let mut sort_order = self
+            .get_primary_key()
+            .into_iter()
+            .map(|(name, _)| name)
+            .collect::<Vec<String>>();
+        sort_order.push("asc".to_string());
+        let mut rows = sort_by_columns(&sort_order, rows, &self.columns)
+            .map_err(|_| ErrorTypes::new(504, "Invalid sorting".to_string()))?;
+        if let Some(limit) = limit {
+            rows = first_live_rows(rows, limit);
+        }
+        let mut header = self.columns.clone();
+        header.push("timestamp".to_string());
+        rows.insert(0, header);
+        Ok(rows)
+    }
+
+    /// This function keeps the rows whose primary key comes after `after`, comparing its columns in order, which is
+    /// where a page of a select sorted by the primary key continues.
+    fn retain_after(&self, rows: &mut Vec<Vec<String>>, after: Option<&[String]>) {
+        if let Some(after) = after {
+            let primary_key = self.get_primary_key();
+            rows.retain(|row| {
+                primary_key
+                    .iter()
+                    .map(|(_, index)| &row[*index])
+                    .gt(after.iter())
+            });
+        }
+    }
+
+    /// This function handles the delete query, writing the tombstones with the timestamp of the write.
+    pub fn execute_delete(
+        &mut self,
+        conditions: Clause,
+        timestamp: &str,
+    ) -> Result<(), ErrorTypes> {
         let mut rows_to_delete = self.find_rows(&conditions, false)?;
         rows_to_delete.remove(0);
         let mut rows: Vec<(u128, Vec<String>)> =
@@ -476,14 +623,14 @@ impl MemTable {
 
         for (key, row) in rows {
             let mut columns = Vec::new();
-            for (i, value) in row.iter().enumerate() {
+            for (i, value) in row.iter().take(self.columns.len()).enumerate() {
                 if primary.contains(&&i) || clustering.contains(&&i) {
                     columns.push(value.to_string());
                 } else {
                     columns.push("X".to_string());
                 }
             }
-            self.insert_row(key, columns, vec![], None, None)?;
+            self.insert_row(key, columns, self.columns.clone(), None, None, timestamp)?;
         }
         Ok(())
     }
@@ -543,7 +690,8 @@ impl MemTable {
         Ok(result)
     }
 
-    /// This function inserts a row in the MemTable
+    /// This function inserts a row in the MemTable, or updates the rows of the clause, with the timestamp of the write,
+    /// which the coordinator chose so every replica keeps the same one.
     pub fn insert_row(
         &mut self,
         key: u128,
@@ -551,17 +699,18 @@ impl MemTable {
         columns_inserted: Vec<String>,
         clause: Option<Clause>,
         columns_update: Option<HashMap<String, String>>,
+        timestamp: &str,
     ) -> Result<(), ErrorTypes> {
         if self.max_entries == 0 {
             self.flush()?;
         }
         self.data.entry(key).or_default();
         if let Some(clause) = clause {
-            self.update_memtable(clause, &columns_update.unwrap())?;
+            self.update_memtable(clause, &columns_update.unwrap(), timestamp)?;
             Ok(())
         } else {
             let mut row_time_id = self.check_line(columns, &columns_inserted);
-            row_time_id.push(chrono::Utc::now().to_rfc3339());
+            row_time_id.push(timestamp.to_string());
             row_time_id.insert(0, key.to_string());
             let vec = self.data.get_mut(&key).unwrap();
             vec.push(row_time_id);
@@ -571,11 +720,28 @@ impl MemTable {
         }
     }
 
+    /// This function inserts a row that was streamed from another node, keeping its timestamp.
+    pub fn insert_streamed_row(
+        &mut self,
+        key: u128,
+        mut row: Vec<String>,
+    ) -> Result<(), ErrorTypes> {
+        if self.max_entries == 0 {
+            self.flush()?;
+        }
+        row.insert(0, key.to_string());
+        self.data.entry(key).or_default().push(row);
+        self.order_data_vec(key);
+        self.max_entries -= 1;
+        Ok(())
+    }
+
     /// This function updates the MemTable
     fn update_memtable(
         &mut self,
         clause: Clause,
         columns_update: &HashMap<String, String>,
+        timestamp: &str,
     ) -> Result<(), ErrorTypes> {
         check_update_columns(&self.partition_key, columns_update)?;
         let mut rows_to_update = self.find_rows(&clause, false)?;
@@ -586,7 +752,7 @@ impl MemTable {
         if !rows_to_update.is_empty() {
             let rows_updated = self.update_rows(rows_to_update, columns_update)?;
             for (key, mut row) in rows_updated {
-                row.push(chrono::Utc::now().to_rfc3339());
+                row.push(timestamp.to_string());
                 row.insert(0, key.to_string());
                 let vec = self.data.entry(key).or_default();
                 vec.push(row);
@@ -594,7 +760,7 @@ impl MemTable {
             }
             Ok(())
         } else {
-            self.update_sstable_rows(clause, columns_update)?;
+            self.update_sstable_rows(clause, columns_update, timestamp)?;
             Ok(())
         }
     }
@@ -656,6 +822,7 @@ impl MemTable {
         &mut self,
         clause: Clause,
         columns_update: &HashMap<String, String>,
+        timestamp: &str,
     ) -> Result<(), ErrorTypes> {
         let updatable_sstables_rows = self.ss_tables.execute_select(&clause, &self.columns)?;
         let mut sstables_updated = Vec::new();
@@ -663,7 +830,7 @@ impl MemTable {
             sstables_updated.push(self.update_rows(vec![(key, row)], columns_update)?[0].clone());
         }
         for (key, row) in sstables_updated {
-            self.insert_row(key, row, self.columns.clone(), None, None)?;
+            self.insert_row(key, row, self.columns.clone(), None, None, timestamp)?;
         }
 
         Ok(())
@@ -684,72 +851,115 @@ impl MemTable {
 
         correct_line
     }
-    /// This function returns the rows that the actual node has to transfer to the new node.
-    pub fn get_rows(&self, partitions: &Vec<(u128, u128)>) -> Vec<(u128, Vec<String>)> {
-        if partitions.is_empty() {
-            return vec![];
-        }
-        let mut rows = self.find_rows(&Clause::Placeholder, true).unwrap();
-        rows.extend(
-            self.ss_tables
-                .execute_select(&Clause::Placeholder, &self.columns)
-                .unwrap(),
-        );
-        rows.remove(0);
-        let rows_grouped = self.group_by_primary_key(clean_rows_select(rows.to_vec()));
-        let mut res = Vec::new();
-        for rows_ in rows_grouped {
-            let rows_filtered = self.filter_lines(rows_);
-            for mut row in rows_filtered {
-                for (key, row_) in rows.iter() {
-                    if &row == row_ {
-                        row.pop();
-                        res.push((*key, row.clone()));
-                    }
+    /// This function returns a page of the rows, with their timestamp, that the actual node has to transfer to another node.
+    ///
+    /// The page has the rows of the keys in `partitions` that come after the key `after`. The SSTable is read line by line and
+    /// the reading stops once there are `limit` rows, so the whole ranges are never loaded. The rows of a key are never split
+    /// between pages, so the next page starts after the last key of this one.
+    pub fn get_rows_page(
+        &self,
+        partitions: &[(u128, u128)],
+        after: Option<u128>,
+        limit: usize,
+    ) -> Result<Vec<(u128, Vec<String>)>, ErrorTypes> {
+        let is_wanted = |key: u128| {
+            after.is_none_or(|after| key > after)
+                && partitions.iter().any(|range| in_range(key, range))
+        };
+        let mut page: BTreeMap<u128, Vec<Vec<String>>> = BTreeMap::new();
+        let mut amount = 0;
+        let mut last_key = None;
+        if let Ok(file) = File::open(self.ss_tables.get_route()) {
+            for line in BufReader::new(file).lines() {
+                let line = line
+                    .map_err(|_| ErrorTypes::new(574, "Error reading sstable file".to_string()))?;
+                let mut row = decode_line(&line);
+                let key = match row.first().and_then(|key| key.parse::<u128>().ok()) {
+                    Some(key) => key,
+                    None => continue,
+                };
+                if !is_wanted(key) {
+                    continue;
                 }
-            }
-        }
-        let mut vec = Vec::new();
-        for (start, end) in partitions {
-            for (key, row) in &res {
-                if key >= start && key <= end {
-                    vec.push((*key, row.clone()));
+                if amount >= limit && last_key != Some(key) {
+                    break;
                 }
+                row.remove(0);
+                page.entry(key).or_default().push(row);
+                amount += 1;
+                last_key = Some(key);
             }
         }
-        vec
-    }
-
-    fn group_by_primary_key(&self, rows: Vec<Vec<String>>) -> Vec<Vec<Vec<String>>> {
-        let pk = self.get_primary_key();
-        let mut hash: HashMap<Vec<String>, Vec<Vec<String>>> = HashMap::new();
-        for row in rows {
-            let mut key = Vec::new();
-            for (_, index) in &pk {
-                key.push(row[*index].clone());
+        let complete = amount < limit;
+        for (key, rows) in self.data.iter() {
+            if is_wanted(*key) && (complete || last_key.is_some_and(|last| *key <= last)) {
+                let rows = rows.iter().map(|row| row[1..].to_vec());
+                page.entry(*key).or_default().extend(rows);
             }
-            hash.entry(key).or_default().push(row);
         }
+        let primary_key = self
+            .get_primary_key()
+            .iter()
+            .map(|(_, index)| *index)
+            .collect::<Vec<usize>>();
         let mut res = Vec::new();
-        for (_k, value) in hash {
-            res.push(value);
+        for (key, rows) in page {
+            for row in newest_rows(rows, &primary_key) {
+                res.push((key, row));
+            }
         }
-        res
+        Ok(res)
     }
 
-    /// This function deletes the rows that have the partition key given
-    pub fn delete_rows(&mut self, partition_key: &u128) {
-        self.data.remove(partition_key);
-        let _ = self.delete_sstables_rows(partition_key);
+    /// This function deletes the rows that were streamed to another node.
+    ///
+    /// A stored row is only deleted if it has the primary key of a streamed row and it is not newer than it,
+    /// so the writes that arrived while the rows were being streamed are kept.
+    pub fn delete_streamed_rows(&mut self, rows: &[(u128, Vec<String>)]) {
+        let primary_key = self
+            .get_primary_key()
+            .iter()
+            .map(|(_, index)| *index)
+            .collect::<Vec<usize>>();
+        let mut streamed = HashMap::new();
+        for (key, row) in rows {
+            let values = primary_key
+                .iter()
+                .map(|index| row[*index].clone())
+                .collect::<Vec<String>>();
+            streamed.insert((*key, values), timestamp(row));
+        }
+        let is_streamed = |key: u128, row: &[String]| {
+            let values = primary_key
+                .iter()
+                .map(|index| row[*index].clone())
+                .collect::<Vec<String>>();
+            match streamed.get(&(key, values)) {
+                Some(streamed_at) => timestamp(row) <= *streamed_at,
+                None => false,
+            }
+        };
+        for key in rows.iter().map(|(key, _)| *key) {
+            if let Some(stored) = self.data.get_mut(&key) {
+                stored.retain(|row| !is_streamed(key, &row[1..]));
+                if stored.is_empty() {
+                    self.data.remove(&key);
+                }
+            }
+        }
+        let _ = self.delete_sstables_rows(is_streamed);
     }
 
-    fn delete_sstables_rows(&self, partition_key: &u128) -> Result<(), ErrorTypes> {
+    fn delete_sstables_rows<F: Fn(u128, &[String]) -> bool>(
+        &self,
+        is_deleted: F,
+    ) -> Result<(), ErrorTypes> {
         let file = File::open(self.ss_tables.get_route())
             .map_err(|_| ErrorTypes::new(000, "The file could not be open".to_string()))?;
         let mut reader = BufReader::new(file);
 
-        let temp_file = "temp_sstable.txt".to_string();
-        filter_file_by_pk(&mut reader, &temp_file, partition_key)?;
+        let temp_file = join(&self.dir, TEMP_FILE);
+        filter_file(&mut reader, &temp_file, is_deleted)?;
 
         fs::remove_file(self.ss_tables.get_route())
             .map_err(|_| ErrorTypes::new(000, "The file could not be removed".to_string()))?;
@@ -758,11 +968,11 @@ impl MemTable {
     }
 }
 
-/// This function the sstable deleting the rows that have the partition key given
-fn filter_file_by_pk(
+/// This function rewrites the sstable without the lines that `is_deleted` matches
+fn filter_file<F: Fn(u128, &[String]) -> bool>(
     reader: &mut BufReader<File>,
     temp_file: &String,
-    partition_key: &u128,
+    is_deleted: F,
 ) -> Result<(), ErrorTypes> {
     let mut archivo_filtrado = File::create(temp_file)
         .map_err(|_| ErrorTypes::new(000, "The file could not be open".to_string()))?;
@@ -773,8 +983,9 @@ fn filter_file_by_pk(
         > 0
     {
         let linea_ = line.trim_end();
-        let arr_linea: Vec<&str> = linea_.split(',').collect();
-        if arr_linea[0].parse::<u128>().unwrap() != *partition_key {
+        let arr_linea: Vec<String> = decode_line(linea_);
+        let key = arr_linea[0].parse::<u128>().unwrap();
+        if !is_deleted(key, &arr_linea[1..]) {
             writeln!(archivo_filtrado, "{}", linea_)
                 .map_err(|_| ErrorTypes::new(000, "The file could not be written".to_string()))?;
         }
@@ -783,6 +994,15 @@ fn filter_file_by_pk(
     Ok(())
 }
 
+/// This function checks if a key belongs to a token range. A range whose start is greater than its end wraps around the ring.
+fn in_range(key: u128, (start, end): &(u128, u128)) -> bool {
+    if start <= end {
+        key >= *start && key <= *end
+    } else {
+        key >= *start || key <= *end
+    }
+}
+
 /// This function renames a file.
 fn rename_file(new_name: String, old_name: String) {
     std::fs::rename(old_name, new_name).unwrap();
@@ -807,7 +1027,7 @@ fn filter_lines_timestamp(all_lines: &[Vec<String>]) -> Vec<String> {
                 actual_key = Some(line_key);
             }
         }
-        res_lines.push(line.join(","));
+        res_lines.push(encode_line(line));
     }
     res_lines
 }
@@ -826,6 +1046,45 @@ fn order_hash(data: &HashMap<u128, Vec<Vec<String>>>) -> Vec<(u128, Vec<Vec<Stri
     ordered_elements
 }
 
+/// This function keeps the first rows until `limit` of them are not tombstones.
+fn first_live_rows(rows: Vec<Vec<String>>, limit: usize) -> Vec<Vec<String>> {
+    let mut live = 0;
+    rows.into_iter()
+        .take_while(|row| {
+            let keep = live < limit;
+            if !is_tombstone(row) {
+                live += 1;
+            }
+            keep
+        })
+        .collect()
+}
+
+/// This function keeps the newest version, by its timestamp, of every row with the same values in the columns of `primary_key`.
+pub fn newest_rows(rows: Vec<Vec<String>>, primary_key: &[usize]) -> Vec<Vec<String>> {
+    let mut newest: HashMap<Vec<String>, Vec<String>> = HashMap::new();
+    for row in rows {
+        let key = primary_key
+            .iter()
+            .map(|index| row[*index].clone())
+            .collect::<Vec<String>>();
+        let is_newer = match newest.get(&key) {
+            Some(current) => timestamp(&row) > timestamp(current),
+            None => true,
+        };
+        if is_newer {
+            newest.insert(key, row);
+        }
+    }
+    newest.into_values().collect()
+}
+
+/// This function returns the timestamp of a row, which is its last column.
+fn timestamp(row: &[String]) -> Option<chrono::DateTime<chrono::Utc>> {
+    row.last()
+        .and_then(|ts| chrono::DateTime::<chrono::Utc>::from_str(ts).ok())
+}
+
 /// This function cleans the rows of the select query
 pub fn clean_rows_select(rows: Vec<(u128, Vec<String>)>) -> Vec<Vec<String>> {
     let mut res_rows = Vec::new();
@@ -928,6 +1187,7 @@ pub mod test {
             ],
             None,
             None,
+            &chrono::Utc::now().to_rfc3339(),
         );
         let _ = memtable.insert_row(
             2,
@@ -945,6 +1205,7 @@ pub mod test {
             ],
             None,
             None,
+            &chrono::Utc::now().to_rfc3339(),
         );
         let _ = memtable.insert_row(
             3,
@@ -962,6 +1223,7 @@ pub mod test {
             ],
             None,
             None,
+            &chrono::Utc::now().to_rfc3339(),
         );
         let _ = memtable.insert_row(
             4,
@@ -979,6 +1241,7 @@ pub mod test {
             ],
             None,
             None,
+            &chrono::Utc::now().to_rfc3339(),
         );
         let _ = memtable.insert_row(
             5,
@@ -996,6 +1259,7 @@ pub mod test {
             ],
             None,
             None,
+            &chrono::Utc::now().to_rfc3339(),
         );
         let _ = memtable.insert_row(
             6,
@@ -1013,6 +1277,7 @@ pub mod test {
             ],
             None,
             None,
+            &chrono::Utc::now().to_rfc3339(),
         );
         let clause = Clause::Term {
             relation: Relation::Equal {
@@ -1027,15 +1292,134 @@ pub mod test {
                 &[],
                 false,
                 false,
+                (None, None),
             )
             .unwrap();
 
         assert_eq!(4, selected_rows.len());
         assert!(selected_rows.contains(&vec!["EZE".to_string(), "MZA".to_string()]));
+
+        let limited_rows = memtable
+            .execute_select(
+                &clause,
+                &["origin".to_string(), "destination".to_string()],
+                &[],
+                false,
+                false,
+                (Some(2), None),
+            )
+            .unwrap();
+        assert_eq!(3, limited_rows.len());
         assert!(selected_rows.contains(&vec!["EZE".to_string(), "AEP".to_string()]));
         assert!(selected_rows.contains(&vec!["EZE".to_string(), "MIA".to_string()]));
     }
 
+    #[test]
+    fn test_select_ranges_limit() {
+        let dir = std::env::temp_dir().join("select_ranges_test");
+        let mut memtable = MemTable::new(
+            vec![
+                ("id".to_string(), "int".to_string()),
+                ("name".to_string(), "text".to_string()),
+                ("city".to_string(), "text".to_string()),
+            ],
+            vec!["name".to_string()],
+            "t".to_string(),
+            vec!["id".to_string()],
+            dir.to_string_lossy().to_string(),
+        );
+        let ts = "2024-11-02T10:00:00+00:00".to_string();
+        for (key, row) in [
+            (3, vec!["3", "c", "x"]),
+            (1, vec!["1", "a", "x"]),
+            (2, vec!["2", "b", "X"]),
+        ] {
+            let mut row = row.iter().map(|x| x.to_string()).collect::<Vec<String>>();
+            row.push(ts.clone());
+            memtable.insert_streamed_row(key, row).unwrap();
+        }
+        let names = |limit, after: Option<&[String]>| {
+            memtable
+                .select_ranges(&Clause::Placeholder, &[(0, u128::MAX)], (limit, after))
+                .unwrap()
+                .iter()
+                .skip(1)
+                .map(|row| row[1].clone())
+                .collect::<Vec<String>>()
+        };
+        assert_eq!(names(None, None), vec!["a", "b", "c"]);
+        assert_eq!(names(Some(2), None), vec!["a", "b", "c"]);
+        assert_eq!(names(Some(1), None), vec!["a"]);
+        let after = ["a".to_string(), "1".to_string()];
+        assert_eq!(names(None, Some(&after)), vec!["b", "c"]);
+        assert_eq!(names(Some(1), Some(&after)), vec!["b", "c"]);
+        let _ = fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn test_get_rows_page() {
+        let dir = std::env::temp_dir().join("rows_page_test");
+        let mut memtable = MemTable::new(
+            vec![
+                ("id".to_string(), "int".to_string()),
+                ("name".to_string(), "text".to_string()),
+            ],
+            vec!["name".to_string()],
+            "t".to_string(),
+            vec!["id".to_string()],
+            dir.to_string_lossy().to_string(),
+        );
+        let ts = "2024-11-02T10:00:00+00:00".to_string();
+        for key in [5, 1, 4, 2, 3, 2] {
+            let row = vec![key.to_string(), format!("n{}", key), ts.clone()];
+            memtable.insert_streamed_row(key, row).unwrap();
+        }
+        let keys = |after, limit| {
+            memtable
+                .get_rows_page(&[(2, 4), (5, 5)], after, limit)
+                .unwrap()
+                .iter()
+                .map(|(key, _)| *key)
+                .collect::<Vec<u128>>()
+        };
+        assert_eq!(keys(None, 10), vec![2, 3, 4, 5]);
+        assert_eq!(keys(None, 2), vec![2, 3]);
+        assert_eq!(keys(Some(3), 2), vec![4, 5]);
+        assert_eq!(keys(Some(5), 2), Vec::<u128>::new());
+        let _ = fs::remove_dir_all(dir);
+    }
+
+    /// This test checks that a write applied again with its older timestamp, like a replayed batch, does not overwrite a newer write.
+    #[test]
+    fn test_replayed_write() {
+        let dir = std::env::temp_dir().join("replayed_write_test");
+        let mut memtable = MemTable::new(
+            vec![
+                ("id".to_string(), "int".to_string()),
+                ("seat".to_string(), "int".to_string()),
+                ("name".to_string(), "text".to_string()),
+            ],
+            vec!["id".to_string()],
+            "t".to_string(),
+            vec!["seat".to_string()],
+            dir.to_string_lossy().to_string(),
+        );
+        let columns = vec!["id".to_string(), "seat".to_string(), "name".to_string()];
+        for (name, ts) in [
+            ("new", "2024-11-02T10:00:05+00:00"),
+            ("old", "2024-11-02T10:00:00+00:00"),
+        ] {
+            let row = vec!["1".to_string(), "1".to_string(), name.to_string()];
+            memtable
+                .insert_row(1, row, columns.clone(), None, None, ts)
+                .unwrap();
+        }
+        let rows = memtable.get_rows_page(&[(0, 2)], None, 10).unwrap();
+        assert_eq!(rows.len(), 1);
+        assert_eq!(rows[0].1[2], "new");
+        let _ = fs::remove_dir_all(dir);
+    }
+
     /// This test checks if the delete is done correctly with multiple rows. You must set the MAX_ENTRIES to 2
     #[test]
     #[ignore]
@@ -1071,6 +1455,7 @@ pub mod test {
             ],
             None,
             None,
+            &chrono::Utc::now().to_rfc3339(),
         );
 
         let _ = memtable.insert_row(
@@ -1089,6 +1474,7 @@ pub mod test {
             ],
             None,
             None,
+            &chrono::Utc::now().to_rfc3339(),
         );
 
         let _ = memtable.insert_row(
@@ -1107,6 +1493,7 @@ pub mod test {
             ],
             None,
             None,
+            &chrono::Utc::now().to_rfc3339(),
         );
 
         let _ = memtable.insert_row(
@@ -1125,6 +1512,7 @@ pub mod test {
             ],
             None,
             None,
+            &chrono::Utc::now().to_rfc3339(),
         );
 
         let _ = memtable.insert_row(
@@ -1143,6 +1531,7 @@ pub mod test {
             ],
             None,
             None,
+            &chrono::Utc::now().to_rfc3339(),
         );
 
         let clause = Clause::Term {
@@ -1152,7 +1541,7 @@ pub mod test {
             },
         };
 
-        let _res_delete = memtable.execute_delete(clause.clone());
+        let _res_delete = memtable.execute_delete(clause.clone(), &chrono::Utc::now().to_rfc3339());
 
         let selected_rows = memtable
             .execute_select(
@@ -1166,6 +1555,7 @@ pub mod test {
                 &[],
                 false,
                 false,
+                (None, None),
             )
             .unwrap();
         assert_eq!(1, selected_rows.len()); // Only the line of the columns
@@ -1,10 +1,15 @@
 pub mod address;
+pub mod admin;
 pub mod application_state;
+pub mod batchlog;
+pub mod client_connection;
 pub mod clusteringkey;
 pub mod columntypes;
 mod connection;
 pub mod data;
+pub mod data_dir;
 pub mod endpoint_state;
+pub mod events;
 pub mod gossip_digest;
 pub mod gossip_message;
 pub mod gossiper;
@@ -13,14 +18,23 @@ pub mod heartbeat_state;
 pub mod keyspace;
 pub mod log_type;
 pub mod mem_table;
+pub mod node_config;
 pub mod node_message;
+pub mod node_state;
 pub mod nodes;
+pub mod paging;
 pub mod partitionkey;
+pub mod prepared_statements;
 pub mod query_execute;
 pub mod query_simulator;
+pub mod replication_strategy;
 pub mod schema;
+pub mod schema_definition;
 pub mod selectquery;
+pub mod snitch;
 pub mod sstable;
 pub mod status;
+pub mod streaming;
+pub mod system_tables;
 pub mod tabledefinition;
 pub mod users;
@@ -0,0 +1,231 @@
+use super::{
+    data_dir::default_data_dir,
+    hashring::DEFAULT_NUM_TOKENS,
+    snitch::{Location, DEFAULT_DATACENTER, DEFAULT_RACK},
+};
+use crate::errors::error_types::ErrorTypes;
+
+const ADMIN_PORT_OFFSET: u16 = 1000;
+const ADMIN_IP_ADDRESS: &str = "127.0.0.1";
+
+/// Struct representing the configuration a node is started with.
+///
+/// It is built from the command line arguments of the `node` binary. The two addresses are
+/// positional and every other setting is an optional `--flag value` pair.
+///
+/// ### Fields:
+/// - `internal_address` (`String`): The address used to talk with the other nodes.
+/// - `client_address` (`String`): The address used to talk with the clients.
+/// - `admin_address` (`String`): The address used to receive the administration commands, like `decommission`.
+/// - `data_dir` (`String`): The directory where the node keeps its schema and its tables.
+/// - `num_tokens` (`usize`): The amount of tokens the node owns in the ring.
+/// - `allocate_tokens_for_rf` (`Option<usize>`): If present, the tokens are chosen to balance the ownership for this replication factor instead of randomly.
+/// - `replace_address` (`Option<String>`): If present, the node takes the tokens and the data of this dead node the first time it joins the ring.
+/// - `datacenter` (`String`): The datacenter the node belongs to.
+/// - `rack` (`String`): The rack of the datacenter the node belongs to.
+#[derive(Debug, Clone)]
+pub struct NodeConfig {
+    pub internal_address: String,
+    pub client_address: String,
+    pub admin_address: String,
+    pub data_dir: String,
+    pub num_tokens: usize,
+    pub allocate_tokens_for_rf: Option<usize>,
+    pub replace_address: Option<String>,
+    pub datacenter: String,
+    pub rack: String,
+}
+
+impl NodeConfig {
+    /// This function creates a configuration with the default values for the optional settings.
+    pub fn new(internal_address: &str, client_address: &str) -> Result<NodeConfig, ErrorTypes> {
+        let port = get_port(internal_address)?;
+        Ok(NodeConfig {
+            internal_address: internal_address.to_string(),
+            client_address: client_address.to_string(),
+            admin_address: default_admin_address(internal_address)?,
+            data_dir: default_data_dir(&port),
+            num_tokens: DEFAULT_NUM_TOKENS,
+            allocate_tokens_for_rf: None,
+            replace_address: None,
+            datacenter: DEFAULT_DATACENTER.to_string(),
+            rack: DEFAULT_RACK.to_string(),
+        })
+    }
+
+    /// This function builds the configuration from the command line arguments, without the binary name.
+    pub fn from_args(args: &[String]) -> Result<NodeConfig, ErrorTypes> {
+        if args.len() < 2 {
+            return Err(ErrorTypes::new(589, "Missing node addresses".to_string()));
+        }
+        let mut config = NodeConfig::new(&args[0], &args[1])?;
+        let mut options = args[2..].iter();
+        while let Some(option) = options.next() {
+            let value = match options.next() {
+                Some(value) => value,
+                None => {
+                    return Err(ErrorTypes::new(
+                        590,
+                        format!("Missing value for {}", option),
+                    ))
+                }
+            };
+            match option.as_str() {
+                "--admin-address" => config.admin_address = value.to_string(),
+                "--data-dir" => config.data_dir = value.to_string(),
+                "--num-tokens" => config.num_tokens = parse_positive(option, value)?,
+                "--replace-address" => config.replace_address = Some(value.to_string()),
+                "--dc" => config.datacenter = value.to_string(),
+                "--rack" => config.rack = value.to_string(),
+                "--allocate-tokens-for-rf" => {
+                    config.allocate_tokens_for_rf = Some(parse_positive(option, value)?)
+                }
+                _ => return Err(ErrorTypes::new(591, format!("Unknown option {}", option))),
+            }
+        }
+        if config.replace_address.as_ref() == Some(&config.internal_address) {
+            return Err(ErrorTypes::new(
+                591,
+                "A node can not replace its own address".to_string(),
+            ));
+        }
+        Ok(config)
+    }
+
+    /// This function returns the port of the internal address.
+    pub fn get_port(&self) -> String {
+        get_port(&self.internal_address).unwrap_or_default()
+    }
+
+    /// This function returns the datacenter and the rack of the node.
+    pub fn get_location(&self) -> Location {
+        Location::new(&self.datacenter, &self.rack)
+    }
+}
+
+/// This function returns the port of an address with the format `ip:port`.
+fn get_port(address: &str) -> Result<String, ErrorTypes> {
+    match address.split(":").collect::<Vec<&str>>().get(1) {
+        Some(port) => Ok(port.to_string()),
+        None => Err(ErrorTypes::new(592, format!("Invalid address {}", address))),
+    }
+}
+
+/// This function returns the address used for the administration commands when none is configured,
+/// which is the port of the internal address moved `ADMIN_PORT_OFFSET` positions. The commands are not authenticated,
+/// so by default they are only accepted from the local machine.
+fn default_admin_address(internal_address: &str) -> Result<String, ErrorTypes> {
+    let port = get_port(internal_address)?;
+    match port
+        .parse::<u16>()
+        .ok()
+        .and_then(|p| p.checked_add(ADMIN_PORT_OFFSET))
+    {
+        Some(admin_port) => Ok(format!("{}:{}", ADMIN_IP_ADDRESS, admin_port)),
+        None => Err(ErrorTypes::new(
+            592,
+            format!("Invalid address {}", internal_address),
+        )),
+    }
+}
+
+/// This function parses the value of an option that must be a positive number.
+fn parse_positive(option: &str, value: &str) -> Result<usize, ErrorTypes> {
+    match value.parse::<usize>() {
+        Ok(number) if number > 0 => Ok(number),
+        _ => Err(ErrorTypes::new(
+            595,
+            format!("Invalid value {} for {}", value, option),
+        )),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_default_data_dir() {
+        let config = NodeConfig::new("127.0.0.1:8080", "127.0.0.1:8090").unwrap();
+        assert_eq!(config.data_dir, "data/8080");
+        assert_eq!(config.get_port(), "8080");
+        assert_eq!(config.admin_address, "127.0.0.1:9080");
+        let config = NodeConfig::new("10.0.0.2:8080", "10.0.0.2:8090").unwrap();
+        assert_eq!(config.admin_address, "127.0.0.1:9080");
+        assert_eq!(config.get_location(), Location::default());
+    }
+
+    #[test]
+    fn test_data_dir_option() {
+        let args = vec![
+            "127.0.0.1:8081".to_string(),
+            "127.0.0.1:8091".to_string(),
+            "--data-dir".to_string(),
+            "/var/lib/node1".to_string(),
+            "--admin-address".to_string(),
+            "127.0.0.1:7199".to_string(),
+            "--dc".to_string(),
+            "dc2".to_string(),
+            "--rack".to_string(),
+            "rack3".to_string(),
+        ];
+        let config = NodeConfig::from_args(&args).unwrap();
+        assert_eq!(config.data_dir, "/var/lib/node1");
+        assert_eq!(config.admin_address, "127.0.0.1:7199");
+        assert_eq!(config.get_location(), Location::new("dc2", "rack3"));
+    }
+
+    #[test]
+    fn test_token_options() {
+        let args = vec![
+            "127.0.0.1:8081".to_string(),
+            "127.0.0.1:8091".to_string(),
+            "--num-tokens".to_string(),
+            "64".to_string(),
+            "--allocate-tokens-for-rf".to_string(),
+            "3".to_string(),
+        ];
+        let config = NodeConfig::from_args(&args).unwrap();
+        assert_eq!(config.num_tokens, 64);
+        assert_eq!(config.allocate_tokens_for_rf, Some(3));
+
+        let args = vec![
+            "127.0.0.1:8081".to_string(),
+            "127.0.0.1:8091".to_string(),
+            "--num-tokens".to_string(),
+            "0".to_string(),
+        ];
+        assert!(NodeConfig::from_args(&args).is_err());
+    }
+
+    #[test]
+    fn test_unknown_option() {
+        let args = vec![
+            "127.0.0.1:8081".to_string(),
+            "127.0.0.1:8091".to_string(),
+            "--foo".to_string(),
+            "bar".to_string(),
+        ];
+        assert!(NodeConfig::from_args(&args).is_err());
+    }
+
+    #[test]
+    fn test_replace_address_option() {
+        let args = vec![
+            "127.0.0.1:8083".to_string(),
+            "127.0.0.1:8093".to_string(),
+            "--replace-address".to_string(),
+            "127.0.0.1:8081".to_string(),
+        ];
+        let config = NodeConfig::from_args(&args).unwrap();
+        assert_eq!(config.replace_address, Some("127.0.0.1:8081".to_string()));
+
+        let args = vec![
+            "127.0.0.1:8083".to_string(),
+            "127.0.0.1:8093".to_string(),
+            "--replace-address".to_string(),
+            "127.0.0.1:8083".to_string(),
+        ];
+        assert!(NodeConfig::from_args(&args).is_err());
+    }
+}
@@ -1,22 +1,55 @@
-use super::{keyspace::Keyspace, mem_table::MemTable};
+use super::{batchlog::Batch, schema_definition::SchemaDefinition, streaming::StreamTask};
 use crate::protocol::query_parser::clause::Clause;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
-/// This enum represents the internal messages that are sent between nodes
+/// This enum represents the internal messages that are sent between nodes. The writes carry the timestamp the
+/// coordinator chose for them, so every replica keeps the same one, and the selects the primary key of the last row of
+/// the previous page, so the replicas only read the rows after it.
 pub enum NodeMessage {
-    SchemaChange(SchemaChange),
-    Insert(Vec<String>, Vec<String>, String, u128),
-    SelectRequest(Clause, Vec<String>, Vec<String>, String, bool),
+    Insert(Vec<String>, Vec<String>, String, u128, String),
+    SelectRequest(
+        Clause,
+        Vec<String>,
+        Vec<String>,
+        String,
+        bool,
+        Option<usize>,
+        Option<Vec<String>>,
+    ),
+    RangeSelectRequest(
+        Clause,
+        String,
+        Vec<(u128, u128)>,
+        Option<usize>,
+        Option<Vec<String>>,
+    ),
     SelectResponse(Vec<Vec<String>>),
-    ChecksumRequest(Clause, Vec<String>, Vec<String>, String),
+    ChecksumRequest(
+        Clause,
+        Vec<String>,
+        Vec<String>,
+        String,
+        Option<usize>,
+        Option<Vec<String>>,
+    ),
     ChecksumResponse(String),
-    Update(u128, String, HashMap<String, String>, Clause),
-    Delete(String, Clause),
+    Update(u128, String, HashMap<String, String>, Clause, String),
+    Delete(String, Clause, String),
     Confirmation(),
-    TransferFromNode(String),
+    Failure(String),
     RemoveNode(String),
+    StreamCompleted(String),
+    StreamChunk(String, String, String, usize, Vec<(u128, Vec<String>)>),
+    StreamAck(String, usize, bool),
+    StreamRequest(String, Vec<StreamTask>),
+    StreamResponse(bool),
+    SchemaPull(String),
+    SchemaPush(SchemaDefinition),
+    SchemaChange(SchemaChange),
+    BatchStore(Batch),
+    BatchRemove(String),
 }
 
 impl NodeMessage {
@@ -29,6 +62,22 @@ impl NodeMessage {
         send_message
     }
 
+    /// This function returns `true` if the message is a request the other node waits an answer for, so it has to be
+    /// answered even if it could not be executed.
+    pub fn is_request(&self) -> bool {
+        matches!(
+            self,
+            NodeMessage::Insert(..)
+                | NodeMessage::SelectRequest(..)
+                | NodeMessage::RangeSelectRequest(..)
+                | NodeMessage::ChecksumRequest(..)
+                | NodeMessage::Update(..)
+                | NodeMessage::Delete(..)
+                | NodeMessage::SchemaChange(..)
+                | NodeMessage::BatchStore(..)
+        )
+    }
+
     pub fn from_bytes(bytes: Vec<u8>) -> NodeMessage {
         let mut len = bytes;
         let bytes = len.split_off(8);
@@ -42,9 +91,10 @@ impl NodeMessage {
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
-/// This enum represents the schema changes that can be made to the database
+/// This enum represents the changes of the schema that every node has to apply: dropping a keyspace or a table
+/// (named `keyspace.table`) at a moment in milliseconds, or deleting every row of a table.
 pub enum SchemaChange {
-    CreateKeyspace(Keyspace),
-    CreateTable(Box<MemTable>),
-    UseKeyspace(Keyspace),
+    DropKeyspace(String, i64),
+    DropTable(String, i64),
+    Truncate(String),
 }
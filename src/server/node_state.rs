@@ -0,0 +1,13 @@
+use serde::{Deserialize, Serialize};
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+
+/// This enum represents the state of the node in the ring.
+///
+/// A new node is `Joining` while the owners of its ranges stream their data to it, and becomes `Normal` once every owner has finished.
+/// A node that leaves the ring is `Leaving` while it streams its data to the new owners, and `Left` once it is done.
+pub enum NodeState {
+    Joining,
+    Normal,
+    Leaving,
+    Left,
+}
@@ -1,25 +1,43 @@
 use super::address::Address;
+use super::admin::receive_admin_message;
+use super::batchlog::{apply_batch, remove_from_batchlog, replay_batchlog, write_batchlog, Batch};
+use super::client_connection::ClientConnection;
 use super::connection::Connection;
+use super::data_dir::tokens_path;
+use super::events::{get_events, schema_changes};
 use super::gossiper::Gossiper;
 use super::log_type::LogType;
+use super::node_config::NodeConfig;
 use super::node_message::{NodeMessage, SchemaChange};
-use super::schema::Schema;
+use super::node_state::NodeState;
+use super::paging::select_page;
+use super::prepared_statements::{
+    evict_prepared_statements, get_prepared_statement, save_prepared_statement, PreparedStatement,
+};
+use super::replication_strategy::ReplicationStrategy;
+use super::schema::{select_rows, Schema};
+use super::streaming::{
+    restore_replicas, resume_sessions, stream_from_leaving_node, stream_new_replicas, StreamSession,
+};
+use super::system_tables::{select_system_table, SYSTEM_KEYSPACES};
 use crate::errors::error_types::ErrorTypes;
-use crate::protocol::protocol_notations::consistency::Consistency;
+use crate::protocol::protocol_notations::{consistency::Consistency, paging_state::PagingState};
 use crate::protocol::query_parser::clause::Clause;
 use crate::protocol::{
+    frames_headers::version::Version,
     protocol_body::{
-        compression::Compression, result_kind::ResultKind, schema_change::SchemaChangeType,
+        compression::Compression, error_code::ErrorCode, event_kind::EventKindChange,
+        result_kind::ResultKind, schema_change::SchemaChangeType,
     },
     protocol_writer::Protocol,
-    query_parser::query::Query,
+    query_parser::query::{AlterTableOperation, Query},
 };
 use crate::receiver::{
-    message::Message::SolicitationMessage, receiver_impl::receive_message,
+    batch_statement::BatchStatement, message::Message::SolicitationMessage,
+    query_parameters::QueryParameters, receiver_impl::receive_message,
     request_message::RequestMessage,
 };
 use std::collections::HashMap;
-use std::io;
 use std::sync::mpsc::{self, channel, Sender};
 
 use crate::server::{
@@ -27,10 +45,10 @@ use crate::server::{
     gossip_message::GossipMessage, gossiper::get_gossiper, heartbeat_state::HeartbeatState,
     status::Status, users::User,
 };
-use native_tls::{Identity, TlsAcceptor, TlsStream};
+use native_tls::{Identity, TlsAcceptor};
 use std::{
-    fs::File,
     fs::OpenOptions,
+    fs::{self, File},
     io::{BufReader, Read, Write},
     net::{TcpListener, TcpStream},
     sync::{Arc, Mutex},
@@ -39,6 +57,12 @@ use std::{
 
 const SEED_IP_ADDRESS: &str = "127.0.0.1:8080";
 const NODE_MESSAGE: u8 = 1;
+const RING_DELAY: u64 = 5;
+const BOOTSTRAP_TIMEOUT: u64 = 60;
+const SCHEMA_AGREEMENT_TIMEOUT: u64 = 10;
+const AUTHENTICATOR: &str = "org.apache.cassandra.auth.PasswordAuthenticator";
+const UNAUTHENTICATED_MESSAGE: &str = "You have not logged in";
+const FILTERING_MESSAGE: &str = "Cannot execute this query as it might involve data filtering and thus may have unpredictable performance. If you want to execute this query despite the performance unpredictability, use ALLOW FILTERING";
 
 /// ep struct represents the node. It contains the address, the mem tables and the commit log.
 #[derive(Debug)]
@@ -46,22 +70,31 @@ pub struct Node {
     address: Address,
     endpoint_state: EndpointState,
     schema: Arc<Mutex<Schema>>,
+    config: NodeConfig,
 }
 
 impl Node {
-    pub fn new(internal_address: &str, client_address: &str) -> Result<Node, ErrorTypes> {
+    pub fn new(config: &NodeConfig) -> Result<Node, ErrorTypes> {
         let heartbeat_state = HeartbeatState::new();
-        let port = internal_address.split(":").collect::<Vec<&str>>()[1].to_string();
-        let application_state = ApplicationState::new(Status::Up, internal_address.to_string());
+        let port = config.get_port();
+        let schema = Schema::new(&port, &config.data_dir)?;
+        let mut application_state = ApplicationState::new(
+            Status::Up,
+            config.internal_address.to_string(),
+            config.get_location(),
+        );
+        application_state.set_schema_version(schema.get_version());
+        application_state.set_native_address(config.client_address.to_string());
         let endpoint_state = EndpointState::new(heartbeat_state, application_state);
         let node = Node {
-            schema: Arc::new(Mutex::new(Schema::new(&port)?)),
+            schema: Arc::new(Mutex::new(schema)),
             address: Address {
-                i_address: internal_address.to_string(),
-                c_address: client_address.to_string(),
-                i_port: internal_address.split(":").collect::<Vec<&str>>()[1].to_string(),
+                i_address: config.internal_address.to_string(),
+                c_address: config.client_address.to_string(),
+                i_port: port.clone(),
             },
             endpoint_state,
+            config: config.clone(),
         };
         OpenOptions::new()
             .write(true)
@@ -87,7 +120,10 @@ impl Node {
         let gossiper = get_gossiper();
 
         let listener_node: TcpListener = TcpListener::bind(&self.address.i_address).unwrap();
-        gossiper.add_node(self.address.i_address.clone());
+        let saved_tokens = self.read_tokens();
+        if let Some(tokens) = &saved_tokens {
+            self.endpoint_state.set_tokens(tokens.clone());
+        }
         gossiper.put_endpoint_state(
             listener_node.local_addr().unwrap().to_string(),
             self.endpoint_state.clone(),
@@ -123,6 +159,27 @@ impl Node {
 
             thread::sleep(std::time::Duration::from_secs(1));
         });
+        let address = self.address.clone();
+        let schema = Arc::clone(&self.schema);
+        thread::spawn(move || {
+            thread::sleep(std::time::Duration::from_secs(RING_DELAY));
+            resume_sessions(&schema, &address);
+        });
+        let address = self.address.clone();
+        let schema = Arc::clone(&self.schema);
+        thread::spawn(move || replay_batchlog(&schema, &address));
+        let address = self.address.clone();
+        let config = self.config.clone();
+        let schema = Arc::clone(&self.schema);
+        thread::spawn(move || {
+            if let Err(e) = join_ring(&schema, &address, &config, saved_tokens) {
+                write_log_message(
+                    &address.i_port,
+                    LogType::Error,
+                    format!("{} {}", e.get().0, e.get().1),
+                );
+            }
+        });
 
         let schema = Arc::clone(&self.schema);
         let address = self.address.clone();
@@ -130,27 +187,36 @@ impl Node {
         thread::spawn(move || {
             receive_client_message(listener_client, Arc::clone(&schema), &address)
         });
-        let local_address_clone = self.address.i_address.clone();
-        thread::spawn(move || {
-            let address = local_address_clone.clone();
-            let mut input = String::new();
-            io::stdin()
-                .read_line(&mut input)
-                .expect("Error al leer la entrada");
-            if input.to_lowercase().trim() == "exit" {
-                let gossiper = get_gossiper();
-
-                let msg = NodeMessage::TransferFromNode(address.clone());
-                let bytes = msg.to_bytes();
-                gossiper.get_sender(&address).unwrap().send(bytes).unwrap();
+        let schema = Arc::clone(&self.schema);
+        let address = self.address.clone();
+        match TcpListener::bind(&self.config.admin_address) {
+            Ok(listener_admin) => {
+                write_log_message(
+                    &self.address.i_port,
+                    LogType::Info,
+                    format!(
+                        "Listening on {} for admin commands",
+                        self.config.admin_address
+                    ),
+                );
+                thread::spawn(move || receive_admin_message(listener_admin, schema, &address));
             }
-        });
+            Err(e) => write_log_message(
+                &self.address.i_port,
+                LogType::Error,
+                format!(
+                    "Could not listen on {} for admin commands: {}",
+                    self.config.admin_address, e
+                ),
+            ),
+        }
         for stream in listener_node.incoming() {
             match stream {
                 Ok(socket) => {
                     let address_clone = self.address.clone();
                     let (tx_to_thread, rx_from_main) = channel();
                     let (tx_to_main, rx_from_thread) = channel();
+                    let writer = tx_to_thread.clone();
                     let connection = Connection::new(tx_to_thread, rx_from_thread);
                     let schema = Arc::clone(&self.schema);
                     thread::spawn(move || {
@@ -159,7 +225,7 @@ impl Node {
                             Arc::clone(&schema),
                             Some(connection),
                             address_clone,
-                            rx_from_main,
+                            (writer, rx_from_main),
                             tx_to_main,
                         )
                     });
@@ -175,6 +241,156 @@ impl Node {
 
         Ok(())
     }
+
+    /// This function reads the tokens the node saved in its data directory the first time it joined the ring.
+    fn read_tokens(&self) -> Option<Vec<u128>> {
+        let file = File::open(tokens_path(&self.config.data_dir)).ok()?;
+        let tokens = serde_json::from_reader::<File, Vec<u128>>(file).ok()?;
+        if tokens.len() != self.config.num_tokens {
+            write_log_message(
+                &self.address.i_port,
+                LogType::Info,
+                format!(
+                    "Using the {} saved tokens instead of {}",
+                    tokens.len(),
+                    self.config.num_tokens
+                ),
+            );
+        }
+        Some(tokens)
+    }
+}
+
+/// This function places the local node in the ring.
+///
+/// A node that already has tokens, or the seed, is `Normal` right away. A new node first waits until it has the same schema
+/// as the rest of the cluster, then announces itself as `Joining` with its new tokens and waits until every owner has
+/// streamed the affected ranges to it before saving its tokens and becoming `Normal`. If the owners do not finish in time
+/// the join fails, and the node bootstraps again the next time it starts.
+/// A node that replaces a dead one takes its tokens instead, and gets its ranges from the surviving replicas.
+fn join_ring(
+    schema: &Arc<Mutex<Schema>>,
+    address: &Address,
+    config: &NodeConfig,
+    saved_tokens: Option<Vec<u128>>,
+) -> Result<(), ErrorTypes> {
+    let gossiper = get_gossiper();
+    if let Some(tokens) = saved_tokens {
+        gossiper.set_local_state(&address.i_address, tokens, NodeState::Normal);
+        return Ok(());
+    }
+    if address.i_address != SEED_IP_ADDRESS {
+        thread::sleep(std::time::Duration::from_secs(RING_DELAY));
+        if !gossiper.wait_for_schema_agreement(
+            &address.i_address,
+            std::time::Duration::from_secs(SCHEMA_AGREEMENT_TIMEOUT),
+        ) {
+            write_log_message(
+                &address.i_port,
+                LogType::Error,
+                "Joining without schema agreement".to_string(),
+            );
+        }
+    }
+    if let Some(dead) = &config.replace_address {
+        return replace_node(schema, address, config, dead);
+    }
+    let tokens = choose_tokens(address, config);
+    if address.i_address == SEED_IP_ADDRESS {
+        save_tokens(config, &tokens)?;
+        gossiper.set_local_state(&address.i_address, tokens, NodeState::Normal);
+        return Ok(());
+    }
+    gossiper.set_local_state(&address.i_address, tokens.clone(), NodeState::Joining);
+    write_log_message(
+        &address.i_port,
+        LogType::Info,
+        "Joining the ring".to_string(),
+    );
+    thread::sleep(std::time::Duration::from_secs(RING_DELAY));
+    let start = std::time::Instant::now();
+    while !gossiper.is_bootstrap_completed(&address.i_address) {
+        if start.elapsed().as_secs() > BOOTSTRAP_TIMEOUT {
+            return Err(ErrorTypes::new(
+                518,
+                "Timed out waiting for the owners to stream their ranges".to_string(),
+            ));
+        }
+        thread::sleep(std::time::Duration::from_secs(1));
+    }
+    save_tokens(config, &tokens)?;
+    gossiper.set_local_state(&address.i_address, tokens, NodeState::Normal);
+    write_log_message(
+        &address.i_port,
+        LogType::Info,
+        "Joined the ring".to_string(),
+    );
+    Ok(())
+}
+
+/// This function makes the local node take the tokens of a dead node.
+///
+/// The node is `Joining` without tokens while the surviving replicas stream the ranges of the dead node to it,
+/// so the other nodes do not bootstrap it. Once it has every range the dead node is removed and the local node becomes `Normal`.
+fn replace_node(
+    schema: &Arc<Mutex<Schema>>,
+    address: &Address,
+    config: &NodeConfig,
+    dead: &String,
+) -> Result<(), ErrorTypes> {
+    let gossiper = get_gossiper();
+    gossiper.set_local_state(&address.i_address, Vec::new(), NodeState::Joining);
+    thread::sleep(std::time::Duration::from_secs(RING_DELAY));
+    let tokens = match gossiper.get_endpoint_state(dead) {
+        Some(endpoint) if endpoint.is_down() && !endpoint.get_tokens().is_empty() => {
+            endpoint.get_tokens()
+        }
+        Some(_) => {
+            return Err(ErrorTypes::new(
+                584,
+                format!("{} is alive, it can not be replaced", dead),
+            ))
+        }
+        None => return Err(ErrorTypes::new(585, format!("{} is not in the ring", dead))),
+    };
+    write_log_message(
+        &address.i_port,
+        LogType::Info,
+        format!("Replacing {}", dead),
+    );
+    restore_replicas(schema, address, dead, Some(&address.i_address))?;
+    save_tokens(config, &tokens)?;
+    announce_removal(address, dead);
+    gossiper.set_local_state(&address.i_address, tokens, NodeState::Normal);
+    write_log_message(&address.i_port, LogType::Info, format!("Replaced {}", dead));
+    Ok(())
+}
+
+/// This function chooses the tokens of a node that has never joined the ring.
+/// If an allocation replication factor is configured, they are chosen with the ring the node learnt while waiting for the schema.
+fn choose_tokens(address: &Address, config: &NodeConfig) -> Vec<u128> {
+    let gossiper = get_gossiper();
+    let tokens = match config.allocate_tokens_for_rf {
+        Some(rf) => gossiper.allocate_tokens(&address.i_address, config.num_tokens, rf),
+        None => gossiper.random_tokens(config.num_tokens),
+    };
+    write_log_message(
+        &address.i_port,
+        LogType::Info,
+        format!("Chose {} tokens", tokens.len()),
+    );
+    tokens
+}
+
+/// This function saves the tokens of the node in its data directory.
+fn save_tokens(config: &NodeConfig, tokens: &Vec<u128>) -> Result<(), ErrorTypes> {
+    let saved = fs::create_dir_all(&config.data_dir).and_then(|_| {
+        fs::write(
+            tokens_path(&config.data_dir),
+            serde_json::to_string(tokens).unwrap_or_default(),
+        )
+    });
+    saved.map_err(|_| ErrorTypes::new(596, "Error saving the tokens".to_string()))
 }
 
 pub fn write_log_message(address: &String, log_type: LogType, message: String) {
@@ -185,7 +401,6 @@ pub fn write_log_message(address: &String, log_type: LogType, message: String) {
         message
     );
     let mut file = OpenOptions::new()
-        .write(true)
         .append(true)
         .create(true)
         .open(format!("node{}_log.log", address))
@@ -219,8 +434,9 @@ fn receive_client_message(
                         LogType::Info,
                         "Client connected".to_string(),
                     );
-                    let mut stream = acceptor.accept(client_stream).unwrap();
-                    handle_client_message(&mut stream, Arc::clone(&schema), address).unwrap();
+                    let stream = acceptor.accept(client_stream).unwrap();
+                    let mut connection = ClientConnection::new(stream);
+                    handle_client_message(&mut connection, Arc::clone(&schema), address).unwrap();
                 });
             }
             Err(_) => {
@@ -238,19 +454,28 @@ fn receive_client_message(
 }
 
 /// This function is responsible for handling the messages.
+///
+/// Every message to the other node is written by this thread, in the order they arrive to `rx`, so the messages that are
+/// sent from other threads through `writer` are never mixed with the other ones.
 pub fn receive_internal_message(
     mut socket: TcpStream,
     schema: Arc<Mutex<Schema>>,
     connection: Option<Connection>,
     address: Address,
-    rx: mpsc::Receiver<Vec<u8>>,
+    (writer, rx): (mpsc::Sender<Vec<u8>>, mpsc::Receiver<Vec<u8>>),
     tx: mpsc::Sender<Vec<u8>>,
 ) {
     let mut clone = socket.try_clone().unwrap();
     let node = Arc::new(Mutex::new(None));
     let node1 = Arc::clone(&node);
     thread::spawn(move || {
-        match internal_message(&mut clone, connection, &schema, tx.clone(), address.clone()) {
+        match internal_message(
+            &mut clone,
+            connection,
+            &schema,
+            (tx.clone(), writer.clone()),
+            address.clone(),
+        ) {
             Ok(Some(address)) => {
                 let _ = node.lock().unwrap().replace(address);
             }
@@ -258,7 +483,13 @@ pub fn receive_internal_message(
             Err(_) => return,
         }
         loop {
-            match internal_message(&mut clone, None, &schema, tx.clone(), address.clone()) {
+            match internal_message(
+                &mut clone,
+                None,
+                &schema,
+                (tx.clone(), writer.clone()),
+                address.clone(),
+            ) {
                 Ok(Some(address)) => {
                     if node.lock().unwrap().is_none() {
                         let _ = node.lock().unwrap().replace(address);
@@ -287,13 +518,13 @@ fn internal_message(
     socket: &mut TcpStream,
     connection: Option<Connection>,
     schema: &Arc<Mutex<Schema>>,
-    tx: Sender<Vec<u8>>,
+    (tx, writer): (Sender<Vec<u8>>, Sender<Vec<u8>>),
     address: Address,
 ) -> Result<Option<String>, ErrorTypes> {
     let mut source = [0; 1];
     if socket.read_exact(&mut source).is_ok() {
         if source[0] == NODE_MESSAGE {
-            handle_node_message(socket, Arc::clone(schema), tx.clone())?;
+            handle_node_message(socket, Arc::clone(schema), (tx, writer), &address)?;
             Ok(None)
         } else {
             handle_gossip_message(socket, connection, address, Arc::clone(schema))
@@ -310,16 +541,24 @@ fn internal_message(
 }
 
 /// This function is responsible for handling the node messages.
+///
+/// The responses that arrive are sent through `sender` to the reader that waits for them, and the messages that are
+/// answered from another thread are written by the thread of the connection through `writer`.
 fn handle_node_message(
     socket: &mut TcpStream,
     schema: Arc<Mutex<Schema>>,
-    sender: Sender<Vec<u8>>,
+    (sender, writer): (Sender<Vec<u8>>, Sender<Vec<u8>>),
+    address: &Address,
 ) -> Result<(), ErrorTypes> {
-    let mut len = [0; 1024];
-    if socket.read(&mut len).is_ok() {
-        let message = NodeMessage::from_bytes(len.to_vec());
+    let mut len = [0; 8];
+    if socket.read_exact(&mut len).is_ok() {
+        let mut bytes = vec![0; u64::from_be_bytes(len) as usize];
+        if socket.read_exact(&mut bytes).is_err() {
+            return Err(ErrorTypes::new(513, "Error reading message".to_string()));
+        }
+        let message = NodeMessage::from_bytes([len.to_vec(), bytes].concat());
         match message {
-            NodeMessage::Confirmation() => {
+            NodeMessage::Confirmation() | NodeMessage::Failure(_) => {
                 let _ = sender.send(message.to_bytes());
                 return Ok(());
             }
@@ -331,13 +570,83 @@ fn handle_node_message(
                 let _ = sender.send(message.to_bytes());
                 return Ok(());
             }
+            NodeMessage::StreamAck(_, _, _) => {
+                let _ = sender.send(message.to_bytes());
+                return Ok(());
+            }
+            NodeMessage::StreamResponse(_) => {
+                let _ = sender.send(message.to_bytes());
+                return Ok(());
+            }
+            NodeMessage::SchemaPull(node) => {
+                let gossiper = get_gossiper();
+                if gossiper.get_sender(&node).is_none() {
+                    gossiper.establish_connection(&node, address, Arc::clone(&schema));
+                }
+                let definition = schema.lock().unwrap().definition();
+                if let Some(sender) = gossiper.get_sender(&node) {
+                    let _ = sender.send(NodeMessage::SchemaPush(definition).to_bytes());
+                }
+                return Ok(());
+            }
+            NodeMessage::SchemaPush(definition) => {
+                let mut schema_lock = schema.lock().unwrap();
+                let previous = schema_lock.definition();
+                if schema_lock.merge_schema(definition)? {
+                    let version = schema_lock.get_version();
+                    let changes = schema_changes(&previous, &schema_lock.definition());
+                    drop(schema_lock);
+                    changes.into_iter().for_each(publish_schema_change);
+                    write_log_message(
+                        &address.i_port,
+                        LogType::Info,
+                        format!("Schema updated to version {}", version),
+                    );
+                    get_gossiper().set_schema_version(&address.i_address, version);
+                }
+                return Ok(());
+            }
+            NodeMessage::SchemaChange(change) => {
+                let mut schema_lock = schema.lock().unwrap();
+                let previous = schema_lock.definition();
+                let applied = schema_lock.apply_schema_change(change);
+                let version = schema_lock.get_version();
+                let changes = schema_changes(&previous, &schema_lock.definition());
+                drop(schema_lock);
+                let msg = match applied {
+                    Ok(()) => {
+                        changes.into_iter().for_each(publish_schema_change);
+                        get_gossiper().set_schema_version(&address.i_address, version);
+                        NodeMessage::Confirmation()
+                    }
+                    Err(e) => failure(address, e),
+                };
+                socket.write_all(&msg.to_bytes()).unwrap();
+                return Ok(());
+            }
+            NodeMessage::StreamRequest(target, tasks) => {
+                let address = address.clone();
+                thread::spawn(move || {
+                    let streamed = StreamSession::new(&target, tasks)
+                        .run(&schema, &address)
+                        .is_ok();
+                    let msg = NodeMessage::StreamResponse(streamed);
+                    let _ = writer.send(msg.to_bytes());
+                });
+                return Ok(());
+            }
             _ => {}
         }
 
-        schema
-            .lock()
-            .unwrap()
-            .execute_node_message(message, socket)?;
+        let is_request = message.is_request();
+        let result = schema.lock().unwrap().execute_node_message(message, socket);
+        if let Err(e) = result {
+            // El otro nodo espera una respuesta por cada pedido, en orden
+            let msg = failure(address, e);
+            if is_request {
+                socket.write_all(&msg.to_bytes()).unwrap();
+            }
+        }
 
         Ok(())
     } else {
@@ -345,6 +654,114 @@ fn handle_node_message(
     }
 }
 
+/// This function logs an error found while executing a message of another node and returns the message that tells
+/// that node the request failed.
+fn failure(address: &Address, error: ErrorTypes) -> NodeMessage {
+    let (code, message) = error.get();
+    write_log_message(
+        &address.i_port,
+        LogType::Error,
+        format!("{} {}", code, message),
+    );
+    NodeMessage::Failure(message)
+}
+
+/// This function removes the local node from the ring.
+///
+/// The node announces itself as `Leaving`, streams every range it owns to the nodes that take it over and,
+/// once every range was acknowledged, announces itself as `Left` so the other nodes remove it from their rings.
+/// If the streaming fails the node goes back to `Normal` and keeps its data.
+pub fn decommission(schema: &Arc<Mutex<Schema>>, address: &Address) -> Result<(), ErrorTypes> {
+    let gossiper = get_gossiper();
+    let tokens = match gossiper.get_endpoint_state(&address.i_address) {
+        Some(local) if local.get_state() == NodeState::Normal => local.get_tokens(),
+        _ => {
+            return Err(ErrorTypes::new(
+                584,
+                "Only a node in the NORMAL state can be decommissioned".to_string(),
+            ))
+        }
+    };
+    if gossiper.get_normal_nodes(&address.i_address).is_empty() {
+        return Err(ErrorTypes::new(
+            585,
+            "There are no other nodes to take over the ranges".to_string(),
+        ));
+    }
+    gossiper.set_local_state(&address.i_address, tokens.clone(), NodeState::Leaving);
+    write_log_message(
+        &address.i_port,
+        LogType::Info,
+        "Leaving the ring".to_string(),
+    );
+    thread::sleep(std::time::Duration::from_secs(RING_DELAY));
+    if let Err(e) = stream_from_leaving_node(schema, address) {
+        gossiper.set_local_state(&address.i_address, tokens, NodeState::Normal);
+        return Err(e);
+    }
+    gossiper.set_local_state(&address.i_address, tokens, NodeState::Left);
+    thread::sleep(std::time::Duration::from_secs(RING_DELAY));
+    announce_removal(address, &address.i_address);
+    let data_dir = schema.lock().unwrap().get_data_dir();
+    let _ = fs::remove_file(tokens_path(&data_dir));
+    write_log_message(&address.i_port, LogType::Info, "Left the ring".to_string());
+    Ok(())
+}
+
+/// This function removes a dead node from the ring.
+///
+/// The surviving replicas of every range of the dead node stream it to the nodes that take it over, and once every range is
+/// replicated again the other nodes are told to remove it. A node that is still alive has to be decommissioned instead.
+pub fn remove_dead_node(
+    schema: &Arc<Mutex<Schema>>,
+    address: &Address,
+    node: &String,
+) -> Result<(), ErrorTypes> {
+    let gossiper = get_gossiper();
+    if *node == address.i_address {
+        return Err(ErrorTypes::new(
+            584,
+            "A node can not remove itself, use decommission instead".to_string(),
+        ));
+    }
+    match gossiper.get_endpoint_state(node) {
+        Some(endpoint) if endpoint.is_down() => {}
+        Some(_) => {
+            return Err(ErrorTypes::new(
+                584,
+                format!("{} is alive, use decommission instead", node),
+            ))
+        }
+        None => return Err(ErrorTypes::new(585, format!("{} is not in the ring", node))),
+    }
+    write_log_message(
+        &address.i_port,
+        LogType::Info,
+        format!("Replicating the ranges of {}", node),
+    );
+    restore_replicas(schema, address, node, None)?;
+    announce_removal(address, node);
+    Ok(())
+}
+
+/// This function removes a node from the local ring and tells the other nodes to remove it.
+fn announce_removal(address: &Address, node: &String) {
+    let gossiper = get_gossiper();
+    let msg = NodeMessage::RemoveNode(node.to_string());
+    for neighbour in gossiper.get_neighbours().iter() {
+        if neighbour == node {
+            continue;
+        }
+        if let Some(sender) = gossiper.get_sender(neighbour) {
+            let _ = sender.send(msg.to_bytes());
+        }
+    }
+    if *node != address.i_address {
+        gossiper.remove_node(node);
+        write_log_message(&address.i_port, LogType::Info, format!("Removed {}", node));
+    }
+}
+
 /// This function is responsible for handling the gossip messages.
 fn handle_gossip_message(
     socket: &mut TcpStream,
@@ -361,12 +778,12 @@ fn handle_gossip_message(
             Ok(None)
         }
         GossipMessage::Ack(digests, endpoint_states) => {
-            gossiper.ack_handler(digests, endpoint_states, socket, &address)?;
+            gossiper.ack_handler(digests, endpoint_states, socket, &address, schema)?;
             //write_log_message(&address.i_port,LogType::Info,"Ack message received".to_string());
             Ok(None)
         }
         GossipMessage::Ack2(endpoint_states) => {
-            gossiper.ack2_handler(endpoint_states, &address);
+            gossiper.ack2_handler(endpoint_states, &address, schema);
             //write_log_message(                &address.i_port,                LogType::Info,                "Ack2 message received".to_string(),            );
             Ok(None)
         }
@@ -374,74 +791,169 @@ fn handle_gossip_message(
 }
 
 /// This function is responsible for handling the client messages.
+/// Every connection has its own keyspace in use, chosen by the client with `USE`, and its own version of the native
+/// protocol, chosen with the STARTUP. A frame with another version is answered with a ProtocolError.
 fn handle_client_message(
-    client_stream: &mut TlsStream<TcpStream>,
+    client_stream: &mut ClientConnection,
     schema: Arc<Mutex<Schema>>,
     address: Address,
 ) -> Result<(), ErrorTypes> {
     let mut compression: Option<Compression> = None;
+    let mut keyspace: Option<String> = None;
     loop {
-        let mut buf = [0; 1024];
-        match client_stream.read(&mut buf) {
-            Ok(len) => {
-                let message = receive_message(&mut buf[0..len].to_vec(), &compression);
-                match message {
-                    Err(_) => {
-                        write_log_message(
-                            &address.i_port,
-                            LogType::Info,
-                            format!("The client {} has disconnected", address.i_address),
-                        );
-                        return Ok(());
-                    }
-                    Ok(message) => {
-                        if let SolicitationMessage(msg) = message {
-                            handle_protocol_message(
-                                msg,
-                                client_stream,
-                                Arc::clone(&schema),
-                                &mut compression,
-                                address.clone(),
-                            )?;
-                        }
-                    }
-                }
-            }
-            Err(_) => {
-                //Se desconecto el cliente
-                ErrorTypes::new(
-                    514,
-                    "Error reading message, the client has been disconnected".to_string(),
-                );
-                write_log_message(
-                    &address.i_port,
-                    LogType::Error,
-                    "514 Error reading message, the client has been disconnected".to_string(),
-                );
+        let Ok(mut frame) = client_stream.read_frame(&compression) else {
+            write_log_message(
+                &address.i_port,
+                LogType::Info,
+                format!("The client {} has disconnected", address.i_address),
+            );
+            return Ok(());
+        };
+        let (_, version) = Version::from_byte(frame[0]);
+        if let Err(message) = client_stream.check_version(version) {
+            let mut response = Protocol::new();
+            response.write_error(ErrorCode::ProtocolError as i32, &message)?;
+            client_stream.send(&mut response);
+            continue;
+        }
+        let result = match receive_message(&mut frame, &compression) {
+            Ok(SolicitationMessage(msg)) => handle_protocol_message(
+                msg,
+                client_stream,
+                Arc::clone(&schema),
+                &mut compression,
+                address.clone(),
+                &mut keyspace,
+            ),
+            Ok(_) => Err(ErrorTypes::new(514, "Unexpected response".to_string())),
+            Err(e) => {
+                let mut response = Protocol::new();
+                response.write_error(ErrorCode::ProtocolError as i32, &e.get().1)?;
+                client_stream.send(&mut response);
+                continue;
             }
+        };
+        // Un error que no se respondio no cierra la conexion, el cliente recibe un ServerError
+        if let Err(e) = result {
+            write_log_message(
+                &address.i_port,
+                LogType::Error,
+                format!("{} {}", e.get().0, e.get().1),
+            );
+            let mut response = Protocol::new();
+            response.set_compress_algorithm(compression.clone());
+            write_query_error(&mut response, e)?;
+            client_stream.send(&mut response);
         }
     }
 }
 
+/// This function writes the error of a query that was not answered. The writes that did not reach their consistency
+/// level are reported as Unavailable or WriteTimeout, and the rest of the errors as a ServerError.
+fn write_query_error(response: &mut Protocol, error: ErrorTypes) -> Result<(), ErrorTypes> {
+    let message = error.get().1;
+    match error {
+        ErrorTypes::Unavailable {
+            consistency,
+            required,
+            alive,
+        } => response.write_unavailable(&message, consistency, required as i32, alive as i32),
+        ErrorTypes::WriteTimeout {
+            consistency,
+            received,
+            block_for,
+            write_type,
+        } => response.write_write_timeout(
+            &message,
+            consistency,
+            (received as i32, block_for as i32),
+            &write_type,
+        ),
+        _ => response.write_error(ErrorCode::ServerError as i32, &message),
+    }
+}
+
+/// This function answers a query that changed the schema with a SchemaChange result, and sends the change to the
+/// clients that registered for SCHEMA_CHANGE events.
+fn respond_schema_change(
+    client_stream: &mut ClientConnection,
+    mut response: Protocol,
+    change_type: SchemaChangeType,
+    target: &str,
+    name: &String,
+) {
+    response.write_result(
+        ResultKind::SchemaChange,
+        None,
+        Some(change_type.clone()),
+        Some(target.to_string()),
+        Some(name),
+    );
+    client_stream.send(&mut response);
+    publish_schema_change(EventKindChange::Schema(
+        change_type,
+        target.to_string(),
+        name.to_string(),
+    ));
+}
+
+/// This function sends a change of the schema to the clients that registered for it. The statements prepared on the
+/// tables it altered or dropped are forgotten before, so executing them again needs a new PREPARE.
+fn publish_schema_change(change: EventKindChange) {
+    evict_prepared_statements(&change);
+    get_events().publish(change);
+}
+
 /// This function is responsible for receiving a cassandra protocol message.
+/// A QUERY, PREPARE, EXECUTE or BATCH of a client that did not send its STARTUP and authenticate is answered with
+/// an Unauthorized error.
 fn handle_protocol_message(
     message: RequestMessage,
-    client_stream: &mut TlsStream<TcpStream>,
+    client_stream: &mut ClientConnection,
     schema: Arc<Mutex<Schema>>,
     compression_: &mut Option<Compression>,
     address: Address,
+    keyspace: &mut Option<String>,
 ) -> Result<(), ErrorTypes> {
+    let runs_queries = matches!(
+        message,
+        RequestMessage::Query(..)
+            | RequestMessage::Prepare(..)
+            | RequestMessage::Execute(..)
+            | RequestMessage::Batch(..)
+    );
+    if runs_queries && !client_stream.is_authenticated() {
+        let mut response = Protocol::new();
+        response.set_compress_algorithm(compression_.clone());
+        response.write_error(ErrorCode::Unauthorized as i32, UNAUTHENTICATED_MESSAGE)?;
+        client_stream.send(&mut response);
+        return Ok(());
+    }
     match message {
         RequestMessage::StartUp { compression } => {
             let mut response = Protocol::new();
+            client_stream.negotiate();
             *compression_ = compression;
-            response.write_authenticate("PasswordAuthenticator")?;
+            response.write_authenticate(AUTHENTICATOR)?;
             write_log_message(
                 &address.i_port,
                 LogType::Info,
                 "Start up completed".to_string(),
             );
-            client_stream.write_all(&response.get_binary()).unwrap();
+            client_stream.send(&mut response);
+            Ok(())
+        }
+        RequestMessage::Options => {
+            let mut response = Protocol::new();
+            response.write_supported(vec![
+                ("CQL_VERSION".to_string(), vec!["3.0.0".to_string()]),
+                (
+                    "COMPRESSION".to_string(),
+                    vec!["snappy".to_string(), "lz4".to_string()],
+                ),
+                ("PROTOCOL_VERSIONS".to_string(), Version::supported()),
+            ])?;
+            client_stream.send(&mut response);
             Ok(())
         }
         RequestMessage::AuthResponse { auth_response } => {
@@ -452,13 +964,15 @@ fn handle_protocol_message(
             for account in users {
                 if account.name == auth_response.0 && account.password == auth_response.1 {
                     let mut response = Protocol::new();
-                    response.write_auth_success();
+                    response.set_compress_algorithm(compression_.clone());
+                    response.write_auth_success()?;
+                    client_stream.authenticate();
                     write_log_message(
                         &address.i_port,
                         LogType::Info,
                         "Client Authenticated".to_string(),
                     );
-                    client_stream.write_all(&response.get_binary()).unwrap();
+                    client_stream.send(&mut response);
                     return Ok(());
                 }
             }
@@ -474,39 +988,193 @@ fn handle_protocol_message(
                 LogType::Error,
                 format!("{} {}", e.get().0, e.get().1),
             );
-            Err(e)
+            let mut response = Protocol::new();
+            response.set_compress_algorithm(compression_.clone());
+            response.write_error(ErrorCode::BadCredentials as i32, &e.get().1)?;
+            client_stream.send(&mut response);
+            Ok(())
+        }
+        RequestMessage::Register { events } => {
+            client_stream.register(events.clone());
+            let mut response = Protocol::new();
+            response.set_compress_algorithm(compression_.clone());
+            response.write_ready();
+            write_log_message(
+                &address.i_port,
+                LogType::Info,
+                format!("Client registered for {}", events.join(", ")),
+            );
+            client_stream.send(&mut response);
+            Ok(())
+        }
+        RequestMessage::Query(query, _original, parameters) => {
+            let query = query
+                .qualify(parameters.keyspace.as_ref().or(keyspace.as_ref()))
+                .and_then(|query| PreparedStatement::new(query, &schema.lock().unwrap()))
+                .and_then(|statement| statement.bind(&parameters.values));
+            match query {
+                Ok(query) => handle_query(
+                    query,
+                    parameters,
+                    client_stream,
+                    compression_,
+                    schema,
+                    address.clone(),
+                    keyspace,
+                ),
+                Err(e) => respond_invalid(client_stream, compression_, &address, e),
+            }
+        }
+        RequestMessage::Prepare(query, original) => {
+            let statement = query
+                .qualify(keyspace.as_ref())
+                .and_then(|query| PreparedStatement::new(query, &schema.lock().unwrap()));
+            let statement = match statement {
+                Ok(statement) => statement,
+                Err(e) => return respond_invalid(client_stream, compression_, &address, e),
+            };
+            let id = save_prepared_statement(&original, keyspace.as_ref(), statement.clone());
+            let mut response = Protocol::new();
+            response.set_compress_algorithm(compression_.clone());
+            response.write_prepared(
+                &id,
+                statement.get_pk_indexes(),
+                statement.get_bind_markers(),
+            )?;
+            client_stream.send(&mut response);
+            Ok(())
+        }
+        RequestMessage::Execute(id, parameters) => {
+            let Some(statement) = get_prepared_statement(&id) else {
+                return respond_unprepared(client_stream, compression_, &id);
+            };
+            match statement.bind(&parameters.values) {
+                Ok(query) => handle_query(
+                    query,
+                    parameters,
+                    client_stream,
+                    compression_,
+                    schema,
+                    address.clone(),
+                    keyspace,
+                ),
+                Err(e) => respond_invalid(client_stream, compression_, &address, e),
+            }
+        }
+        RequestMessage::Batch(logged, statements, parameters) => {
+            let batch_keyspace = parameters.keyspace.as_ref().or(keyspace.as_ref()).cloned();
+            let mut queries = Vec::new();
+            for (statement, values) in statements {
+                let statement = match statement {
+                    BatchStatement::Query(query) => query
+                        .qualify(batch_keyspace.as_ref())
+                        .and_then(|query| PreparedStatement::new(query, &schema.lock().unwrap())),
+                    BatchStatement::Prepared(id) => match get_prepared_statement(&id) {
+                        Some(statement) => Ok(statement),
+                        None => return respond_unprepared(client_stream, compression_, &id),
+                    },
+                };
+                match statement.and_then(|statement| statement.bind(&values)) {
+                    Ok(query) => queries.push(query),
+                    Err(e) => return respond_invalid(client_stream, compression_, &address, e),
+                }
+            }
+            handle_query(
+                Query::Batch { logged, queries },
+                parameters,
+                client_stream,
+                compression_,
+                schema,
+                address.clone(),
+                keyspace,
+            )
+        }
+        RequestMessage::SyntaxError(error) => {
+            let mut response = Protocol::new();
+            response.set_compress_algorithm(compression_.clone());
+            response.write_syntax_error(&error)?;
+            write_log_message(
+                &address.i_port,
+                LogType::Error,
+                format!("{} {}", error.code, error),
+            );
+            client_stream.send(&mut response);
+            Ok(())
         }
-        RequestMessage::Query(query, consistency, _original) => handle_query(
-            query,
-            consistency,
-            client_stream,
-            compression_,
-            schema,
-            address.clone(),
-        ),
     }
 }
 
-/// This function is responsible for handling the queries.
+/// This function answers the client with an Unprepared error, for a statement that was not prepared in this node.
+fn respond_unprepared(
+    client_stream: &mut ClientConnection,
+    compression: &Option<Compression>,
+    id: &[u8],
+) -> Result<(), ErrorTypes> {
+    let mut response = Protocol::new();
+    response.set_compress_algorithm(compression.clone());
+    response.write_unprepared(id)?;
+    client_stream.send(&mut response);
+    Ok(())
+}
+
+/// This function answers the client with an Invalid error, for a query that can not run as it was sent.
+fn respond_invalid(
+    client_stream: &mut ClientConnection,
+    compression: &Option<Compression>,
+    address: &Address,
+    error: ErrorTypes,
+) -> Result<(), ErrorTypes> {
+    let mut response = Protocol::new();
+    response.set_compress_algorithm(compression.clone());
+    response.write_error(ErrorCode::Invalid as i32, &error.get().1)?;
+    write_log_message(
+        &address.i_port,
+        LogType::Error,
+        format!("{} {}", error.get().0, error.get().1),
+    );
+    client_stream.send(&mut response);
+    Ok(())
+}
+
+/// This function is responsible for handling the queries. The names of their tables are already qualified with their keyspace.
 fn handle_query(
     query: Query,
-    consistency: Consistency,
-    client_stream: &mut TlsStream<TcpStream>,
+    parameters: QueryParameters,
+    client_stream: &mut ClientConnection,
     compression: &Option<Compression>,
     schema: Arc<Mutex<Schema>>,
     address: Address,
+    keyspace: &mut Option<String>,
 ) -> Result<(), ErrorTypes> {
+    let consistency = parameters.consistency;
     let mut response = Protocol::new();
     response.set_compress_algorithm(compression.clone());
+    if let Some(keyspace) = query
+        .written_keyspaces()
+        .into_iter()
+        .find(|keyspace| SYSTEM_KEYSPACES.contains(keyspace))
+    {
+        let e = ErrorTypes::new(580, format!("{} keyspace is not user-modifiable", keyspace));
+        write_log_message(
+            &address.i_port,
+            LogType::Error,
+            format!("{} {}", e.get().0, e.get().1),
+        );
+        response.write_error(ErrorCode::Unauthorized as i32, &e.get().1)?;
+        client_stream.send(&mut response);
+        return Ok(());
+    }
     match query {
         Query::CreateTable {
             table_name,
             columns_type,
             clustering_key,
             primary_key,
+            if_not_exists,
         } => handle_query_create_table(
             schema,
             (table_name, columns_type, clustering_key, primary_key),
+            if_not_exists,
             address,
             client_stream,
             response,
@@ -526,10 +1194,11 @@ fn handle_query(
         Query::CreateKeyspace {
             keyspace_name,
             replication,
+            if_not_exists,
         } => handle_query_create_keyspace(
             schema,
             address,
-            keyspace_name,
+            (keyspace_name, if_not_exists),
             replication,
             client_stream,
             response,
@@ -539,17 +1208,24 @@ fn handle_query(
             conditions,
             selected_columns,
             order,
+            limit,
+            per_partition_limit,
+            allow_filtering,
         } => handle_query_select(
             schema,
             (table_name, conditions, selected_columns, order),
+            ((limit, per_partition_limit), allow_filtering, parameters),
             address,
-            consistency,
             client_stream,
             response,
         ),
-        Query::Use { keyspace_name } => {
-            handle_query_use(schema.clone(), keyspace_name, client_stream, response)
-        }
+        Query::Use { keyspace_name } => handle_query_use(
+            schema.clone(),
+            keyspace_name,
+            keyspace,
+            client_stream,
+            response,
+        ),
         Query::Update {
             table_name,
             column_value,
@@ -574,27 +1250,129 @@ fn handle_query(
             client_stream,
             response,
         ),
+        Query::DropKeyspace {
+            keyspace_name,
+            if_exists,
+        } => handle_query_drop_keyspace(
+            schema,
+            address,
+            (keyspace_name, if_exists),
+            client_stream,
+            response,
+        ),
+        Query::DropTable {
+            table_name,
+            if_exists,
+        } => handle_query_drop_table(
+            schema,
+            address,
+            (table_name, if_exists),
+            client_stream,
+            response,
+        ),
+        Query::Truncate { table_name } => {
+            handle_query_truncate(schema, address, table_name, client_stream, response)
+        }
+        Query::AlterTable {
+            table_name,
+            operation,
+        } => handle_query_alter_table(
+            schema,
+            address,
+            (table_name, operation),
+            client_stream,
+            response,
+        ),
+        Query::AlterKeyspace {
+            keyspace_name,
+            replication,
+        } => handle_query_alter_keyspace(
+            schema,
+            address,
+            (keyspace_name, replication),
+            client_stream,
+            response,
+        ),
+        Query::Batch { logged, queries } => handle_query_batch(
+            schema,
+            (logged, queries),
+            address,
+            consistency,
+            client_stream,
+            response,
+        ),
     }
 }
 
 type TableInfo = (String, Vec<(String, String)>, Vec<String>, Vec<String>);
+type SelectOptions = ((Option<usize>, Option<usize>), bool, QueryParameters);
+
+/// This function announces a change of the local schema and sends it to the other nodes, waiting until every live node
+/// agrees on the new version. The nodes receive the whole schema, or only the change if there is one.
+/// If they do not agree before `SCHEMA_AGREEMENT_TIMEOUT` the change is kept anyway,
+/// and the nodes that missed it pull it later through gossip.
+fn announce_schema(schema: &Arc<Mutex<Schema>>, address: &Address, change: Option<SchemaChange>) {
+    let schema_lock = schema.lock().unwrap();
+    let (version, definition) = (schema_lock.get_version(), schema_lock.definition());
+    drop(schema_lock);
+    let gossiper = get_gossiper();
+    gossiper.set_schema_version(&address.i_address, version);
+    match change {
+        Some(change) => {
+            if let Err(e) = gossiper.schema_change(change) {
+                write_log_message(
+                    &address.i_port,
+                    LogType::Error,
+                    format!("{} {}", e.get().0, e.get().1),
+                );
+            }
+        }
+        None => gossiper.push_schema(definition),
+    }
+    if !gossiper.wait_for_schema_agreement(
+        &address.i_address,
+        std::time::Duration::from_secs(SCHEMA_AGREEMENT_TIMEOUT),
+    ) {
+        write_log_message(
+            &address.i_port,
+            LogType::Error,
+            "Timed out waiting for schema agreement".to_string(),
+        );
+    }
+}
+
+/// This function answers a query that creates a keyspace or table that already exists: with a Void result if the query
+/// has IF NOT EXISTS, and with an AlreadyExists error otherwise.
+fn respond_already_exists(
+    (keyspace, table): (&str, &str),
+    if_not_exists: bool,
+    client_stream: &mut ClientConnection,
+    mut response: Protocol,
+) -> Result<(), ErrorTypes> {
+    if if_not_exists {
+        response.write_result(ResultKind::Void, None, None, None, None);
+    } else {
+        response.write_already_exists(keyspace, table)?;
+    }
+    client_stream.send(&mut response);
+    Ok(())
+}
 
 fn handle_query_create_table(
     schema: Arc<Mutex<Schema>>,
     info_table: TableInfo,
+    if_not_exists: bool,
     address: Address,
-    client_stream: &mut TlsStream<TcpStream>,
-    mut response: Protocol,
+    client_stream: &mut ClientConnection,
+    response: Protocol,
 ) -> Result<(), ErrorTypes> {
     let (table_name, columns_type, clustering_key, primary_key) = info_table;
     let mut schema_lock = schema.lock().unwrap();
-    let result = schema_lock.create_table(
-        &table_name,
-        columns_type,
-        clustering_key,
-        primary_key,
-        address.i_port.clone(),
-    );
+    if schema_lock.contains_table(&table_name) {
+        let names = table_name.split_once('.').unwrap_or(("", &table_name));
+        return respond_already_exists(names, if_not_exists, client_stream, response);
+    }
+    let result = schema_lock.create_table(&table_name, columns_type, clustering_key, primary_key);
     if schema_lock.save_schema().is_err() {
         let e = ErrorTypes::new(516, "Error saving schema".to_string());
         write_log_message(
@@ -607,20 +1385,15 @@ fn handle_query_create_table(
     drop(schema_lock);
     write_log_message(&address.i_port, LogType::Info, "Table created".to_string());
     match result {
-        Ok(table) => {
-            let gossiper = get_gossiper();
-            let _ = gossiper.schema_change(NodeMessage::SchemaChange(SchemaChange::CreateTable(
-                Box::new(table),
-            )));
-            response.write_result(
-                ResultKind::SchemaChange,
-                None,
-                None,
-                Some(SchemaChangeType::Created),
-                Some("TABLE".to_string()),
-                Some(&table_name),
+        Ok(_) => {
+            announce_schema(&schema, &address, None);
+            respond_schema_change(
+                client_stream,
+                response,
+                SchemaChangeType::Created,
+                "TABLE",
+                &table_name,
             );
-            client_stream.write_all(&response.get_binary()).unwrap();
             Ok(())
         }
         Err(e) => Err(e),
@@ -630,12 +1403,21 @@ fn handle_query_create_table(
 fn handle_query_create_keyspace(
     schema: Arc<Mutex<Schema>>,
     address: Address,
-    keyspace_name: String,
-    replication: usize,
-    client_stream: &mut TlsStream<TcpStream>,
-    mut response: Protocol,
+    (keyspace_name, if_not_exists): (String, bool),
+    replication: HashMap<String, String>,
+    client_stream: &mut ClientConnection,
+    response: Protocol,
 ) -> Result<(), ErrorTypes> {
+    let replication = ReplicationStrategy::from_options(&replication)?;
     let mut schema_lock = schema.lock().unwrap();
+    if schema_lock.get_keyspace(&keyspace_name).is_ok() {
+        return respond_already_exists(
+            (&keyspace_name, ""),
+            if_not_exists,
+            client_stream,
+            response,
+        );
+    }
     let result = schema_lock.create_keyspace(&keyspace_name, replication);
     if schema_lock.save_schema().is_err() {
         let e = ErrorTypes::new(517, "Error saving schema".to_string());
@@ -648,20 +1430,15 @@ fn handle_query_create_keyspace(
     }
     drop(schema_lock);
     match result {
-        Ok(keyspace) => {
-            let gossiper = get_gossiper();
-            gossiper.schema_change(NodeMessage::SchemaChange(SchemaChange::CreateKeyspace(
-                keyspace,
-            )))?;
-            response.write_result(
-                ResultKind::SchemaChange,
-                None,
-                None,
-                Some(SchemaChangeType::Created),
-                Some("KEYSPACE".to_string()),
-                Some(&keyspace_name),
+        Ok(_) => {
+            announce_schema(&schema, &address, None);
+            respond_schema_change(
+                client_stream,
+                response,
+                SchemaChangeType::Created,
+                "KEYSPACE",
+                &keyspace_name,
             );
-            client_stream.write_all(&response.get_binary()).unwrap();
 
             Ok(())
         }
@@ -669,24 +1446,293 @@ fn handle_query_create_keyspace(
     }
 }
 
+fn handle_query_drop_keyspace(
+    schema: Arc<Mutex<Schema>>,
+    address: Address,
+    (keyspace_name, if_exists): (String, bool),
+    client_stream: &mut ClientConnection,
+    mut response: Protocol,
+) -> Result<(), ErrorTypes> {
+    let mut schema_lock = schema.lock().unwrap();
+    if let Err(e) = schema_lock.get_keyspace(&keyspace_name) {
+        if !if_exists {
+            response.write_error(ErrorCode::Invalid as i32, &e.get().1)?;
+            client_stream.send(&mut response);
+            return Ok(());
+        }
+        response.write_result(ResultKind::Void, None, None, None, None);
+        client_stream.send(&mut response);
+        return Ok(());
+    }
+    let change =
+        SchemaChange::DropKeyspace(keyspace_name.clone(), chrono::Utc::now().timestamp_millis());
+    schema_lock.apply_schema_change(change.clone())?;
+    drop(schema_lock);
+    write_log_message(
+        &address.i_port,
+        LogType::Info,
+        format!("Keyspace {} dropped", keyspace_name),
+    );
+    announce_schema(&schema, &address, Some(change));
+    respond_schema_change(
+        client_stream,
+        response,
+        SchemaChangeType::Dropped,
+        "KEYSPACE",
+        &keyspace_name,
+    );
+    Ok(())
+}
+
+fn handle_query_drop_table(
+    schema: Arc<Mutex<Schema>>,
+    address: Address,
+    (table_name, if_exists): (String, bool),
+    client_stream: &mut ClientConnection,
+    mut response: Protocol,
+) -> Result<(), ErrorTypes> {
+    let mut schema_lock = schema.lock().unwrap();
+    if !schema_lock.contains_table(&table_name) {
+        if !if_exists {
+            response.write_error(
+                ErrorCode::Invalid as i32,
+                &format!("Table {} not found", table_name),
+            )?;
+            client_stream.send(&mut response);
+            return Ok(());
+        }
+        response.write_result(ResultKind::Void, None, None, None, None);
+        client_stream.send(&mut response);
+        return Ok(());
+    }
+    let change = SchemaChange::DropTable(table_name.clone(), chrono::Utc::now().timestamp_millis());
+    schema_lock.apply_schema_change(change.clone())?;
+    drop(schema_lock);
+    write_log_message(
+        &address.i_port,
+        LogType::Info,
+        format!("Table {} dropped", table_name),
+    );
+    announce_schema(&schema, &address, Some(change));
+    respond_schema_change(
+        client_stream,
+        response,
+        SchemaChangeType::Dropped,
+        "TABLE",
+        &table_name,
+    );
+    Ok(())
+}
+
+/// This function deletes every row of a table in every node. Every node has to be alive, otherwise the rows of a node
+/// that missed it would come back through read repair.
+fn handle_query_truncate(
+    schema: Arc<Mutex<Schema>>,
+    address: Address,
+    table_name: String,
+    client_stream: &mut ClientConnection,
+    mut response: Protocol,
+) -> Result<(), ErrorTypes> {
+    let gossiper = get_gossiper();
+    let neighbours = gossiper.get_neighbours();
+    let alive = neighbours
+        .iter()
+        .filter(|node| !gossiper.is_down(node))
+        .count();
+    if alive < neighbours.len() {
+        response.write_unavailable(
+            "Every node has to be alive to truncate a table",
+            Consistency::All,
+            neighbours.len() as i32 + 1,
+            alive as i32 + 1,
+        )?;
+        client_stream.send(&mut response);
+        return Ok(());
+    }
+    let mut schema_lock = schema.lock().unwrap();
+    if !schema_lock.contains_table(&table_name) {
+        response.write_error(
+            ErrorCode::Invalid as i32,
+            &format!("Table {} not found", table_name),
+        )?;
+        client_stream.send(&mut response);
+        return Ok(());
+    }
+    let change = SchemaChange::Truncate(table_name.clone());
+    schema_lock.apply_schema_change(change.clone())?;
+    drop(schema_lock);
+    gossiper.schema_change(change)?;
+    write_log_message(
+        &address.i_port,
+        LogType::Info,
+        format!("Table {} truncated", table_name),
+    );
+    response.write_result(ResultKind::Void, None, None, None, None);
+    client_stream.send(&mut response);
+    Ok(())
+}
+
+fn handle_query_alter_table(
+    schema: Arc<Mutex<Schema>>,
+    address: Address,
+    (table_name, operation): (String, AlterTableOperation),
+    client_stream: &mut ClientConnection,
+    response: Protocol,
+) -> Result<(), ErrorTypes> {
+    let mut schema_lock = schema.lock().unwrap();
+    if let Err(e) = schema_lock.alter_table(&table_name, operation) {
+        drop(schema_lock);
+        return respond_alter_error(client_stream, response, e);
+    }
+    schema_lock.save_schema()?;
+    drop(schema_lock);
+    write_log_message(
+        &address.i_port,
+        LogType::Info,
+        format!("Table {} altered", table_name),
+    );
+    announce_schema(&schema, &address, None);
+    respond_schema_change(
+        client_stream,
+        response,
+        SchemaChangeType::Updated,
+        "TABLE",
+        &table_name,
+    );
+    Ok(())
+}
+
+/// This function changes the replication of a keyspace in every node. Once the nodes agree on it, the ranges are streamed
+/// in the background to the nodes that start replicating them.
+fn handle_query_alter_keyspace(
+    schema: Arc<Mutex<Schema>>,
+    address: Address,
+    (keyspace_name, replication): (String, HashMap<String, String>),
+    client_stream: &mut ClientConnection,
+    response: Protocol,
+) -> Result<(), ErrorTypes> {
+    let replication = match ReplicationStrategy::from_options(&replication) {
+        Ok(replication) => replication,
+        Err(e) => return respond_alter_error(client_stream, response, e),
+    };
+    let mut schema_lock = schema.lock().unwrap();
+    let previous = match schema_lock.alter_keyspace(&keyspace_name, replication.clone()) {
+        Ok(previous) => previous,
+        Err(e) => {
+            drop(schema_lock);
+            return respond_alter_error(client_stream, response, e);
+        }
+    };
+    schema_lock.save_schema()?;
+    drop(schema_lock);
+    write_log_message(
+        &address.i_port,
+        LogType::Info,
+        format!("Keyspace {} altered", keyspace_name),
+    );
+    announce_schema(&schema, &address, None);
+    if previous != replication {
+        let name = keyspace_name.clone();
+        thread::spawn(move || {
+            if let Err(e) = stream_new_replicas(&schema, &address, &name, &previous) {
+                write_log_message(
+                    &address.i_port,
+                    LogType::Error,
+                    format!(
+                        "Could not stream the ranges of {} to its new replicas, they will get the missing rows through read repair: {}",
+                        name,
+                        e.get().1
+                    ),
+                );
+            }
+        });
+    }
+    respond_schema_change(
+        client_stream,
+        response,
+        SchemaChangeType::Updated,
+        "KEYSPACE",
+        &keyspace_name,
+    );
+    Ok(())
+}
+
+/// This function answers the errors of the user in an ALTER. An unknown keyspace, table or column, or a column that can
+/// not be added or dropped, is Invalid, and invalid replication options are a ConfigError. The rest of the errors are
+/// returned, so the client gets a ServerError.
+fn respond_alter_error(
+    client_stream: &mut ClientConnection,
+    mut response: Protocol,
+    error: ErrorTypes,
+) -> Result<(), ErrorTypes> {
+    let code = match error.get().0 {
+        540 | 541 | 542 | 545 | 554 | 555 | 556 => ErrorCode::Invalid,
+        597 => ErrorCode::ConfigError,
+        _ => return Err(error),
+    };
+    response.write_error(code as i32, &error.get().1)?;
+    client_stream.send(&mut response);
+    Ok(())
+}
+
+/// This function answers a select with its `LIMIT` and `PER PARTITION LIMIT`. Scanning the whole ring to filter the rows needs
+/// `ALLOW FILTERING`; without it the query is rejected with an `Invalid` error.
 fn handle_query_select(
     schema: Arc<Mutex<Schema>>,
     info_select: (String, Clause, Vec<String>, Vec<String>),
+    (limits, allow_filtering, parameters): SelectOptions,
     address: Address,
-    consistency: Consistency,
-    client_stream: &mut TlsStream<TcpStream>,
+    client_stream: &mut ClientConnection,
     mut response: Protocol,
 ) -> Result<(), ErrorTypes> {
+    if let Some((metadata, rows)) = select_system_table(&schema, &info_select, limits, &address)? {
+        response.write_rows(&metadata, &rows, parameters.skip_metadata, None)?;
+        client_stream.send(&mut response);
+        return Ok(());
+    }
     let (table_name, conditions, selected_columns, order) = info_select;
-    let mut schema_lock = schema.lock().unwrap();
-    let rows = schema_lock.execute_select(
-        (table_name, conditions, selected_columns, order),
-        &address,
-        consistency,
-    )?;
+    let schema_lock = schema.lock().unwrap();
+    if !allow_filtering && schema_lock.needs_filtering(&table_name, &conditions)? {
+        drop(schema_lock);
+        response.write_error(ErrorCode::Invalid as i32, FILTERING_MESSAGE)?;
+        client_stream.send(&mut response);
+        return Ok(());
+    }
     drop(schema_lock);
-    response.write_result(ResultKind::Rows, Some(rows), None, None, None, None);
-    client_stream.write_all(&response.get_binary()).unwrap();
+    let info_select = (table_name.clone(), conditions, selected_columns, order);
+    let consistency = parameters.consistency;
+    let (mut rows, next) = match parameters.page_size {
+        Some(page_size) => {
+            let paging_state = parameters
+                .paging_state
+                .map(|paging_state| PagingState::from_bytes(&paging_state))
+                .transpose()?;
+            let (rows, next) = select_page(
+                &schema,
+                info_select,
+                limits,
+                (page_size, paging_state),
+                &address,
+                consistency,
+            )?;
+            (rows, next.map(|next| next.to_bytes()))
+        }
+        None => (
+            select_rows(&schema, info_select, limits, None, &address, consistency)?,
+            None,
+        ),
+    };
+    let header = match rows.is_empty() {
+        true => Vec::new(),
+        false => rows.remove(0),
+    };
+    let mut metadata = schema
+        .lock()
+        .unwrap()
+        .get_rows_metadata(&table_name, &header)?;
+    let rows = metadata.parse_rows(&rows);
+    response.write_rows(&metadata, &rows, parameters.skip_metadata, next.as_deref())?;
+    client_stream.send(&mut response);
     Ok(())
 }
 
@@ -695,51 +1741,50 @@ fn handle_query_insert(
     info_insert: (String, Vec<String>, Vec<Vec<String>>),
     address: Address,
     consistency: Consistency,
-    client_stream: &mut TlsStream<TcpStream>,
+    client_stream: &mut ClientConnection,
     mut response: Protocol,
 ) -> Result<(), ErrorTypes> {
     let (table_name, columns_name, values) = info_insert;
     let mut schema_lock = schema.lock().unwrap();
 
-    schema_lock.execute_insert(table_name, values, columns_name, &address, consistency)?;
+    // El coordinador elige el timestamp de la escritura, el mismo en todas las replicas
+    let timestamp = chrono::Utc::now().to_rfc3339();
+    schema_lock.execute_insert(
+        table_name,
+        values,
+        columns_name,
+        &address,
+        consistency,
+        &timestamp,
+    )?;
     drop(schema_lock);
-    response.write_result(ResultKind::Void, None, None, None, None, None);
-    client_stream.write_all(&response.get_binary()).unwrap();
+    response.write_result(ResultKind::Void, None, None, None, None);
+    client_stream.send(&mut response);
     write_log_message(&address.i_port, LogType::Info, "Row inserted".to_string());
     Ok(())
 }
 
+/// This function changes the keyspace in use of the connection of the client, without affecting the other clients.
 fn handle_query_use(
     schema: Arc<Mutex<Schema>>,
     keyspace_name: String,
-    client_stream: &mut TlsStream<TcpStream>,
+    keyspace: &mut Option<String>,
+    client_stream: &mut ClientConnection,
     mut response: Protocol,
 ) -> Result<(), ErrorTypes> {
-    let mut schema_lock = schema.lock().unwrap();
-    let result = schema_lock.set_keyspace(&keyspace_name);
-    if schema_lock.save_schema().is_err() {
-        return Err(ErrorTypes::new(518, "Error saving schema".to_string()));
-    }
+    let schema_lock = schema.lock().unwrap();
+    schema_lock.get_keyspace(&keyspace_name)?;
     drop(schema_lock);
-    match result {
-        Ok(keyspace) => {
-            response.write_result(
-                ResultKind::SetKeyspace,
-                None,
-                Some(&keyspace_name),
-                None,
-                None,
-                None,
-            );
-            let gossiper = get_gossiper();
-            gossiper.schema_change(NodeMessage::SchemaChange(SchemaChange::UseKeyspace(
-                keyspace,
-            )))?;
-            client_stream.write_all(&response.get_binary()).unwrap();
-            Ok(())
-        }
-        Err(e) => Err(e),
-    }
+    *keyspace = Some(keyspace_name.clone());
+    response.write_result(
+        ResultKind::SetKeyspace,
+        Some(&keyspace_name),
+        None,
+        None,
+        None,
+    );
+    client_stream.send(&mut response);
+    Ok(())
 }
 
 fn handle_query_update(
@@ -747,15 +1792,23 @@ fn handle_query_update(
     info_update: (String, HashMap<String, String>, Clause),
     consistency: Consistency,
     address: String,
-    client_stream: &mut TlsStream<TcpStream>,
+    client_stream: &mut ClientConnection,
     mut response: Protocol,
 ) -> Result<(), ErrorTypes> {
     let (table_name, column_value, conditions) = info_update;
     let mut schema_lock = schema.lock().unwrap();
-    schema_lock.execute_update(table_name, column_value, conditions, address, consistency)?;
+    let timestamp = chrono::Utc::now().to_rfc3339();
+    schema_lock.execute_update(
+        table_name,
+        column_value,
+        conditions,
+        address,
+        consistency,
+        &timestamp,
+    )?;
     drop(schema_lock);
-    response.write_result(ResultKind::Void, None, None, None, None, None);
-    client_stream.write_all(&response.get_binary()).unwrap();
+    response.write_result(ResultKind::Void, None, None, None, None);
+    client_stream.send(&mut response);
     Ok(())
 }
 
@@ -765,15 +1818,89 @@ fn handle_query_delete(
     conditions: Clause,
     address: Address,
     consistency: Consistency,
-    client_stream: &mut TlsStream<TcpStream>,
+    client_stream: &mut ClientConnection,
     mut response: Protocol,
 ) -> Result<(), ErrorTypes> {
     let mut schema_lock = schema.lock().unwrap();
 
-    schema_lock.execute_delete(table_name, conditions, address.i_address, consistency)?;
+    let timestamp = chrono::Utc::now().to_rfc3339();
+    schema_lock.execute_delete(
+        table_name,
+        conditions,
+        address.i_address,
+        consistency,
+        &timestamp,
+    )?;
     drop(schema_lock);
-    response.write_result(ResultKind::Void, None, None, None, None, None);
-    client_stream.write_all(&response.get_binary()).unwrap();
+    response.write_result(ResultKind::Void, None, None, None, None);
+    client_stream.send(&mut response);
     write_log_message(&address.i_port, LogType::Info, "Rows deleted".to_string());
     Ok(())
 }
+
+/// This function reports a statement of a batch that did not reach its consistency level as a timeout of the batch.
+fn batch_write_error(error: ErrorTypes, batch_type: &str) -> ErrorTypes {
+    match error {
+        ErrorTypes::WriteTimeout {
+            consistency,
+            received,
+            block_for,
+            ..
+        } => ErrorTypes::WriteTimeout {
+            consistency,
+            received,
+            block_for,
+            write_type: batch_type.to_string(),
+        },
+        error => error,
+    }
+}
+
+/// This function applies the statements of a batch in order. A logged batch is written to the batchlog of other nodes
+/// before, so its statements are eventually applied even if the coordinator fails in the middle of them.
+fn handle_query_batch(
+    schema: Arc<Mutex<Schema>>,
+    (logged, queries): (bool, Vec<Query>),
+    address: Address,
+    consistency: Consistency,
+    client_stream: &mut ClientConnection,
+    mut response: Protocol,
+) -> Result<(), ErrorTypes> {
+    let schema_lock = schema.lock().unwrap();
+    let invalid = queries.iter().find_map(|query| match query {
+        Query::Insert { table_name, .. }
+        | Query::Update { table_name, .. }
+        | Query::Delete { table_name, .. } => (!schema_lock.contains_table(table_name))
+            .then(|| format!("Table {} does not exist", table_name)),
+        _ => Some("Only INSERT, UPDATE and DELETE can be in a batch".to_string()),
+    });
+    drop(schema_lock);
+    if let Some(message) = invalid {
+        response.write_error(ErrorCode::Invalid as i32, &message)?;
+        client_stream.send(&mut response);
+        return Ok(());
+    }
+    if !logged {
+        let timestamp = chrono::Utc::now().to_rfc3339();
+        apply_batch(&schema, queries, &address, consistency, &timestamp)
+            .map_err(|e| batch_write_error(e, "UNLOGGED_BATCH"))?;
+    } else {
+        let mut batch = Batch::new(&address.i_address, queries.clone());
+        let endpoints = match write_batchlog(&schema, &address, &mut batch) {
+            Ok(endpoints) => endpoints,
+            Err(e) => {
+                response.write_unavailable(&e.get().1, Consistency::One, 1, 0)?;
+                client_stream.send(&mut response);
+                return Ok(());
+            }
+        };
+        // Si falla, el batch queda en el batchlog y se aplica mas tarde con el mismo timestamp
+        apply_batch(&schema, queries, &address, consistency, &batch.timestamp())
+            .map_err(|e| batch_write_error(e, "BATCH"))?;
+        remove_from_batchlog(&schema, &address, &endpoints, &batch.id);
+    }
+    response.write_result(ResultKind::Void, None, None, None, None);
+    client_stream.send(&mut response);
+    write_log_message(&address.i_port, LogType::Info, "Batch applied".to_string());
+    Ok(())
+}
@@ -0,0 +1,163 @@
+use super::{
+    address::Address,
+    schema::{select_rows, Schema},
+};
+use crate::{
+    errors::error_types::ErrorTypes,
+    protocol::{
+        protocol_notations::{consistency::Consistency, paging_state::PagingState},
+        query_parser::clause::Clause,
+    },
+};
+use std::sync::{Arc, Mutex};
+
+/// This function reads the page of `page_size` rows of a select that starts at the paging state, or at its first row,
+/// and returns it, with the names of the selected columns as its first row, and the paging state of the next page if
+/// there can be more rows after it.
+///
+/// Without `ORDER BY` nor `PER PARTITION LIMIT` the rows are sorted by their primary key, so the replicas only read
+/// the rows after the primary key of the paging state, until the row after the page. Otherwise the select is executed
+/// again from its first row, until the row after the page, and the page starts after the row with the primary key of
+/// the paging state. The columns of the primary key are selected to find that row even if the query did not select
+/// them, and removed from the page.
+pub fn select_page(
+    schema: &Arc<Mutex<Schema>>,
+    info_select: (String, Clause, Vec<String>, Vec<String>),
+    (limit, per_partition_limit): (Option<usize>, Option<usize>),
+    (page_size, paging_state): (usize, Option<PagingState>),
+    address: &Address,
+    consistency: Consistency,
+) -> Result<(Vec<Vec<String>>, Option<PagingState>), ErrorTypes> {
+    let (table_name, conditions, mut selected_columns, order) = info_select;
+    let primary_key = schema.lock().unwrap().get_primary_key(&table_name)?;
+    let selected = match selected_columns.as_slice() {
+        [all] if all == "*" => None,
+        _ => Some(selected_columns.len()),
+    };
+    if selected.is_some() {
+        for column in &primary_key {
+            if !selected_columns.contains(column) {
+                selected_columns.push(column.to_string());
+            }
+        }
+    }
+    let returned = paging_state.as_ref().map_or(0, |state| state.returned);
+    let bounded = order.is_empty() && per_partition_limit.is_none();
+    let (after, skipped) = match &paging_state {
+        Some(state) if bounded => (Some(state.key.clone()), returned),
+        _ => (None, 0),
+    };
+    let limit = limit.map(|limit| limit.saturating_sub(skipped));
+    let fetch = returned - skipped + page_size + 1;
+    let capped = limit.is_none_or(|limit| limit > fetch);
+    let limits = (
+        if capped { Some(fetch) } else { limit },
+        per_partition_limit,
+    );
+    let info_select = (table_name, conditions, selected_columns, order);
+    let mut rows = select_rows(schema, info_select, limits, after, address, consistency)?;
+    if rows.is_empty() {
+        return Ok((rows, None));
+    }
+    let header = rows.remove(0);
+    let key_indexes = primary_key
+        .iter()
+        .filter_map(|column| header.iter().position(|name| name == column))
+        .collect::<Vec<usize>>();
+    let more_rows = capped && rows.len() >= fetch;
+    let (mut page, next) = page_rows(
+        rows,
+        &key_indexes,
+        page_size,
+        paging_state.as_ref().filter(|_| !bounded),
+        more_rows,
+    );
+    let next = next.map(|next| PagingState {
+        returned: next.returned + skipped,
+        ..next
+    });
+    page.insert(0, header);
+    if let Some(selected) = selected {
+        page.iter_mut().for_each(|row| row.truncate(selected));
+    }
+    Ok((page, next))
+}
+
+/// This function returns the page of `page_size` rows that starts after the row with the primary key of the paging
+/// state, or at the first row, and the paging state of the next page if there are more rows after it.
+///
+/// If the row of the paging state is not found, because it was deleted, the page starts where that row was, after the
+/// other rows the previous pages returned. `more_rows` tells if the select can have more rows after the ones that were read.
+pub fn page_rows(
+    rows: Vec<Vec<String>>,
+    key_indexes: &[usize],
+    page_size: usize,
+    paging_state: Option<&PagingState>,
+    more_rows: bool,
+) -> (Vec<Vec<String>>, Option<PagingState>) {
+    let key = |row: &Vec<String>| {
+        key_indexes
+            .iter()
+            .map(|i| row[*i].to_string())
+            .collect::<Vec<String>>()
+    };
+    let (start, returned) = match paging_state {
+        Some(state) => {
+            let start = rows
+                .iter()
+                .position(|row| key(row) == state.key)
+                .map_or(state.returned.saturating_sub(1).min(rows.len()), |i| i + 1);
+            (start, state.returned)
+        }
+        None => (0, 0),
+    };
+    let end = (start + page_size).min(rows.len());
+    let has_more = end > start && (end < rows.len() || more_rows);
+    let page = rows[start..end].to_vec();
+    let next = match page.last() {
+        Some(last) if has_more => Some(PagingState {
+            key: key(last),
+            returned: returned + page.len(),
+        }),
+        _ => None,
+    };
+    (page, next)
+}
+
+#[cfg(test)]
+pub mod test {
+    use super::*;
+
+    fn rows(ids: &[&str]) -> Vec<Vec<String>> {
+        ids.iter()
+            .map(|id| vec!["EZE".to_string(), id.to_string(), "ON TIME".to_string()])
+            .collect()
+    }
+
+    #[test]
+    fn test_page_rows() {
+        let all = rows(&["1", "2", "3", "4", "5"]);
+        let (page, next) = page_rows(all.clone(), &[0, 1], 2, None, false);
+        assert_eq!(page, rows(&["1", "2"]));
+        let next = next.unwrap();
+        assert_eq!(next.key, vec!["EZE", "2"]);
+        assert_eq!(PagingState::from_bytes(&next.to_bytes()).unwrap(), next);
+
+        let (page, next) = page_rows(all.clone(), &[0, 1], 2, Some(&next), false);
+        assert_eq!(page, rows(&["3", "4"]));
+        let (page, last) = page_rows(all, &[0, 1], 2, next.as_ref(), false);
+        assert_eq!(page, rows(&["5"]));
+        assert!(last.is_none());
+
+        // La ultima fila de la pagina anterior se borro, la pagina empieza donde estaba
+        let state = PagingState {
+            key: vec!["EZE".to_string(), "2".to_string()],
+            returned: 2,
+        };
+        let (page, _) = page_rows(rows(&["1", "3", "4", "5"]), &[0, 1], 2, Some(&state), false);
+        assert_eq!(page, rows(&["3", "4"]));
+        let (_, next) = page_rows(rows(&["1", "2"]), &[0, 1], 2, None, true);
+        assert!(next.is_some());
+        assert!(PagingState::from_bytes(b"page").is_err());
+    }
+}
@@ -0,0 +1,316 @@
+use super::schema::Schema;
+use crate::{
+    errors::error_types::ErrorTypes,
+    protocol::{
+        protocol_body::{
+            data_type::DataType, event_kind::EventKindChange, schema_change::SchemaChangeType,
+        },
+        protocol_notations::{
+            columns_metadata::ColumnsMetadata, cql_value::CqlValue, value::Value,
+        },
+        query_parser::query::Query,
+    },
+};
+use chksum_md5 as md5;
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::{Mutex, OnceLock},
+};
+
+const MAX_PREPARED_STATEMENTS: usize = 1000;
+
+static PREPARED_STATEMENTS: OnceLock<Mutex<PreparedCache>> = OnceLock::new();
+
+/// This struct keeps the statements that the clients prepared, up to `MAX_PREPARED_STATEMENTS` of them. Once it is full,
+/// the statement that was used the longest time ago is forgotten, and a client that executes it gets an Unprepared error.
+///
+/// ### Fields:
+/// - `statements`: The statements, by their identifier.
+/// - `order`: The identifiers of the statements, from the least to the most recently used.
+#[derive(Default)]
+struct PreparedCache {
+    statements: HashMap<Vec<u8>, PreparedStatement>,
+    order: VecDeque<Vec<u8>>,
+}
+
+impl PreparedCache {
+    /// This function keeps a statement, forgetting the least recently used one if the cache is full.
+    fn insert(&mut self, id: Vec<u8>, statement: PreparedStatement) {
+        if self.statements.insert(id.clone(), statement).is_none()
+            && self.statements.len() > MAX_PREPARED_STATEMENTS
+        {
+            if let Some(oldest) = self.order.pop_front() {
+                self.statements.remove(&oldest);
+            }
+        }
+        self.touch(id);
+    }
+
+    /// This function returns a statement and marks it as the most recently used.
+    fn get(&mut self, id: &[u8]) -> Option<PreparedStatement> {
+        let statement = self.statements.get(id).cloned()?;
+        self.touch(id.to_vec());
+        Some(statement)
+    }
+
+    /// This function forgets the statements that `is_stale` matches.
+    fn retain<F: Fn(&PreparedStatement) -> bool>(&mut self, is_stale: F) {
+        self.statements.retain(|_, statement| !is_stale(statement));
+        let statements = &self.statements;
+        self.order.retain(|id| statements.contains_key(id));
+    }
+
+    fn touch(&mut self, id: Vec<u8>) {
+        self.order.retain(|used| *used != id);
+        self.order.push_back(id);
+    }
+}
+
+/// This struct represents a query whose bind markers `?` take the type of the column they are bound to, so the values
+/// a client sends for them can be read from their binary form.
+///
+/// The statements that the clients prepare are kept by the node, so they can execute them without parsing them again.
+#[derive(Debug, Clone)]
+pub struct PreparedStatement {
+    query: Query,
+    bind_markers: ColumnsMetadata,
+    pk_indexes: Vec<u16>,
+}
+
+impl PreparedStatement {
+    /// This function creates the statement of a query whose table is qualified with its keyspace. It fails if a bind
+    /// marker is bound to a column that the table does not have.
+    pub fn new(query: Query, schema: &Schema) -> Result<PreparedStatement, ErrorTypes> {
+        if let Query::Batch { queries, .. } = &query {
+            return PreparedStatement::new_batch(query.clone(), queries, schema);
+        }
+        let markers = query.bind_markers();
+        let table_name = query.table_name().cloned().unwrap_or_default();
+        if markers.is_empty() {
+            return Ok(PreparedStatement {
+                query,
+                bind_markers: ColumnsMetadata::new(&table_name, vec![]),
+                pk_indexes: vec![],
+            });
+        }
+        let (columns_type, partition_key) = schema.get_columns(&table_name)?;
+        let columns = markers
+            .into_iter()
+            .map(
+                |marker| match columns_type.iter().find(|(column, _)| *column == marker) {
+                    Some((_, column_type)) => Ok((marker, DataType::from_name(column_type))),
+                    None => Err(ErrorTypes::new(
+                        560,
+                        format!("Undefined column name {}", marker),
+                    )),
+                },
+            )
+            .collect::<Result<Vec<(String, DataType)>, ErrorTypes>>()?;
+        let pk_indexes = partition_key
+            .iter()
+            .map(|key| {
+                columns
+                    .iter()
+                    .position(|(column, _)| column == key)
+                    .map(|i| i as u16)
+            })
+            .collect::<Option<Vec<u16>>>()
+            .unwrap_or_default();
+        Ok(PreparedStatement {
+            query,
+            bind_markers: ColumnsMetadata::new(&table_name, columns),
+            pk_indexes,
+        })
+    }
+
+    /// This function creates the statement of a batch, whose bind markers take their types from the statement they are
+    /// in. The metadata of the markers names the table of the first statement.
+    fn new_batch(
+        query: Query,
+        queries: &[Query],
+        schema: &Schema,
+    ) -> Result<PreparedStatement, ErrorTypes> {
+        let columns = queries
+            .iter()
+            .map(|query| {
+                PreparedStatement::new(query.clone(), schema)
+                    .map(|statement| statement.bind_markers.columns)
+            })
+            .collect::<Result<Vec<Vec<(String, DataType)>>, ErrorTypes>>()?
+            .concat();
+        let table_name = queries
+            .first()
+            .and_then(Query::table_name)
+            .cloned()
+            .unwrap_or_default();
+        Ok(PreparedStatement {
+            query,
+            bind_markers: ColumnsMetadata::new(&table_name, columns),
+            pk_indexes: vec![],
+        })
+    }
+
+    /// This function returns the query with `values` bound to its bind markers, in their order. Each value is read with
+    /// the type of its marker.
+    pub fn bind(&self, values: &[Value]) -> Result<Query, ErrorTypes> {
+        let values = values
+            .iter()
+            .enumerate()
+            .map(|(i, value)| {
+                let data_type = self
+                    .bind_markers
+                    .columns
+                    .get(i)
+                    .map_or(DataType::Varchar, |(_, data_type)| *data_type);
+                CqlValue::from_value(data_type, value).map(|value| value.to_string())
+            })
+            .collect::<Result<Vec<String>, ErrorTypes>>()?;
+        self.query.clone().bind(&values)
+    }
+
+    /// This function returns the table and the column that each bind marker is bound to, with its type.
+    pub fn get_bind_markers(&self) -> &ColumnsMetadata {
+        &self.bind_markers
+    }
+
+    /// This function returns the position of the bind markers of the columns of the partition key, in the order of
+    /// those columns. It is empty if a column of the partition key does not have one.
+    pub fn get_pk_indexes(&self) -> &[u16] {
+        &self.pk_indexes
+    }
+
+    /// This function returns if the statement uses a table that `uses` matches, in any of its queries if it is a batch.
+    fn uses_table<F: Fn(&str) -> bool>(&self, uses: F) -> bool {
+        match &self.query {
+            Query::Batch { queries, .. } => queries
+                .iter()
+                .any(|query| query.table_name().is_some_and(|name| uses(name))),
+            query => query.table_name().is_some_and(|name| uses(name)),
+        }
+    }
+}
+
+fn prepared_statements() -> &'static Mutex<PreparedCache> {
+    PREPARED_STATEMENTS.get_or_init(|| Mutex::new(PreparedCache::default()))
+}
+
+/// This function keeps a statement that a client prepared in `keyspace` and returns its identifier, which is the MD5 of
+/// the keyspace and the query. Preparing the same query twice returns the same identifier.
+pub fn save_prepared_statement(
+    query: &str,
+    keyspace: Option<&String>,
+    statement: PreparedStatement,
+) -> Vec<u8> {
+    let key = format!("{}{}", keyspace.cloned().unwrap_or_default(), query);
+    let id = md5::hash(key.as_bytes()).into_inner().to_vec();
+    prepared_statements()
+        .lock()
+        .unwrap()
+        .insert(id.clone(), statement);
+    id
+}
+
+/// This function returns the prepared statement with the identifier `id`, if it was prepared in this node and it was not
+/// forgotten since then.
+pub fn get_prepared_statement(id: &[u8]) -> Option<PreparedStatement> {
+    prepared_statements().lock().unwrap().get(id)
+}
+
+/// This function forgets the statements of the tables that a schema change altered or dropped, and the ones of the tables
+/// of a dropped keyspace, so the clients that execute them get an Unprepared error and prepare them again.
+pub fn evict_prepared_statements(change: &EventKindChange) {
+    let EventKindChange::Schema(change_type, target, name) = change else {
+        return;
+    };
+    let mut statements = prepared_statements().lock().unwrap();
+    match (change_type, target.as_str()) {
+        (SchemaChangeType::Updated | SchemaChangeType::Dropped, "TABLE") => {
+            statements.retain(|statement| statement.uses_table(|table| table == name))
+        }
+        (SchemaChangeType::Dropped, "KEYSPACE") => statements.retain(|statement| {
+            statement.uses_table(|table| table.split_once('.').map(|(k, _)| k) == Some(name))
+        }),
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+pub mod test {
+    use super::*;
+    use crate::{
+        protocol::query_parser::parser_impl::parse_query,
+        server::replication_strategy::ReplicationStrategy,
+    };
+
+    #[test]
+    fn test_prepared_statement() {
+        let dir = std::env::temp_dir().join("prepared_statement_test");
+        let mut schema = Schema::new(&"9992".to_string(), &dir.to_string_lossy()).unwrap();
+        schema
+            .create_keyspace(&"prepared_ks".to_string(), ReplicationStrategy::Simple(1))
+            .unwrap();
+        schema
+            .create_table(
+                "prepared_ks.t",
+                vec![
+                    ("id".to_string(), "int".to_string()),
+                    ("status".to_string(), "text".to_string()),
+                ],
+                vec![],
+                vec!["id".to_string()],
+            )
+            .unwrap();
+
+        let query =
+            parse_query("UPDATE prepared_ks.t SET status = ? WHERE id = ?;".to_string()).unwrap();
+        let statement = PreparedStatement::new(query, &schema).unwrap();
+        assert_eq!(
+            statement.get_bind_markers().columns,
+            vec![
+                ("status".to_string(), DataType::Varchar),
+                ("id".to_string(), DataType::Int)
+            ]
+        );
+        assert_eq!(statement.get_pk_indexes(), &[1]);
+
+        let values = [
+            CqlValue::Text("DELAYED' WHERE id = 2;".to_string()).to_value(),
+            CqlValue::Int(1).to_value(),
+        ];
+        let expected = parse_query(
+            "UPDATE prepared_ks.t SET status = 'DELAYED'' WHERE id = 2;' WHERE id = 1;".to_string(),
+        )
+        .unwrap();
+        assert_eq!(statement.bind(&values).unwrap(), expected);
+        assert!(statement.bind(&values[..1]).is_err());
+
+        let query = parse_query("SELECT * FROM prepared_ks.t WHERE gate = ?;".to_string()).unwrap();
+        assert!(PreparedStatement::new(query, &schema).is_err());
+
+        let id = save_prepared_statement("UPDATE", None, statement.clone());
+        evict_prepared_statements(&EventKindChange::Schema(
+            SchemaChangeType::Updated,
+            "KEYSPACE".to_string(),
+            "prepared_ks".to_string(),
+        ));
+        assert!(get_prepared_statement(&id).is_some());
+        evict_prepared_statements(&EventKindChange::Schema(
+            SchemaChangeType::Updated,
+            "TABLE".to_string(),
+            "prepared_ks.t".to_string(),
+        ));
+        assert!(get_prepared_statement(&id).is_none());
+
+        let mut cache = PreparedCache::default();
+        for i in 0..=MAX_PREPARED_STATEMENTS {
+            cache.insert(vec![i as u8, (i >> 8) as u8], statement.clone());
+            if i == 1 {
+                cache.get(&[0, 0]);
+            }
+        }
+        assert_eq!(cache.statements.len(), MAX_PREPARED_STATEMENTS);
+        assert!(cache.get(&[0, 0]).is_some());
+        assert!(cache.get(&[1, 0]).is_none());
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
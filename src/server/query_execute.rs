@@ -1,21 +1,26 @@
 use native_tls::TlsStream;
 use std::{
-    collections::HashMap,
+    collections::{HashMap, VecDeque},
     fs,
-    io::{self, Read, Write},
+    io::{self, Write},
     net::TcpStream,
-    sync::{Arc, Mutex},
+    sync::{Arc, Mutex, OnceLock},
 };
 
 use crate::{
     errors::error_types::ErrorTypes,
     protocol::{
-        protocol_body::{compression::Compression, query_flags::QueryFlags},
-        protocol_notations::consistency::Consistency,
+        frames_headers::version::PROTOCOL_VERSION,
+        protocol_body::{
+            compression::Compression, data_type::DataType, error_code::ErrorCode,
+            event_kind::EventKindChange, query_flags::QueryFlags,
+        },
+        protocol_notations::{consistency::Consistency, cql_value::CqlValue},
         protocol_writer::Protocol,
     },
     receiver::{
-        receiver_impl::receive_message, response_message::ResponseMessage,
+        receiver_impl::{read_frame, receive_message},
+        response_message::ResponseMessage,
         result_response::ResultResponse,
     },
 };
@@ -100,7 +105,17 @@ pub fn insert_simulador(
     }
     for i in 0..2 {
         let table = if i == 0 { "departures" } else { "arrivals" };
-        querys.push(format!("INSERT INTO {} (id, status, origin, destination, departure_time, arrival_time, fuel, velocity, height, latitude, longitude, distance_traveled) VALUES ({}, 'ON TIME', '{}', '{}', '{}', '{}', {}, 0, 0, 0, 0, 0);", table, id, origin, destination, arrival_time, departure_time, fuel));
+        querys.push((
+            format!("INSERT INTO {} (id, status, origin, destination, departure_time, arrival_time, fuel, velocity, height, latitude, longitude, distance_traveled) VALUES (?, 'ON TIME', ?, ?, ?, ?, ?, 0, 0, 0, 0, 0);", table),
+            vec![
+                CqlValue::Int(id as i32),
+                CqlValue::Text(origin.clone()),
+                CqlValue::Text(destination.clone()),
+                CqlValue::parse(DataType::Date, &arrival_time)?,
+                CqlValue::parse(DataType::Date, &departure_time)?,
+                CqlValue::Float(fuel as f32),
+            ],
+        ));
     }
     send_querys(querys, server, compression)?;
     Ok(Flight::new(
@@ -196,49 +211,295 @@ pub fn conect_server(
             ));
         }
     }
-    let mut buffer = [0; 1024];
-    let read = server.read(&mut buffer);
-    if read.is_err() {
-        return Err(ErrorTypes::new(
-            520,
-            "Error receiving message from server".to_string(),
-        ));
+    // Los eventos llegan en cualquier momento, no responden al mensaje
+    loop {
+        let mut frame = read_frame(server, &mut Vec::new())
+            .map_err(|_| ErrorTypes::new(520, "Error receiving message from server".to_string()))?;
+        match receive_message(&mut frame, &compression.clone())? {
+            ReplyMessage(ResponseMessage::Event { .. }) => continue,
+            message => return Ok(message),
+        }
     }
-    if let Ok(read) = read {
-        receive_message(&mut buffer[0..read].to_vec(), &compression.clone())
-    } else {
-        Err(ErrorTypes::new(
-            1,
-            "Error receiving message from server".to_string(),
-        ))
+}
+
+/// This function registers the connection for the types of events, like `STATUS_CHANGE`, so the server sends them to
+/// it when they happen.
+pub fn register(
+    server: &mut TlsStream<TcpStream>,
+    compression: &Option<Compression>,
+    events: Vec<String>,
+) -> Result<(), ErrorTypes> {
+    let mut msg = Protocol::new();
+    msg.set_compress_algorithm(compression.clone());
+    msg.write_register(events)?;
+    match conect_server(server, Some(msg), compression)? {
+        ReplyMessage(ResponseMessage::Ready { .. }) => Ok(()),
+        ReplyMessage(ResponseMessage::Error { code: _, message }) => {
+            Err(ErrorTypes::new(529, message))
+        }
+        _ => Err(ErrorTypes::new(530, "Unexpected message".to_string())),
+    }
+}
+
+/// This function receives the events that the connection registered for, and calls `on_event` with every one of them
+/// until the server closes the connection.
+pub fn listen_events<F: FnMut(EventKindChange)>(
+    server: &mut TlsStream<TcpStream>,
+    compression: &Option<Compression>,
+    mut on_event: F,
+) -> Result<(), ErrorTypes> {
+    let mut pending = Vec::new();
+    loop {
+        let mut frame = read_frame(server, &mut pending)
+            .map_err(|_| ErrorTypes::new(520, "Error receiving message from server".to_string()))?;
+        if let ReplyMessage(ResponseMessage::Event { event }) =
+            receive_message(&mut frame, compression)?
+        {
+            on_event(event);
+        }
     }
 }
 
+/// This function executes the queries as one logged batch, with their values bound to their bind markers `?`, so either
+/// every query is applied or none is. The batch has to succeed without returning rows.
 pub fn send_querys(
-    querys: Vec<String>,
+    querys: Vec<(String, Vec<CqlValue>)>,
     server: Arc<Mutex<TlsStream<TcpStream>>>,
     compression: Option<Compression>,
 ) -> Result<(), ErrorTypes> {
     let mut server = server.lock().unwrap();
+    let message = execute_batch(&mut server, &compression, &querys, Consistency::Quorum)?;
 
-    for query in querys {
-        let mut msg = Protocol::new();
-        msg.set_compress_algorithm(compression.clone());
-        msg.write_query(&query, Consistency::Quorum, vec![QueryFlags::SkipMetadata])?;
-        let message = conect_server(&mut server, Some(msg), &compression)?;
+    let msg = match message {
+        ReplyMessage(ResponseMessage::Result {
+            kind: ResultResponse::Void,
+        }) => Ok(()),
+        ReplyMessage(_) => Err(ErrorTypes::new(522, "Unexpected message".to_string())),
+        _ => Err(ErrorTypes::new(523, "Error receiving message".to_string())),
+    };
+    msg.as_ref()
+        .map_err(|_| ErrorTypes::new(524, "Error receiving message".to_string()))?;
+
+    Ok(())
+}
+
+/// This function prepares a query with bind markers `?` in the server and returns the identifier of the statement.
+pub fn prepare(
+    server: &mut TlsStream<TcpStream>,
+    compression: &Option<Compression>,
+    query: &str,
+) -> Result<Vec<u8>, ErrorTypes> {
+    let mut msg = Protocol::new();
+    msg.set_compress_algorithm(compression.clone());
+    msg.write_prepare(query)?;
+    match conect_server(server, Some(msg), compression)? {
+        ReplyMessage(ResponseMessage::Result {
+            kind: ResultResponse::Prepared { id, .. },
+        }) => {
+            prepared_ids()
+                .lock()
+                .unwrap()
+                .insert(query.to_string(), id.clone());
+            Ok(id)
+        }
+        ReplyMessage(ResponseMessage::Error { code, message }) => Err(ErrorTypes::new(
+            563,
+            format!("Error preparing the query: {} {}", code, message),
+        )),
+        _ => Err(ErrorTypes::new(566, "Unexpected message".to_string())),
+    }
+}
+
+/// This function executes a query with `values` bound to its bind markers `?`, in their order, and returns the answer
+/// of the server. The query is prepared the first time it is executed, and again if the server does not have it.
+pub fn execute(
+    server: &mut TlsStream<TcpStream>,
+    compression: &Option<Compression>,
+    query: &str,
+    values: &[CqlValue],
+    consistency: Consistency,
+) -> Result<Message, ErrorTypes> {
+    execute_prepared(server, compression, query, |msg, id| {
+        msg.write_execute(id, consistency, values)
+    })
+}
+
+/// This function sends the Execute message that `write` writes with the identifier of the prepared query, and returns
+/// the answer of the server. The query is prepared the first time, and again if the server does not have it.
+fn execute_prepared<F>(
+    server: &mut TlsStream<TcpStream>,
+    compression: &Option<Compression>,
+    query: &str,
+    write: F,
+) -> Result<Message, ErrorTypes>
+where
+    F: Fn(&mut Protocol, &[u8]) -> Result<(), ErrorTypes>,
+{
+    let prepared = prepared_ids().lock().unwrap().get(query).cloned();
+    let id = match prepared {
+        Some(id) => id,
+        None => prepare(server, compression, query)?,
+    };
+    let mut msg = Protocol::new();
+    msg.set_compress_algorithm(compression.clone());
+    write(&mut msg, &id)?;
+    let message = conect_server(server, Some(msg), compression)?;
+    match message {
+        ReplyMessage(ResponseMessage::Error { code, .. })
+            if code == ErrorCode::Unprepared as i32 =>
+        {
+            let id = prepare(server, compression, query)?;
+            let mut msg = Protocol::new();
+            msg.set_compress_algorithm(compression.clone());
+            write(&mut msg, &id)?;
+            conect_server(server, Some(msg), compression)
+        }
+        message => Ok(message),
+    }
+}
+
+/// This struct iterates the rows of a select executed like in `execute`, whose result the server returns in pages of
+/// `page_size` rows. The next page is asked for when the rows of the previous one were read, with the paging state
+/// that the server returned with it, so the whole result is never received at once.
+///
+/// ### Fields:
+/// - `server`, `compression`, `query`, `values` and `consistency`: The select to execute, like in `execute`.
+/// - `page_size`: The amount of rows of each page.
+/// - `paging_state`: The position where the next page starts, or none if there are no more pages.
+/// - `columns`: The names of the selected columns, from the metadata of the first page.
+/// - `rows`: The rows of the page that were not read yet, with their values read as the types of their columns.
+/// - `started`: If the first page was asked for.
+pub struct PagedRows<'a> {
+    server: &'a mut TlsStream<TcpStream>,
+    compression: &'a Option<Compression>,
+    query: &'a str,
+    values: &'a [CqlValue],
+    consistency: Consistency,
+    page_size: i32,
+    paging_state: Option<Vec<u8>>,
+    columns: Vec<String>,
+    rows: VecDeque<Vec<CqlValue>>,
+    started: bool,
+}
 
-        let msg = match message {
+/// This function returns an iterator over the rows of a select, with `values` bound to its bind markers `?`, that
+/// receives them from the server in pages of `page_size` rows.
+pub fn execute_paged<'a>(
+    server: &'a mut TlsStream<TcpStream>,
+    compression: &'a Option<Compression>,
+    query: &'a str,
+    values: &'a [CqlValue],
+    consistency: Consistency,
+    page_size: i32,
+) -> PagedRows<'a> {
+    PagedRows {
+        server,
+        compression,
+        query,
+        values,
+        consistency,
+        page_size,
+        paging_state: None,
+        columns: Vec::new(),
+        rows: VecDeque::new(),
+        started: false,
+    }
+}
+
+impl PagedRows<'_> {
+    /// This function returns the names of the selected columns. They are known once the first row was read.
+    pub fn columns(&self) -> &[String] {
+        &self.columns
+    }
+
+    /// This function asks the server for the next page and keeps its rows, with the paging state of the page after it.
+    fn next_page(&mut self) -> Result<(), ErrorTypes> {
+        let (consistency, values, page_size) = (self.consistency, self.values, self.page_size);
+        let paging_state = self.paging_state.take();
+        let message = execute_prepared(self.server, self.compression, self.query, |msg, id| {
+            msg.write_execute_page(id, consistency, values, page_size, paging_state.as_deref())
+        })?;
+        match message {
             ReplyMessage(ResponseMessage::Result {
-                kind: ResultResponse::Void,
-            }) => Ok(()),
-            ReplyMessage(_) => Err(ErrorTypes::new(522, "Unexpected message".to_string())),
-            _ => Err(ErrorTypes::new(523, "Error receiving message".to_string())),
+                kind:
+                    ResultResponse::Rows {
+                        metadata,
+                        rows,
+                        paging_state,
+                    },
+            }) => {
+                if let Some(metadata) = metadata {
+                    self.columns = metadata.columns.into_iter().map(|(name, _)| name).collect();
+                }
+                self.rows.extend(rows);
+                self.paging_state = paging_state;
+                Ok(())
+            }
+            ReplyMessage(ResponseMessage::Error { code, message }) => Err(ErrorTypes::new(
+                567,
+                format!("Error reading the rows: {} {}", code, message),
+            )),
+            _ => Err(ErrorTypes::new(568, "Unexpected message".to_string())),
+        }
+    }
+}
+
+impl Iterator for PagedRows<'_> {
+    type Item = Result<Vec<CqlValue>, ErrorTypes>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.rows.is_empty() && (!self.started || self.paging_state.is_some()) {
+            self.started = true;
+            if let Err(e) = self.next_page() {
+                self.paging_state = None;
+                return Some(Err(e));
+            }
+        }
+        self.rows.pop_front().map(Ok)
+    }
+}
+
+/// This function executes the queries as one logged batch, each one with its values bound to its bind markers `?`, and
+/// returns the answer of the server. The queries are prepared like in `execute`.
+pub fn execute_batch(
+    server: &mut TlsStream<TcpStream>,
+    compression: &Option<Compression>,
+    querys: &[(String, Vec<CqlValue>)],
+    consistency: Consistency,
+) -> Result<Message, ErrorTypes> {
+    let mut statements = Vec::new();
+    for (query, values) in querys {
+        let prepared = prepared_ids().lock().unwrap().get(query).cloned();
+        let id = match prepared {
+            Some(id) => id,
+            None => prepare(server, compression, query)?,
         };
-        msg.as_ref()
-            .map_err(|_| ErrorTypes::new(524, "Error receiving message".to_string()))?;
+        statements.push((id, values.clone()));
+    }
+    let mut msg = Protocol::new();
+    msg.set_compress_algorithm(compression.clone());
+    msg.write_batch(true, &statements, consistency)?;
+    let message = conect_server(server, Some(msg), compression)?;
+    match message {
+        ReplyMessage(ResponseMessage::Error { code, .. })
+            if code == ErrorCode::Unprepared as i32 =>
+        {
+            for ((query, _), statement) in querys.iter().zip(statements.iter_mut()) {
+                statement.0 = prepare(server, compression, query)?;
+            }
+            let mut msg = Protocol::new();
+            msg.set_compress_algorithm(compression.clone());
+            msg.write_batch(true, &statements, consistency)?;
+            conect_server(server, Some(msg), compression)
+        }
+        message => Ok(message),
     }
+}
 
-    Ok(())
+/// This function returns the identifiers of the statements that this client prepared, by their query.
+fn prepared_ids() -> &'static Mutex<HashMap<String, Vec<u8>>> {
+    static PREPARED_IDS: OnceLock<Mutex<HashMap<String, Vec<u8>>>> = OnceLock::new();
+    PREPARED_IDS.get_or_init(|| Mutex::new(HashMap::new()))
 }
 
 pub fn use_keyspace(
@@ -265,10 +526,53 @@ pub fn use_keyspace(
     }
 }
 
+/// This function asks the server for the options it supports, with the values it supports for every one of them.
+pub fn options(
+    server: &mut TlsStream<TcpStream>,
+) -> Result<HashMap<String, Vec<String>>, ErrorTypes> {
+    let mut msg = Protocol::new();
+    msg.write_options();
+    match conect_server(server, Some(msg), &None)? {
+        ReplyMessage(ResponseMessage::Supported { options }) => Ok(options),
+        ReplyMessage(ResponseMessage::Error { code: _, message }) => {
+            Err(ErrorTypes::new(569, message))
+        }
+        _ => Err(ErrorTypes::new(570, "Unexpected message".to_string())),
+    }
+}
+
+/// This function starts the connection with the server, after checking that it supports the version of the native
+/// protocol of the client and the compression algorithm.
 pub fn startup(
     server: &mut TlsStream<TcpStream>,
     compression: Option<Compression>,
 ) -> Result<(), ErrorTypes> {
+    let supported = options(server)?;
+    let version = format!("{}/v{}", PROTOCOL_VERSION, PROTOCOL_VERSION);
+    if !supported
+        .get("PROTOCOL_VERSIONS")
+        .is_some_and(|versions| versions.contains(&version))
+    {
+        return Err(ErrorTypes::new(
+            571,
+            "The server does not support the protocol version".to_string(),
+        ));
+    }
+    if let Some(compression) = &compression {
+        let name = match compression {
+            Compression::Snappy => "snappy",
+            Compression::LZ4 => "lz4",
+        };
+        if !supported
+            .get("COMPRESSION")
+            .is_some_and(|algorithms| algorithms.iter().any(|algorithm| algorithm == name))
+        {
+            return Err(ErrorTypes::new(
+                572,
+                "The server does not support the compression algorithm".to_string(),
+            ));
+        }
+    }
     let mut msg = Protocol::new();
     msg.write_startup(compression)?;
     let startup = msg.get_binary();
@@ -313,7 +617,7 @@ pub fn authenticate(
     }
 }
 
-/// This function creates the tables in the database.
+/// This function creates the tables in the database, unless they already exist.
 pub fn create_tables(
     server: &mut TlsStream<TcpStream>,
     compression: Option<Compression>,
@@ -326,12 +630,12 @@ pub fn create_tables(
     for table in tables {
         let mut msg = Protocol::new();
         msg.set_compress_algorithm(compression.clone());
-        let query = format!("CREATE TABLE {} (id int, status text, origin text, destination text, arrival_time date, departure_time date, fuel float, velocity float, height float, latitude float, longitude float, distance_traveled float, PRIMARY KEY (({}), id, {}));", table.0, table.1, table.2);
+        let query = format!("CREATE TABLE IF NOT EXISTS {} (id int, status text, origin text, destination text, arrival_time date, departure_time date, fuel float, velocity float, height float, latitude float, longitude float, distance_traveled float, PRIMARY KEY (({}), id, {}));", table.0, table.1, table.2);
         msg.write_query(&query, Consistency::Quorum, vec![QueryFlags::SkipMetadata])?;
         let message = conect_server(server, Some(msg), &compression)?;
         match message {
             ReplyMessage(ResponseMessage::Result {
-                kind: ResultResponse::SchemaChange { .. },
+                kind: ResultResponse::SchemaChange { .. } | ResultResponse::Void,
             }) => continue,
             ReplyMessage(ResponseMessage::Result { .. }) => {
                 return Err(ErrorTypes::new(
@@ -353,7 +657,7 @@ pub fn create_tables(
     Ok(())
 }
 
-/// This function creates the keyspace in the database.
+/// This function creates the keyspace in the database, unless it already exists.
 pub fn create_keyspace(
     server: &mut TlsStream<TcpStream>,
     compression: Option<Compression>,
@@ -361,14 +665,14 @@ pub fn create_keyspace(
     let mut msg = Protocol::new();
     msg.set_compress_algorithm(compression.clone());
     msg.write_query(
-        "CREATE KEYSPACE flights_keyspace WITH REPLICATION = { 'replication_factor': 3};",
+        "CREATE KEYSPACE IF NOT EXISTS flights_keyspace WITH REPLICATION = { 'replication_factor': 3};",
         Consistency::Quorum,
         vec![QueryFlags::SkipMetadata],
     )?;
     let message = conect_server(server, Some(msg), &compression)?;
     match message {
         ReplyMessage(ResponseMessage::Result {
-            kind: ResultResponse::SchemaChange { .. },
+            kind: ResultResponse::SchemaChange { .. } | ResultResponse::Void,
         }) => Ok(()),
         ReplyMessage(ResponseMessage::Result { .. }) => {
             Err(ErrorTypes::new(537, "Unexpected message".to_string()))
@@ -0,0 +1,460 @@
+use super::snitch::Location;
+use crate::{
+    errors::error_types::ErrorTypes, protocol::protocol_notations::consistency::Consistency,
+};
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    fmt,
+    str::FromStr,
+};
+
+const CLASS: &str = "class";
+const REPLICATION_FACTOR: &str = "replication_factor";
+const SIMPLE_STRATEGY: &str = "SimpleStrategy";
+const NETWORK_TOPOLOGY_STRATEGY: &str = "NetworkTopologyStrategy";
+const STRATEGY_PACKAGE: &str = "org.apache.cassandra.locator";
+
+/// This enum represents how the replicas of a keyspace are placed in the ring.
+///
+/// - `Simple`: The replicas are the first `n` different nodes found walking the ring clockwise, no matter where they are.
+/// - `NetworkTopology`: Every datacenter keeps its own amount of replicas. Inside a datacenter the replicas are placed in
+///   different racks whenever it is possible.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum ReplicationStrategy {
+    Simple(usize),
+    NetworkTopology(BTreeMap<String, usize>),
+}
+
+impl ReplicationStrategy {
+    /// This function builds the strategy from the replication options of a `CREATE KEYSPACE` query.
+    ///
+    /// If there is no `class`, the options are taken as the ones of a `SimpleStrategy`.
+    pub fn from_options(options: &HashMap<String, String>) -> Result<Self, ErrorTypes> {
+        let class = options
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(CLASS))
+            .map(|(_, value)| value.as_str())
+            .unwrap_or(SIMPLE_STRATEGY);
+        let mut factors = options
+            .iter()
+            .filter(|(key, _)| !key.eq_ignore_ascii_case(CLASS));
+        match class.rsplit('.').next().unwrap_or(class) {
+            SIMPLE_STRATEGY => match (factors.next(), factors.next()) {
+                (Some((key, value)), None) if key.eq_ignore_ascii_case(REPLICATION_FACTOR) => {
+                    match parse_factor(key, value)? {
+                        0 => Err(invalid_options("The replication_factor must be positive")),
+                        rf => Ok(ReplicationStrategy::Simple(rf)),
+                    }
+                }
+                _ => Err(invalid_options(
+                    "SimpleStrategy only accepts the replication_factor option",
+                )),
+            },
+            NETWORK_TOPOLOGY_STRATEGY => {
+                let mut datacenters = BTreeMap::new();
+                for (datacenter, value) in factors {
+                    datacenters.insert(datacenter.to_string(), parse_factor(datacenter, value)?);
+                }
+                if datacenters.values().sum::<usize>() == 0 {
+                    return Err(invalid_options(
+                        "NetworkTopologyStrategy needs a replication factor for at least one datacenter",
+                    ));
+                }
+                Ok(ReplicationStrategy::NetworkTopology(datacenters))
+            }
+            _ => Err(invalid_options(&format!(
+                "Unknown replication strategy {}",
+                class
+            ))),
+        }
+    }
+
+    /// This function returns the replication options of the strategy, with the full name of its class, as
+    /// `system_schema.keyspaces` shows them.
+    pub fn to_options(&self) -> Vec<(String, String)> {
+        let (class, mut factors) = match self {
+            ReplicationStrategy::Simple(rf) => (
+                SIMPLE_STRATEGY,
+                vec![(REPLICATION_FACTOR.to_string(), rf.to_string())],
+            ),
+            ReplicationStrategy::NetworkTopology(datacenters) => (
+                NETWORK_TOPOLOGY_STRATEGY,
+                datacenters
+                    .iter()
+                    .map(|(datacenter, rf)| (datacenter.to_string(), rf.to_string()))
+                    .collect(),
+            ),
+        };
+        factors.insert(
+            0,
+            (CLASS.to_string(), format!("{}.{}", STRATEGY_PACKAGE, class)),
+        );
+        factors
+    }
+
+    /// This function returns the amount of replicas of every key, adding the ones of every datacenter.
+    pub fn total_factor(&self) -> usize {
+        match self {
+            ReplicationStrategy::Simple(rf) => *rf,
+            ReplicationStrategy::NetworkTopology(datacenters) => datacenters.values().sum(),
+        }
+    }
+
+    /// This function chooses the replicas of a range from the nodes found walking the ring clockwise from its token.
+    /// The replicas are returned in the order they were found, so the first one is the owner of the range.
+    pub fn select_replicas<'a>(
+        &self,
+        walk: impl Iterator<Item = &'a String>,
+        locations: &HashMap<String, Location>,
+    ) -> Vec<String> {
+        let mut nodes: Vec<&String> = Vec::new();
+        for node in walk {
+            if let ReplicationStrategy::Simple(rf) = self {
+                if nodes.len() == *rf {
+                    break;
+                }
+            }
+            if !nodes.contains(&node) {
+                nodes.push(node);
+            }
+        }
+        let datacenters = match self {
+            ReplicationStrategy::Simple(_) => {
+                return nodes.into_iter().map(|node| node.to_string()).collect()
+            }
+            ReplicationStrategy::NetworkTopology(datacenters) => datacenters,
+        };
+        let mut chosen: HashSet<&String> = HashSet::new();
+        for (datacenter, rf) in datacenters {
+            let candidates = nodes
+                .iter()
+                .filter(|node| location(locations, node).datacenter == *datacenter)
+                .collect::<Vec<&&String>>();
+            let mut racks = HashSet::new();
+            let mut selected: Vec<&String> = Vec::new();
+            for node in candidates.iter() {
+                if selected.len() < *rf && racks.insert(location(locations, node).rack) {
+                    selected.push(node);
+                }
+            }
+            for node in candidates.iter() {
+                if selected.len() < *rf && !selected.contains(node) {
+                    selected.push(node);
+                }
+            }
+            chosen.extend(selected);
+        }
+        nodes
+            .into_iter()
+            .filter(|node| chosen.contains(node))
+            .map(|node| node.to_string())
+            .collect()
+    }
+
+    /// This function returns the replicas that have to acknowledge a write for it to succeed with the consistency level,
+    /// keeping the order of `replicas`. The writes to the rest of the replicas can be done in the background.
+    ///
+    /// The levels that are local to a datacenter only count the replicas in `datacenter`, which is the one of the coordinator.
+    /// If there are not enough `replicas` the write is `Unavailable`.
+    pub fn block_for(
+        &self,
+        consistency: &Consistency,
+        replicas: &[String],
+        datacenter: &str,
+        locations: &HashMap<String, Location>,
+    ) -> Result<Vec<String>, ErrorTypes> {
+        let mut blocking: HashSet<&String> = HashSet::new();
+        for (candidates, needed) in self.requirements(consistency, replicas, datacenter, locations)
+        {
+            if candidates.len() < needed {
+                return Err(ErrorTypes::Unavailable {
+                    consistency: *consistency,
+                    required: needed,
+                    alive: candidates.len(),
+                });
+            }
+            blocking.extend(candidates.into_iter().take(needed));
+        }
+        Ok(replicas
+            .iter()
+            .filter(|replica| blocking.contains(replica))
+            .cloned()
+            .collect())
+    }
+
+    /// This function returns the groups of `replicas` that the consistency level counts, with the amount of
+    /// acknowledgements that every group needs. There is a group for every datacenter with `EACH_QUORUM`, and only one
+    /// for the rest of the levels.
+    pub fn requirements<'a>(
+        &self,
+        consistency: &Consistency,
+        replicas: &'a [String],
+        datacenter: &str,
+        locations: &HashMap<String, Location>,
+    ) -> Vec<(Vec<&'a String>, usize)> {
+        match consistency {
+            Consistency::Any | Consistency::One => vec![(replicas.iter().collect(), 1)],
+            Consistency::Two => vec![(replicas.iter().collect(), 2)],
+            Consistency::Three => vec![(replicas.iter().collect(), 3)],
+            Consistency::Quorum | Consistency::Serial => {
+                vec![(replicas.iter().collect(), quorum(self.total_factor()))]
+            }
+            Consistency::All => vec![(replicas.iter().collect(), self.total_factor())],
+            Consistency::LocalOne => {
+                vec![(self.datacenter_replicas(replicas, datacenter, locations), 1)]
+            }
+            Consistency::LocalQuorum | Consistency::LocalSerial => vec![(
+                self.datacenter_replicas(replicas, datacenter, locations),
+                quorum(self.datacenter_factor(datacenter)),
+            )],
+            Consistency::EachQuorum => match self {
+                ReplicationStrategy::Simple(rf) => vec![(replicas.iter().collect(), quorum(*rf))],
+                ReplicationStrategy::NetworkTopology(datacenters) => datacenters
+                    .iter()
+                    .filter(|(_, rf)| **rf > 0)
+                    .map(|(datacenter, rf)| {
+                        (
+                            self.datacenter_replicas(replicas, datacenter, locations),
+                            quorum(*rf),
+                        )
+                    })
+                    .collect(),
+            },
+        }
+    }
+
+    /// This function returns the replicas a read with the consistency level can use. The levels that are local to a
+    /// datacenter only use the replicas in `datacenter`, the rest use every replica starting with the ones in `datacenter`.
+    pub fn read_replicas(
+        &self,
+        consistency: &Consistency,
+        replicas: &[String],
+        datacenter: &str,
+        locations: &HashMap<String, Location>,
+    ) -> Vec<String> {
+        let local = self.datacenter_replicas(replicas, datacenter, locations);
+        let mut nodes = local.into_iter().cloned().collect::<Vec<String>>();
+        if !matches!(
+            consistency,
+            Consistency::LocalOne | Consistency::LocalQuorum | Consistency::LocalSerial
+        ) {
+            for replica in replicas {
+                if !nodes.contains(replica) {
+                    nodes.push(replica.to_string());
+                }
+            }
+        }
+        nodes
+    }
+
+    /// This function returns the amount of replicas a datacenter keeps. With a `SimpleStrategy` the whole cluster is taken as one datacenter.
+    fn datacenter_factor(&self, datacenter: &str) -> usize {
+        match self {
+            ReplicationStrategy::Simple(rf) => *rf,
+            ReplicationStrategy::NetworkTopology(datacenters) => {
+                datacenters.get(datacenter).copied().unwrap_or(0)
+            }
+        }
+    }
+
+    /// This function returns the replicas that belong to a datacenter. With a `SimpleStrategy` the whole cluster is taken as one datacenter.
+    fn datacenter_replicas<'a>(
+        &self,
+        replicas: &'a [String],
+        datacenter: &str,
+        locations: &HashMap<String, Location>,
+    ) -> Vec<&'a String> {
+        replicas
+            .iter()
+            .filter(|replica| match self {
+                ReplicationStrategy::Simple(_) => true,
+                ReplicationStrategy::NetworkTopology(_) => {
+                    location(locations, replica).datacenter == datacenter
+                }
+            })
+            .collect()
+    }
+}
+
+/// The strategy is stored as the replication factor for a `SimpleStrategy`, and as `datacenter=factor` pairs
+/// separated by commas for a `NetworkTopologyStrategy`.
+impl fmt::Display for ReplicationStrategy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReplicationStrategy::Simple(rf) => write!(f, "{}", rf),
+            ReplicationStrategy::NetworkTopology(datacenters) => write!(
+                f,
+                "{}",
+                datacenters
+                    .iter()
+                    .map(|(datacenter, rf)| format!("{}={}", datacenter, rf))
+                    .collect::<Vec<String>>()
+                    .join(",")
+            ),
+        }
+    }
+}
+
+impl FromStr for ReplicationStrategy {
+    type Err = ErrorTypes;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if !s.contains('=') {
+            return parse_factor(REPLICATION_FACTOR, s).map(ReplicationStrategy::Simple);
+        }
+        let mut datacenters = BTreeMap::new();
+        for pair in s.split(',') {
+            match pair.split_once('=') {
+                Some((datacenter, rf)) => {
+                    datacenters.insert(datacenter.to_string(), parse_factor(datacenter, rf)?);
+                }
+                None => return Err(invalid_options(&format!("Invalid replication {}", s))),
+            }
+        }
+        Ok(ReplicationStrategy::NetworkTopology(datacenters))
+    }
+}
+
+/// This function returns the amount of replicas that make a quorum.
+fn quorum(rf: usize) -> usize {
+    rf / 2 + 1
+}
+
+/// This function returns the location of a node, or the default one if it is not known yet.
+fn location(locations: &HashMap<String, Location>, node: &String) -> Location {
+    locations.get(node).cloned().unwrap_or_default()
+}
+
+/// This function parses the replication factor of an option.
+fn parse_factor(option: &str, value: &str) -> Result<usize, ErrorTypes> {
+    value.trim().parse::<usize>().map_err(|_| {
+        invalid_options(&format!(
+            "Invalid replication factor {} for {}",
+            value, option
+        ))
+    })
+}
+
+fn invalid_options(message: &str) -> ErrorTypes {
+    ErrorTypes::new(597, message.to_string())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn options(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+        pairs
+            .iter()
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect()
+    }
+
+    fn topology() -> (Vec<String>, HashMap<String, Location>) {
+        let nodes = [
+            ("127.0.0.1:8080", "dc1", "rack1"),
+            ("127.0.0.1:8081", "dc1", "rack1"),
+            ("127.0.0.1:8082", "dc2", "rack1"),
+            ("127.0.0.1:8083", "dc1", "rack2"),
+            ("127.0.0.1:8084", "dc2", "rack2"),
+        ];
+        let walk = nodes.iter().map(|(node, _, _)| node.to_string()).collect();
+        let locations = nodes
+            .iter()
+            .map(|(node, datacenter, rack)| (node.to_string(), Location::new(datacenter, rack)))
+            .collect();
+        (walk, locations)
+    }
+
+    #[test]
+    fn test_from_options() {
+        let simple = options(&[("replication_factor", "3")]);
+        assert_eq!(
+            ReplicationStrategy::from_options(&simple).unwrap(),
+            ReplicationStrategy::Simple(3)
+        );
+        let topology = options(&[
+            ("class", "NetworkTopologyStrategy"),
+            ("dc1", "2"),
+            ("dc2", "1"),
+        ]);
+        let strategy = ReplicationStrategy::from_options(&topology).unwrap();
+        assert_eq!(strategy.total_factor(), 3);
+        assert_eq!(
+            strategy.to_string().parse::<ReplicationStrategy>().unwrap(),
+            strategy
+        );
+        let shown = strategy
+            .to_options()
+            .into_iter()
+            .collect::<HashMap<String, String>>();
+        assert_eq!(
+            shown["class"],
+            "org.apache.cassandra.locator.NetworkTopologyStrategy"
+        );
+        assert_eq!(ReplicationStrategy::from_options(&shown).unwrap(), strategy);
+        assert!(ReplicationStrategy::from_options(&options(&[("class", "Foo")])).is_err());
+        assert!(
+            ReplicationStrategy::from_options(&options(&[("replication_factor", "0")])).is_err()
+        );
+    }
+
+    #[test]
+    fn test_rack_aware_replicas() {
+        let (walk, locations) = topology();
+        let strategy = ReplicationStrategy::NetworkTopology(BTreeMap::from([
+            ("dc1".to_string(), 2),
+            ("dc2".to_string(), 1),
+        ]));
+        let replicas = strategy.select_replicas(walk.iter(), &locations);
+        assert_eq!(
+            replicas,
+            vec!["127.0.0.1:8080", "127.0.0.1:8082", "127.0.0.1:8083"]
+        );
+        let simple = ReplicationStrategy::Simple(2).select_replicas(walk.iter(), &locations);
+        assert_eq!(simple, vec!["127.0.0.1:8080", "127.0.0.1:8081"]);
+    }
+
+    #[test]
+    fn test_block_for() {
+        let (walk, locations) = topology();
+        let strategy = ReplicationStrategy::NetworkTopology(BTreeMap::from([
+            ("dc1".to_string(), 2),
+            ("dc2".to_string(), 2),
+        ]));
+        let replicas = strategy.select_replicas(walk.iter(), &locations);
+        let local_quorum = strategy
+            .block_for(&Consistency::LocalQuorum, &replicas, "dc2", &locations)
+            .unwrap();
+        assert_eq!(local_quorum, vec!["127.0.0.1:8082", "127.0.0.1:8084"]);
+        let each_quorum = strategy
+            .block_for(&Consistency::EachQuorum, &replicas, "dc1", &locations)
+            .unwrap();
+        assert_eq!(each_quorum.len(), 4);
+        let local_one = strategy
+            .block_for(&Consistency::LocalOne, &replicas, "dc2", &locations)
+            .unwrap();
+        assert_eq!(local_one, vec!["127.0.0.1:8082"]);
+        assert_eq!(
+            strategy.block_for(&Consistency::LocalOne, &replicas, "dc3", &locations),
+            Err(ErrorTypes::Unavailable {
+                consistency: Consistency::LocalOne,
+                required: 1,
+                alive: 0
+            })
+        );
+        let each_quorum =
+            strategy.requirements(&Consistency::EachQuorum, &replicas[1..], "dc1", &locations);
+        assert_eq!(
+            each_quorum
+                .iter()
+                .map(|(candidates, needed)| (candidates.len(), *needed))
+                .collect::<Vec<(usize, usize)>>(),
+            vec![(1, 2), (2, 2)]
+        );
+        assert_eq!(
+            strategy.read_replicas(&Consistency::LocalOne, &replicas, "dc2", &locations),
+            vec!["127.0.0.1:8082", "127.0.0.1:8084"]
+        );
+    }
+}
@@ -1,44 +1,75 @@
 use crate::{
     errors::error_types::ErrorTypes,
     protocol::{
-        protocol_notations::consistency::Consistency,
-        query_parser::{clause::Clause, parser_impl::parse_conditions, relation::Relation},
+        protocol_body::data_type::DataType,
+        protocol_notations::{columns_metadata::ColumnsMetadata, consistency::Consistency},
+        query_parser::{clause::Clause, query::AlterTableOperation, relation::Relation},
     },
     server::{
         gossiper::get_gossiper,
         keyspace::Keyspace,
         log_type::LogType,
-        mem_table::{is_tombstone, MemTable},
+        mem_table::{field_filter, is_tombstone, newest_rows, MemTable},
         nodes::write_log_message,
+        sstable::sort_by_columns,
     },
 };
 use chrono::{DateTime, FixedOffset};
 
 use std::{
-    collections::{HashMap, HashSet},
-    fs::File,
+    collections::{BTreeMap, HashMap, HashSet},
+    fs::{self, File},
     io::Write,
     net::TcpStream,
     sync::{Arc, Mutex, MutexGuard},
     thread::{self},
+    time::Duration,
 };
 
 use super::{
     address::Address,
+    batchlog::remove_batch,
+    columntypes::ColumnTypes,
+    data_dir::{join, schema_path, table_dir},
     node_message::{NodeMessage, SchemaChange},
+    replication_strategy::ReplicationStrategy,
+    schema_definition::{is_dropped, prevails, schema_version, SchemaDefinition, TableSchema},
     selectquery::{self, SelectQuery},
+    streaming::{SourcedTasks, StreamTask},
 };
 use chksum_md5 as md5;
-use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use serde::{
+    de::{Error as DeError, IgnoredAny, SeqAccess, Visitor},
+    Deserialize, Deserializer, Serialize, Serializer,
+};
+use std::fmt;
+
+const INITIAL_SCHEMA: &str = "schema.json";
+const REQUEST_TIMEOUT: u64 = 10;
+
+/// The table, the conditions, the selected columns, the order and the limit of a select on a single partition.
+type SelectInfo = (
+    String,
+    Clause,
+    Vec<String>,
+    Vec<String>,
+    Option<usize>,
+    Option<Vec<String>>,
+);
 
 #[derive(Clone, Debug)]
 
-/// This struct represents the schema of the node. It contains the version, the keyspaces, the actual keyspace and the commit log.
+/// This struct represents the schema of the node. It contains the version, the keyspaces, when the dropped keyspaces and tables
+/// were dropped and the directory where the node stores its data.
+///
+/// The keyspace in use is not part of the schema: every client chooses its own one, so the tables are referred
+/// to by their fully qualified name `keyspace.table`.
 pub struct Schema {
-    version: i32,
+    version: String,
     keyspaces: HashMap<Keyspace, HashMap<String, Arc<Mutex<MemTable>>>>,
-    actual_keyspace: Option<Keyspace>,
+    dropped: BTreeMap<String, i64>,
     port: String,
+    data_dir: String,
 }
 
 impl Serialize for Schema {
@@ -61,7 +92,7 @@ impl Serialize for Schema {
             })
             .collect();
 
-        (&self.version, &keyspaces, &self.actual_keyspace).serialize(serializer)
+        (&self.version, &keyspaces, &self.dropped).serialize(serializer)
     }
 }
 
@@ -70,11 +101,7 @@ impl<'de> Deserialize<'de> for Schema {
     where
         D: Deserializer<'de>,
     {
-        let (version, keyspaces, actual_keyspace): (
-            i32,
-            HashMap<Keyspace, HashMap<String, MemTable>>,
-            Option<Keyspace>,
-        ) = Deserialize::deserialize(deserializer)?;
+        let (keyspaces, dropped) = deserializer.deserialize_seq(SchemaVisitor)?;
 
         // Reconstruir los `Arc<Mutex<MemTable>>` a partir de `MemTable`
         let keyspaces = keyspaces
@@ -88,113 +115,393 @@ impl<'de> Deserialize<'de> for Schema {
             })
             .collect();
 
-        Ok(Schema {
-            version,
+        let mut schema = Schema {
+            version: String::new(),
             keyspaces,
-            actual_keyspace,
+            dropped,
             port: "".to_string(),
-        })
+            data_dir: "".to_string(),
+        };
+        schema.update_version();
+        Ok(schema)
+    }
+}
+
+/// This visitor reads the keyspaces and the dropped keyspaces and tables of a stored schema, skipping its version.
+struct SchemaVisitor;
+
+impl<'de> Visitor<'de> for SchemaVisitor {
+    type Value = (
+        HashMap<Keyspace, HashMap<String, MemTable>>,
+        BTreeMap<String, i64>,
+    );
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a schema")
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
+    where
+        A: SeqAccess<'de>,
+    {
+        seq.next_element::<IgnoredAny>()?
+            .ok_or_else(|| A::Error::invalid_length(0, &self))?;
+        let keyspaces = seq
+            .next_element()?
+            .ok_or_else(|| A::Error::invalid_length(1, &self))?;
+        // Los schemas guardados por versiones anteriores tienen el keyspace en uso en lugar de los borrados.
+        let dropped = seq
+            .next_element::<serde_json::Value>()?
+            .and_then(|dropped| serde_json::from_value(dropped).ok())
+            .unwrap_or_default();
+        while seq.next_element::<IgnoredAny>()?.is_some() {}
+        Ok((keyspaces, dropped))
     }
 }
 
 impl Schema {
-    pub fn new(port: &String) -> Result<Schema, ErrorTypes> {
-        match Self::read_schema(port) {
+    /// This function loads the schema stored in `data_dir`, or creates an empty one if there is none.
+    pub fn new(port: &String, data_dir: &str) -> Result<Schema, ErrorTypes> {
+        match Self::read_schema(port, data_dir) {
             Ok(schema) => Ok(schema),
             _ => Ok(Schema {
-                version: 0,
+                version: schema_version(&SchemaDefinition::default()),
                 keyspaces: HashMap::new(),
-                actual_keyspace: None,
+                dropped: BTreeMap::new(),
                 port: port.to_string(),
+                data_dir: data_dir.to_string(),
             }),
         }
     }
-    /// This function is responsible for setting a keyspace in the node.
-    pub fn set_keyspace(&mut self, keyspace: &String) -> Result<Keyspace, ErrorTypes> {
-        for key in self.keyspaces.keys() {
-            if key.get_name() == keyspace {
-                self.actual_keyspace = Some(key.clone());
-                return Ok(key.clone());
-            }
+    /// This function returns the keyspace with the given name.
+    pub fn get_keyspace(&self, keyspace: &str) -> Result<Keyspace, ErrorTypes> {
+        match self.keyspaces.keys().find(|key| key.get_name() == keyspace) {
+            Some(key) => Ok(key.clone()),
+            None => Err(ErrorTypes::new(540, "Keyspace not found".to_string())),
         }
-        Err(ErrorTypes::new(540, "Keyspace not found".to_string()))
     }
 
-    pub fn set_id(&mut self, id: &String) {
-        self.port = id.to_string();
-        for (_, tables) in self.keyspaces.iter_mut() {
+    /// This function sets the port of the node and moves every table to its directory inside `data_dir`.
+    fn set_data_dir(&mut self, port: &String, data_dir: &str) {
+        self.port = port.to_string();
+        self.data_dir = data_dir.to_string();
+        for (keyspace, tables) in self.keyspaces.iter_mut() {
             for (name, table) in tables.iter_mut() {
-                table.lock().unwrap().set_id(id, name);
+                table
+                    .lock()
+                    .unwrap()
+                    .set_dir(&table_dir(data_dir, keyspace.get_name(), name));
             }
         }
     }
 
-    /// This function is responsible for incrementing the version of the schema.
-    fn increment_version(&mut self) {
-        self.version += 1;
+    /// This function is responsible for updating the version of the schema after its definition changes.
+    fn update_version(&mut self) {
+        self.version = schema_version(&self.definition());
     }
 
-    /// This function is responsible for getting the primary key of a table.
-    fn get_pk(&self, table_name: &str) -> Result<Vec<usize>, ErrorTypes> {
-        let k_s = match &self.actual_keyspace {
-            Some(k_s) => k_s,
-            _ => return Err(ErrorTypes::new(541, "Keyspace not selected".to_string())),
-        };
-        let hash_mt = match self.keyspaces.get(k_s) {
-            Some(hash_mt) => hash_mt,
-            _ => return Err(ErrorTypes::new(542, "Keyspace not found".to_string())),
-        };
-        match hash_mt.get(table_name) {
-            Some(table) => {
-                let table = table.lock().unwrap();
-                let p_k = table.get_pk().clone();
-                drop(table);
-                Ok(p_k)
+    /// This function returns the version of the schema, which is the same in every node that has the same keyspaces and tables.
+    pub fn get_version(&self) -> String {
+        self.version.clone()
+    }
+
+    /// This function returns the definition of every keyspace and table of the schema, and of the ones that were dropped.
+    pub fn definition(&self) -> SchemaDefinition {
+        let keyspaces = self
+            .keyspaces
+            .iter()
+            .map(|(keyspace, tables)| {
+                let tables = tables
+                    .iter()
+                    .map(|(name, table)| {
+                        (
+                            name.clone(),
+                            TableSchema::from_table(&table.lock().unwrap()),
+                        )
+                    })
+                    .collect();
+                (keyspace.get_name().to_string(), (keyspace.clone(), tables))
+            })
+            .collect();
+        SchemaDefinition {
+            keyspaces,
+            dropped: self.dropped.clone(),
+        }
+    }
+
+    /// This function adds the keyspaces and tables of the schema of another node that the local schema does not have,
+    /// drops the ones that the other node dropped, and saves the schema if it changed. It returns whether the local schema changed.
+    ///
+    /// If both schemas define the same keyspace or table differently, every node keeps the same definition.
+    pub fn merge_schema(&mut self, definition: SchemaDefinition) -> Result<bool, ErrorTypes> {
+        let previous = self.version.clone();
+        for (name, dropped_at) in definition.dropped {
+            self.add_dropped(name, dropped_at);
+        }
+        for (name, (keyspace, tables)) in definition.keyspaces {
+            if is_dropped(&self.dropped, &name, None, keyspace.created_at) {
+                continue;
             }
-            _ => Err(ErrorTypes::new(543, "Table not found".to_string())),
+            let local = self
+                .keyspaces
+                .keys()
+                .find(|k| k.get_name() == name)
+                .cloned();
+            let local = match local {
+                Some(local)
+                    if local != keyspace
+                        && prevails(
+                            (&local, local.changed_at()),
+                            (&keyspace, keyspace.changed_at()),
+                        ) =>
+                {
+                    let tables = self.keyspaces.remove(&local).unwrap_or_default();
+                    self.keyspaces.insert(keyspace.clone(), tables);
+                    keyspace
+                }
+                Some(local) => local,
+                None => {
+                    self.keyspaces.insert(keyspace.clone(), HashMap::new());
+                    keyspace
+                }
+            };
+            let data_dir = self.data_dir.clone();
+            let dropped = &self.dropped;
+            let local_tables = self.keyspaces.entry(local.clone()).or_default();
+            for (table_name, table) in tables {
+                if is_dropped(dropped, &name, Some(&table_name), table.created_at) {
+                    continue;
+                }
+                let replace = match local_tables.get(&table_name) {
+                    Some(local_table) => {
+                        let mut local_table = local_table.lock().unwrap();
+                        let local_schema = TableSchema::from_table(&local_table);
+                        let prevails = local_schema != table
+                            && prevails(
+                                (&local_schema, local_schema.changed_at()),
+                                (&table, table.changed_at()),
+                            );
+                        // Una tabla alterada en otro nodo conserva sus filas.
+                        if prevails && local_schema.created_at == table.created_at {
+                            local_table.alter_columns(table.columns_type.clone())?;
+                            local_table.altered_at = table.altered_at;
+                            false
+                        } else {
+                            prevails
+                        }
+                    }
+                    None => true,
+                };
+                if replace {
+                    let dir = table_dir(&data_dir, local.get_name(), &table_name);
+                    local_tables.insert(table_name, Arc::new(Mutex::new(table.to_table(dir))));
+                }
+            }
+        }
+        self.update_version();
+        if self.version == previous {
+            return Ok(false);
         }
+        self.save_schema()?;
+        Ok(true)
+    }
+
+    /// This function applies a change of the schema that was made in another node, or in the local one.
+    /// Dropping a keyspace or table that the node does not have only remembers that it was dropped.
+    pub fn apply_schema_change(&mut self, change: SchemaChange) -> Result<(), ErrorTypes> {
+        match change {
+            SchemaChange::DropKeyspace(name, dropped_at)
+            | SchemaChange::DropTable(name, dropped_at) => {
+                self.add_dropped(name, dropped_at);
+                self.save_schema()
+            }
+            SchemaChange::Truncate(table_name) => match self.get_table(&table_name) {
+                Ok(table) => table.lock().unwrap().truncate(),
+                Err(_) => Ok(()),
+            },
+        }
+    }
+
+    /// This function remembers that a keyspace (`keyspace`) or table (`keyspace.table`) was dropped at `dropped_at`,
+    /// and drops it if the schema has it.
+    fn add_dropped(&mut self, name: String, dropped_at: i64) {
+        let local = self.dropped.entry(name).or_insert(dropped_at);
+        *local = (*local).max(dropped_at);
+        self.remove_dropped();
+        self.update_version();
+    }
+
+    /// This function removes every keyspace and table that was dropped after it was created, deleting their files.
+    fn remove_dropped(&mut self) {
+        let data_dir = self.data_dir.clone();
+        let dropped = &self.dropped;
+        self.keyspaces.retain(|keyspace, tables| {
+            if is_dropped(dropped, keyspace.get_name(), None, keyspace.created_at) {
+                let _ = fs::remove_dir_all(join(&data_dir, keyspace.get_name()));
+                return false;
+            }
+            tables.retain(|name, table| {
+                let mut table = table.lock().unwrap();
+                if is_dropped(dropped, keyspace.get_name(), Some(name), table.created_at) {
+                    let _ = table.truncate();
+                    return false;
+                }
+                true
+            });
+            true
+        });
     }
-    ///This function is responsible for creating a table in the node.
+
+    /// This function returns whether the schema has a table, whose name is qualified with its keyspace.
+    pub fn contains_table(&self, table_name: &str) -> bool {
+        self.get_table(table_name).is_ok()
+    }
+
+    /// This function returns the metadata of the columns of the rows of a select, whose names are `header`, with the
+    /// types they have in the table.
+    pub fn get_rows_metadata(
+        &self,
+        table_name: &str,
+        header: &[String],
+    ) -> Result<ColumnsMetadata, ErrorTypes> {
+        let table = self.get_table(table_name)?.lock().unwrap();
+        let columns = header
+            .iter()
+            .map(|name| {
+                let column_type = table
+                    .columns_type
+                    .iter()
+                    .find(|(column, _)| column == name)
+                    .map_or("text", |(_, column_type)| column_type);
+                (name.to_string(), DataType::from_name(column_type))
+            })
+            .collect();
+        Ok(ColumnsMetadata::new(table_name, columns))
+    }
+
+    /// This function returns the columns of the partition key and the clustering key of a table, in that order.
+    pub fn get_primary_key(&self, table_name: &str) -> Result<Vec<String>, ErrorTypes> {
+        let table = self.get_table(table_name)?.lock().unwrap();
+        Ok(table
+            .get_primary_key()
+            .into_iter()
+            .map(|(column, _)| column)
+            .collect())
+    }
+
+    /// This function returns the columns of a table, whose name is qualified with its keyspace, with their types, and
+    /// the columns of its partition key.
+    pub fn get_columns(&self, table_name: &str) -> Result<(ColumnTypes, Vec<String>), ErrorTypes> {
+        let table = self.get_table(table_name)?.lock().unwrap();
+        let partition_key = table
+            .get_partition_key()
+            .into_iter()
+            .map(|(column, _)| column)
+            .collect();
+        Ok((table.columns_type.clone(), partition_key))
+    }
+
+    /// This function is responsible for getting the primary key of a table.
+    fn get_pk(&self, table_name: &str) -> Result<Vec<usize>, ErrorTypes> {
+        let table = self.get_table(table_name)?.lock().unwrap();
+        let p_k = table.get_pk().clone();
+        drop(table);
+        Ok(p_k)
+    }
+    ///This function is responsible for creating a table in the node. The name of the table is qualified with its keyspace.
+    ///It fails if the keyspace already has a table with that name.
     pub fn create_table(
         &mut self,
-        table_name: &String,
+        table_name: &str,
         columns_type: Vec<(String, String)>,
         clustering_key: Vec<String>,
         primary_key: Vec<String>,
-        port: String,
     ) -> Result<MemTable, ErrorTypes> {
-        if self.actual_keyspace.is_some() {
-            match self
-                .keyspaces
-                .get_mut(&self.actual_keyspace.clone().unwrap())
-            {
-                Some(keyspaces) => {
-                    let memtable = MemTable::new(
-                        columns_type,
-                        primary_key,
-                        table_name.clone(),
-                        clustering_key,
-                        port,
-                    );
-                    keyspaces.insert(
-                        table_name.to_string(),
-                        Arc::new(Mutex::new(memtable.clone())),
-                    );
-                    self.increment_version();
-                    Ok(memtable)
+        let (keyspace, table_name) = self.split_table_name(table_name)?;
+        let (keyspace, table_name) = (keyspace.clone(), table_name.to_string());
+        match self.keyspaces.get_mut(&keyspace) {
+            Some(keyspaces) if keyspaces.contains_key(&table_name) => {
+                Err(ErrorTypes::new(557, "Table already exists".to_string()))
+            }
+            Some(keyspaces) => {
+                let memtable = MemTable::new(
+                    columns_type,
+                    primary_key,
+                    table_name.clone(),
+                    clustering_key,
+                    table_dir(&self.data_dir, keyspace.get_name(), &table_name),
+                );
+                keyspaces.insert(table_name, Arc::new(Mutex::new(memtable.clone())));
+                self.update_version();
+                Ok(memtable)
+            }
+            None => Err(ErrorTypes::new(544, "Keyspace not found".to_string())),
+        }
+    }
+
+    /// This function adds a column to a table, or drops one of its columns. The name of the table is qualified with its keyspace.
+    /// The rows that the table already has read the new column as null.
+    pub fn alter_table(
+        &mut self,
+        table_name: &str,
+        operation: AlterTableOperation,
+    ) -> Result<(), ErrorTypes> {
+        let mut table = self.get_table(table_name)?.lock().unwrap();
+        let mut columns_type = table.columns_type.clone();
+        match operation {
+            AlterTableOperation::Add(column, column_type) => {
+                if table.columns.contains(&column) {
+                    return Err(ErrorTypes::new(541, "Column already exists".to_string()));
                 }
-                None => Err(ErrorTypes::new(544, "Keyspace not found".to_string())),
+                columns_type.push((column, column_type));
+            }
+            AlterTableOperation::Drop(column) => {
+                if !table.columns.contains(&column) {
+                    return Err(ErrorTypes::new(542, "Column not found".to_string()));
+                }
+                if table
+                    .get_primary_key()
+                    .iter()
+                    .any(|(key, _)| *key == column)
+                {
+                    return Err(ErrorTypes::new(
+                        545,
+                        "Primary key columns can not be dropped".to_string(),
+                    ));
+                }
+                columns_type.retain(|(name, _)| *name != column);
             }
-        } else {
-            Err(ErrorTypes::new(545, "Keyspace not selected".to_string()))
         }
+        table.alter_columns(columns_type)?;
+        // La alteracion tiene que ser posterior a la version anterior aunque ocurra en el mismo milisegundo.
+        table.altered_at = chrono::Utc::now()
+            .timestamp_millis()
+            .max(table.created_at.max(table.altered_at) + 1);
+        drop(table);
+        self.update_version();
+        Ok(())
+    }
+
+    /// This function changes the replication strategy of a keyspace, returning the previous one.
+    pub fn alter_keyspace(
+        &mut self,
+        keyspace_name: &str,
+        replication: ReplicationStrategy,
+    ) -> Result<ReplicationStrategy, ErrorTypes> {
+        let mut keyspace = self.get_keyspace(keyspace_name)?;
+        let tables = self.keyspaces.remove(&keyspace).unwrap_or_default();
+        let previous = keyspace.replication.clone();
+        keyspace.alter(replication);
+        self.keyspaces.insert(keyspace, tables);
+        self.update_version();
+        Ok(previous)
     }
 
     /// This function is responsible for creating a keyspace in the node.
     pub fn create_keyspace(
         &mut self,
         keyspace_name: &String,
-        replication: usize,
+        replication: ReplicationStrategy,
     ) -> Result<Keyspace, ErrorTypes> {
         for key in self.keyspaces.keys() {
             if key.get_name() == keyspace_name {
@@ -203,7 +510,7 @@ impl Schema {
         }
         let new_keyspace = Keyspace::new(keyspace_name.to_string(), replication);
         self.keyspaces.insert(new_keyspace.clone(), HashMap::new());
-        self.increment_version();
+        self.update_version();
         Ok(new_keyspace)
     }
 
@@ -214,8 +521,9 @@ impl Schema {
         conditions: Clause,
         address: String,
         consistency: Consistency,
+        timestamp: &str,
     ) -> Result<(), ErrorTypes> {
-        let replication = self.get_replication()?;
+        let replication = self.get_replication(&table_name)?;
         let table = self.get_table(&table_name)?;
 
         let p_k = table
@@ -225,199 +533,286 @@ impl Schema {
             .iter()
             .map(|(x, _)| x.clone())
             .collect::<Vec<String>>();
-        let mut result: Vec<&String> = Vec::new();
-        search_pk(&conditions, &mut result, &p_k);
+        let partitions = match search_partitions(&conditions, &p_k) {
+            Some(partitions) => partitions,
+            None => return Err(ErrorTypes::new(547, "Error getting node".to_string())),
+        };
         let gossiper = get_gossiper();
-        let shared_table = Arc::clone(table);
-        if let (Some(node), key) = gossiper.get_node(result) {
-            update(
+        for partition in partitions {
+            let (node, key) = gossiper.get_node(partition.iter().collect());
+            if node.is_none() {
+                return Err(ErrorTypes::new(547, "Error getting node".to_string()));
+            }
+            let conditions = restrict_partition(&conditions, &p_k, partition);
+            let (address_clone, table_name, column_value, timestamp) = (
                 address.clone(),
-                key,
                 table_name.clone(),
                 column_value.clone(),
-                conditions.clone(),
-                &node,
-                Arc::clone(table),
+                timestamp.to_string(),
             );
-            let replicas = gossiper.get_replicas(key, replication, &node)?;
-
-            if consistency == Consistency::One {
-                thread::spawn(move || {
-                    for node in replicas.iter() {
-                        update(
-                            address.clone(),
-                            key,
-                            table_name.clone(),
-                            column_value.clone(),
-                            conditions.clone(),
-                            node,
-                            Arc::clone(&shared_table),
-                        );
-                    }
-                });
-                return Ok(());
-            }
-
-            if consistency == Consistency::Quorum {
-                let mut replicas_completed = 0;
-                let needed = (replication - 1) / 2 + if (replication - 1) % 2 == 0 { 0 } else { 1 };
-                for node in replicas.iter() {
-                    if replicas_completed == needed {
-                        break;
-                    } else {
-                        update(
-                            address.clone(),
-                            key,
-                            table_name.clone(),
-                            column_value.clone(),
-                            conditions.clone(),
-                            node,
-                            Arc::clone(table),
-                        );
-                    }
-                    replicas_completed += 1;
-                }
-                let address_clone = address.clone();
-                let table_name = table_name.clone();
-                thread::spawn(move || {
-                    for node in replicas[replicas_completed..].iter() {
-                        update(
-                            address_clone.clone(),
-                            key,
-                            table_name.clone(),
-                            column_value.clone(),
-                            conditions.clone(),
-                            node,
-                            Arc::clone(&shared_table),
-                        );
-                    }
-                });
-                return Ok(());
-            }
+            let table = Arc::clone(table);
+            write_to_replicas(key, &replication, &consistency, &address, move |node| {
+                update(
+                    &address_clone,
+                    (key, &timestamp),
+                    &table_name,
+                    &column_value,
+                    &conditions,
+                    node,
+                    &table,
+                )
+            })?;
         }
-        Err(ErrorTypes::new(547, "Error getting node".to_string()))
+        Ok(())
     }
 
     /// This function is responsible for executing the select query.
+    ///
+    /// If the conditions restrict the partition key to several values, with `IN` or `OR`, every partition is read from its own replicas
+    /// and the rows are merged, sorted as the query asks. If they do not restrict the partition key, the ring has to be scanned
+    /// with `select_ranges`.
+    ///
+    /// `limits` are the `LIMIT` and the `PER PARTITION LIMIT` of the query. Every partition is read with the smallest of them.
+    ///
+    /// With `after`, the primary key of the last row of the previous page, the partitions before its partition are not
+    /// read and only the rows after it are read from its partition.
     pub fn execute_select(
         &mut self,
         info_select: (String, Clause, Vec<String>, Vec<String>),
+        limits: (Option<usize>, Option<usize>),
+        after: Option<Vec<String>>,
         address: &Address,
         consistency: Consistency,
     ) -> Result<Vec<Vec<String>>, ErrorTypes> {
-        let replication = self.get_replication()?;
-        let table = self.get_table(&info_select.0)?;
-        let table_lock = table.lock().unwrap();
-        let p_k = table_lock
+        let p_k = self
+            .get_table(&info_select.0)?
+            .lock()
+            .unwrap()
             .get_partition_key()
             .iter()
             .map(|(x, _)| x.clone())
             .collect::<Vec<String>>();
-        let mut result: Vec<&String> = Vec::new();
-        search_pk(&info_select.1, &mut result, &p_k);
+        let gossiper = get_gossiper();
+        let partitions = match search_partitions(&info_select.1, &p_k) {
+            Some(partitions) => partitions,
+            None => {
+                return Err(ErrorTypes::new(
+                    551,
+                    "The conditions do not restrict the partition key".to_string(),
+                ))
+            }
+        };
+        let (table_name, conditions, selected_columns, order) = info_select;
+        let (limit, per_partition_limit) = limits;
+        let partition_limit = match (limit, per_partition_limit) {
+            (Some(limit), Some(per_partition_limit)) => Some(limit.min(per_partition_limit)),
+            _ => limit.or(per_partition_limit),
+        };
+        if partitions.len() == 1 {
+            let (_, key) = gossiper.get_node(partitions[0].iter().collect());
+            let info_partition = (
+                table_name,
+                conditions,
+                selected_columns,
+                order,
+                partition_limit,
+                after,
+            );
+            let mut rows = self.select_partition(info_partition, key, address, &consistency)?;
+            truncate_rows(&mut rows, limit);
+            return Ok(rows);
+        }
+        let start = after.as_ref().map(|after| {
+            partitions
+                .iter()
+                .position(|partition| after.starts_with(partition))
+                .unwrap_or_default()
+        });
+        let mut rows: Vec<Vec<String>> = Vec::new();
+        for (i, partition) in partitions.into_iter().enumerate() {
+            if start.is_some_and(|start| i < start) {
+                continue;
+            }
+            let (_, key) = gossiper.get_node(partition.iter().collect());
+            let info_partition = (
+                table_name.clone(),
+                restrict_partition(&conditions, &p_k, partition),
+                selected_columns.clone(),
+                order.clone(),
+                partition_limit,
+                after.clone().filter(|_| start == Some(i)),
+            );
+            let mut partition_rows =
+                self.select_partition(info_partition, key, address, &consistency)?;
+            if partition_rows.is_empty() {
+                continue;
+            }
+            let header = partition_rows.remove(0);
+            if rows.is_empty() {
+                rows.push(header);
+            }
+            rows.extend(partition_rows);
+        }
+        if rows.is_empty() {
+            rows.push(self.select_header(&table_name, &selected_columns)?);
+        }
+        if !order.is_empty() && rows.len() > 1 {
+            let header = rows.remove(0);
+            rows = sort_by_columns(&order, rows, &header).map_err(|_| {
+                ErrorTypes::new(
+                    550,
+                    "The columns of ORDER BY have to be selected to sort the rows of several partitions"
+                        .to_string(),
+                )
+            })?;
+            rows.insert(0, header);
+        }
+        truncate_rows(&mut rows, limit);
+        Ok(rows)
+    }
+
+    /// This function returns the header of the rows of a select, which are the selected columns or every column of the table.
+    fn select_header(
+        &self,
+        table_name: &str,
+        selected_columns: &[String],
+    ) -> Result<Vec<String>, ErrorTypes> {
+        if selected_columns == ["*"] {
+            return Ok(self.get_table(table_name)?.lock().unwrap().columns.clone());
+        }
+        Ok(selected_columns.to_vec())
+    }
+
+    /// This function tells if a select on the table with the conditions has to scan the whole ring filtering the rows,
+    /// which needs `ALLOW FILTERING`. Reading every row of a table does not filter them.
+    pub fn needs_filtering(
+        &self,
+        table_name: &str,
+        conditions: &Clause,
+    ) -> Result<bool, ErrorTypes> {
+        Ok(*conditions != Clause::Placeholder && self.scans_ring(table_name, conditions)?)
+    }
+
+    /// This function tells if a select on the table with the conditions has to read every range of the ring,
+    /// because the conditions do not restrict the partition key.
+    pub fn scans_ring(&self, table_name: &str, conditions: &Clause) -> Result<bool, ErrorTypes> {
+        let p_k = self
+            .get_table(table_name)?
+            .lock()
+            .unwrap()
+            .get_partition_key()
+            .iter()
+            .map(|(x, _)| x.clone())
+            .collect::<Vec<String>>();
+        Ok(search_partitions(conditions, &p_k).is_none())
+    }
+
+    /// This function reads the rows of a single partition, whose token is `key`, from its replicas.
+    fn select_partition(
+        &self,
+        info_select: SelectInfo,
+        key: u128,
+        address: &Address,
+        consistency: &Consistency,
+    ) -> Result<Vec<Vec<String>>, ErrorTypes> {
+        let replication = self.get_replication(&info_select.0)?;
+        let table = self.get_table(&info_select.0)?;
+        let table_lock = table.lock().unwrap();
         let mut rows: Vec<Vec<String>> = Vec::new();
         let mut found = false;
         let gossiper = get_gossiper();
-        if let (Some(node), key) = gossiper.get_node(result) {
-            let mut replicas = gossiper.get_replicas(key, replication, &node)?;
-            replicas.insert(0, node.clone());
-            let mut node = node;
-            for replica in replicas.iter() {
-                if gossiper.is_down(replica) {
-                    continue;
-                }
-                let query = SelectQuery {
-                    conditions: &info_select.1,
-                    selected_columns: &info_select.2,
-                    order: &info_select.3,
-                    table_name: &info_select.0,
-                    needs_ts: true,
-                    needs_tb: true,
-                };
+        let replicas = gossiper.get_replicas(key, &replication)?;
+        let datacenter = gossiper.get_location(&address.i_address).datacenter;
+        let locations = gossiper.get_locations();
+        let blocking = replication.block_for(consistency, &replicas, &datacenter, &locations)?;
+        let replicas = replication.read_replicas(consistency, &replicas, &datacenter, &locations);
+        let limit = info_select.4;
+        let mut info_select = info_select;
+        if blocking.len() > 1 {
+            // Las replicas pueden tener distintas lapidas en la particion, asi que se leen completas y el limite se
+            // aplica despues de unir sus filas.
+            info_select.4 = None;
+        }
+        let mut node = String::new();
+        for replica in replicas.iter() {
+            if gossiper.is_down(replica) {
+                continue;
+            }
+            let query = SelectQuery {
+                conditions: &info_select.1,
+                selected_columns: &info_select.2,
+                order: &info_select.3,
+                table_name: &info_select.0,
+                needs_ts: true,
+                needs_tb: true,
+                limit: info_select.4,
+                after: info_select.5.as_deref(),
+            };
 
-                match select(address.clone(), replica, &table_lock, query) {
-                    Ok(rows_) => {
-                        rows = rows_;
-                        found = true;
-                        node = replica.to_string();
-                        break;
-                    }
-                    Err(_) => {
-                        continue;
-                    }
+            match select(address.clone(), replica, &table_lock, query) {
+                Ok(rows_) => {
+                    rows = rows_;
+                    found = true;
+                    node = replica.to_string();
+                    break;
                 }
-            }
-            if !found {
-                return Err(ErrorTypes::new(548, "Unrecheable data".to_string()));
-            }
-            drop(table_lock);
-            if consistency == Consistency::One {
-                let rows_clone = rows.clone();
-                let node_clone = node.clone();
-                let table = Arc::clone(table);
-                let i_adr = address.i_address.clone();
-                thread::spawn(move || {
-                    check_read_repair(
-                        info_select,
-                        rows_clone,
-                        i_adr,
-                        &node_clone,
-                        key,
-                        table,
-                        replicas.clone(),
-                    )
-                });
-                let mut send_rows = Vec::new();
-                send_rows.push(rows.remove(0));
-                for mut row in rows {
-                    if is_tombstone(&row) {
-                        continue;
-                    }
-                    row.pop();
-                    send_rows.push(row);
+                Err(_) => {
+                    continue;
                 }
-                return Ok(send_rows);
             }
-            if consistency == Consistency::Quorum {
-                let rows_clone = rows.clone();
-                let table = Arc::clone(table);
-                let replicas_clone = replicas.clone();
-                let failed = check_read_repair(
-                    info_select.clone(),
-                    rows_clone.clone(),
-                    address.i_address.clone(),
-                    &node,
+        }
+        if !found {
+            return Err(ErrorTypes::new(548, "Unrecheable data".to_string()));
+        }
+        drop(table_lock);
+        if blocking.len() == 1 {
+            let rows_clone = rows.clone();
+            let node_clone = node.clone();
+            let table = Arc::clone(table);
+            let i_adr = address.i_address.clone();
+            thread::spawn(move || {
+                check_read_repair(
+                    info_select,
+                    rows_clone,
+                    i_adr,
+                    &node_clone,
                     key,
-                    Arc::clone(&table),
+                    table,
                     replicas.clone(),
-                );
-                if failed == 0 {
-                    let mut send_rows = Vec::new();
-                    send_rows.push(rows.remove(0));
-                    for mut row in rows {
-                        if is_tombstone(&row) {
-                            continue;
-                        }
-                        row.pop();
-                        send_rows.push(row);
-                    }
-                    return Ok(send_rows);
-                } else {
-                    write_log_message(&address.i_port, LogType::Info, "Read repair".to_string());
-
-                    return read_repair(
-                        rows,
-                        replicas_clone,
-                        &node,
-                        info_select,
-                        address,
-                        &table,
-                        key,
-                    );
-                }
+                )
+            });
+            Ok(live_rows(rows, limit))
+        } else {
+            let rows_clone = rows.clone();
+            let table = Arc::clone(table);
+            let replicas_clone = replicas.clone();
+            let failed = check_read_repair(
+                info_select.clone(),
+                rows_clone.clone(),
+                address.i_address.clone(),
+                &node,
+                key,
+                Arc::clone(&table),
+                replicas.clone(),
+            );
+            if failed == 0 {
+                Ok(live_rows(rows, limit))
+            } else {
+                write_log_message(&address.i_port, LogType::Info, "Read repair".to_string());
+
+                let mut rows = read_repair(
+                    rows,
+                    replicas_clone,
+                    &node,
+                    info_select,
+                    address,
+                    &table,
+                    key,
+                )?;
+                truncate_rows(&mut rows, limit);
+                Ok(rows)
             }
         }
-        Err(ErrorTypes::new(530, "Error getting node".to_string()))
     }
 
     pub fn execute_delete(
@@ -426,8 +821,9 @@ impl Schema {
         conditions: Clause,
         address: String,
         consistency: Consistency,
+        timestamp: &str,
     ) -> Result<(), ErrorTypes> {
-        let replication = self.get_replication()?;
+        let replication = self.get_replication(&table_name)?;
         let table = self.get_table(&table_name)?;
         let gossiper = get_gossiper();
 
@@ -439,72 +835,33 @@ impl Schema {
             .map(|(x, _)| x.clone())
             .collect::<Vec<String>>();
 
-        let mut result: Vec<&String> = Vec::new();
-        search_pk(&conditions, &mut result, &p_k);
-
-        if let (Some(node), key) = gossiper.get_node(result) {
-            delete(
-                address.clone(),
-                table_name.clone(),
-                conditions.clone(),
-                &node,
-                Arc::clone(table),
-            );
-
-            let replicas = gossiper.get_replicas(key, replication, &node)?;
-            let shared_table = Arc::clone(table);
-            let address_clone = address.clone();
-            let table_name = table_name.clone();
-            if consistency == Consistency::One {
-                thread::spawn(move || {
-                    for node in replicas.iter() {
-                        delete(
-                            address_clone.clone(),
-                            table_name.clone(),
-                            conditions.clone(),
-                            node,
-                            Arc::clone(&shared_table),
-                        );
-                    }
-                });
-                return Ok(());
-            }
-
-            if consistency == Consistency::Quorum {
-                let mut replicas_completed = 0;
-                let needed = (replication - 1) / 2 + if (replication - 1) % 2 == 0 { 0 } else { 1 };
-                for node in replicas.iter() {
-                    if replicas_completed == needed {
-                        break;
-                    } else {
-                        delete(
-                            address.clone(),
-                            table_name.clone(),
-                            conditions.clone(),
-                            node,
-                            Arc::clone(table),
-                        );
-                    }
-                    replicas_completed += 1;
-                }
-                thread::spawn(move || {
-                    for node in replicas[replicas_completed..].iter() {
-                        delete(
-                            address_clone.clone(),
-                            table_name.clone(),
-                            conditions.clone(),
-                            node,
-                            Arc::clone(&shared_table),
-                        );
-                    }
-                });
-                return Ok(());
+        let partitions = match search_partitions(&conditions, &p_k) {
+            Some(partitions) => partitions,
+            None => return Err(ErrorTypes::new(549, "Error getting node".to_string())),
+        };
+        for partition in partitions {
+            let (node, key) = gossiper.get_node(partition.iter().collect());
+            if node.is_none() {
+                return Err(ErrorTypes::new(549, "Error getting node".to_string()));
             }
+            let conditions = restrict_partition(&conditions, &p_k, partition);
+            let (address_clone, table_name, timestamp) =
+                (address.clone(), table_name.clone(), timestamp.to_string());
+            let table = Arc::clone(table);
+            write_to_replicas(key, &replication, &consistency, &address, move |node| {
+                delete(
+                    &address_clone,
+                    &table_name,
+                    (&conditions, &timestamp),
+                    node,
+                    &table,
+                )
+            })?;
         }
-        Err(ErrorTypes::new(549, "Error getting node".to_string()))
+        Ok(())
     }
 
-    /// This function is responsible for executing the insert query.
+    /// This function is responsible for executing the insert query, writing every row with the timestamp the coordinator chose.
     pub fn execute_insert(
         &mut self,
         table_name: String,
@@ -512,9 +869,10 @@ impl Schema {
         columns: Vec<String>,
         address: &Address,
         consistency: Consistency,
+        timestamp: &str,
     ) -> Result<(), ErrorTypes> {
         let gossiper = get_gossiper();
-        let replication = self.get_replication()?;
+        let replication = self.get_replication(&table_name)?;
         let p_k = self.get_pk(&table_name)?;
         let table = self.get_table(&table_name)?;
         for row in values {
@@ -524,75 +882,31 @@ impl Schema {
                     values_to_hash.push(value);
                 }
             }
-            if let (Some(node), key) = gossiper.get_node(values_to_hash) {
-                insert(
-                    address,
-                    key,
-                    row.clone(),
-                    &table_name,
-                    &columns,
-                    Arc::clone(table),
-                    &node,
+            if let (Some(_), key) = gossiper.get_node(values_to_hash) {
+                let (address, table_name, columns, timestamp) = (
+                    address.clone(),
+                    table_name.clone(),
+                    columns.clone(),
+                    timestamp.to_string(),
                 );
-
-                let shared_table_clone = Arc::clone(table);
-
-                let replicas = gossiper.get_replicas(key, replication, &node)?;
-                if consistency == Consistency::One {
-                    let address_clone = address.clone();
-                    let table_name = table_name.clone();
-                    let columns = columns.clone();
-                    thread::spawn(move || {
-                        for replica in replicas.iter() {
-                            insert(
-                                &address_clone.clone(),
-                                key,
-                                row.clone(),
-                                &table_name.clone(),
-                                &columns,
-                                Arc::clone(&shared_table_clone),
-                                replica,
-                            );
-                        }
-                    });
-                } else if consistency == Consistency::Quorum {
-                    let mut replicas_completed = 0;
-
-                    let needed =
-                        (replication - 1) / 2 + if (replication - 1) % 2 == 0 { 0 } else { 1 };
-                    for node in replicas.iter() {
-                        if replicas_completed == needed {
-                            break;
-                        } else {
-                            insert(
-                                address,
-                                key,
-                                row.clone(),
-                                &table_name.clone(),
-                                &columns,
-                                Arc::clone(table),
-                                node,
-                            );
-                        }
-                        replicas_completed += 1;
-                    }
-                    let table_name = table_name.clone();
-                    let address_clone = address.clone();
-                    let columns = columns.clone();
-                    thread::spawn(move || {
-                        for replica in replicas[replicas_completed..].iter() {
-                            insert(
-                                &address_clone.clone(),
-                                key,
-                                row.clone(),
-                                &table_name.clone(),
-                                &columns,
-                                Arc::clone(&shared_table_clone),
-                                replica,
-                            );
-                        }
-                    });
-                }
+                let table = Arc::clone(table);
+                write_to_replicas(
+                    key,
+                    &replication,
+                    &consistency,
+                    &address.i_address.clone(),
+                    move |node| {
+                        insert(
+                            &address,
+                            (key, &timestamp),
+                            row.clone(),
+                            &table_name,
+                            &columns,
+                            &table,
+                            node,
+                        )
+                    },
+                )?;
             }
         }
         Ok(())
@@ -605,49 +919,14 @@ impl Schema {
         client_stream: &mut TcpStream,
     ) -> Result<(), ErrorTypes> {
         match message {
-            NodeMessage::SchemaChange(schema_change) => match schema_change {
-                SchemaChange::CreateKeyspace(keyspace) => {
-                    self.keyspaces.insert(keyspace, HashMap::new());
-                    self.increment_version();
-                    let msg = NodeMessage::Confirmation();
-                    client_stream.write_all(&msg.to_bytes()).unwrap();
-                    Ok(())
-                }
-                SchemaChange::CreateTable(mut memtable) => {
-                    let table_name = memtable.table_name.clone();
-                    if let Some(keyspace) = self.actual_keyspace.clone() {
-                        memtable.set_id(&self.port, &table_name);
-                        self.keyspaces
-                            .get_mut(&keyspace)
-                            .unwrap()
-                            .insert(table_name.clone(), Arc::new(Mutex::new(*memtable)));
-                        self.increment_version();
-                        let msg = NodeMessage::Confirmation();
-                        client_stream.write_all(&msg.to_bytes()).unwrap();
-                        return Ok(());
-                    }
-                    Err(ErrorTypes::new(550, "Keyspace not selected".to_string()))
-                }
-                SchemaChange::UseKeyspace(keyspace) => {
-                    if self.keyspaces.contains_key(&keyspace) {
-                        self.actual_keyspace = Some(keyspace);
-                        self.increment_version();
-                        let msg = NodeMessage::Confirmation();
-                        client_stream.write_all(&msg.to_bytes()).unwrap();
-                        Ok(())
-                    } else {
-                        Err(ErrorTypes::new(551, "Keyspace not found".to_string()))
-                    }
-                }
-            },
-            NodeMessage::Insert(columns, values, table_name, key) => {
+            NodeMessage::Insert(columns, values, table_name, key, timestamp) => {
                 write_log_message(
                     &self.port,
                     LogType::Info,
                     format!("Insert {:?} in {}", values, table_name),
                 );
                 let mut table = self.get_table(&table_name)?.lock().unwrap();
-                table.insert_row(key, values.clone(), columns, None, None)?;
+                table.insert_row(key, values.clone(), columns, None, None, &timestamp)?;
                 let msg = NodeMessage::Confirmation();
                 client_stream.write_all(&msg.to_bytes()).unwrap();
                 Ok(())
@@ -658,33 +937,69 @@ impl Schema {
                 order,
                 table_name,
                 needs_ts,
+                limit,
+                after,
             ) => {
+                let table = self.get_table(&table_name)?.lock().unwrap();
+                let result = table.execute_select(
+                    &conditions,
+                    &selected_columns,
+                    &order,
+                    needs_ts,
+                    true,
+                    (limit, after.as_deref()),
+                )?;
+                let response = NodeMessage::SelectResponse(result);
+                client_stream.write_all(&response.to_bytes()).unwrap();
+                Ok(())
+            }
+            NodeMessage::RangeSelectRequest(conditions, table_name, ranges, limit, after) => {
                 let table = self.get_table(&table_name)?.lock().unwrap();
                 let result =
-                    table.execute_select(&conditions, &selected_columns, &order, needs_ts, true)?;
+                    table.select_ranges(&conditions, &ranges, (limit, after.as_deref()))?;
                 let response = NodeMessage::SelectResponse(result);
                 client_stream.write_all(&response.to_bytes()).unwrap();
                 Ok(())
             }
-            NodeMessage::Update(key, table_name, column_value, conditions) => {
+            NodeMessage::Update(key, table_name, column_value, conditions, timestamp) => {
                 let mut table = self.get_table(&table_name)?.lock().unwrap();
-                table.insert_row(key, vec![], vec![], Some(conditions), Some(column_value))?;
+                table.insert_row(
+                    key,
+                    vec![],
+                    vec![],
+                    Some(conditions),
+                    Some(column_value),
+                    &timestamp,
+                )?;
                 drop(table);
                 let msg = NodeMessage::Confirmation();
                 client_stream.write_all(&msg.to_bytes()).unwrap();
                 Ok(())
             }
-            NodeMessage::Delete(table_name, conditions) => {
+            NodeMessage::Delete(table_name, conditions, timestamp) => {
                 let mut table = self.get_table(&table_name)?.lock().unwrap();
-                table.execute_delete(conditions)?;
+                table.execute_delete(conditions, &timestamp)?;
                 let msg = NodeMessage::Confirmation();
                 client_stream.write_all(&msg.to_bytes()).unwrap();
                 Ok(())
             }
-            NodeMessage::ChecksumRequest(conditions, selected_columns, order, table_name) => {
+            NodeMessage::ChecksumRequest(
+                conditions,
+                selected_columns,
+                order,
+                table_name,
+                limit,
+                after,
+            ) => {
                 let table = self.get_table(&table_name)?.lock().unwrap();
-                let mut result =
-                    table.execute_select(&conditions, &selected_columns, &order, false, true)?;
+                let mut result = table.execute_select(
+                    &conditions,
+                    &selected_columns,
+                    &order,
+                    false,
+                    true,
+                    (limit, after.as_deref()),
+                )?;
                 result.remove(0);
                 if let Ok(checksum) = md5::chksum(
                     result
@@ -703,155 +1018,438 @@ impl Schema {
                     ))
                 }
             }
-            NodeMessage::TransferFromNode(node) => self.transfer_from_node(&node),
+            NodeMessage::StreamChunk(session, keyspace, table_name, seq, rows) => {
+                let stored = match self.get_table_in(&keyspace, &table_name) {
+                    Some(table) => {
+                        let mut table = table.lock().unwrap();
+                        rows.into_iter()
+                            .try_for_each(|(key, row)| table.insert_streamed_row(key, row))
+                            .is_ok()
+                    }
+                    None => false,
+                };
+                let msg = NodeMessage::StreamAck(session, seq, stored);
+                client_stream.write_all(&msg.to_bytes()).unwrap();
+                Ok(())
+            }
+            NodeMessage::StreamCompleted(node) => {
+                write_log_message(
+                    &self.port,
+                    LogType::Info,
+                    format!("{} finished streaming its ranges", node),
+                );
+                get_gossiper().stream_completed(&node);
+                Ok(())
+            }
+            NodeMessage::BatchStore(batch) => {
+                batch.save(&self.data_dir)?;
+                let msg = NodeMessage::Confirmation();
+                client_stream.write_all(&msg.to_bytes()).unwrap();
+                Ok(())
+            }
+            NodeMessage::BatchRemove(id) => {
+                remove_batch(&self.data_dir, &id);
+                Ok(())
+            }
             NodeMessage::RemoveNode(node) => {
                 write_log_message(&self.port, LogType::Info, format!("Removing {}", node));
                 let gossiper = get_gossiper();
                 gossiper.remove_node(&node);
-
-                Err(ErrorTypes::new(565, "Removed node".to_string()))
+                Ok(())
             }
             _ => Err(ErrorTypes::new(553, "Unexpected message".to_string())),
         }
     }
 
-    fn transfer_from_node(&mut self, node: &String) -> Result<(), ErrorTypes> {
+    /// This function returns the ranges of every table that the local node has to stream to a joining node.
+    /// The local node keeps every range it streams: the ones it stops replicating are only deleted by `cleanup_tasks`
+    /// once the joining node is `Normal`, since the reads do not reach the joining node before.
+    pub fn bootstrap_tasks(&self, new_node: &String, local_address: &String) -> Vec<StreamTask> {
         let gossiper = get_gossiper();
-        for keyspace in self.keyspaces.keys() {
-            let rf = keyspace.replication;
-            let partitions = gossiper.get_partitions_remove(node, rf);
-            for table in self.keyspaces.get(keyspace).unwrap().values() {
-                let mut table_lock = table.lock().unwrap();
-                for (obj, partition) in partitions.iter() {
-                    let rows = table_lock.get_rows(partition);
-                    for (key, row) in rows {
-                        table_lock.delete_rows(&key);
-                        let msg = NodeMessage::Insert(
-                            table_lock.columns.clone(),
-                            row.clone(),
-                            table_lock.table_name.clone(),
-                            key,
-                        );
-                        if let Some(sender) = gossiper.get_sender(obj) {
-                            if sender.send(msg.to_bytes()).is_err() {
-                                return Err(ErrorTypes::new(
-                                    553,
-                                    "Error sending message to node".to_string(),
-                                ));
-                            }
-                            let p = obj.split(":").collect::<Vec<&str>>();
-                            write_log_message(
-                                &p[1].to_string(),
-                                LogType::Info,
-                                format!("Sending {:?} to {}", row, obj),
-                            );
-                        }
-                    }
-                }
+        let mut tasks = Vec::new();
+        for (keyspace, tables) in self.keyspaces.iter() {
+            let ranges = gossiper
+                .get_partitions(new_node, local_address, &keyspace.replication)
+                .into_iter()
+                .map(|(range, _)| range)
+                .collect::<Vec<(u128, u128)>>();
+            if ranges.is_empty() {
+                continue;
+            }
+            for table_name in tables.keys() {
+                tasks.push(StreamTask::new(
+                    keyspace.get_name(),
+                    table_name,
+                    ranges.clone(),
+                    false,
+                ));
             }
         }
-        let p = node.split(":").collect::<Vec<&str>>();
-        write_log_message(
-            &p[1].to_string(),
-            LogType::Info,
-            "Removing myself".to_string(),
-        );
-        let neighours = gossiper.get_neighbours();
-        let msg = NodeMessage::RemoveNode(node.to_string());
-        for node in neighours.iter() {
-            if let Some(sender) = gossiper.get_sender(node) {
-                if sender.send(msg.to_bytes()).is_err() {
-                    return Err(ErrorTypes::new(
-                        553,
-                        "Error sending message to node".to_string(),
-                    ));
+        tasks
+    }
+
+    /// This function returns the ranges of every table that the ring has and the local node does not replicate, so
+    /// their rows can be deleted. The ranges without replicas, because there are not enough nodes, are kept.
+    pub fn cleanup_tasks(&self, local_address: &String) -> Vec<StreamTask> {
+        let gossiper = get_gossiper();
+        let mut tasks = Vec::new();
+        for (keyspace, tables) in self.keyspaces.iter() {
+            let ranges = gossiper
+                .get_ranges(&keyspace.replication)
+                .into_iter()
+                .filter(|(_, replicas)| !replicas.is_empty() && !replicas.contains(local_address))
+                .map(|(range, _)| range)
+                .collect::<Vec<(u128, u128)>>();
+            if ranges.is_empty() {
+                continue;
+            }
+            for table_name in tables.keys() {
+                tasks.push(StreamTask::new(
+                    keyspace.get_name(),
+                    table_name,
+                    ranges.clone(),
+                    true,
+                ));
+            }
+        }
+        tasks
+    }
+
+    /// This function returns, for every node that takes over ranges of the leaving node, the ranges of every table it has to receive.
+    pub fn decommission_tasks(&self, node: &String) -> HashMap<String, Vec<StreamTask>> {
+        let gossiper = get_gossiper();
+        let mut sessions: HashMap<String, Vec<StreamTask>> = HashMap::new();
+        for (keyspace, tables) in self.keyspaces.iter() {
+            let partitions = gossiper.get_partitions_remove(node, &keyspace.replication);
+            for (target, ranges) in partitions {
+                for table_name in tables.keys() {
+                    sessions
+                        .entry(target.clone())
+                        .or_default()
+                        .push(StreamTask::new(
+                            keyspace.get_name(),
+                            table_name,
+                            ranges.clone(),
+                            true,
+                        ));
                 }
             }
         }
-        Ok(())
+        sessions
     }
 
-    pub fn new_node(&self, new_node: &String, local_address: &String) {
-        let p = local_address.split(":").collect::<Vec<&str>>();
-        write_log_message(
-            &p[1].to_string(),
-            LogType::Info,
-            format!("New node {}", new_node),
-        );
-        for keyspace in self.keyspaces.keys() {
-            let rf = keyspace.replication;
-            let gossiper = get_gossiper();
-            let partitions = gossiper.get_partitions(new_node, local_address, rf);
-            for table in self.keyspaces.get(keyspace).unwrap().values() {
-                let mut table_lock = table.lock().unwrap();
-                let rows = table_lock.get_rows(&partitions);
-                for (key, row) in rows {
-                    table_lock.delete_rows(&key);
-                    let msg = NodeMessage::Insert(
-                        table_lock.columns.clone(),
-                        row.clone(),
-                        table_lock.table_name.clone(),
-                        key,
-                    );
-
-                    loop {
-                        if let Some(sender) = gossiper.get_sender(new_node) {
-                            if sender.send(msg.to_bytes()).is_ok() {
-                                write_log_message(
-                                    &p[1].to_string(),
-                                    LogType::Info,
-                                    format!(
-                                        "Sending {:?} to {} {}",
-                                        row, new_node, table_lock.table_name
-                                    ),
-                                );
-                                break;
-                            }
-                        }
-                    }
+    /// This function returns, for every pair of source and target nodes, the ranges of every table that the source has to stream
+    /// to the target so the ranges of a dead node are replicated again.
+    pub fn restore_tasks(
+        &self,
+        dead: &String,
+        replacement: Option<&String>,
+    ) -> HashMap<(String, String), Vec<StreamTask>> {
+        let gossiper = get_gossiper();
+        let mut sessions: HashMap<(String, String), Vec<StreamTask>> = HashMap::new();
+        for (keyspace, tables) in self.keyspaces.iter() {
+            let partitions =
+                gossiper.get_partitions_restore(dead, replacement, &keyspace.replication);
+            for (nodes, ranges) in partitions {
+                for table_name in tables.keys() {
+                    sessions
+                        .entry(nodes.clone())
+                        .or_default()
+                        .push(StreamTask::new(
+                            keyspace.get_name(),
+                            table_name,
+                            ranges.clone(),
+                            false,
+                        ));
                 }
             }
         }
+        sessions
+    }
+
+    /// This function returns, for every pair of source and target nodes, the ranges of every table of a keyspace that the source
+    /// has to stream to the target after its replication changed from `before` to the actual one, and the nodes that stop replicating some range.
+    pub fn replication_tasks(
+        &self,
+        keyspace_name: &str,
+        before: &ReplicationStrategy,
+    ) -> Result<(SourcedTasks, HashSet<String>), ErrorTypes> {
+        let keyspace = self.get_keyspace(keyspace_name)?;
+        let (partitions, leaving) =
+            get_gossiper().get_partitions_replication(before, &keyspace.replication);
+        let mut sessions: SourcedTasks = HashMap::new();
+        let tables = self.keyspaces.get(&keyspace).cloned().unwrap_or_default();
+        for (nodes, ranges) in partitions {
+            for table_name in tables.keys() {
+                sessions
+                    .entry(nodes.clone())
+                    .or_default()
+                    .push(StreamTask::new(
+                        keyspace.get_name(),
+                        table_name,
+                        ranges.clone(),
+                        false,
+                    ));
+            }
+        }
+        Ok((sessions, leaving))
     }
-    fn get_table(&mut self, table_name: &str) -> Result<&Arc<Mutex<MemTable>>, ErrorTypes> {
-        let k_s = match &self.actual_keyspace {
-            Some(k_s) => k_s,
-            _ => return Err(ErrorTypes::new(554, "Keyspace not selected".to_string())),
-        };
-        let hash_mt = match self.keyspaces.get_mut(k_s) {
-            Some(hash_mt) => hash_mt,
-            _ => return Err(ErrorTypes::new(555, "Keyspace not found".to_string())),
+
+    /// This function returns the directory where the node stores its data.
+    pub fn get_data_dir(&self) -> String {
+        self.data_dir.clone()
+    }
+
+    /// This function returns a table of a keyspace, without taking into account the actual keyspace.
+    pub fn get_table_in(&self, keyspace: &str, table_name: &str) -> Option<Arc<Mutex<MemTable>>> {
+        self.keyspaces
+            .iter()
+            .find(|(k_s, _)| k_s.get_name() == keyspace)
+            .and_then(|(_, tables)| tables.get(table_name))
+            .cloned()
+    }
+
+    /// This function splits the fully qualified name of a table in its keyspace and the name of the table inside it.
+    fn split_table_name<'a>(
+        &self,
+        table_name: &'a str,
+    ) -> Result<(&Keyspace, &'a str), ErrorTypes> {
+        let (keyspace, table_name) = match table_name.split_once('.') {
+            Some(names) => names,
+            None => return Err(ErrorTypes::new(554, "Keyspace not selected".to_string())),
         };
-        match hash_mt.get(&table_name.to_owned()) {
+        match self.keyspaces.keys().find(|k_s| k_s.get_name() == keyspace) {
+            Some(k_s) => Ok((k_s, table_name)),
+            None => Err(ErrorTypes::new(555, "Keyspace not found".to_string())),
+        }
+    }
+
+    fn get_table(&self, table_name: &str) -> Result<&Arc<Mutex<MemTable>>, ErrorTypes> {
+        let (k_s, table_name) = self.split_table_name(table_name)?;
+        match self
+            .keyspaces
+            .get(k_s)
+            .and_then(|tables| tables.get(table_name))
+        {
             Some(table) => Ok(table),
             _ => Err(ErrorTypes::new(556, "Table not found".to_string())),
         }
     }
 
-    fn get_replication(&self) -> Result<usize, ErrorTypes> {
-        let k_s = match &self.actual_keyspace {
-            Some(k_s) => k_s,
-            _ => return Err(ErrorTypes::new(557, "Keyspace not selected".to_string())),
-        };
-        Ok(k_s.replication)
+    fn get_replication(&self, table_name: &str) -> Result<ReplicationStrategy, ErrorTypes> {
+        let (k_s, _) = self.split_table_name(table_name)?;
+        Ok(k_s.replication.clone())
     }
 
+    /// This function writes the schema in the data directory of the node.
     pub fn save_schema(&self) -> Result<(), ErrorTypes> {
         let serialized = serde_json::to_string(&self).unwrap();
-        let mut file = std::fs::File::create("schema.json").unwrap();
+        if std::fs::create_dir_all(&self.data_dir).is_err() {
+            return Err(ErrorTypes::new(
+                593,
+                "Error creating the data directory".to_string(),
+            ));
+        }
+        let mut file = match std::fs::File::create(schema_path(&self.data_dir)) {
+            Ok(file) => file,
+            Err(_) => {
+                return Err(ErrorTypes::new(
+                    594,
+                    "Error creating the schema file".to_string(),
+                ))
+            }
+        };
         file.write_all(serialized.as_bytes()).unwrap();
         Ok(())
     }
-    pub fn read_schema(id: &String) -> Result<Schema, ErrorTypes> {
-        if let Ok(file) = std::fs::File::open("schema.json") {
-            if let Ok(mut schema) = serde_json::from_reader::<File, Schema>(file) {
-                schema.set_id(id);
-                return Ok(schema);
+
+    /// This function reads the schema stored in the data directory of the node.
+    /// If the node has never saved one, the `schema.json` of the working directory is used as the initial schema.
+    pub fn read_schema(port: &String, data_dir: &str) -> Result<Schema, ErrorTypes> {
+        let file = match std::fs::File::open(schema_path(data_dir)) {
+            Ok(file) => file,
+            Err(_) => match std::fs::File::open(INITIAL_SCHEMA) {
+                Ok(file) => file,
+                Err(_) => return Err(ErrorTypes::new(559, "There is not a schema.".to_string())),
+            },
+        };
+        if let Ok(mut schema) = serde_json::from_reader::<File, Schema>(file) {
+            schema.set_data_dir(port, data_dir);
+            return Ok(schema);
+        }
+        Err(ErrorTypes::new(558, "There is not a schema.".to_string()))
+    }
+}
+
+/// This function reads the rows of a select, from the replicas of its partitions or, if the conditions do not restrict
+/// the partition key, from every range of the ring. The first row has the names of the selected columns.
+/// With `after`, only the rows whose primary key comes after it are read.
+pub fn select_rows(
+    schema: &Arc<Mutex<Schema>>,
+    info_select: (String, Clause, Vec<String>, Vec<String>),
+    limits: (Option<usize>, Option<usize>),
+    after: Option<Vec<String>>,
+    address: &Address,
+    consistency: Consistency,
+) -> Result<Vec<Vec<String>>, ErrorTypes> {
+    let mut schema_lock = schema.lock().unwrap();
+    if schema_lock.scans_ring(&info_select.0, &info_select.1)? {
+        drop(schema_lock);
+        return select_ranges(schema, info_select, limits, after, address, &consistency);
+    }
+    schema_lock.execute_select(info_select, limits, after, address, consistency)
+}
+
+/// This function reads the rows that meet the conditions from every range of the ring.
+///
+/// The ranges with the same replicas are read together, from as many live replicas as the consistency level needs,
+/// and the newest version of every row wins. The schema is only locked to get the table, so it is not held while
+/// the replicas answer, and a replica that fails or does not answer in time counts as unavailable.
+///
+/// Without `ORDER BY` nor `PER PARTITION LIMIT` the replicas only send their first `LIMIT` live rows. If a replica
+/// cut its rows before enough of them were left alive once merged, the ranges are read again without the limit.
+/// With `after`, the replicas only send the rows whose primary key comes after it.
+pub fn select_ranges(
+    schema: &Arc<Mutex<Schema>>,
+    info_select: (String, Clause, Vec<String>, Vec<String>),
+    (limit, per_partition_limit): (Option<usize>, Option<usize>),
+    after: Option<Vec<String>>,
+    address: &Address,
+    consistency: &Consistency,
+) -> Result<Vec<Vec<String>>, ErrorTypes> {
+    let (table_name, conditions, selected_columns, order) = info_select;
+    let (replication, table) = {
+        let schema = schema.lock().unwrap();
+        (
+            schema.get_replication(&table_name)?,
+            Arc::clone(schema.get_table(&table_name)?),
+        )
+    };
+    let (columns, primary_key, partition_key) = {
+        let table_lock = table.lock().unwrap();
+        (
+            table_lock.columns.clone(),
+            table_lock.get_primary_key(),
+            table_lock.get_pk(),
+        )
+    };
+    let gossiper = get_gossiper();
+    let datacenter = gossiper.get_location(&address.i_address).datacenter;
+    let locations = gossiper.get_locations();
+    let mut groups: HashMap<Vec<String>, Vec<(u128, u128)>> = HashMap::new();
+    for (range, replicas) in gossiper.get_ranges(&replication) {
+        groups.entry(replicas).or_default().push(range);
+    }
+    let indexes = primary_key
+        .iter()
+        .map(|(_, index)| *index)
+        .collect::<Vec<usize>>();
+    let primary_key_of = |row: &Vec<String>| {
+        indexes
+            .iter()
+            .map(|index| row[*index].clone())
+            .collect::<Vec<String>>()
+    };
+    let mut sort_order = primary_key
+        .iter()
+        .map(|(name, _)| name.to_string())
+        .collect::<Vec<String>>();
+    sort_order.push("asc".to_string());
+    // Devuelve las filas de todos los rangos y la ultima fila de cada replica que corto sus filas en el limite.
+    let read = |replica_limit: Option<usize>| {
+        let mut rows = Vec::new();
+        let mut cut_at = Vec::new();
+        for (replicas, ranges) in groups.iter() {
+            let needed = replication
+                .block_for(consistency, replicas, &datacenter, &locations)?
+                .len();
+            let mut answered = 0;
+            for replica in replication.read_replicas(consistency, replicas, &datacenter, &locations)
+            {
+                if answered == needed {
+                    break;
+                }
+                if gossiper.is_down(&replica) {
+                    continue;
+                }
+                let result = if replica == address.i_address {
+                    table.lock().unwrap().select_ranges(
+                        &conditions,
+                        ranges,
+                        (replica_limit, after.as_deref()),
+                    )
+                } else {
+                    redirect_select_ranges(
+                        &conditions,
+                        &replica,
+                        &table_name,
+                        ranges,
+                        (replica_limit, after.as_deref()),
+                    )
+                };
+                if let Ok(mut replica_rows) = result {
+                    replica_rows.remove(0);
+                    let live = replica_rows.iter().filter(|row| !is_tombstone(row)).count();
+                    if replica_limit.is_some_and(|limit| live >= limit) {
+                        if let Some(last) = replica_rows.last() {
+                            cut_at.push(primary_key_of(last));
+                        }
+                    }
+                    rows.extend(replica_rows);
+                    answered += 1;
+                }
+            }
+            if answered < needed {
+                return Err(ErrorTypes::new(548, "Unrecheable data".to_string()));
             }
-            return Err(ErrorTypes::new(558, "There is not a schema.".to_string()));
         }
-        Err(ErrorTypes::new(559, "There is not a schema.".to_string()))
+        let rows = sort_by_columns(&sort_order, newest_rows(rows, &indexes), &columns)
+            .map_err(|_| ErrorTypes::new(504, "Invalid sorting".to_string()))?;
+        Ok((rows, cut_at))
+    };
+    let replica_limit = limit.filter(|_| order.is_empty() && per_partition_limit.is_none());
+    let (mut rows, cut_at) = read(replica_limit)?;
+    let end = cut_at
+        .iter()
+        .filter_map(|key| rows.iter().position(|row| primary_key_of(row) == *key))
+        .min();
+    if let (Some(end), Some(limit)) = (end, replica_limit) {
+        rows.truncate(end + 1);
+        if rows.iter().filter(|row| !is_tombstone(row)).count() < limit {
+            rows = read(None)?.0;
+        }
+    }
+    let mut rows = rows
+        .into_iter()
+        .filter(|row| !is_tombstone(row))
+        .map(|mut row| {
+            row.pop();
+            row
+        })
+        .collect::<Vec<Vec<String>>>();
+    if let Some(per_partition_limit) = per_partition_limit {
+        let mut read: HashMap<Vec<String>, usize> = HashMap::new();
+        rows.retain(|row| {
+            let partition = partition_key.iter().map(|index| row[*index].clone());
+            let count = read.entry(partition.collect()).or_default();
+            *count += 1;
+            *count <= per_partition_limit
+        });
+    }
+    if !order.is_empty() {
+        rows = sort_by_columns(&order, rows, &columns)
+            .map_err(|_| ErrorTypes::new(504, "Invalid sorting".to_string()))?;
+    }
+    if let Some(limit) = limit {
+        rows.truncate(limit);
+    }
+    if selected_columns == ["*"] {
+        rows.insert(0, columns);
+        return Ok(rows);
     }
+    let mut rows = field_filter(rows, columns, selected_columns.clone(), false)?;
+    rows.insert(0, selected_columns);
+    Ok(rows)
 }
 
 fn redirect_select(
@@ -861,6 +1459,7 @@ fn redirect_select(
     node: &String,
     table_name: &str,
     needs_ts: bool,
+    (limit, after): (Option<usize>, Option<&[String]>),
 ) -> Result<Vec<Vec<String>>, ErrorTypes> {
     let msg = NodeMessage::SelectRequest(
         conditions.clone(),
@@ -868,23 +1467,20 @@ fn redirect_select(
         order.to_owned(),
         table_name.to_owned(),
         needs_ts,
+        limit,
+        after.map(|after| after.to_vec()),
     );
     let gossiper = get_gossiper();
-    if let Some(sender) = gossiper.get_sender(node) {
-        if sender.send(msg.to_bytes()).is_err() {
-            return Err(ErrorTypes::new(
-                560,
-                "Error sending message to node".to_string(),
-            ));
-        }
-        let bytes = gossiper.receive(node);
-        if bytes.is_empty() {
-            return Err(ErrorTypes::new(
-                565,
-                "Couldn't receive the message".to_string(),
-            ));
-        }
-        let message = NodeMessage::from_bytes(bytes[1..].to_vec());
+    if gossiper.get_sender(node).is_some() {
+        let message = match gossiper.request(node, &msg, Duration::from_secs(REQUEST_TIMEOUT)) {
+            Some(message) => message,
+            None => {
+                return Err(ErrorTypes::new(
+                    565,
+                    "Couldn't receive the message".to_string(),
+                ))
+            }
+        };
 
         match message {
             NodeMessage::SelectResponse(result) => return Ok(result),
@@ -895,22 +1491,49 @@ fn redirect_select(
     Err(ErrorTypes::new(562, "Error getting sender".to_string()))
 }
 
-fn get_checksum(
+fn redirect_select_ranges(
     conditions: &Clause,
-    selected_columns: &[String],
-    order: &[String],
     node: &String,
     table_name: &str,
+    ranges: &[(u128, u128)],
+    (limit, after): (Option<usize>, Option<&[String]>),
+) -> Result<Vec<Vec<String>>, ErrorTypes> {
+    let msg = NodeMessage::RangeSelectRequest(
+        conditions.clone(),
+        table_name.to_owned(),
+        ranges.to_vec(),
+        limit,
+        after.map(|after| after.to_vec()),
+    );
+    let gossiper = get_gossiper();
+    if gossiper.get_sender(node).is_none() {
+        return Err(ErrorTypes::new(562, "Error getting sender".to_string()));
+    }
+    match gossiper.request(node, &msg, Duration::from_secs(REQUEST_TIMEOUT)) {
+        // Una respuesta sin encabezado indica que la replica no pudo leer los rangos.
+        Some(NodeMessage::SelectResponse(result)) if !result.is_empty() => Ok(result),
+        Some(_) => Err(ErrorTypes::new(561, "Unexpected message".to_string())),
+        None => Err(ErrorTypes::new(
+            565,
+            "Couldn't receive the message".to_string(),
+        )),
+    }
+}
+
+fn get_checksum(
+    query: &SelectQuery,
+    node: &String,
     address: &String,
     table: &Arc<Mutex<MemTable>>,
 ) -> Result<String, ErrorTypes> {
     if address == node {
         let result = table.lock().unwrap().execute_select(
-            conditions,
-            selected_columns,
-            order,
+            query.conditions,
+            query.selected_columns,
+            query.order,
             false,
             false,
+            (query.limit, query.after),
         )?;
         let mut result = result.clone();
         result.remove(0);
@@ -926,21 +1549,24 @@ fn get_checksum(
     }
 
     let msg = NodeMessage::ChecksumRequest(
-        conditions.clone(),
-        selected_columns.to_owned(),
-        order.to_owned(),
-        table_name.to_owned(),
+        query.conditions.clone(),
+        query.selected_columns.to_owned(),
+        query.order.to_owned(),
+        query.table_name.to_owned(),
+        query.limit,
+        query.after.map(|after| after.to_vec()),
     );
     let gossiper = get_gossiper();
-    if let Some(sender) = gossiper.get_sender(node) {
-        if sender.send(msg.to_bytes()).is_err() {
-            return Err(ErrorTypes::new(
-                563,
-                "Error sending message to node".to_string(),
-            ));
-        }
-        let bytes = gossiper.receive(node);
-        let message = NodeMessage::from_bytes(bytes[1..].to_vec());
+    if gossiper.get_sender(node).is_some() {
+        let message = match gossiper.request(node, &msg, Duration::from_secs(REQUEST_TIMEOUT)) {
+            Some(message) => message,
+            None => {
+                return Err(ErrorTypes::new(
+                    565,
+                    "Couldn't receive the message".to_string(),
+                ))
+            }
+        };
         match message {
             NodeMessage::ChecksumResponse(checksum) => return Ok(checksum),
             _ => return Err(ErrorTypes::new(564, "Unexpected message".to_string())),
@@ -955,28 +1581,29 @@ fn redirect_insert(
     values: Vec<String>,
     columns: &[String],
     node: &String,
-    key: u128,
+    (key, timestamp): (u128, &str),
     table_name: String,
 ) -> Result<(), ErrorTypes> {
-    let msg = NodeMessage::Insert(columns.to_vec(), values, table_name, key);
+    let msg = NodeMessage::Insert(
+        columns.to_vec(),
+        values,
+        table_name,
+        key,
+        timestamp.to_string(),
+    );
     let gossiper = get_gossiper();
-    if let Some(sender) = gossiper.get_sender(node) {
-        if sender.send(msg.to_bytes()).is_err() {
-            return Err(ErrorTypes::new(
-                566,
-                "Error sending message to node".to_string(),
-            ));
-        }
+    if gossiper.get_sender(node).is_some() {
         let id = node.split(":").collect::<Vec<&str>>()[1].to_string();
         write_log_message(&id, LogType::Info, "Inserting".to_string());
-        let bytes = gossiper.receive(node);
-        if bytes.is_empty() {
-            return Err(ErrorTypes::new(
-                565,
-                "Couldn't receive the message".to_string(),
-            ));
-        }
-        let message = NodeMessage::from_bytes(bytes[1..].to_vec());
+        let message = match gossiper.request(node, &msg, Duration::from_secs(REQUEST_TIMEOUT)) {
+            Some(message) => message,
+            None => {
+                return Err(ErrorTypes::new(
+                    565,
+                    "Couldn't receive the message".to_string(),
+                ))
+            }
+        };
 
         match message {
             NodeMessage::Confirmation() => return Ok(()),
@@ -987,54 +1614,147 @@ fn redirect_insert(
 }
 
 ///This function is responsible for searching the primary key in the conditions of a select query.
-fn search_pk<'a>(clause: &'a Clause, result: &mut Vec<&'a String>, pk: &Vec<String>) {
-    match clause {
-        Clause::And { left, right } => {
-            search_pk(left, result, pk);
-            search_pk(right, result, pk);
+/// This function returns the values of the partition key of every partition that the conditions can match, in the order of
+/// the partition key, or `None` if the conditions do not restrict every column of the partition key.
+///
+/// The conditions are split in alternatives, one for every side of an `OR`, and each alternative restricts every column
+/// of the partition key to the values of its `=` and `IN` relations.
+fn search_partitions(clause: &Clause, pk: &[String]) -> Option<Vec<Vec<String>>> {
+    if pk.is_empty() {
+        return None;
+    }
+    let mut partitions: Vec<Vec<String>> = Vec::new();
+    for alternative in partition_restrictions(clause, pk) {
+        let mut keys: Vec<Vec<String>> = vec![Vec::new()];
+        for column in pk {
+            let values = alternative.get(column)?;
+            keys = keys
+                .into_iter()
+                .flat_map(|key| {
+                    values.iter().map(move |value| {
+                        let mut key = key.clone();
+                        key.push(value.to_string());
+                        key
+                    })
+                })
+                .collect();
         }
+        for key in keys {
+            if !partitions.contains(&key) {
+                partitions.push(key);
+            }
+        }
+    }
+    Some(partitions)
+}
+
+/// This function keeps the header of the rows read from a replica and, after it, the rows that are not tombstones,
+/// without their timestamp. With a limit, only the first `limit` of them are kept.
+fn live_rows(mut rows: Vec<Vec<String>>, limit: Option<usize>) -> Vec<Vec<String>> {
+    let mut live = vec![rows.remove(0)];
+    live.extend(
+        rows.into_iter()
+            .filter(|row| !is_tombstone(row))
+            .map(|mut row| {
+                row.pop();
+                row
+            }),
+    );
+    truncate_rows(&mut live, limit);
+    live
+}
+
+/// This function keeps the header and, with a limit, only the first `limit` rows after it.
+fn truncate_rows(rows: &mut Vec<Vec<String>>, limit: Option<usize>) {
+    if let Some(limit) = limit {
+        rows.truncate(limit + 1);
+    }
+}
+
+/// This function returns the alternatives of the conditions, with the values that each one allows for the columns of the partition key.
+fn partition_restrictions(clause: &Clause, pk: &[String]) -> Vec<HashMap<String, Vec<String>>> {
+    match clause {
         Clause::Or { left, right } => {
-            search_pk(left, result, pk);
-            search_pk(right, result, pk);
+            let mut alternatives = partition_restrictions(left, pk);
+            alternatives.extend(partition_restrictions(right, pk));
+            alternatives
         }
-        Clause::Not { right } => search_pk(right, result, pk),
-        Clause::Term {
-            relation: Relation::Equal { v1, v2 },
-        } => {
-            if pk.contains(v1) {
-                result.push(v2);
+        Clause::And { left, right } => {
+            let right = partition_restrictions(right, pk);
+            let mut alternatives = Vec::new();
+            for left in partition_restrictions(left, pk) {
+                for right in right.iter() {
+                    let mut alternative = left.clone();
+                    for (column, values) in right {
+                        let values = match alternative.get(column) {
+                            Some(left_values) => values
+                                .iter()
+                                .filter(|value| left_values.contains(value))
+                                .cloned()
+                                .collect(),
+                            None => values.clone(),
+                        };
+                        alternative.insert(column.to_string(), values);
+                    }
+                    alternatives.push(alternative);
+                }
             }
+            alternatives
         }
-        _ => {}
+        Clause::Term {
+            relation: Relation::Equal { v1, v2 },
+        } if pk.contains(v1) => vec![HashMap::from([(v1.to_string(), vec![v2.to_string()])])],
+        Clause::Term {
+            relation: Relation::In { v1, values },
+        } if pk.contains(v1) => vec![HashMap::from([(v1.to_string(), values.clone())])],
+        _ => vec![HashMap::new()],
+    }
+}
+
+/// This function restricts the conditions to one partition, adding an equality for every column of the partition key.
+fn restrict_partition(conditions: &Clause, pk: &[String], partition: Vec<String>) -> Clause {
+    let mut partition_conditions = conditions.clone();
+    for (column, value) in pk.iter().zip(partition) {
+        partition_conditions = Clause::And {
+            left: Box::new(Clause::Term {
+                relation: Relation::Equal {
+                    v1: column.to_string(),
+                    v2: value,
+                },
+            }),
+            right: Box::new(partition_conditions),
+        };
     }
+    partition_conditions
 }
 
 fn redirect_update(
     node: &String,
-    key: u128,
+    (key, timestamp): (u128, &str),
     table_name: String,
     column_value: HashMap<String, String>,
     conditions: Clause,
 ) -> Result<(), ErrorTypes> {
-    let msg = NodeMessage::Update(key, table_name, column_value, conditions);
+    let msg = NodeMessage::Update(
+        key,
+        table_name,
+        column_value,
+        conditions,
+        timestamp.to_string(),
+    );
     let gossiper = get_gossiper();
-    if let Some(sender) = gossiper.get_sender(node) {
-        if sender.send(msg.to_bytes()).is_err() {
-            return Err(ErrorTypes::new(
-                569,
-                "Error sending message to node".to_string(),
-            ));
-        }
+    if gossiper.get_sender(node).is_some() {
         let id = node.split(":").collect::<Vec<&str>>()[1].to_string();
         write_log_message(&id, LogType::Info, "Updating".to_string());
-        let bytes = gossiper.receive(node);
-        if bytes.is_empty() {
-            return Err(ErrorTypes::new(
-                565,
-                "Couldn't receive the message".to_string(),
-            ));
-        }
-        let message = NodeMessage::from_bytes(bytes[1..].to_vec());
+        let message = match gossiper.request(node, &msg, Duration::from_secs(REQUEST_TIMEOUT)) {
+            Some(message) => message,
+            None => {
+                return Err(ErrorTypes::new(
+                    565,
+                    "Couldn't receive the message".to_string(),
+                ))
+            }
+        };
 
         match message {
             NodeMessage::Confirmation() => return Ok(()),
@@ -1048,26 +1768,22 @@ fn redirect_delete(
     node: &String,
     table_name: String,
     conditions: Clause,
+    timestamp: &str,
 ) -> Result<(), ErrorTypes> {
-    let msg = NodeMessage::Delete(table_name, conditions);
+    let msg = NodeMessage::Delete(table_name, conditions, timestamp.to_string());
     let gossiper = get_gossiper();
-    if let Some(sender) = gossiper.get_sender(node) {
-        if sender.send(msg.to_bytes()).is_err() {
-            return Err(ErrorTypes::new(
-                571,
-                "Error sending message to node".to_string(),
-            ));
-        }
+    if gossiper.get_sender(node).is_some() {
         let id = node.split(":").collect::<Vec<&str>>()[1].to_string();
         write_log_message(&id, LogType::Info, "Deleting".to_string());
-        let bytes = gossiper.receive(node);
-        if bytes.is_empty() {
-            return Err(ErrorTypes::new(
-                565,
-                "Couldn't receive the message".to_string(),
-            ));
-        }
-        let message = NodeMessage::from_bytes(bytes[1..].to_vec());
+        let message = match gossiper.request(node, &msg, Duration::from_secs(REQUEST_TIMEOUT)) {
+            Some(message) => message,
+            None => {
+                return Err(ErrorTypes::new(
+                    565,
+                    "Couldn't receive the message".to_string(),
+                ))
+            }
+        };
 
         match message {
             NodeMessage::Confirmation() => return Ok(()),
@@ -1077,91 +1793,182 @@ fn redirect_delete(
     Err(ErrorTypes::new(573, "Error getting sender".to_string()))
 }
 
+/// This function writes a key to its live replicas and waits for the acknowledgements that the consistency level needs.
+///
+/// The replicas that are down are skipped, and if there are not enough live ones nothing is written and the write is
+/// `Unavailable`. The live replicas are written in order until every group of the consistency level has its
+/// acknowledgements, so a replica that fails is replaced by the next one, and the rest are written in the background.
+/// If the replicas that acknowledged the write are not enough, it is a `WriteTimeout`.
+fn write_to_replicas<F>(
+    key: u128,
+    strategy: &ReplicationStrategy,
+    consistency: &Consistency,
+    coordinator: &str,
+    write: F,
+) -> Result<(), ErrorTypes>
+where
+    F: Fn(&String) -> Result<(), ErrorTypes> + Send + 'static,
+{
+    let gossiper = get_gossiper();
+    let live = gossiper
+        .get_replicas(key, strategy)?
+        .into_iter()
+        .filter(|replica| !gossiper.is_down(replica))
+        .collect::<Vec<String>>();
+    let datacenter = gossiper.get_location(coordinator).datacenter;
+    let locations = gossiper.get_locations();
+    let requirements = strategy.requirements(consistency, &live, &datacenter, &locations);
+    for (candidates, needed) in requirements.iter() {
+        if candidates.len() < *needed {
+            return Err(ErrorTypes::Unavailable {
+                consistency: *consistency,
+                required: *needed,
+                alive: candidates.len(),
+            });
+        }
+    }
+    let pending = |acked: &[&String], replica: &String| {
+        requirements.iter().any(|(candidates, needed)| {
+            candidates.contains(&replica)
+                && candidates.iter().filter(|c| acked.contains(c)).count() < *needed
+        })
+    };
+    let mut acked = Vec::new();
+    let mut background = Vec::new();
+    for replica in live.iter() {
+        if !pending(&acked, replica) {
+            background.push(replica.to_string());
+            continue;
+        }
+        match write(replica) {
+            Ok(()) => acked.push(replica),
+            Err(e) => write_log_message(
+                &coordinator
+                    .split(":")
+                    .last()
+                    .unwrap_or_default()
+                    .to_string(),
+                LogType::Error,
+                format!("Write to {} failed: {}", replica, e.get().1),
+            ),
+        }
+    }
+    let block_for = requirements.iter().map(|(_, needed)| needed).sum::<usize>();
+    let received = requirements
+        .iter()
+        .map(|(candidates, needed)| {
+            candidates
+                .iter()
+                .filter(|c| acked.contains(c))
+                .count()
+                .min(*needed)
+        })
+        .sum::<usize>();
+    thread::spawn(move || {
+        for replica in background.iter() {
+            let _ = write(replica);
+        }
+    });
+    if received < block_for {
+        return Err(ErrorTypes::WriteTimeout {
+            consistency: *consistency,
+            received,
+            block_for,
+            write_type: "SIMPLE".to_string(),
+        });
+    }
+    Ok(())
+}
+
+/// This function writes a row in a replica, which can be the local node, with the timestamp of the write.
 fn insert(
     address: &Address,
-    key: u128,
+    (key, timestamp): (u128, &str),
     row: Vec<String>,
     table_name: &str,
     columns: &[String],
-    table: Arc<Mutex<MemTable>>,
+    table: &Arc<Mutex<MemTable>>,
     node: &String,
-) {
+) -> Result<(), ErrorTypes> {
     if *node == address.i_address {
-        if table
+        table
             .lock()
             .unwrap()
-            .insert_row(key, row.clone(), columns.to_vec(), None, None)
-            .is_ok()
-        {
-            write_log_message(&address.i_port, LogType::Info, "Inserting".to_string());
-        }
-        return;
-    }
-    if redirect_insert(row.clone(), columns, node, key, table_name.to_owned()).is_ok() {
-        write_log_message(
-            &address.i_port,
-            LogType::Info,
-            format!("Redirecting insert to {}", node),
-        );
+            .insert_row(key, row, columns.to_vec(), None, None, timestamp)?;
+        write_log_message(&address.i_port, LogType::Info, "Inserting".to_string());
+        return Ok(());
     }
+    redirect_insert(row, columns, node, (key, timestamp), table_name.to_owned())?;
+    write_log_message(
+        &address.i_port,
+        LogType::Info,
+        format!("Redirecting insert to {}", node),
+    );
+    Ok(())
 }
 
+/// This function updates the rows of a partition in a replica, which can be the local node, with the timestamp of the write.
 fn update(
-    address: String,
-    key: u128,
-    table_name: String,
-    column_value: HashMap<String, String>,
-    conditions: Clause,
+    address: &String,
+    (key, timestamp): (u128, &str),
+    table_name: &str,
+    column_value: &HashMap<String, String>,
+    conditions: &Clause,
     node: &String,
-    table: Arc<Mutex<MemTable>>,
-) {
-    if address == *node {
-        let _ = table.lock().unwrap().insert_row(
+    table: &Arc<Mutex<MemTable>>,
+) -> Result<(), ErrorTypes> {
+    let id = address.split(":").collect::<Vec<&str>>()[1].to_string();
+    if address == node {
+        table.lock().unwrap().insert_row(
             key,
             vec![],
             vec![],
             Some(conditions.clone()),
             Some(column_value.clone()),
-        );
-        let id = address.split(":").collect::<Vec<&str>>()[1].to_string();
+            timestamp,
+        )?;
         write_log_message(&id, LogType::Info, "Updating".to_string());
-    } else {
-        let _ = redirect_update(
-            node,
-            key,
-            table_name.clone(),
-            column_value.clone(),
-            conditions.clone(),
-        );
-        let id = address.split(":").collect::<Vec<&str>>()[1].to_string();
-        write_log_message(
-            &id,
-            LogType::Info,
-            format!("Redirecting update to {}", node),
-        );
+        return Ok(());
     }
+    redirect_update(
+        node,
+        (key, timestamp),
+        table_name.to_string(),
+        column_value.clone(),
+        conditions.clone(),
+    )?;
+    write_log_message(
+        &id,
+        LogType::Info,
+        format!("Redirecting update to {}", node),
+    );
+    Ok(())
 }
 
+/// This function deletes the rows of a partition in a replica, which can be the local node, with the timestamp of the write.
 fn delete(
-    address: String,
-    table_name: String,
-    conditions: Clause,
+    address: &String,
+    table_name: &str,
+    (conditions, timestamp): (&Clause, &str),
     node: &String,
-    table: Arc<Mutex<MemTable>>,
-) {
-    if address == *node {
-        let _ = table.lock().unwrap().execute_delete(conditions.clone());
-        let id = address.split(":").collect::<Vec<&str>>()[1].to_string();
+    table: &Arc<Mutex<MemTable>>,
+) -> Result<(), ErrorTypes> {
+    let id = address.split(":").collect::<Vec<&str>>()[1].to_string();
+    if address == node {
+        table
+            .lock()
+            .unwrap()
+            .execute_delete(conditions.clone(), timestamp)?;
         write_log_message(&id, LogType::Info, "Deleting".to_string());
-    } else {
-        let _ = redirect_delete(node, table_name.clone(), conditions.clone());
-        let id = address.split(":").collect::<Vec<&str>>()[1].to_string();
-        write_log_message(
-            &id,
-            LogType::Info,
-            format!("Redirecting delete to {}", node),
-        );
+        return Ok(());
     }
+    redirect_delete(node, table_name.to_string(), conditions.clone(), timestamp)?;
+    write_log_message(
+        &id,
+        LogType::Info,
+        format!("Redirecting delete to {}", node),
+    );
+    Ok(())
 }
 
 fn select(
@@ -1177,6 +1984,7 @@ fn select(
             query.order,
             query.needs_ts,
             query.needs_tb,
+            (query.limit, query.after),
         );
     }
     redirect_select(
@@ -1186,11 +1994,12 @@ fn select(
         node,
         query.table_name,
         query.needs_ts,
+        (query.limit, query.after),
     )
 }
 
 fn check_read_repair(
-    info_select: (String, Clause, Vec<String>, Vec<String>),
+    info_select: SelectInfo,
     rows: Vec<Vec<String>>,
     address: String,
     node: &String,
@@ -1218,15 +2027,17 @@ fn check_read_repair(
         if replica == node {
             continue;
         }
-        if let Ok(checksum_replica) = get_checksum(
-            &info_select.1,
-            &info_select.2,
-            &[],
-            replica,
-            &info_select.0,
-            &address,
-            &table,
-        ) {
+        let query = SelectQuery {
+            conditions: &info_select.1,
+            selected_columns: &info_select.2,
+            order: &info_select.3,
+            table_name: &info_select.0,
+            needs_ts: false,
+            needs_tb: true,
+            limit: info_select.4,
+            after: info_select.5.as_deref(),
+        };
+        if let Ok(checksum_replica) = get_checksum(&query, replica, &address, &table) {
             if !set.contains(&checksum_replica) {
                 failed += 1;
             }
@@ -1239,13 +2050,13 @@ fn read_repair(
     mut rows: Vec<Vec<String>>,
     replicas: Vec<String>,
     node: &String,
-    info_select: (String, Clause, Vec<String>, Vec<String>),
+    info_select: SelectInfo,
     address: &Address,
     table: &Arc<Mutex<MemTable>>,
     key: u128,
 ) -> Result<Vec<Vec<String>>, ErrorTypes> {
     let mut hash = HashMap::new();
-    rows.remove(0);
+    let header = rows.remove(0);
     let len = rows.len();
     hash.insert(node, rows);
     for replica in replicas.iter() {
@@ -1259,6 +2070,8 @@ fn read_repair(
             table_name: &info_select.0,
             needs_ts: true,
             needs_tb: true,
+            limit: info_select.4,
+            after: info_select.5.as_deref(),
         };
         if let Ok(mut rows) = select(address.clone(), replica, &table.lock().unwrap(), query) {
             rows.remove(0);
@@ -1317,43 +2130,192 @@ fn read_repair(
             hash.insert(column.to_string(), max_ts.2[i].to_string());
         }
         let pk = table.lock().unwrap().get_primary_key();
-        let mut condition = Vec::new();
+        let mut conditiona = Clause::Placeholder;
         let mut columns_to_update = info_select.2.clone();
-        for (i, (column, pos)) in pk.iter().enumerate() {
+        for (column, pos) in pk.iter() {
             if hash.contains_key(column) {
                 hash.remove(column);
             }
             columns_to_update.remove(*pos);
-            condition.push(format!("{} = {}", column, max_ts.2[*pos]));
-            if i == pk.len() - 1 {
-                break;
-            }
-            condition.push("AND".to_string());
+            let term = Clause::Term {
+                relation: Relation::Equal {
+                    v1: column.to_string(),
+                    v2: max_ts.2[*pos].to_string(),
+                },
+            };
+            conditiona = match conditiona {
+                Clause::Placeholder => term,
+                left => Clause::And {
+                    left: Box::new(left),
+                    right: Box::new(term),
+                },
+            };
         }
 
-        let conditiona = parse_conditions(condition).unwrap();
+        // La reparacion es de mejor esfuerzo, una replica que falla se repara en la proxima lectura.
+        // Se escribe con el timestamp de la fila mas nueva, para no pisar escrituras posteriores
+        let timestamp = max_ts.1.to_rfc3339();
         for node in to_repair.iter() {
-            update(
-                address.i_address.clone(),
-                key,
-                info_select.0.clone(),
-                hash.clone(),
-                conditiona.clone(),
+            let _ = update(
+                &address.i_address,
+                (key, &timestamp),
+                &info_select.0,
+                &hash,
+                &conditiona,
                 node,
-                Arc::clone(table),
+                table,
             );
         }
         for node in to_insert.iter() {
-            insert(
+            let _ = insert(
                 address,
-                key,
+                (key, &timestamp),
                 max_ts.2.clone(),
                 &info_select.0,
-                &info_select.2.clone(),
-                Arc::clone(table),
+                &info_select.2,
+                table,
                 node,
             );
         }
     }
+    new_rows.retain(|row| !is_tombstone(row));
+    new_rows.insert(0, header);
     Ok(new_rows)
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::protocol::{
+        protocol_notations::cql_value::NULL_TEXT, query_parser::parser_impl::parse_query,
+    };
+
+    #[test]
+    fn test_merge_schema() {
+        let dir = std::env::temp_dir().join("merge_schema_test");
+        let dir_a = dir.join("a").to_string_lossy().to_string();
+        let dir_b = dir.join("b").to_string_lossy().to_string();
+        let mut a = Schema::new(&"9990".to_string(), &dir_a).unwrap();
+        let mut b = Schema::new(&"9991".to_string(), &dir_b).unwrap();
+        a.create_keyspace(&"merge_ks".to_string(), ReplicationStrategy::Simple(2))
+            .unwrap();
+        a.create_table(
+            "merge_ks.t",
+            vec![
+                ("id".to_string(), "int".to_string()),
+                ("name".to_string(), "text".to_string()),
+            ],
+            vec!["id".to_string()],
+            vec!["name".to_string()],
+        )
+        .unwrap();
+        assert_ne!(a.get_version(), b.get_version());
+
+        assert!(b.merge_schema(a.definition()).unwrap());
+        assert_eq!(a.get_version(), b.get_version());
+        assert!(!a.merge_schema(b.definition()).unwrap());
+        assert!(b.get_table_in("merge_ks", "t").is_some());
+
+        let table = b.get_table_in("merge_ks", "t").unwrap();
+        table
+            .lock()
+            .unwrap()
+            .insert_row(
+                1,
+                vec!["1".to_string(), "a".to_string()],
+                vec!["id".to_string(), "name".to_string()],
+                None,
+                None,
+                &chrono::Utc::now().to_rfc3339(),
+            )
+            .unwrap();
+        a.alter_table(
+            "merge_ks.t",
+            AlterTableOperation::Add("gate".to_string(), "text".to_string()),
+        )
+        .unwrap();
+        assert!(a
+            .alter_table("merge_ks.t", AlterTableOperation::Drop("name".to_string()))
+            .is_err());
+        assert!(b.merge_schema(a.definition()).unwrap());
+        assert_eq!(a.get_version(), b.get_version());
+        assert_eq!(
+            table.lock().unwrap().get_row_no_ts(1).unwrap(),
+            vec![vec![
+                "1".to_string(),
+                "a".to_string(),
+                NULL_TEXT.to_string()
+            ]]
+        );
+
+        let stale = b.definition();
+        a.apply_schema_change(SchemaChange::DropTable(
+            "merge_ks.t".to_string(),
+            chrono::Utc::now().timestamp_millis(),
+        ))
+        .unwrap();
+        assert!(!a.merge_schema(stale).unwrap());
+        assert!(!a.contains_table("merge_ks.t"));
+        assert!(b.merge_schema(a.definition()).unwrap());
+        assert!(!b.contains_table("merge_ks.t"));
+        assert_eq!(a.get_version(), b.get_version());
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn test_search_partitions() {
+        let pk = vec!["origin".to_string()];
+        let conditions = |query: &str| match parse_query(query.to_string()).unwrap() {
+            crate::protocol::query_parser::query::Query::Select { conditions, .. } => conditions,
+            _ => panic!("Unexpected query"),
+        };
+        let partitions = search_partitions(
+            &conditions("SELECT * FROM t WHERE origin IN ('EZE', 'AEP') AND id = 1;"),
+            &pk,
+        );
+        assert_eq!(
+            partitions,
+            Some(vec![vec!["EZE".to_string()], vec!["AEP".to_string()]])
+        );
+        let partitions = search_partitions(
+            &conditions("SELECT * FROM t WHERE origin = 'EZE' OR origin = 'MDQ';"),
+            &pk,
+        );
+        assert_eq!(
+            partitions,
+            Some(vec![vec!["EZE".to_string()], vec!["MDQ".to_string()]])
+        );
+        let partitions = search_partitions(
+            &conditions("SELECT * FROM t WHERE origin = 'EZE' OR id = 1;"),
+            &pk,
+        );
+        assert_eq!(partitions, None);
+        let partitions = search_partitions(
+            &conditions("SELECT * FROM t WHERE origin = 'EZE' AND origin = 'AEP';"),
+            &pk,
+        );
+        assert_eq!(partitions, Some(vec![]));
+    }
+
+    #[test]
+    fn test_live_rows() {
+        let rows = [
+            vec!["id", "name", "timestamp"],
+            vec!["1", "a", "2024-11-02T10:00:00+00:00"],
+            vec!["2", "X", "2024-11-02T10:00:01+00:00"],
+            vec!["3", "c", "2024-11-02T10:00:00+00:00"],
+            vec!["4", "d", "2024-11-02T10:00:00+00:00"],
+        ]
+        .iter()
+        .map(|row| row.iter().map(|x| x.to_string()).collect())
+        .collect::<Vec<Vec<String>>>();
+        let names = |limit| {
+            live_rows(rows.clone(), limit)
+                .iter()
+                .map(|row| row[1].clone())
+                .collect::<Vec<String>>()
+        };
+        assert_eq!(names(Some(2)), vec!["name", "a", "c"]);
+        assert_eq!(names(None), vec!["name", "a", "c", "d"]);
+    }
+}
@@ -0,0 +1,142 @@
+use super::{keyspace::Keyspace, mem_table::MemTable};
+use chksum_md5 as md5;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// This struct represents the definition of a table, without its data.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct TableSchema {
+    pub name: String,
+    pub columns_type: Vec<(String, String)>,
+    pub partition_key: Vec<String>,
+    pub clustering_key: Vec<String>,
+    #[serde(default)]
+    pub created_at: i64,
+    #[serde(default)]
+    pub altered_at: i64,
+}
+
+/// This struct represents the definition of a schema: every keyspace with the definition of its tables, sorted by name,
+/// and when every dropped keyspace (`keyspace`) and table (`keyspace.table`) was dropped, in milliseconds.
+///
+/// A keyspace or table created before it was dropped is a copy of a node that missed the drop, so it is not added back.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct SchemaDefinition {
+    pub keyspaces: BTreeMap<String, (Keyspace, BTreeMap<String, TableSchema>)>,
+    pub dropped: BTreeMap<String, i64>,
+}
+
+impl TableSchema {
+    /// This function returns the definition of a table.
+    pub fn from_table(table: &MemTable) -> Self {
+        TableSchema {
+            name: table.table_name.clone(),
+            columns_type: table.columns_type.clone(),
+            partition_key: table
+                .get_partition_key()
+                .into_iter()
+                .map(|(column, _)| column)
+                .collect(),
+            clustering_key: table
+                .get_clustering_key()
+                .into_iter()
+                .map(|(column, _)| column)
+                .collect(),
+            created_at: table.created_at,
+            altered_at: table.altered_at,
+        }
+    }
+
+    /// This function returns when the definition of the table last changed, either because it was created or altered.
+    pub fn changed_at(&self) -> i64 {
+        self.created_at.max(self.altered_at)
+    }
+
+    /// This function creates an empty table with this definition, whose files are stored in the directory `dir`.
+    pub fn to_table(&self, dir: String) -> MemTable {
+        let mut table = MemTable::new(
+            self.columns_type.clone(),
+            self.partition_key.clone(),
+            self.name.clone(),
+            self.clustering_key.clone(),
+            dir,
+        );
+        table.created_at = self.created_at;
+        table.altered_at = self.altered_at;
+        table
+    }
+}
+
+/// This function returns the version of a schema, which is a hash of its definition. Two nodes with the same keyspaces and
+/// tables have the same version, no matter the order in which they were created.
+pub fn schema_version(definition: &SchemaDefinition) -> String {
+    let serialized = serde_json::to_string(definition).unwrap_or_default();
+    match md5::chksum(serialized) {
+        Ok(digest) => digest.to_hex_lowercase(),
+        Err(_) => String::new(),
+    }
+}
+
+/// This function returns whether a keyspace, or a table of it, created at `created_at` was dropped afterwards.
+/// A table is also dropped when its keyspace is.
+pub fn is_dropped(
+    dropped: &BTreeMap<String, i64>,
+    keyspace: &str,
+    table: Option<&str>,
+    created_at: i64,
+) -> bool {
+    let dropped_at = |name: &str| dropped.get(name).is_some_and(|ts| *ts >= created_at);
+    dropped_at(keyspace)
+        || table.is_some_and(|table| dropped_at(&format!("{}.{}", keyspace, table)))
+}
+
+/// This function chooses between two different definitions of the same keyspace or table, created or altered in different nodes.
+/// The newest one prevails and, if both changed at the same time, every node keeps the same one, so their schemas end up agreeing.
+pub fn prevails<T: Serialize>(local: (&T, i64), remote: (&T, i64)) -> bool {
+    if local.1 != remote.1 {
+        return remote.1 > local.1;
+    }
+    serde_json::to_string(remote.0).unwrap_or_default()
+        > serde_json::to_string(local.0).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::server::replication_strategy::ReplicationStrategy;
+
+    #[test]
+    fn test_schema_version() {
+        let table = TableSchema {
+            name: "t".to_string(),
+            columns_type: vec![
+                ("id".to_string(), "int".to_string()),
+                ("name".to_string(), "text".to_string()),
+            ],
+            partition_key: vec!["name".to_string()],
+            clustering_key: vec!["id".to_string()],
+            created_at: 1,
+            altered_at: 0,
+        };
+        let keyspace = Keyspace::new("ks".to_string(), ReplicationStrategy::Simple(2));
+        let mut definition = SchemaDefinition::default();
+        let empty = schema_version(&definition);
+        definition.keyspaces.insert(
+            "ks".to_string(),
+            (keyspace, BTreeMap::from([("t".to_string(), table.clone())])),
+        );
+        let version = schema_version(&definition);
+        assert_ne!(version, empty);
+        assert_eq!(version, schema_version(&definition.clone()));
+        assert_eq!(
+            TableSchema::from_table(&table.to_table("data".to_string())),
+            table
+        );
+
+        definition.dropped.insert("ks.t".to_string(), 1);
+        assert!(is_dropped(&definition.dropped, "ks", Some("t"), 1));
+        assert!(!is_dropped(&definition.dropped, "ks", Some("t"), 2));
+        assert!(!is_dropped(&definition.dropped, "ks", None, 1));
+        assert_ne!(schema_version(&definition), version);
+    }
+}
@@ -7,6 +7,8 @@ pub struct SelectQuery<'a> {
     pub table_name: &'a str,
     pub needs_ts: bool,
     pub needs_tb: bool,
+    pub limit: Option<usize>,
+    pub after: Option<&'a [String]>,
 }
 
 impl<'a> SelectQuery<'a> {
@@ -17,6 +19,7 @@ impl<'a> SelectQuery<'a> {
         table_name: &'a str,
         needs_ts: bool,
         needs_tb: bool,
+        (limit, after): (Option<usize>, Option<&'a [String]>),
     ) -> Self {
         Self {
             conditions,
@@ -25,6 +28,8 @@ impl<'a> SelectQuery<'a> {
             table_name,
             needs_ts,
             needs_tb,
+            limit,
+            after,
         }
     }
 }
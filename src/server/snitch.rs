@@ -0,0 +1,29 @@
+use serde::{Deserialize, Serialize};
+
+pub const DEFAULT_DATACENTER: &str = "dc1";
+pub const DEFAULT_RACK: &str = "rack1";
+
+/// This struct represents where a node is placed in the cluster: its datacenter and its rack.
+///
+/// Every node reads its own location from its configuration and announces it through gossip, so the
+/// replication strategies can place the replicas of a key in different datacenters and racks.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq, Hash)]
+pub struct Location {
+    pub datacenter: String,
+    pub rack: String,
+}
+
+impl Location {
+    pub fn new(datacenter: &str, rack: &str) -> Self {
+        Location {
+            datacenter: datacenter.to_string(),
+            rack: rack.to_string(),
+        }
+    }
+}
+
+impl Default for Location {
+    fn default() -> Self {
+        Location::new(DEFAULT_DATACENTER, DEFAULT_RACK)
+    }
+}
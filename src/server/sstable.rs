@@ -56,7 +56,7 @@ impl SSTable {
                 }
             };
 
-            let mut splitted_line: Vec<String> = line.split(",").map(|x| x.to_string()).collect();
+            let mut splitted_line: Vec<String> = decode_line(&line);
             let time_stamp_line = splitted_line[1..].to_vec();
             splitted_line.pop();
             let id = splitted_line.remove(0).parse::<u128>().unwrap();
@@ -94,13 +94,47 @@ pub fn meets_conditions(
         }
         Clause::Term { relation } => meets_relation(relation, values),
         Clause::Placeholder => Ok(true),
-        _ => Ok(false),
     }
 }
 
+/// This function joins the values of a row in a line of the SSTable. The values with commas or double quotes are written
+/// between double quotes, with their double quotes doubled.
+pub fn encode_line(values: &[String]) -> String {
+    values
+        .iter()
+        .map(|value| match value.contains([',', '"']) {
+            true => format!("\"{}\"", value.replace('"', "\"\"")),
+            false => value.to_string(),
+        })
+        .collect::<Vec<String>>()
+        .join(",")
+}
+
+/// This function splits a line of the SSTable in the values of its row, as `encode_line` wrote them.
+pub fn decode_line(line: &str) -> Vec<String> {
+    let mut values = Vec::new();
+    let mut value = String::new();
+    let mut quoted = false;
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                chars.next();
+                value.push('"');
+            }
+            '"' if quoted => quoted = false,
+            '"' if value.is_empty() => quoted = true,
+            ',' if !quoted => values.push(std::mem::take(&mut value)),
+            c => value.push(c),
+        }
+    }
+    values.push(value);
+    values
+}
+
 /// This function cleans a line from a file removing the id and timestamp.
 pub fn clean_line(line: String) -> Vec<String> {
-    let mut splitted_line: Vec<String> = line.split(",").map(|x| x.to_string()).collect();
+    let mut splitted_line: Vec<String> = decode_line(&line);
     splitted_line.pop();
     splitted_line.remove(0);
     splitted_line
@@ -172,6 +206,10 @@ fn meets_relation(
             }
             Err(ErrorTypes::new(580, "The columns are invalid".to_string()))
         }
+        Relation::In { v1, values: list } => match values.get(v1) {
+            Some(value) => Ok(list.contains(value)),
+            None => Err(ErrorTypes::new(576, "The columns are invalid".to_string())),
+        },
     }
 }
 
@@ -269,4 +307,18 @@ pub mod test {
         assert_eq!(result[1][0], "2");
         assert_eq!(result[2][0], "1");
     }
+
+    #[test]
+    fn test_encode_line() {
+        let values = vec![
+            "1".to_string(),
+            "Buenos Aires, AR".to_string(),
+            "say \"hi\"".to_string(),
+            "".to_string(),
+        ];
+        let line = encode_line(&values);
+        assert_eq!(line, "1,\"Buenos Aires, AR\",\"say \"\"hi\"\"\",");
+        assert_eq!(decode_line(&line), values);
+        assert_eq!(decode_line("1,EZE,X"), vec!["1", "EZE", "X"]);
+    }
 }
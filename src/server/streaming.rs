@@ -0,0 +1,571 @@
+use super::{
+    address::Address,
+    data_dir::{join, streams_dir},
+    gossiper::get_gossiper,
+    log_type::LogType,
+    node_message::NodeMessage,
+    node_state::NodeState,
+    nodes::write_log_message,
+    replication_strategy::ReplicationStrategy,
+    schema::Schema,
+};
+use crate::errors::error_types::ErrorTypes;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    fs,
+    sync::{Arc, Mutex},
+    thread,
+    time::Duration,
+};
+
+const CHUNK_SIZE: usize = 100;
+const ACK_TIMEOUT: u64 = 10;
+const MAX_RETRIES: u64 = 3;
+const STREAM_REQUEST_TIMEOUT: u64 = 300;
+
+/// The tasks that every pair of nodes `(source, target)` has to stream.
+pub type SourcedTasks = HashMap<(String, String), Vec<StreamTask>>;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+/// This struct represents the token ranges of a table that have to be streamed to another node.
+///
+/// ### Fields:
+/// - `keyspace` (`String`): The keyspace of the table.
+/// - `table` (`String`): The name of the table.
+/// - `ranges` (`Vec<(u128, u128)>`): The token ranges to stream.
+/// - `cleanup` (`bool`): If the local node stops replicating the ranges, so it has to delete them once they are acknowledged.
+/// - `done` (`bool`): If the ranges were already streamed.
+/// - `position` (`Option<u128>`): The last key that the target acknowledged, where a resumed task continues.
+pub struct StreamTask {
+    pub keyspace: String,
+    pub table: String,
+    pub ranges: Vec<(u128, u128)>,
+    pub cleanup: bool,
+    pub done: bool,
+    #[serde(default)]
+    pub position: Option<u128>,
+}
+
+impl StreamTask {
+    pub fn new(keyspace: &str, table: &str, ranges: Vec<(u128, u128)>, cleanup: bool) -> Self {
+        StreamTask {
+            keyspace: keyspace.to_string(),
+            table: table.to_string(),
+            ranges,
+            cleanup,
+            done: false,
+            position: None,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+/// This struct represents a streaming session, which sends the ranges of every task to one node.
+///
+/// The session is saved in the data directory while it is running, so a node that fails in the middle of it
+/// can resume the tasks that were not acknowledged.
+///
+/// ### Fields:
+/// - `id` (`String`): The identifier of the session.
+/// - `target` (`String`): The node that receives the rows.
+/// - `tasks` (`Vec<StreamTask>`): The ranges to stream, per table.
+/// - `streamed_rows` (`usize`): The amount of rows that the target already acknowledged.
+pub struct StreamSession {
+    pub id: String,
+    pub target: String,
+    pub tasks: Vec<StreamTask>,
+    pub streamed_rows: usize,
+}
+
+impl StreamSession {
+    pub fn new(target: &str, tasks: Vec<StreamTask>) -> Self {
+        StreamSession {
+            id: format!(
+                "{}_{}",
+                target.replace(":", "_"),
+                chrono::Utc::now().timestamp_millis()
+            ),
+            target: target.to_string(),
+            tasks,
+            streamed_rows: 0,
+        }
+    }
+
+    /// This function streams every task that is not done. Each task is retried before giving up, and a failed session stays saved to be resumed.
+    pub fn run(
+        &mut self,
+        schema: &Arc<Mutex<Schema>>,
+        address: &Address,
+    ) -> Result<(), ErrorTypes> {
+        let data_dir = schema.lock().unwrap().get_data_dir();
+        self.save(&data_dir)?;
+        for index in 0..self.tasks.len() {
+            if self.tasks[index].done {
+                continue;
+            }
+            let mut attempt = 1;
+            while let Err(e) = self.stream_task(index, schema, address) {
+                if attempt == MAX_RETRIES {
+                    write_log_message(
+                        &address.i_port,
+                        LogType::Error,
+                        format!("Streaming session {} failed: {}", self.id, e.get().1),
+                    );
+                    return Err(e);
+                }
+                thread::sleep(Duration::from_secs(attempt));
+                attempt += 1;
+            }
+            self.tasks[index].done = true;
+            self.save(&data_dir)?;
+        }
+        let _ = fs::remove_file(session_path(&data_dir, &self.id));
+        write_log_message(
+            &address.i_port,
+            LogType::Info,
+            format!(
+                "Streaming session {} finished, {} rows sent to {}",
+                self.id, self.streamed_rows, self.target
+            ),
+        );
+        Ok(())
+    }
+
+    /// This function sends the rows of a task in chunks, waiting for the acknowledgement of each one before reading the next.
+    ///
+    /// The last acknowledged key is saved with the session, so a retried or resumed task continues after it. If the local node
+    /// stops replicating the ranges, every chunk is deleted once it is acknowledged.
+    fn stream_task(
+        &mut self,
+        index: usize,
+        schema: &Arc<Mutex<Schema>>,
+        address: &Address,
+    ) -> Result<(), ErrorTypes> {
+        let task = self.tasks[index].clone();
+        let (table, data_dir) = {
+            let schema = schema.lock().unwrap();
+            (
+                schema.get_table_in(&task.keyspace, &task.table),
+                schema.get_data_dir(),
+            )
+        };
+        let table = match table {
+            Some(table) => table,
+            None => return Ok(()),
+        };
+        let gossiper = get_gossiper();
+        if gossiper.get_sender(&self.target).is_none() {
+            gossiper.establish_connection(&self.target, address, Arc::clone(schema));
+        }
+        let sender = match gossiper.get_sender(&self.target) {
+            Some(sender) => sender,
+            None => {
+                return Err(ErrorTypes::new(
+                    533,
+                    format!("There is no connection with {}", self.target),
+                ))
+            }
+        };
+        let mut seq = 0;
+        loop {
+            let position = self.tasks[index].position;
+            let rows = table
+                .lock()
+                .unwrap()
+                .get_rows_page(&task.ranges, position, CHUNK_SIZE)?;
+            let last_key = match rows.last() {
+                Some((key, _)) => *key,
+                None => return Ok(()),
+            };
+            let msg = NodeMessage::StreamChunk(
+                self.id.clone(),
+                task.keyspace.clone(),
+                task.table.clone(),
+                seq,
+                rows.clone(),
+            );
+            if sender.send(msg.to_bytes()).is_err() {
+                return Err(ErrorTypes::new(
+                    534,
+                    format!("Error sending rows to {}", self.target),
+                ));
+            }
+            self.wait_ack(seq)?;
+            if task.cleanup {
+                table.lock().unwrap().delete_streamed_rows(&rows);
+            }
+            self.streamed_rows += rows.len();
+            self.tasks[index].position = Some(last_key);
+            self.save(&data_dir)?;
+            write_log_message(
+                &address.i_port,
+                LogType::Info,
+                format!(
+                    "Streamed {} rows of {}.{} to {}",
+                    rows.len(),
+                    task.keyspace,
+                    task.table,
+                    self.target
+                ),
+            );
+            seq += 1;
+        }
+    }
+
+    /// This function waits until the target acknowledges the chunk `seq` of the session.
+    fn wait_ack(&self, seq: usize) -> Result<(), ErrorTypes> {
+        let gossiper = get_gossiper();
+        let ack = gossiper.receive_matching(
+            &self.target,
+            Duration::from_secs(ACK_TIMEOUT),
+            |message| matches!(message, NodeMessage::StreamAck(id, ack, _) if id == &self.id && *ack == seq),
+        )?;
+        if let Some(NodeMessage::StreamAck(_, _, stored)) = ack {
+            if stored {
+                return Ok(());
+            }
+            return Err(ErrorTypes::new(
+                535,
+                format!("{} could not store the rows", self.target),
+            ));
+        }
+        Err(ErrorTypes::new(
+            536,
+            format!("{} did not acknowledge the rows", self.target),
+        ))
+    }
+
+    /// This function saves the session in the data directory.
+    fn save(&self, data_dir: &str) -> Result<(), ErrorTypes> {
+        let saved = fs::create_dir_all(streams_dir(data_dir)).and_then(|_| {
+            fs::write(
+                session_path(data_dir, &self.id),
+                serde_json::to_string(self).unwrap_or_default(),
+            )
+        });
+        saved.map_err(|_| ErrorTypes::new(583, "Error saving the streaming session".to_string()))
+    }
+}
+
+/// This function returns the route of the file of a session.
+fn session_path(data_dir: &str, id: &str) -> String {
+    join(&streams_dir(data_dir), &format!("{}.json", id))
+}
+
+/// This function resumes the sessions that did not finish the last time the node was running.
+pub fn resume_sessions(schema: &Arc<Mutex<Schema>>, address: &Address) {
+    let data_dir = schema.lock().unwrap().get_data_dir();
+    let entries = match fs::read_dir(streams_dir(&data_dir)) {
+        Ok(entries) => entries,
+        Err(_) => return,
+    };
+    for entry in entries.flatten() {
+        let session = fs::read_to_string(entry.path())
+            .ok()
+            .and_then(|content| serde_json::from_str::<StreamSession>(&content).ok());
+        if let Some(mut session) = session {
+            write_log_message(
+                &address.i_port,
+                LogType::Info,
+                format!("Resuming streaming session {}", session.id),
+            );
+            let _ = session.run(schema, address);
+        }
+    }
+}
+
+/// This function streams to a joining node the ranges that the local node owns and the new node takes over.
+pub fn stream_to_new_node(
+    schema: &Arc<Mutex<Schema>>,
+    new_node: &String,
+    address: &Address,
+) -> Result<(), ErrorTypes> {
+    write_log_message(
+        &address.i_port,
+        LogType::Info,
+        format!("New node {}", new_node),
+    );
+    let tasks = schema
+        .lock()
+        .unwrap()
+        .bootstrap_tasks(new_node, &address.i_address);
+    StreamSession::new(new_node, tasks).run(schema, address)
+}
+
+/// This function deletes the rows of the ranges that the local node does not replicate anymore, like the ones that a
+/// node that joined the ring took over. It returns the amount of deleted rows. Only a `Normal` node can be cleaned up,
+/// since the ranges of the other ones are not in the ring.
+pub fn cleanup(schema: &Arc<Mutex<Schema>>, address: &Address) -> Result<usize, ErrorTypes> {
+    match get_gossiper().get_endpoint_state(&address.i_address) {
+        Some(local) if local.get_state() == NodeState::Normal => {}
+        _ => {
+            return Err(ErrorTypes::new(
+                584,
+                "Only a node in the NORMAL state can be cleaned up".to_string(),
+            ))
+        }
+    }
+    let tasks = schema.lock().unwrap().cleanup_tasks(&address.i_address);
+    let mut deleted = 0;
+    for task in tasks {
+        let table = schema
+            .lock()
+            .unwrap()
+            .get_table_in(&task.keyspace, &task.table);
+        let table = match table {
+            Some(table) => table,
+            None => continue,
+        };
+        let mut position = None;
+        loop {
+            let mut table = table.lock().unwrap();
+            let rows = table.get_rows_page(&task.ranges, position, CHUNK_SIZE)?;
+            position = match rows.last() {
+                Some((key, _)) => Some(*key),
+                None => break,
+            };
+            table.delete_streamed_rows(&rows);
+            deleted += rows.len();
+        }
+    }
+    write_log_message(
+        &address.i_port,
+        LogType::Info,
+        format!("Cleanup finished, {} rows deleted", deleted),
+    );
+    Ok(deleted)
+}
+
+/// This function streams every range of the local node to the nodes that will own it once the local node leaves the ring.
+pub fn stream_from_leaving_node(
+    schema: &Arc<Mutex<Schema>>,
+    address: &Address,
+) -> Result<(), ErrorTypes> {
+    let sessions = schema
+        .lock()
+        .unwrap()
+        .decommission_tasks(&address.i_address);
+    for (target, tasks) in sessions {
+        StreamSession::new(&target, tasks).run(schema, address)?;
+    }
+    Ok(())
+}
+
+/// This function replicates again the ranges of a dead node, asking a surviving replica of every range to stream it to its new owner.
+/// If there is a `replacement`, it is the node that takes the tokens of the dead node and receives every range.
+pub fn restore_replicas(
+    schema: &Arc<Mutex<Schema>>,
+    address: &Address,
+    dead: &String,
+    replacement: Option<&String>,
+) -> Result<(), ErrorTypes> {
+    let sessions = schema.lock().unwrap().restore_tasks(dead, replacement);
+    run_sessions(schema, address, sessions)
+}
+
+/// This function streams the ranges of a keyspace to the nodes that start replicating them after its replication changed from `before`.
+/// The nodes that stop replicating some range keep their copy, so it is logged that they can be cleaned.
+pub fn stream_new_replicas(
+    schema: &Arc<Mutex<Schema>>,
+    address: &Address,
+    keyspace_name: &str,
+    before: &ReplicationStrategy,
+) -> Result<(), ErrorTypes> {
+    let (sessions, leaving) = schema
+        .lock()
+        .unwrap()
+        .replication_tasks(keyspace_name, before)?;
+    if !leaving.is_empty() {
+        let mut leaving = leaving.into_iter().collect::<Vec<String>>();
+        leaving.sort();
+        write_log_message(
+            &address.i_port,
+            LogType::Info,
+            format!(
+                "{} stop replicating ranges of {}, their copies can be deleted",
+                leaving.join(", "),
+                keyspace_name
+            ),
+        );
+    }
+    run_sessions(schema, address, sessions)
+}
+
+/// This function runs every session, grouped by source and target. The local node streams its own sessions and asks the
+/// other sources to stream theirs.
+fn run_sessions(
+    schema: &Arc<Mutex<Schema>>,
+    address: &Address,
+    sessions: SourcedTasks,
+) -> Result<(), ErrorTypes> {
+    for ((source, target), tasks) in sessions {
+        if source == address.i_address {
+            StreamSession::new(&target, tasks).run(schema, address)?;
+        } else {
+            request_stream(schema, address, &source, &target, tasks)?;
+        }
+    }
+    Ok(())
+}
+
+/// This function asks `source` to stream the ranges of the tasks to `target` and waits until it finishes.
+fn request_stream(
+    schema: &Arc<Mutex<Schema>>,
+    address: &Address,
+    source: &String,
+    target: &String,
+    tasks: Vec<StreamTask>,
+) -> Result<(), ErrorTypes> {
+    let gossiper = get_gossiper();
+    if gossiper.get_sender(source).is_none() {
+        gossiper.establish_connection(source, address, Arc::clone(schema));
+    }
+    let sender = match gossiper.get_sender(source) {
+        Some(sender) => sender,
+        None => {
+            return Err(ErrorTypes::new(
+                533,
+                format!("There is no connection with {}", source),
+            ))
+        }
+    };
+    write_log_message(
+        &address.i_port,
+        LogType::Info,
+        format!(
+            "Asking {} to stream {} tables to {}",
+            source,
+            tasks.len(),
+            target
+        ),
+    );
+    let msg = NodeMessage::StreamRequest(target.to_string(), tasks);
+    if sender.send(msg.to_bytes()).is_err() {
+        return Err(ErrorTypes::new(
+            534,
+            format!("Error sending the streaming request to {}", source),
+        ));
+    }
+    let response = gossiper.receive_matching(
+        source,
+        Duration::from_secs(STREAM_REQUEST_TIMEOUT),
+        |message| matches!(message, NodeMessage::StreamResponse(_)),
+    )?;
+    if let Some(NodeMessage::StreamResponse(streamed)) = response {
+        if streamed {
+            return Ok(());
+        }
+        return Err(ErrorTypes::new(
+            535,
+            format!("{} could not stream its ranges to {}", source, target),
+        ));
+    }
+    Err(ErrorTypes::new(
+        536,
+        format!("{} did not finish streaming to {}", source, target),
+    ))
+}
+
+#[cfg(test)]
+pub mod test {
+    use super::*;
+    use std::{
+        io::{Read, Write},
+        net::TcpListener,
+    };
+
+    /// This function answers the chunks of the sessions like a target node, refusing the chunk `refused` the first time.
+    /// It returns the amount of rows of every chunk it received.
+    fn fake_target(listener: TcpListener, refused: usize) -> Arc<Mutex<Vec<usize>>> {
+        let chunks = Arc::new(Mutex::new(Vec::new()));
+        let received = Arc::clone(&chunks);
+        thread::spawn(move || {
+            let (mut socket, _) = listener.accept().unwrap();
+            let mut refused = Some(refused);
+            let mut header = [0; 9];
+            while socket.read_exact(&mut header).is_ok() {
+                let mut bytes =
+                    vec![0; u64::from_be_bytes(header[1..].try_into().unwrap()) as usize];
+                socket.read_exact(&mut bytes).unwrap();
+                let message = NodeMessage::from_bytes([header[1..].to_vec(), bytes].concat());
+                if let NodeMessage::StreamChunk(id, _, _, seq, rows) = message {
+                    let mut received = received.lock().unwrap();
+                    received.push(rows.len());
+                    let stored = refused != Some(received.len() - 1);
+                    if !stored {
+                        refused = None;
+                    }
+                    let ack = NodeMessage::StreamAck(id, seq, stored);
+                    socket.write_all(&ack.to_bytes()).unwrap();
+                }
+            }
+        });
+        chunks
+    }
+
+    #[test]
+    fn test_stream_session() {
+        let dir = std::env::temp_dir().join("stream_session_test");
+        let mut schema = Schema::new(&"9993".to_string(), &dir.to_string_lossy()).unwrap();
+        schema
+            .create_keyspace(&"stream_ks".to_string(), ReplicationStrategy::Simple(1))
+            .unwrap();
+        schema
+            .create_table(
+                "stream_ks.t",
+                vec![
+                    ("id".to_string(), "int".to_string()),
+                    ("name".to_string(), "text".to_string()),
+                ],
+                vec!["name".to_string()],
+                vec!["id".to_string()],
+            )
+            .unwrap();
+        let table = schema.get_table_in("stream_ks", "t").unwrap();
+        let ts = "2024-11-02T10:00:00+00:00".to_string();
+        for key in 0..250 {
+            let row = vec![key.to_string(), format!("n{}", key), ts.clone()];
+            table.lock().unwrap().insert_streamed_row(key, row).unwrap();
+        }
+        let schema = Arc::new(Mutex::new(schema));
+        let address = Address {
+            i_address: "127.0.0.1:9993".to_string(),
+            c_address: "127.0.0.1:9994".to_string(),
+            i_port: "9993".to_string(),
+        };
+
+        // El segundo chunk se rechaza una vez, y el reintento sigue desde el ultimo chunk confirmado
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let target = listener.local_addr().unwrap().to_string();
+        let chunks = fake_target(listener, 1);
+        let task = StreamTask::new("stream_ks", "t", vec![(0, u128::MAX)], false);
+        let mut session = StreamSession::new(&target, vec![task]);
+        session.run(&schema, &address).unwrap();
+        assert_eq!(*chunks.lock().unwrap(), vec![100, 100, 100, 50]);
+        assert_eq!(session.streamed_rows, 250);
+        assert_eq!(session.tasks[0].position, Some(249));
+
+        // Una sesion retomada solo manda las filas despues de la ultima confirmada
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let target = listener.local_addr().unwrap().to_string();
+        let chunks = fake_target(listener, usize::MAX);
+        let mut task = StreamTask::new("stream_ks", "t", vec![(0, u128::MAX)], true);
+        task.position = Some(199);
+        let mut session = StreamSession::new(&target, vec![task]);
+        session.run(&schema, &address).unwrap();
+        assert_eq!(*chunks.lock().unwrap(), vec![50]);
+        assert_eq!(
+            table
+                .lock()
+                .unwrap()
+                .get_rows_page(&[(0, u128::MAX)], None, 300)
+                .unwrap()
+                .len(),
+            200
+        );
+
+        let _ = fs::remove_dir_all(dir);
+        let _ = fs::remove_file("node9993_log.log");
+    }
+}
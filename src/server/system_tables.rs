@@ -0,0 +1,428 @@
+use super::{
+    address::Address, endpoint_state::EndpointState, gossiper::get_gossiper, node_state::NodeState,
+    schema::Schema, schema_definition::SchemaDefinition, sstable::meets_conditions,
+};
+use crate::{
+    errors::error_types::ErrorTypes,
+    protocol::{
+        frames_headers::version::PROTOCOL_VERSION,
+        protocol_body::data_type::DataType,
+        protocol_notations::{columns_metadata::ColumnsMetadata, cql_value::CqlValue},
+        query_parser::clause::Clause,
+    },
+};
+use chksum_md5 as md5;
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+
+const CLUSTER_NAME: &str = "Aerolineas Rusticas";
+const CQL_VERSION: &str = "3.0.0";
+const RELEASE_VERSION: &str = "4.0.0";
+/// The tokens of the ring are the 128 bits hash of murmur3_x64_128, not the 64 bits tokens of Cassandra's
+/// Murmur3Partitioner, so the partitioner has its own name and the drivers do not route queries by those tokens.
+const PARTITIONER: &str = "aerolineas_rusticas.Murmur3x128Partitioner";
+
+/// The keyspaces of the read-only tables, that the clients can not write nor change.
+pub const SYSTEM_KEYSPACES: [&str; 2] = ["system", "system_schema"];
+
+type SystemRows = (ColumnsMetadata, Vec<Vec<CqlValue>>);
+
+/// The columns of `system.local`, the node that answers the query.
+const LOCAL_COLUMNS: [(&str, DataType); 15] = [
+    ("key", DataType::Varchar),
+    ("bootstrapped", DataType::Varchar),
+    ("broadcast_address", DataType::Inet),
+    ("cluster_name", DataType::Varchar),
+    ("cql_version", DataType::Varchar),
+    ("data_center", DataType::Varchar),
+    ("host_id", DataType::Uuid),
+    ("listen_address", DataType::Inet),
+    ("native_protocol_version", DataType::Varchar),
+    ("partitioner", DataType::Varchar),
+    ("rack", DataType::Varchar),
+    ("release_version", DataType::Varchar),
+    ("rpc_address", DataType::Inet),
+    ("schema_version", DataType::Uuid),
+    ("tokens", DataType::Set),
+];
+
+/// The columns of `system.peers`, every other node of the ring. `peer` and `rpc_address` are the IP where the node
+/// talks with the clients and `native_port` its port, since the nodes of a cluster may share the IP. `preferred_ip` is
+/// the internal IP of the node, and `status` is `UP` or `DOWN`, as the gossiper sees it.
+const PEERS_COLUMNS: [(&str, DataType); 11] = [
+    ("peer", DataType::Inet),
+    ("data_center", DataType::Varchar),
+    ("host_id", DataType::Uuid),
+    ("native_port", DataType::Int),
+    ("preferred_ip", DataType::Inet),
+    ("rack", DataType::Varchar),
+    ("release_version", DataType::Varchar),
+    ("rpc_address", DataType::Inet),
+    ("schema_version", DataType::Uuid),
+    ("status", DataType::Varchar),
+    ("tokens", DataType::Set),
+];
+
+/// The columns of `system_schema.keyspaces`.
+const KEYSPACES_COLUMNS: [(&str, DataType); 3] = [
+    ("keyspace_name", DataType::Varchar),
+    ("durable_writes", DataType::Boolean),
+    ("replication", DataType::Map),
+];
+
+/// The columns of `system_schema.tables`.
+const TABLES_COLUMNS: [(&str, DataType); 3] = [
+    ("keyspace_name", DataType::Varchar),
+    ("table_name", DataType::Varchar),
+    ("id", DataType::Uuid),
+];
+
+/// The columns of `system_schema.columns`. `kind` is `partition_key`, `clustering` or `regular`, and `position` is
+/// the one of the column in its key, or -1 for a regular column.
+const COLUMNS_COLUMNS: [(&str, DataType); 7] = [
+    ("keyspace_name", DataType::Varchar),
+    ("table_name", DataType::Varchar),
+    ("column_name", DataType::Varchar),
+    ("clustering_order", DataType::Varchar),
+    ("kind", DataType::Varchar),
+    ("position", DataType::Int),
+    ("type", DataType::Varchar),
+];
+
+/// This function answers a select of one of the read-only tables that describe the cluster: `system.local` and
+/// `system.peers`, with the nodes of the ring, and `system_schema.keyspaces`, `system_schema.tables` and
+/// `system_schema.columns`, with the schema. The nodes do not store them, their rows are built from the state of the
+/// gossiper and from the schema when they are read. It returns `None` if the table is not one of them.
+///
+/// The conditions, the limit and the limit per partition apply as in the other tables, without `ALLOW FILTERING`
+/// because the tables are small. The first column of every table is its partition key.
+pub fn select_system_table(
+    schema: &Arc<Mutex<Schema>>,
+    info_select: &(String, Clause, Vec<String>, Vec<String>),
+    (limit, per_partition_limit): (Option<usize>, Option<usize>),
+    address: &Address,
+) -> Result<Option<SystemRows>, ErrorTypes> {
+    let (table_name, conditions, selected_columns, order) = info_select;
+    let (columns, rows): (&[(&str, DataType)], Vec<Vec<String>>) = match table_name.as_str() {
+        "system.local" => (&LOCAL_COLUMNS, vec![local_row(address)]),
+        "system.peers" => (&PEERS_COLUMNS, peers_rows(address)),
+        "system_schema.keyspaces" => (
+            &KEYSPACES_COLUMNS,
+            keyspaces_rows(&schema.lock().unwrap().definition()),
+        ),
+        "system_schema.tables" => (
+            &TABLES_COLUMNS,
+            tables_rows(&schema.lock().unwrap().definition()),
+        ),
+        "system_schema.columns" => (
+            &COLUMNS_COLUMNS,
+            columns_rows(&schema.lock().unwrap().definition()),
+        ),
+        _ => return Ok(None),
+    };
+    if !order.is_empty() {
+        return Err(ErrorTypes::new(
+            574,
+            "ORDER BY is not supported in the system tables".to_string(),
+        ));
+    }
+    let names = columns
+        .iter()
+        .map(|(column, _)| column.to_string())
+        .collect::<Vec<String>>();
+    let mut partitions: HashMap<String, usize> = HashMap::new();
+    let mut selected_rows = Vec::new();
+    for row in rows {
+        let values = names
+            .iter()
+            .zip(row.iter().cloned())
+            .collect::<HashMap<&String, String>>();
+        if !meets_conditions(&values, conditions)? {
+            continue;
+        }
+        let in_partition = partitions.entry(row[0].clone()).or_default();
+        if per_partition_limit.is_some_and(|per_partition| *in_partition >= per_partition) {
+            continue;
+        }
+        *in_partition += 1;
+        selected_rows.push(row);
+        if limit.is_some_and(|limit| selected_rows.len() >= limit) {
+            break;
+        }
+    }
+    let indexes = match selected_columns.as_slice() {
+        [all] if all == "*" => (0..columns.len()).collect(),
+        _ => selected_columns
+            .iter()
+            .map(|selected| {
+                names
+                    .iter()
+                    .position(|column| column == selected)
+                    .ok_or_else(|| {
+                        ErrorTypes::new(573, format!("Undefined column name {}", selected))
+                    })
+            })
+            .collect::<Result<Vec<usize>, ErrorTypes>>()?,
+    };
+    let mut metadata = ColumnsMetadata::new(
+        table_name,
+        indexes
+            .iter()
+            .map(|i| (columns[*i].0.to_string(), columns[*i].1))
+            .collect(),
+    );
+    let rows = selected_rows
+        .iter()
+        .map(|row| indexes.iter().map(|i| row[*i].clone()).collect())
+        .collect::<Vec<Vec<String>>>();
+    let rows = metadata.parse_rows(&rows);
+    Ok(Some((metadata, rows)))
+}
+
+/// This function returns the row of `system.local`, with the client address of the node as its `rpc_address`.
+fn local_row(address: &Address) -> Vec<String> {
+    let endpoint = get_gossiper().get_endpoint_state(&address.i_address);
+    let location = endpoint
+        .as_ref()
+        .map(|endpoint| endpoint.get_location())
+        .unwrap_or_default();
+    vec![
+        "local".to_string(),
+        bootstrapped(endpoint.as_ref()),
+        ip(&address.i_address),
+        CLUSTER_NAME.to_string(),
+        CQL_VERSION.to_string(),
+        location.datacenter,
+        uuid(&address.i_address),
+        ip(&address.i_address),
+        PROTOCOL_VERSION.to_string(),
+        PARTITIONER.to_string(),
+        location.rack,
+        RELEASE_VERSION.to_string(),
+        ip(&address.c_address),
+        endpoint
+            .as_ref()
+            .map(|endpoint| endpoint.get_schema_version())
+            .unwrap_or_default(),
+        tokens(endpoint.as_ref()),
+    ]
+}
+
+/// This function returns the rows of `system.peers`, one for every other node that the gossiper knows.
+fn peers_rows(address: &Address) -> Vec<Vec<String>> {
+    let endpoints = get_gossiper().endpoint_state_map.lock().unwrap().clone();
+    let mut peers = endpoints
+        .into_values()
+        .filter(|endpoint| endpoint.get_address() != address.i_address)
+        .collect::<Vec<EndpointState>>();
+    peers.sort_by_key(|endpoint| endpoint.get_address());
+    peers
+        .iter()
+        .map(|endpoint| {
+            let peer = endpoint.get_address();
+            let native = endpoint.get_native_address();
+            let location = endpoint.get_location();
+            vec![
+                ip(&native),
+                location.datacenter,
+                uuid(&peer),
+                port(&native),
+                ip(&peer),
+                location.rack,
+                RELEASE_VERSION.to_string(),
+                ip(&native),
+                endpoint.get_schema_version(),
+                match endpoint.is_down() {
+                    true => "DOWN".to_string(),
+                    false => "UP".to_string(),
+                },
+                tokens(Some(endpoint)),
+            ]
+        })
+        .collect()
+}
+
+/// This function returns the rows of `system_schema.keyspaces`, one for every keyspace of the schema.
+fn keyspaces_rows(definition: &SchemaDefinition) -> Vec<Vec<String>> {
+    definition
+        .keyspaces
+        .iter()
+        .map(|(name, (keyspace, _))| {
+            vec![
+                name.to_string(),
+                true.to_string(),
+                CqlValue::Map(keyspace.replication.to_options()).to_string(),
+            ]
+        })
+        .collect()
+}
+
+/// This function returns the rows of `system_schema.tables`, one for every table of the schema.
+fn tables_rows(definition: &SchemaDefinition) -> Vec<Vec<String>> {
+    definition
+        .keyspaces
+        .iter()
+        .flat_map(|(keyspace, (_, tables))| {
+            tables.keys().map(move |table| {
+                vec![
+                    keyspace.to_string(),
+                    table.to_string(),
+                    uuid(&format!("{}.{}", keyspace, table)),
+                ]
+            })
+        })
+        .collect()
+}
+
+/// This function returns the rows of `system_schema.columns`, one for every column of every table of the schema,
+/// sorted by their names as Cassandra does.
+fn columns_rows(definition: &SchemaDefinition) -> Vec<Vec<String>> {
+    let mut rows = Vec::new();
+    for (keyspace, (_, tables)) in &definition.keyspaces {
+        for (table, table_schema) in tables {
+            let mut columns = table_schema.columns_type.clone();
+            columns.sort();
+            for (column, column_type) in columns {
+                let partition = table_schema
+                    .partition_key
+                    .iter()
+                    .position(|key| *key == column);
+                let clustering = table_schema
+                    .clustering_key
+                    .iter()
+                    .position(|key| *key == column);
+                let (kind, position, order) = match (partition, clustering) {
+                    (Some(position), _) => ("partition_key", position as i32, "none"),
+                    (None, Some(position)) => ("clustering", position as i32, "asc"),
+                    (None, None) => ("regular", -1, "none"),
+                };
+                rows.push(vec![
+                    keyspace.to_string(),
+                    table.to_string(),
+                    column,
+                    order.to_string(),
+                    kind.to_string(),
+                    position.to_string(),
+                    column_type.to_lowercase(),
+                ]);
+            }
+        }
+    }
+    rows
+}
+
+/// This function returns how far the node is in joining the ring, with the names Cassandra uses.
+fn bootstrapped(endpoint: Option<&EndpointState>) -> String {
+    match endpoint.map(|endpoint| endpoint.get_state()) {
+        Some(NodeState::Normal) | Some(NodeState::Leaving) => "COMPLETED",
+        Some(NodeState::Joining) => "IN_PROGRESS",
+        Some(NodeState::Left) => "DECOMMISSIONED",
+        None => "NEEDS_BOOTSTRAP",
+    }
+    .to_string()
+}
+
+/// This function returns the IP of an address with its port, like `127.0.0.1` for `127.0.0.1:8080`.
+fn ip(address: &str) -> String {
+    address
+        .rsplit_once(':')
+        .map_or(address, |(ip, _)| ip)
+        .to_string()
+}
+
+/// This function returns the port of an address, like `9042` for `127.0.0.1:9042`.
+fn port(address: &str) -> String {
+    address
+        .rsplit_once(':')
+        .map_or("", |(_, port)| port)
+        .to_string()
+}
+
+/// This function returns a UUID that is always the same for a text, like the identifier of a node from its internal
+/// address or the one of a table from its name. It is a name based UUID of version 3 (RFC 4122): the md5 of the text
+/// with the bits of the version and of the variant set, like `6ba7b810-9dad-31d1-80b4-00c04fd430c8`.
+fn uuid(text: &str) -> String {
+    let Ok(digest) = md5::chksum(text) else {
+        return String::new();
+    };
+    let mut bytes = digest.as_bytes().to_vec();
+    bytes[6] = (bytes[6] & 0x0f) | 0x30;
+    bytes[8] = (bytes[8] & 0x3f) | 0x80;
+    let hex = bytes
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect::<String>();
+    format!(
+        "{}-{}-{}-{}-{}",
+        &hex[0..8],
+        &hex[8..12],
+        &hex[12..16],
+        &hex[16..20],
+        &hex[20..32]
+    )
+}
+
+/// This function returns the tokens of a node as the text of a set.
+fn tokens(endpoint: Option<&EndpointState>) -> String {
+    let tokens = endpoint
+        .map(|endpoint| endpoint.get_tokens())
+        .unwrap_or_default();
+    CqlValue::Set(tokens.iter().map(|token| token.to_string()).collect()).to_string()
+}
+
+#[cfg(test)]
+pub mod test {
+    use super::*;
+    use crate::server::{
+        application_state::ApplicationState, heartbeat_state::HeartbeatState, snitch::Location,
+        status::Status,
+    };
+
+    #[test]
+    fn test_peers_rows() {
+        let address = Address {
+            i_address: "127.0.0.1:18080".to_string(),
+            c_address: "127.0.0.1:19042".to_string(),
+            i_port: "18080".to_string(),
+        };
+        for (internal, native) in [
+            (&address.i_address, &address.c_address),
+            (
+                &"127.0.0.2:18081".to_string(),
+                &"127.0.0.3:19043".to_string(),
+            ),
+        ] {
+            let mut application_state =
+                ApplicationState::new(Status::Up, internal.to_string(), Location::default());
+            application_state.set_native_address(native.to_string());
+            get_gossiper().put_endpoint_state(
+                internal.to_string(),
+                EndpointState::new(HeartbeatState::new(), application_state),
+            );
+        }
+        let peers = peers_rows(&address);
+        assert!(!peers.iter().any(|row| row[2] == uuid(&address.i_address)));
+        let peer = peers
+            .iter()
+            .find(|row| row[2] == uuid("127.0.0.2:18081"))
+            .unwrap();
+        assert_eq!(peer[0], "127.0.0.3");
+        assert_eq!(peer[3], "19043");
+        assert_eq!(peer[4], "127.0.0.2");
+        assert_eq!(peer[7], "127.0.0.3");
+        assert_eq!(peer[9], "UP");
+    }
+
+    #[test]
+    fn test_uuid() {
+        let id = uuid("127.0.0.1:8080");
+        assert_eq!(id, uuid("127.0.0.1:8080"));
+        assert_ne!(id, uuid("127.0.0.1:8081"));
+        let groups = id.split('-').map(str::len).collect::<Vec<usize>>();
+        assert_eq!(groups, vec![8, 4, 4, 4, 12]);
+        assert_eq!(&id[14..15], "3");
+        assert!(matches!(&id[19..20], "8" | "9" | "a" | "b"));
+    }
+}
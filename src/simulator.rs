@@ -1,9 +1,10 @@
 use aerolineas_rusticas::{
     errors::error_types::ErrorTypes,
-    protocol::protocol_body::compression::Compression,
+    protocol::{protocol_body::compression::Compression, protocol_notations::cql_value::CqlValue},
     server::{
         query_execute::{
             authenticate, get_airports, insert_simulador, prompt, send_querys, startup,
+            use_keyspace,
         },
         query_simulator::QuerySimulator,
     },
@@ -63,6 +64,7 @@ pub fn conect_server() -> Result<TlsStream<TcpStream>, ErrorTypes> {
         COMPRESSION,
     )?;
     println!("Simulator authenticated!");
+    use_keyspace(&mut server, COMPRESSION)?;
     Ok(server)
 }
 
@@ -99,19 +101,26 @@ pub fn update_flight(
     Ok(())
 }
 
-/// This function creates the query to update the flight position.
-fn create_update_query(table: &str, flight: &Flight, field: &str, place: &str) -> String {
-    format!(
-        "UPDATE {} SET fuel = {}, distance_traveled = {}, velocity = {}, height = {}, latitude = {}, longitude = {} WHERE id = {} AND {} = '{}';",
-        table,
-        flight.get_fuel(),
-        flight.get_distance_traveled(),
-        flight.get_velocity(),
-        flight.get_height(),
-        flight.get_latitude(),
-        flight.get_longitude(),
-        flight.get_flight_code(),
-        field,
-        place
-    )
+/// This function creates the query to update the flight position, with the values bound to its bind markers.
+fn create_update_query(
+    table: &str,
+    flight: &Flight,
+    field: &str,
+    place: &str,
+) -> (String, Vec<CqlValue>) {
+    let query = format!(
+        "UPDATE {} SET fuel = ?, distance_traveled = ?, velocity = ?, height = ?, latitude = ?, longitude = ? WHERE id = ? AND {} = ?;",
+        table, field
+    );
+    let values = vec![
+        CqlValue::Float(flight.get_fuel() as f32),
+        CqlValue::Float(flight.get_distance_traveled() as f32),
+        CqlValue::Float(flight.get_velocity() as f32),
+        CqlValue::Float(flight.get_height() as f32),
+        CqlValue::Float(flight.get_latitude() as f32),
+        CqlValue::Float(flight.get_longitude() as f32),
+        CqlValue::Int(flight.get_flight_code()),
+        CqlValue::Text(place.to_string()),
+    ];
+    (query, values)
 }
@@ -1,12 +1,13 @@
 use native_tls::TlsStream;
-use std::collections::HashMap;
+use std::collections::{HashMap, VecDeque};
 use std::net::TcpStream;
+use std::sync::mpsc::Receiver;
 use std::time::Instant;
 
 use crate::protocol;
 use crate::ui::{map::CustomMapMemory, places, plugins};
 
-use protocol::protocol_body::compression::Compression;
+use protocol::protocol_body::{compression::Compression, event_kind::EventKindChange};
 
 use egui::Context;
 use walkers::{HttpOptions, HttpTiles, Map, Tiles};
@@ -80,6 +81,9 @@ fn providers(egui_ctx: Context) -> HashMap<Provider, Box<dyn Tiles + Send>> {
     providers
 }
 
+/// The amount of events of the cluster that the application shows.
+const SHOWN_EVENTS: usize = 5;
+
 /// Main application structure.
 pub struct MyApp<'a> {
     providers: HashMap<Provider, Box<dyn Tiles + Send>>,
@@ -87,6 +91,8 @@ pub struct MyApp<'a> {
     map_memory: CustomMapMemory,
     click_watcher: plugins::ClickWatcher,
     tcp_stream: &'a mut TlsStream<TcpStream>,
+    events: Receiver<EventKindChange>,
+    cluster_events: VecDeque<EventKindChange>,
 }
 
 impl<'a> MyApp<'a> {
@@ -94,6 +100,7 @@ impl<'a> MyApp<'a> {
     ///
     /// # Arguments
     /// * `egui_ctx` - The Egui context.
+    /// * `events` - The events of the cluster that the server sends.
     ///
     /// # Returns
     /// `MyApp` - A new instance of `MyApp`.
@@ -101,6 +108,7 @@ impl<'a> MyApp<'a> {
         egui_ctx: Context,
         server: &'a mut TlsStream<TcpStream>,
         compression: Option<Compression>,
+        events: Receiver<EventKindChange>,
     ) -> Self {
         let _ = compression;
 
@@ -112,6 +120,8 @@ impl<'a> MyApp<'a> {
             map_memory: CustomMapMemory::default(),
             click_watcher: ClickWatcher::new(CustomMapMemory::new()),
             tcp_stream: server,
+            events,
+            cluster_events: VecDeque::new(),
         }
     }
 }
@@ -130,6 +140,13 @@ impl eframe::App for MyApp<'_> {
 
         let start = Instant::now();
 
+        for event in self.events.try_iter() {
+            if self.cluster_events.len() == SHOWN_EVENTS {
+                self.cluster_events.pop_front();
+            }
+            self.cluster_events.push_back(event);
+        }
+
         egui::CentralPanel::default()
             .frame(rimless)
             .show(ctx, |ui| {
@@ -160,6 +177,7 @@ impl eframe::App for MyApp<'_> {
                     zoom(ui, &mut self.map_memory.map_memory);
                     //go_to_my_position(ui, &mut self.map_memory.map_memory);
                     self.click_watcher.show_position(ui);
+                    cluster_events(ui, &self.cluster_events);
                     let _ = self.click_watcher.clicked_airport_info(
                         ui,
                         self.tcp_stream,
@@ -239,13 +239,10 @@ impl ClickWatcher {
                                     return;
                                 }
                             };
-                            let headers = plane_data[0].clone();
-                            let data = plane_data[1].to_vec();
-                            let result = (headers, data);
 
-                            for (header, value) in result.0.iter().zip(result.1.iter()) {
+                            for (header, value) in plane_data.iter() {
                                 if header == "status" {
-                                    actual_status = value.clone();
+                                    actual_status = value.to_string();
                                 }
                                 ui.vertical(|ui| {
                                     ui.label(format!("{}: {}", header, value));
@@ -401,16 +398,14 @@ impl Plugin for &mut ClickWatcher {
                 .clone()
                 .unwrap()
                 .arrivals
-                .len()
-                == 1
+                .is_empty()
                 && self
                     .app_state
                     .search_results
                     .clone()
                     .unwrap()
                     .departures
-                    .len()
-                    == 1
+                    .is_empty()
             {
                 return;
             }
@@ -427,10 +422,7 @@ impl Plugin for &mut ClickWatcher {
             let colors = [Color32::RED, Color32::GREEN];
 
             for (i, result) in vec.iter().enumerate() {
-                if result.len() <= 1 {
-                    continue;
-                }
-                for plane_directions in &result[1..] {
+                for plane_directions in result {
                     let origin = self
                         .map_memory
                         .airport_locations_by_name
@@ -11,17 +11,18 @@ use crate::receiver::response_message::ResponseMessage;
 use crate::{
     errors::error_types::ErrorTypes,
     protocol::{
-        protocol_body::{compression::Compression, query_flags::QueryFlags},
-        protocol_notations::consistency,
-        protocol_writer::Protocol,
+        protocol_body::{compression::Compression, data_type::DataType},
+        protocol_notations::{consistency::Consistency, cql_value::CqlValue},
     },
     receiver::result_response::ResultResponse,
-    server::query_execute::conect_server,
+    server::query_execute::execute,
 };
 
 use super::windows::{get_planes_positions, make_query, Airport, AppState};
 
 const COMPRESSION: Option<Compression> = None;
+const ARRIVAL_COLUMNS: [&str; 5] = ["id", "status", "origin", "destination", "arrival_time"];
+const DEPARTURE_COLUMNS: [&str; 5] = ["id", "status", "origin", "destination", "departure_time"];
 
 #[derive(Clone)]
 pub enum SearchType {
@@ -32,6 +33,7 @@ pub enum SearchType {
 // Struct to encapsulate search results
 #[derive(Default, Clone, Debug)]
 pub struct SearchResults {
+    pub columns: Vec<String>,         // Names of the columns of the flights
     pub arrivals: Vec<Vec<String>>,   // Results for arrivals
     pub departures: Vec<Vec<String>>, // Results for departures
 }
@@ -80,7 +82,8 @@ impl AppState {
                                     SearchType::Departures,
                                 ),
                             ) {
-                                (Ok(arrivals), Ok(departures)) => SearchResults {
+                                (Ok((columns, arrivals)), Ok((_, departures))) => SearchResults {
+                                    columns,
                                     arrivals,
                                     departures,
                                 },
@@ -91,7 +94,7 @@ impl AppState {
                                 }
                             };
                             self.search_results = Some(results);
-                        } else if enlapsed as u32 % 5 == 0 {
+                        } else if (enlapsed as u32).is_multiple_of(5) {
                             if let Some(results) = &mut self.search_results {
                                 match make_query(
                                     airport,
@@ -99,7 +102,7 @@ impl AppState {
                                     server,
                                     SearchType::Arrivals,
                                 ) {
-                                    Ok(arrivals) => results.arrivals = arrivals,
+                                    Ok((_, arrivals)) => results.arrivals = arrivals,
                                     Err(_) => {
                                         self.search_error =
                                             Some("Failed to fetch arrivals".to_string());
@@ -112,7 +115,7 @@ impl AppState {
                                     server,
                                     SearchType::Departures,
                                 ) {
-                                    Ok(departures) => results.departures = departures,
+                                    Ok((_, departures)) => results.departures = departures,
                                     Err(_) => {
                                         self.search_error =
                                             Some("Failed to fetch departures".to_string());
@@ -127,29 +130,23 @@ impl AppState {
                             ui.separator();
                             {
                                 ui.collapsing(RichText::new("Arrivals").size(18.0), |ui| {
-                                    for arrival in &results.arrivals {
-                                        let mut text = String::new();
-                                        for a in &arrival[..5] {
-                                            text.push_str(a);
-                                            text.push_str(" | ");
-                                        }
-                                        ui.label(RichText::new(text).size(18.0));
-                                    }
+                                    flights_table(
+                                        ui,
+                                        &results.columns,
+                                        &results.arrivals,
+                                        &ARRIVAL_COLUMNS,
+                                    );
                                 });
                             }
 
                             {
                                 ui.collapsing(RichText::new("Departures").size(18.0), |ui| {
-                                    for departure in &results.departures {
-                                        let mut text = String::new();
-                                        for a in &departure[..4] {
-                                            text.push_str(a);
-                                            text.push_str(" | ");
-                                        }
-                                        text.push_str(&departure[5]);
-                                        text.push_str(" | ");
-                                        ui.label(RichText::new(text).size(18.0));
-                                    }
+                                    flights_table(
+                                        ui,
+                                        &results.columns,
+                                        &results.departures,
+                                        &DEPARTURE_COLUMNS,
+                                    );
                                 });
                             }
                         }
@@ -173,29 +170,35 @@ impl AppState {
         }
     }
 
+    /// This function returns the columns of the row of a plane with their values.
     pub fn search_plane_info(
         &self,
-        plane_id: &String,
+        plane_id: &str,
         plane_info: Vec<String>,
         server: &mut TlsStream<TcpStream>,
-    ) -> Result<Vec<Vec<String>>, ErrorTypes> {
-        let query = format!(
-            "SELECT id, status, origin, destination, arrival_time, departure_time, fuel, velocity, height, latitude, longitude  FROM arrivals WHERE id = {} AND destination = {};",
-            plane_id, &plane_info[1]
-        );
-
-        let mut protocol = Protocol::new();
-        protocol.set_compress_algorithm(COMPRESSION);
-        protocol.write_query(
-            &query,
-            consistency::Consistency::One,
-            vec![QueryFlags::SkipMetadata],
-        )?;
-        let message = conect_server(server, Some(protocol), &COMPRESSION);
+    ) -> Result<Vec<(String, CqlValue)>, ErrorTypes> {
+        let query = "SELECT id, status, origin, destination, arrival_time, departure_time, fuel, velocity, height, latitude, longitude FROM arrivals WHERE id = ? AND destination = ?;";
+        let values = [
+            CqlValue::parse(DataType::Int, plane_id)?,
+            CqlValue::Text(plane_info[1].to_string()),
+        ];
+        let message = execute(server, &COMPRESSION, query, &values, Consistency::One);
         match message {
             Ok(ReplyMessage(msg)) => match msg {
                 ResponseMessage::Result { kind } => match kind {
-                    ResultResponse::Rows { metadata: _, rows } => Ok(rows),
+                    ResultResponse::Rows {
+                        metadata: Some(metadata),
+                        rows,
+                        ..
+                    } => match rows.into_iter().next() {
+                        Some(row) => Ok(metadata
+                            .columns
+                            .into_iter()
+                            .map(|(name, _)| name)
+                            .zip(row)
+                            .collect()),
+                        None => Err(ErrorTypes::new(620, "Plane not found".to_string())),
+                    },
                     _ => Err(ErrorTypes::new(620, "Unexpected message".to_string())),
                 },
                 _ => Err(ErrorTypes::new(625, "Unexpected message".to_string())),
@@ -205,3 +208,24 @@ impl AppState {
         }
     }
 }
+
+/// This function shows the flights with the `shown` columns, under a header with their names as the column metadata
+/// of the ROWS result has them. A value that a flight does not have is shown empty.
+fn flights_table(ui: &mut Ui, columns: &[String], flights: &[Vec<String>], shown: &[&str]) {
+    let positions = shown
+        .iter()
+        .filter_map(|name| columns.iter().position(|column| column == name))
+        .collect::<Vec<usize>>();
+    let line = |values: &[String]| {
+        let mut text = String::new();
+        for position in &positions {
+            text.push_str(values.get(*position).map_or("", String::as_str));
+            text.push_str(" | ");
+        }
+        text
+    };
+    ui.label(RichText::new(line(columns)).size(18.0).strong());
+    for flight in flights {
+        ui.label(RichText::new(line(flight)).size(18.0));
+    }
+}
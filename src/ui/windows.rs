@@ -1,19 +1,23 @@
 // use crate::ui::plugins::ImagesPluginData;
 
-use std::{collections::HashMap, net::TcpStream};
+use std::{
+    collections::{HashMap, VecDeque},
+    net::TcpStream,
+};
 
 use crate::{
     errors::error_types::ErrorTypes,
     protocol::{
-        protocol_body::{compression::Compression, query_flags::QueryFlags},
-        protocol_notations::consistency::{self, Consistency},
-        protocol_writer::*,
+        protocol_body::{
+            compression::Compression, data_type::DataType, event_kind::EventKindChange,
+        },
+        protocol_notations::{consistency::Consistency, cql_value::CqlValue},
     },
     receiver::{
         message::Message::ReplyMessage, response_message::ResponseMessage,
         result_response::ResultResponse,
     },
-    server::query_execute::conect_server,
+    server::query_execute::{execute_batch, execute_paged},
 };
 use egui::{Align2, RichText, Ui, Window};
 use native_tls::TlsStream;
@@ -23,6 +27,7 @@ use walkers::MapMemory;
 
 use super::search_results::{SearchResults, SearchType};
 const COMPRESSION: Option<Compression> = None;
+const PAGE_SIZE: i32 = 8;
 
 #[derive(Debug, Deserialize, Clone, PartialEq, Eq, Hash)]
 /// Represents an airport.
@@ -108,38 +113,66 @@ pub fn zoom(ui: &Ui, map_memory: &mut MapMemory) {
         });
 }
 
+/// Shows the window with the last changes of the cluster that the server sent as events.
+///
+/// # Arguments
+/// * `ui` - The Egui UI.
+/// * `events` - The events, from the oldest to the newest.
+pub fn cluster_events(ui: &Ui, events: &VecDeque<EventKindChange>) {
+    if events.is_empty() {
+        return;
+    }
+    Window::new("Cluster")
+        .collapsible(true)
+        .resizable(false)
+        .anchor(Align2::RIGHT_BOTTOM, [-10., -10.])
+        .show(ui.ctx(), |ui| {
+            for event in events.iter().rev() {
+                let text = match event {
+                    EventKindChange::Topology(change, node) => format!("{:?} {}", change, node),
+                    EventKindChange::Status(status, node) => format!("{} is {:?}", node, status),
+                    EventKindChange::Schema(change_type, target, name) => {
+                        format!("{:?} {} {}", change_type, target.to_lowercase(), name)
+                    }
+                };
+                ui.label(text);
+            }
+        });
+}
+
 pub fn switch_flight_state(
     server: &mut TlsStream<TcpStream>,
     flight_id: String,
     flight_status: String,
     flight_info: Vec<String>,
 ) -> Result<(), ErrorTypes> {
-    let querys = vec![
-        format!(
-            "UPDATE arrivals SET status = '{}' WHERE id = {} AND destination = '{}';",
-            flight_status, flight_id, &flight_info[1]
+    let id = CqlValue::parse(DataType::Int, &flight_id)?;
+    let status = CqlValue::Text(flight_status.to_string());
+    let querys = [
+        (
+            "UPDATE arrivals SET status = ? WHERE id = ? AND destination = ?;".to_string(),
+            vec![
+                status.clone(),
+                id.clone(),
+                CqlValue::Text(flight_info[1].to_string()),
+            ],
         ),
-        format!(
-            "UPDATE departures SET status = '{}' WHERE id = {} AND origin = '{}';",
-            flight_status, flight_id, &flight_info[0]
+        (
+            "UPDATE departures SET status = ? WHERE id = ? AND origin = ?;".to_string(),
+            vec![status, id, CqlValue::Text(flight_info[0].to_string())],
         ),
     ];
-    for query in querys {
-        let mut msg = Protocol::new();
-        msg.set_compress_algorithm(COMPRESSION);
-        msg.write_query(&query, Consistency::Quorum, vec![QueryFlags::SkipMetadata])?;
-        let message = conect_server(server, Some(msg), &COMPRESSION)?;
+    let message = execute_batch(server, &COMPRESSION, &querys, Consistency::Quorum)?;
 
-        let msg = match message {
-            ReplyMessage(ResponseMessage::Result {
-                kind: ResultResponse::Void,
-            }) => Ok(()),
-            ReplyMessage(_) => Err(ErrorTypes::new(2, "Unexpected message".to_string())),
-            _ => Err(ErrorTypes::new(3, "Error receiving message".to_string())),
-        };
-        msg.as_ref()
-            .map_err(|_| ErrorTypes::new(4, "Error receiving message".to_string()))?;
-    }
+    let msg = match message {
+        ReplyMessage(ResponseMessage::Result {
+            kind: ResultResponse::Void,
+        }) => Ok(()),
+        ReplyMessage(_) => Err(ErrorTypes::new(2, "Unexpected message".to_string())),
+        _ => Err(ErrorTypes::new(3, "Error receiving message".to_string())),
+    };
+    msg.as_ref()
+        .map_err(|_| ErrorTypes::new(4, "Error receiving message".to_string()))?;
     Ok(())
 }
 /// Centers the map at the user's position. When map is "detached", show a windows with an option to go back to my position.
@@ -179,16 +212,16 @@ pub struct AppState {
 
 pub fn get_planes_positions(results: &SearchResults) -> HashMap<(String, String), Vec<String>> {
     let mut planes_positions = HashMap::new();
-    if results.arrivals.len() > 1 {
-        for arrival in &results.arrivals[1..] {
+    if !results.arrivals.is_empty() {
+        for arrival in &results.arrivals {
             let id = arrival[0].clone();
             let status = arrival[1].clone();
             planes_positions.insert((id, status), arrival[2..].to_vec());
         }
     }
 
-    if results.departures.len() > 1 {
-        for departure in &results.departures[1..] {
+    if !results.departures.is_empty() {
+        for departure in &results.departures {
             let id = departure[0].clone();
             let status = departure[1].clone();
             planes_positions.insert((id, status), departure[2..].to_vec());
@@ -244,35 +277,27 @@ pub fn make_query(
     date: &str,
     server: &mut TlsStream<TcpStream>,
     type_flight: SearchType,
-) -> Result<Vec<Vec<String>>, ErrorTypes> {
+) -> Result<(Vec<String>, Vec<Vec<String>>), ErrorTypes> {
     let query = match type_flight {
-        SearchType::Arrivals => format!(
-            "SELECT id, status, origin, destination, arrival_time, departure_time, latitude, longitude FROM arrivals WHERE destination = {} AND  arrival_time = {};",
-            airport.code, date
-        ),
-        SearchType::Departures => format!(
-            "SELECT id, status, origin, destination, arrival_time, departure_time, latitude, longitude FROM departures WHERE origin = {} AND departure_time = {};",
-            airport.code, date
-        ),
+        SearchType::Arrivals => "SELECT id, status, origin, destination, arrival_time, departure_time, latitude, longitude FROM arrivals WHERE destination = ? AND arrival_time = ?;",
+        SearchType::Departures => "SELECT id, status, origin, destination, arrival_time, departure_time, latitude, longitude FROM departures WHERE origin = ? AND departure_time = ?;",
     };
+    let values = [
+        CqlValue::Text(airport.code.to_string()),
+        CqlValue::parse(DataType::Date, date)?,
+    ];
 
-    let mut protocol = Protocol::new();
-    protocol.set_compress_algorithm(COMPRESSION);
-    protocol.write_query(
-        query.as_str(),
-        consistency::Consistency::Quorum,
-        vec![QueryFlags::SkipMetadata],
-    )?;
-
-    let message = conect_server(server, Some(protocol), &COMPRESSION)?;
-    match message {
-        ReplyMessage(msg) => match msg {
-            ResponseMessage::Result { kind } => match kind {
-                ResultResponse::Rows { metadata: _, rows } => Ok(rows),
-                _ => Err(ErrorTypes::new(604, "Unexpected message".to_string())),
-            },
-            _ => Err(ErrorTypes::new(605, "Unexpected message".to_string())),
-        },
-        _ => Err(ErrorTypes::new(606, "Error receiving message".to_string())),
-    }
+    let mut pages = execute_paged(
+        server,
+        &COMPRESSION,
+        query,
+        &values,
+        Consistency::Quorum,
+        PAGE_SIZE,
+    );
+    let flights = pages
+        .by_ref()
+        .map(|row| row.map(|values| values.iter().map(CqlValue::to_string).collect()))
+        .collect::<Result<Vec<Vec<String>>, ErrorTypes>>()?;
+    Ok((pages.columns().to_vec(), flights))
 }
@@ -0,0 +1,261 @@
+use std::{
+    collections::HashMap,
+    env, fs,
+    net::TcpStream,
+    path::{Path, PathBuf},
+    process::{Child, Command, Stdio},
+    sync::mpsc::{channel, Receiver},
+    thread,
+    time::Duration,
+};
+
+use aerolineas_rusticas::{
+    protocol::protocol_body::{event_kind::EventKindChange, schema_change::SchemaChangeType},
+    server::query_execute::{authenticate, listen_events, prepare, register, startup},
+};
+use native_tls::{TlsConnector, TlsStream};
+use openssl::ssl::{SslContextBuilder, SslMethod, SslVerifyMode};
+use scylla::client::{session::Session, session_builder::SessionBuilder, Compression};
+
+// Un nodo solo tiene que ser la semilla para entrar al anillo sin otros nodos
+const INTERNAL_ADDRESS: &str = "127.0.0.1:8080";
+const CLIENT_ADDRESS: &str = "127.0.0.1:9042";
+
+/// The node runs in its own directory, with the certificate and the users it reads from the working directory, and is
+/// killed when the test ends even if it fails.
+struct LocalNode {
+    process: Child,
+    dir: PathBuf,
+}
+
+impl LocalNode {
+    fn start() -> LocalNode {
+        let dir = env::temp_dir().join(format!("aerolineas_driver_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let manifest = Path::new(env!("CARGO_MANIFEST_DIR"));
+        for file in ["identity.pfx", "users.json"] {
+            fs::copy(manifest.join(file), dir.join(file)).unwrap();
+        }
+        let process = Command::new(env!("CARGO_BIN_EXE_node"))
+            .args([INTERNAL_ADDRESS, CLIENT_ADDRESS, "--data-dir", "data"])
+            .current_dir(&dir)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+            .unwrap();
+        LocalNode { process, dir }
+    }
+}
+
+impl Drop for LocalNode {
+    fn drop(&mut self) {
+        let _ = self.process.kill();
+        let _ = self.process.wait();
+        let _ = fs::remove_dir_all(&self.dir);
+    }
+}
+
+async fn connect() -> Session {
+    let mut context = SslContextBuilder::new(SslMethod::tls()).unwrap();
+    context.set_verify(SslVerifyMode::NONE);
+    let context = context.build();
+    for _ in 0..30 {
+        let session = SessionBuilder::new()
+            .known_node(CLIENT_ADDRESS)
+            .user("client_ui", "1234")
+            .compression(Some(Compression::Lz4))
+            .tls_context(Some(context.clone()))
+            .fetch_schema_metadata(false)
+            .build()
+            .await;
+        // El nodo acepta clientes antes de terminar de entrar al anillo
+        if let Ok(session) = session {
+            if bootstrapped(&session).await {
+                return session;
+            }
+        }
+        tokio::time::sleep(Duration::from_secs(1)).await;
+    }
+    panic!("The driver could not connect to the node");
+}
+
+/// The client of the UI connects to the node and sends its STARTUP, without authenticating yet.
+fn ui_connection() -> TlsStream<TcpStream> {
+    let connector = TlsConnector::builder()
+        .danger_accept_invalid_certs(true)
+        .build()
+        .unwrap();
+    let stream = TcpStream::connect(CLIENT_ADDRESS).unwrap();
+    let mut server = connector.connect("127.0.0.1", stream).unwrap();
+    startup(&mut server, None).unwrap();
+    server
+}
+
+/// The client of the UI registers a connection for the schema changes and receives them in another thread.
+fn schema_events() -> Receiver<EventKindChange> {
+    let mut server = ui_connection();
+    authenticate(
+        "client_ui".to_string(),
+        "1234".to_string(),
+        &mut server,
+        None,
+    )
+    .unwrap();
+    register(&mut server, &None, vec!["SCHEMA_CHANGE".to_string()]).unwrap();
+    let (sender, events) = channel();
+    thread::spawn(move || {
+        let _ = listen_events(&mut server, &None, |event| {
+            let _ = sender.send(event);
+        });
+    });
+    events
+}
+
+async fn bootstrapped(session: &Session) -> bool {
+    let Ok(result) = session
+        .query_unpaged("SELECT bootstrapped FROM system.local", ())
+        .await
+    else {
+        return false;
+    };
+    let Ok(rows) = result.into_rows_result() else {
+        return false;
+    };
+    matches!(rows.single_row::<(String,)>(), Ok((state,)) if state == "COMPLETED")
+        && session.refresh_metadata().await.is_ok()
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_driver_queries_node() {
+    let _node = LocalNode::start();
+    let session = connect().await;
+
+    let local = session
+        .query_unpaged(
+            "SELECT cluster_name, native_protocol_version FROM system.local WHERE key = 'local'",
+            (),
+        )
+        .await
+        .unwrap()
+        .into_rows_result()
+        .unwrap();
+    let (cluster_name, version) = local.single_row::<(String, String)>().unwrap();
+    assert_eq!(cluster_name, "Aerolineas Rusticas");
+    assert_eq!(version, "4");
+
+    let unauthenticated = prepare(&mut ui_connection(), &None, "SELECT * FROM system.local");
+    assert!(unauthenticated
+        .unwrap_err()
+        .get()
+        .1
+        .contains(&format!("{} You have not logged in", 0x2100)));
+
+    let system = session
+        .query_unpaged(
+            "CREATE TABLE system_schema.flights (id int, origin text, PRIMARY KEY ((origin), id))",
+            (),
+        )
+        .await;
+    assert!(system
+        .unwrap_err()
+        .to_string()
+        .contains("system_schema keyspace is not user-modifiable"));
+
+    let events = schema_events();
+
+    session
+        .query_unpaged(
+            "CREATE KEYSPACE driver WITH replication = {'class': 'SimpleStrategy', 'replication_factor': 1}",
+            (),
+        )
+        .await
+        .unwrap();
+    session
+        .query_unpaged(
+            "CREATE TABLE driver.flights (id int, origin text, destination text, PRIMARY KEY ((origin), id))",
+            (),
+        )
+        .await
+        .unwrap();
+
+    for name in ["driver", "driver.flights"] {
+        let event = events.recv_timeout(Duration::from_secs(5)).unwrap();
+        let target = if name.contains('.') {
+            "TABLE"
+        } else {
+            "KEYSPACE"
+        };
+        assert_eq!(
+            event,
+            EventKindChange::Schema(
+                SchemaChangeType::Created,
+                target.to_string(),
+                name.to_string()
+            )
+        );
+    }
+
+    let keyspace = session
+        .query_unpaged(
+            "SELECT replication FROM system_schema.keyspaces WHERE keyspace_name = 'driver'",
+            (),
+        )
+        .await
+        .unwrap()
+        .into_rows_result()
+        .unwrap();
+    let (replication,) = keyspace.single_row::<(HashMap<String, String>,)>().unwrap();
+    assert_eq!(replication["replication_factor"], "1");
+    let columns = session
+        .query_unpaged(
+            "SELECT column_name, kind, type FROM system_schema.columns WHERE keyspace_name = 'driver' AND table_name = 'flights'",
+            (),
+        )
+        .await
+        .unwrap()
+        .into_rows_result()
+        .unwrap()
+        .rows::<(String, String, String)>()
+        .unwrap()
+        .collect::<Result<Vec<(String, String, String)>, _>>()
+        .unwrap();
+    assert_eq!(
+        columns[2],
+        (
+            "origin".to_string(),
+            "partition_key".to_string(),
+            "text".to_string()
+        )
+    );
+    session
+        .query_unpaged(
+            "INSERT INTO driver.flights (origin, id, destination) VALUES ('EZE', 1, 'MAD')",
+            (),
+        )
+        .await
+        .unwrap();
+    session
+        .query_unpaged(
+            "INSERT INTO driver.flights (origin, id, destination) VALUES (?, ?, ?)",
+            ("EZE", 2, "JFK"),
+        )
+        .await
+        .unwrap();
+
+    let rows = session
+        .query_unpaged(
+            "SELECT id, destination FROM driver.flights WHERE origin = 'EZE'",
+            (),
+        )
+        .await
+        .unwrap()
+        .into_rows_result()
+        .unwrap()
+        .rows::<(i32, String)>()
+        .unwrap()
+        .collect::<Result<Vec<(i32, String)>, _>>()
+        .unwrap();
+    assert_eq!(rows, vec![(1, "MAD".to_string()), (2, "JFK".to_string())]);
+}